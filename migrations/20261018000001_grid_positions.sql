@@ -0,0 +1,6 @@
+-- Character positions are stored as grid tiles, not pixels.
+-- Rows still on the old pixel default move to the village start tile.
+ALTER TABLE characters ALTER COLUMN pos_x SET DEFAULT 8;
+ALTER TABLE characters ALTER COLUMN pos_y SET DEFAULT 8;
+
+UPDATE characters SET pos_x = 8, pos_y = 8 WHERE pos_x = 400 AND pos_y = 300;
@@ -0,0 +1,4 @@
+-- The starting map id is 'milles_village'; 'village_milles' matched no map.
+ALTER TABLE characters ALTER COLUMN current_map SET DEFAULT 'milles_village';
+
+UPDATE characters SET current_map = 'milles_village' WHERE current_map = 'village_milles';
@@ -0,0 +1,25 @@
+-- Account roles, bans and suspensions, and the GM audit trail
+--
+-- The first admin is promoted by hand:
+--   UPDATE users SET role = 'admin' WHERE username = '...';
+ALTER TABLE users ADD COLUMN IF NOT EXISTS role VARCHAR(20) NOT NULL DEFAULT 'player'
+    CHECK (role IN ('player', 'gm', 'admin'));
+ALTER TABLE users ADD COLUMN IF NOT EXISTS suspended_until TIMESTAMP WITH TIME ZONE;
+
+-- Banned accounts have is_active = FALSE
+UPDATE users SET is_active = TRUE WHERE is_active IS NULL;
+ALTER TABLE users ALTER COLUMN is_active SET NOT NULL;
+
+CREATE TABLE IF NOT EXISTS gm_audit_log (
+    id BIGSERIAL PRIMARY KEY,
+    gm_user_id UUID NOT NULL REFERENCES users(id),
+    action VARCHAR(50) NOT NULL,
+    -- No foreign keys: the log outlives deleted characters
+    target_user_id UUID,
+    target_character_id UUID,
+    details TEXT NOT NULL DEFAULT '',
+    created_at TIMESTAMP WITH TIME ZONE NOT NULL DEFAULT CURRENT_TIMESTAMP
+);
+
+CREATE INDEX IF NOT EXISTS idx_gm_audit_log_created_at ON gm_audit_log(created_at DESC);
+CREATE INDEX IF NOT EXISTS idx_gm_audit_log_target_user ON gm_audit_log(target_user_id);
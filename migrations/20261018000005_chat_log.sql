@@ -0,0 +1,17 @@
+-- Chat history for moderation; `message` is what the sender typed,
+-- before the word filter
+CREATE TABLE IF NOT EXISTS chat_log (
+    id BIGSERIAL PRIMARY KEY,
+    channel VARCHAR(20) NOT NULL CHECK (channel IN ('map', 'whisper', 'party', 'global')),
+    -- NULL for guests; no foreign key so the log outlives deleted characters
+    sender_character_id UUID,
+    sender_name VARCHAR(50) NOT NULL,
+    recipient_name VARCHAR(50),
+    map_id VARCHAR(50) NOT NULL,
+    message TEXT NOT NULL,
+    filtered BOOLEAN NOT NULL DEFAULT FALSE,
+    sent_at TIMESTAMP WITH TIME ZONE NOT NULL DEFAULT CURRENT_TIMESTAMP
+);
+
+CREATE INDEX IF NOT EXISTS idx_chat_log_sent_at ON chat_log(sent_at DESC);
+CREATE INDEX IF NOT EXISTS idx_chat_log_sender ON chat_log(sender_character_id);
@@ -0,0 +1,46 @@
+-- Guilds, their members and invites, and the shared guild bank
+CREATE TABLE IF NOT EXISTS guilds (
+    id UUID PRIMARY KEY DEFAULT gen_random_uuid(),
+    name VARCHAR(20) NOT NULL,
+    notice TEXT NOT NULL DEFAULT '',
+    bank_gold BIGINT NOT NULL DEFAULT 0 CHECK (bank_gold >= 0),
+    created_at TIMESTAMP WITH TIME ZONE NOT NULL DEFAULT CURRENT_TIMESTAMP
+);
+
+-- "Knights" and "knights" are the same guild
+CREATE UNIQUE INDEX IF NOT EXISTS idx_guilds_name ON guilds(LOWER(name));
+
+-- A character is in at most one guild; each guild has exactly one leader
+CREATE TABLE IF NOT EXISTS guild_members (
+    character_id UUID PRIMARY KEY REFERENCES characters(id) ON DELETE CASCADE,
+    guild_id UUID NOT NULL REFERENCES guilds(id) ON DELETE CASCADE,
+    rank VARCHAR(20) NOT NULL CHECK (rank IN ('leader', 'officer', 'member')),
+    joined_at TIMESTAMP WITH TIME ZONE NOT NULL DEFAULT CURRENT_TIMESTAMP
+);
+
+CREATE INDEX IF NOT EXISTS idx_guild_members_guild ON guild_members(guild_id);
+CREATE UNIQUE INDEX IF NOT EXISTS idx_guild_members_leader ON guild_members(guild_id) WHERE rank = 'leader';
+
+CREATE TABLE IF NOT EXISTS guild_invites (
+    guild_id UUID NOT NULL REFERENCES guilds(id) ON DELETE CASCADE,
+    character_id UUID NOT NULL REFERENCES characters(id) ON DELETE CASCADE,
+    invited_by VARCHAR(50) NOT NULL,
+    created_at TIMESTAMP WITH TIME ZONE NOT NULL DEFAULT CURRENT_TIMESTAMP,
+    PRIMARY KEY (guild_id, character_id)
+);
+
+CREATE INDEX IF NOT EXISTS idx_guild_invites_character ON guild_invites(character_id);
+
+CREATE TABLE IF NOT EXISTS guild_bank_items (
+    guild_id UUID NOT NULL REFERENCES guilds(id) ON DELETE CASCADE,
+    slot_index INT NOT NULL CHECK (slot_index >= 0),
+    item_id INT NOT NULL,  -- const ItemDef.id
+    quantity INT NOT NULL CHECK (quantity >= 1),
+    enhancement_level INT NOT NULL DEFAULT 0 CHECK (enhancement_level >= 0),
+    PRIMARY KEY (guild_id, slot_index)
+);
+
+-- Guild chat is logged like the other channels
+ALTER TABLE chat_log DROP CONSTRAINT IF EXISTS chat_log_channel_check;
+ALTER TABLE chat_log ADD CONSTRAINT chat_log_channel_check
+    CHECK (channel IN ('map', 'whisper', 'party', 'global', 'guild'));
@@ -0,0 +1,5 @@
+-- Where a character returns after dying, set at an inn. NULL until bound,
+-- in which case they come back at the start of the map they died on.
+ALTER TABLE characters ADD COLUMN IF NOT EXISTS bind_map_id VARCHAR(50);
+ALTER TABLE characters ADD COLUMN IF NOT EXISTS bind_x INT;
+ALTER TABLE characters ADD COLUMN IF NOT EXISTS bind_y INT;
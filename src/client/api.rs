@@ -0,0 +1,132 @@
+//! REST API client
+//!
+//! Requests run on the IO task pool so the frame loop never blocks.
+//! Systems keep the returned `ApiTask` and poll it each frame.
+
+use bevy::tasks::{IoTaskPool, Task};
+use futures_lite::future;
+use reqwest::Method;
+use serde::de::DeserializeOwned;
+use serde::Serialize;
+
+use crate::shared::api::ApiErrorBody;
+
+use super::net::NetworkConfig;
+
+/// Why a request failed
+#[derive(Debug, Clone, PartialEq)]
+pub enum RequestError {
+    /// The server answered with an error status
+    Api(ApiErrorBody),
+    /// No usable answer (connection refused, bad body, ...)
+    Transport(String),
+}
+
+impl std::fmt::Display for RequestError {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            Self::Api(body) if !body.fields.is_empty() => {
+                let messages: Vec<_> = body.fields.iter().map(|e| e.message.as_str()).collect();
+                write!(f, "{}", messages.join("\n"))
+            }
+            Self::Api(body) => write!(f, "{}", body.message),
+            Self::Transport(e) => write!(f, "Server unavailable: {}", e),
+        }
+    }
+}
+
+/// An in-flight API request
+pub struct ApiTask<T>(Task<Result<T, RequestError>>);
+
+impl<T> ApiTask<T> {
+    /// Result if the request has finished
+    pub fn poll(&mut self) -> Option<Result<T, RequestError>> {
+        future::block_on(future::poll_once(&mut self.0))
+    }
+
+    /// Let the request finish in the background, ignoring the result
+    pub fn detach(self) {
+        self.0.detach();
+    }
+}
+
+/// Start a JSON request against the API root
+pub fn request<B, T>(
+    config: &NetworkConfig,
+    method: Method,
+    path: &str,
+    token: Option<&str>,
+    body: Option<&B>,
+) -> ApiTask<T>
+where
+    B: Serialize,
+    T: DeserializeOwned + Send + 'static,
+{
+    let url = format!("{}{}", config.api_url, path);
+    let token = token.map(str::to_string);
+    let body = body.map(|b| serde_json::to_value(b).expect("request body serializes"));
+
+    ApiTask(IoTaskPool::get().spawn(async move { send(method, url, token, body).await }))
+}
+
+pub fn get<T: DeserializeOwned + Send + 'static>(config: &NetworkConfig, path: &str, token: Option<&str>) -> ApiTask<T> {
+    request::<(), T>(config, Method::GET, path, token, None)
+}
+
+pub fn post<B: Serialize, T: DeserializeOwned + Send + 'static>(
+    config: &NetworkConfig,
+    path: &str,
+    token: Option<&str>,
+    body: &B,
+) -> ApiTask<T> {
+    request(config, Method::POST, path, token, Some(body))
+}
+
+pub fn delete<T: DeserializeOwned + Send + 'static>(config: &NetworkConfig, path: &str, token: Option<&str>) -> ApiTask<T> {
+    request::<(), T>(config, Method::DELETE, path, token, None)
+}
+
+// Native builds have no async runtime, so the IO thread blocks on the request.
+#[cfg(not(target_arch = "wasm32"))]
+async fn send<T: DeserializeOwned>(
+    method: Method,
+    url: String,
+    token: Option<String>,
+    body: Option<serde_json::Value>,
+) -> Result<T, RequestError> {
+    let mut req = reqwest::blocking::Client::new().request(method, url);
+    if let Some(token) = token {
+        req = req.bearer_auth(token);
+    }
+    if let Some(body) = body {
+        req = req.json(&body);
+    }
+    let res = req.send().map_err(|e| RequestError::Transport(e.to_string()))?;
+    if !res.status().is_success() {
+        let status = res.status();
+        return Err(res.json::<ApiErrorBody>().map_or_else(|_| RequestError::Transport(status.to_string()), RequestError::Api));
+    }
+    res.json::<T>().map_err(|e| RequestError::Transport(e.to_string()))
+}
+
+#[cfg(target_arch = "wasm32")]
+async fn send<T: DeserializeOwned>(
+    method: Method,
+    url: String,
+    token: Option<String>,
+    body: Option<serde_json::Value>,
+) -> Result<T, RequestError> {
+    let mut req = reqwest::Client::new().request(method, url);
+    if let Some(token) = token {
+        req = req.bearer_auth(token);
+    }
+    if let Some(body) = body {
+        req = req.json(&body);
+    }
+    let res = req.send().await.map_err(|e| RequestError::Transport(e.to_string()))?;
+    if !res.status().is_success() {
+        let status = res.status();
+        return Err(res.json::<ApiErrorBody>().await.map_or_else(|_| RequestError::Transport(status.to_string()), RequestError::Api));
+    }
+    res.json::<T>().await.map_err(|e| RequestError::Transport(e.to_string()))
+}
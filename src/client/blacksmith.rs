@@ -0,0 +1,294 @@
+//! Blacksmith - the window opened by talking to a blacksmith
+//!
+//! Pressing E at a blacksmith asks the server what it can enhance. The
+//! window lists every piece of equipment in the bag with its tooltip,
+//! what the next level costs and its odds, and an Enhance button. The
+//! server rolls each attempt and answers with our bag and gold, which
+//! replace the local copies; how it went is told in the chat log. Escape,
+//! the Close button or walking away closes it.
+
+use bevy::prelude::*;
+
+use super::api::{self, ApiTask};
+use super::chat::ChatLog;
+use super::components::{GridPosition, HudUI, PlayerComponent};
+use super::net::NetworkConfig;
+use super::resources::{GameAssets, Session};
+use crate::shared::api::{BlacksmithResponse, EnhanceOption, EnhanceRequest};
+use crate::shared::data::enhancements::EnhanceOutcome;
+use crate::shared::data::items::get_item_by_id;
+use crate::shared::data::maps::NPC_REACH;
+use crate::shared::domain::Player;
+
+const WINDOW_BG: Color = Color::srgba(0.0, 0.0, 0.0, 0.6);
+const TITLE_COLOR: Color = Color::srgb(1.0, 0.85, 0.4);
+const TEXT_COLOR: Color = Color::srgb(0.95, 0.95, 0.95);
+const HINT_COLOR: Color = Color::srgb(0.6, 0.6, 0.65);
+const BUTTON_NORMAL: Color = Color::srgb(0.25, 0.25, 0.3);
+const BUTTON_HOVER: Color = Color::srgb(0.35, 0.35, 0.42);
+
+/// The blacksmith being visited
+#[derive(Debug, Clone)]
+pub struct BlacksmithNpc {
+    pub id: String,
+    pub name: String,
+    pub x: i32,
+    pub y: i32,
+}
+
+/// The open smithy and the request in flight, if any
+#[derive(Resource, Default)]
+pub struct BlacksmithStatus {
+    pub npc: Option<BlacksmithNpc>,
+    /// Options, bag and gold as the server last reported them
+    pub smithy: Option<BlacksmithResponse>,
+    request: Option<ApiTask<BlacksmithResponse>>,
+    /// Bag slot being enhanced and its item's name, for the result message
+    attempt: Option<(usize, String)>,
+}
+
+impl BlacksmithStatus {
+    /// Ask the server what a blacksmith can do; the window opens with the answer
+    pub fn open(&mut self, config: &NetworkConfig, session: &Session, npc: BlacksmithNpc) {
+        self.request = Some(api::get(config, &format!("/blacksmiths/{}", npc.id), Some(&session.token)));
+        self.npc = Some(npc);
+        self.smithy = None;
+        self.attempt = None;
+    }
+
+    pub fn close(&mut self) {
+        *self = Self::default();
+    }
+}
+
+/// What the next level of an option costs, and its odds
+pub fn option_label(option: &EnhanceOption) -> String {
+    let material = get_item_by_id(option.material_id).map(|d| d.name).unwrap_or("Unknown item");
+    let mut label = format!(
+        "To +{}: {}g, {} {} - {:.0}% success",
+        option.level, option.gold_cost, option.material_quantity, material, option.success_chance * 100.0,
+    );
+    if option.destroy_chance > 0.0 {
+        label.push_str(&format!(", {:.0}% destroy", option.destroy_chance * 100.0));
+    }
+    label
+}
+
+/// Chat line for how an attempt on `item` went
+pub fn outcome_message(item: &str, outcome: EnhanceOutcome) -> String {
+    match outcome {
+        EnhanceOutcome::Success => format!("Enhancement succeeded: {}", item),
+        EnhanceOutcome::Failure => format!("Enhancement failed; {} is unchanged", item),
+        EnhanceOutcome::Destroyed => format!("Enhancement failed; {} was destroyed", item),
+    }
+}
+
+/// A blacksmith window button
+#[derive(Component, Debug, Clone, Copy)]
+pub enum BlacksmithButton {
+    /// Enhance the equipment in a bag slot
+    Enhance(usize),
+    Close,
+}
+
+/// Container of the blacksmith window (hidden while no smithy is open)
+#[derive(Component)]
+pub struct BlacksmithWindowUI;
+
+pub fn spawn_blacksmith_window(mut commands: Commands, mut status: ResMut<BlacksmithStatus>) {
+    status.close();
+    commands.spawn((
+        Node {
+            position_type: PositionType::Absolute,
+            // Same spot as the shop window; only one NPC is visited at a time
+            left: Val::Percent(50.0),
+            top: Val::Px(120.0),
+            margin: UiRect::left(Val::Px(-170.0)),
+            width: Val::Px(340.0),
+            flex_direction: FlexDirection::Column,
+            row_gap: Val::Px(4.0),
+            padding: UiRect::all(Val::Px(8.0)),
+            ..default()
+        },
+        BackgroundColor(WINDOW_BG),
+        BorderRadius::all(Val::Px(6.0)),
+        Visibility::Hidden,
+        HudUI,
+        BlacksmithWindowUI,
+    ));
+}
+
+/// Send button presses to the server, one request at a time
+pub fn blacksmith_buttons(
+    mut buttons: Query<(&Interaction, &BlacksmithButton, &mut BackgroundColor), Changed<Interaction>>,
+    mut status: ResMut<BlacksmithStatus>,
+    session: Option<Res<Session>>,
+    config: Res<NetworkConfig>,
+) {
+    for (interaction, button, mut color) in &mut buttons {
+        match interaction {
+            Interaction::Pressed => {}
+            Interaction::Hovered => {
+                *color = BackgroundColor(BUTTON_HOVER);
+                continue;
+            }
+            Interaction::None => {
+                *color = BackgroundColor(BUTTON_NORMAL);
+                continue;
+            }
+        }
+        let slot_index = match *button {
+            BlacksmithButton::Enhance(slot_index) => slot_index,
+            BlacksmithButton::Close => {
+                status.close();
+                return;
+            }
+        };
+        let (Some(session), Some(npc), Some(smithy)) = (&session, &status.npc, &status.smithy) else { continue; };
+        if status.request.is_some() {
+            continue;
+        }
+        let path = format!("/blacksmiths/{}/enhance", npc.id);
+        let name = smithy.inventory.slots.get(slot_index).and_then(|s| s.as_ref()).map(|s| s.display_name());
+        status.request = Some(api::post(&config, &path, Some(&session.token), &EnhanceRequest { slot_index }));
+        status.attempt = name.map(|name| (slot_index, name));
+    }
+}
+
+/// Apply blacksmith answers to our character; results and refusals go to the chat log
+pub fn poll_blacksmith_requests(
+    mut status: ResMut<BlacksmithStatus>,
+    mut player: Query<&mut Player, With<PlayerComponent>>,
+    mut chat_log: ResMut<ChatLog>,
+) {
+    // Polling must not count as a change, or the window would rebuild every frame
+    let Some(result) = status.bypass_change_detection().request.as_mut().and_then(|t| t.poll()) else { return; };
+    status.request = None;
+    let attempt = status.attempt.take();
+    match result {
+        Ok(smithy) => {
+            if let Ok(mut player) = player.get_single_mut() {
+                player.inventory = smithy.inventory.clone();
+                player.gold = smithy.gold;
+            }
+            if let (Some(outcome), Some((slot_index, name))) = (smithy.outcome, attempt) {
+                // A success names the item at its new level
+                let enhanced = smithy.inventory.slots.get(slot_index).and_then(|s| s.as_ref());
+                let item = match (outcome, enhanced) {
+                    (EnhanceOutcome::Success, Some(stack)) => stack.display_name(),
+                    _ => name,
+                };
+                chat_log.push_system(outcome_message(&item, outcome));
+            }
+            status.smithy = Some(smithy);
+        }
+        Err(e) => {
+            chat_log.push_system(e.to_string());
+            // A smithy that never opened has nothing to show
+            if status.smithy.is_none() {
+                status.close();
+            }
+        }
+    }
+}
+
+/// Close the smithy on Escape or once we walk away from the blacksmith
+pub fn close_blacksmith(
+    keyboard_input: Res<ButtonInput<KeyCode>>,
+    player: Query<&GridPosition, With<PlayerComponent>>,
+    mut status: ResMut<BlacksmithStatus>,
+) {
+    let Some(npc) = &status.npc else { return; };
+    let away = player.get_single()
+        .is_ok_and(|pos| (pos.x - npc.x).abs().max((pos.y - npc.y).abs()) > NPC_REACH);
+    if away || keyboard_input.just_pressed(KeyCode::Escape) {
+        status.close();
+    }
+}
+
+fn spawn_button(parent: &mut ChildBuilder, font: TextFont, label: &str, button: BlacksmithButton) {
+    parent.spawn((
+        Button,
+        Node {
+            padding: UiRect::axes(Val::Px(8.0), Val::Px(2.0)),
+            ..default()
+        },
+        BackgroundColor(BUTTON_NORMAL),
+        BorderRadius::all(Val::Px(4.0)),
+        button,
+    )).with_children(|btn| {
+        btn.spawn((Text::new(label), font, TextColor(TEXT_COLOR)));
+    });
+}
+
+/// Rebuild the window whenever the smithy changes
+pub fn update_blacksmith_window(
+    mut commands: Commands,
+    assets: Res<GameAssets>,
+    status: Res<BlacksmithStatus>,
+    mut container: Query<(Entity, &mut Visibility), With<BlacksmithWindowUI>>,
+) {
+    if !status.is_changed() {
+        return;
+    }
+    let Ok((container, mut visibility)) = container.get_single_mut() else { return; };
+    commands.entity(container).despawn_descendants();
+    let (Some(npc), Some(smithy)) = (&status.npc, &status.smithy) else {
+        *visibility = Visibility::Hidden;
+        return;
+    };
+    *visibility = Visibility::Inherited;
+
+    let font = |size: f32| TextFont {
+        font: assets.ui_font.clone(),
+        font_size: size,
+        ..default()
+    };
+    let row = || Node {
+        justify_content: JustifyContent::SpaceBetween,
+        align_items: AlignItems::Center,
+        ..default()
+    };
+    commands.entity(container).with_children(|window| {
+        window.spawn((Text::new(npc.name.clone()), font(16.0), TextColor(TITLE_COLOR)));
+        if smithy.options.is_empty() {
+            window.spawn((Text::new("Nothing in your bag can be enhanced"), font(14.0), TextColor(HINT_COLOR)));
+        }
+        for option in &smithy.options {
+            let Some(stack) = smithy.inventory.slots.get(option.slot_index).and_then(|s| s.as_ref()) else { continue; };
+            window.spawn(row()).with_children(|row| {
+                row.spawn((Text::new(stack.tooltip().join("  ")), font(14.0), TextColor(TEXT_COLOR)));
+                spawn_button(row, font(14.0), "Enhance", BlacksmithButton::Enhance(option.slot_index));
+            });
+            window.spawn((Text::new(option_label(option)), font(12.0), TextColor(HINT_COLOR)));
+        }
+
+        window.spawn(row()).with_children(|row| {
+            row.spawn((Text::new(format!("{} gold", smithy.gold)), font(14.0), TextColor(TEXT_COLOR)));
+            spawn_button(row, font(14.0), "Close", BlacksmithButton::Close);
+        });
+        window.spawn((Text::new("Gold and materials are spent even if it fails"), font(12.0), TextColor(HINT_COLOR)));
+    });
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::shared::data::enhancements::ENHANCEMENT_STONE_ID;
+
+    #[test]
+    fn test_option_label_shows_cost_and_odds() {
+        let mut option = EnhanceOption {
+            slot_index: 0,
+            level: 7,
+            gold_cost: 2_200,
+            material_id: ENHANCEMENT_STONE_ID,
+            material_quantity: 3,
+            success_chance: 0.5,
+            destroy_chance: 0.0,
+        };
+        assert_eq!(option_label(&option), "To +7: 2200g, 3 Enhancement Stone - 50% success");
+        option.destroy_chance = 0.1;
+        assert_eq!(option_label(&option), "To +7: 2200g, 3 Enhancement Stone - 50% success, 10% destroy");
+    }
+}
@@ -0,0 +1,419 @@
+//! Chat - message log and input box
+//!
+//! Press Enter to open the input box and Enter again to send; Escape
+//! cancels. Lines go to the current map unless prefixed with a command:
+//! `/w <name> <message>` whispers, `/p` talks to the party, `/gu` to the
+//! guild and `/g` to everyone online. `/invite`, `/accept`, `/decline`, `/leave` and
+//! `/kick` manage the party, and `/trade` trades with a nearby player.
+//! While the box is open, gameplay keys are ignored.
+
+use std::collections::VecDeque;
+
+use bevy::input::keyboard::{Key, KeyboardInput};
+use bevy::input::ButtonState;
+use bevy::prelude::*;
+
+use super::components::{HudUI, PlayerComponent};
+use super::net::ServerConnection;
+use super::party::PartyStatus;
+use super::resources::GameAssets;
+use super::trade::{parse_trade_command, resolve_trade_command, TradeCommand, TradeStatus};
+use crate::shared::domain::character::models::Player;
+use crate::shared::domain::item::inventory::Inventory;
+use crate::shared::protocol::{ChatChannel, ChatMessage, ClientMessage, MAX_CHAT_LEN};
+
+/// Lines kept in the log
+const MAX_LINES: usize = 50;
+/// Lines shown in the window
+const VISIBLE_LINES: usize = 8;
+/// Room for a command prefix on top of the message itself
+const MAX_INPUT_LEN: usize = MAX_CHAT_LEN + 24;
+
+const WINDOW_BG: Color = Color::srgba(0.0, 0.0, 0.0, 0.45);
+const INPUT_BG: Color = Color::srgba(0.1, 0.1, 0.16, 0.9);
+const MAP_COLOR: Color = Color::srgb(0.95, 0.95, 0.95);
+const WHISPER_COLOR: Color = Color::srgb(1.0, 0.55, 0.85);
+const PARTY_COLOR: Color = Color::srgb(0.45, 0.85, 1.0);
+const GLOBAL_COLOR: Color = Color::srgb(1.0, 0.8, 0.2);
+const GUILD_COLOR: Color = Color::srgb(0.55, 1.0, 0.35);
+const SYSTEM_COLOR: Color = Color::srgb(0.6, 0.9, 0.6);
+const ERROR_COLOR: Color = Color::srgb(1.0, 0.4, 0.4);
+
+/// One rendered line
+#[derive(Debug, Clone, PartialEq)]
+pub struct ChatLine {
+    pub text: String,
+    pub color: Color,
+}
+
+/// Recent chat, NPC and system lines (oldest first)
+#[derive(Resource, Default)]
+pub struct ChatLog {
+    lines: VecDeque<ChatLine>,
+}
+
+impl ChatLog {
+    fn push(&mut self, text: String, color: Color) {
+        if self.lines.len() == MAX_LINES {
+            self.lines.pop_front();
+        }
+        self.lines.push_back(ChatLine { text, color });
+    }
+
+    /// A message from the server; `own_id` tells our whisper echoes apart
+    pub fn push_message(&mut self, msg: &ChatMessage, own_id: Option<&str>) {
+        let (text, color) = match msg.channel {
+            ChatChannel::Map => (format!("{}: {}", msg.from, msg.text), MAP_COLOR),
+            ChatChannel::Whisper if own_id == Some(msg.from_id.as_str()) => (
+                format!("To {}: {}", msg.to.as_deref().unwrap_or("?"), msg.text),
+                WHISPER_COLOR,
+            ),
+            ChatChannel::Whisper => (format!("From {}: {}", msg.from, msg.text), WHISPER_COLOR),
+            ChatChannel::Party => (format!("[Party] {}: {}", msg.from, msg.text), PARTY_COLOR),
+            ChatChannel::Global => (format!("[Global] {}: {}", msg.from, msg.text), GLOBAL_COLOR),
+            ChatChannel::Guild => (format!("[Guild] {}: {}", msg.from, msg.text), GUILD_COLOR),
+        };
+        self.push(text, color);
+    }
+
+    /// NPC speech and other local notices
+    pub fn push_system(&mut self, text: impl Into<String>) {
+        self.push(text.into(), SYSTEM_COLOR);
+    }
+
+    pub fn push_error(&mut self, text: impl Into<String>) {
+        self.push(text.into(), ERROR_COLOR);
+    }
+}
+
+/// State of the input box
+#[derive(Resource, Default)]
+pub struct ChatInput {
+    pub open: bool,
+    pub text: String,
+}
+
+/// Run condition: the input box has the keyboard
+pub fn is_typing(input: Res<ChatInput>) -> bool {
+    input.open
+}
+
+/// Container of the visible lines
+#[derive(Component)]
+pub struct ChatLinesUI;
+
+/// Input box (hidden while closed)
+#[derive(Component)]
+pub struct ChatInputUI;
+
+/// Text of the input box
+#[derive(Component)]
+pub struct ChatInputText;
+
+/// What a line typed in the chat box asks for
+#[derive(Debug, Clone, PartialEq)]
+pub enum ChatCommand {
+    /// Ready to send as is
+    Send(ClientMessage),
+    /// Join the party of the latest invite
+    Accept,
+    /// Forget the latest invite
+    Decline,
+    /// Remove a party member, by name
+    Kick(String),
+    Trade(TradeCommand),
+}
+
+/// Turn typed text into a command; Err explains a bad one
+pub fn parse_chat_input(input: &str) -> Result<ChatCommand, String> {
+    let input = input.trim();
+    let Some(command) = input.strip_prefix('/') else {
+        return Ok(ChatCommand::Send(ClientMessage::Chat { channel: ChatChannel::Map, to: None, text: input.to_string() }));
+    };
+
+    let (name, rest) = command.split_once(char::is_whitespace).unwrap_or((command, ""));
+    let rest = rest.trim_start();
+    let needs_rest = |usage: &str| if rest.is_empty() { Err(format!("Usage: /{} {}", name, usage)) } else { Ok(rest.to_string()) };
+    let channel = match name.to_lowercase().as_str() {
+        "w" | "whisper" => {
+            let (to, text) = rest.split_once(char::is_whitespace).unwrap_or((rest, ""));
+            if to.is_empty() || text.trim().is_empty() {
+                return Err("Usage: /w <name> <message>".to_string());
+            }
+            return Ok(ChatCommand::Send(ClientMessage::Chat {
+                channel: ChatChannel::Whisper,
+                to: Some(to.to_string()),
+                text: text.trim().to_string(),
+            }));
+        }
+        "invite" => return needs_rest("<name>").map(|name| ChatCommand::Send(ClientMessage::PartyInvite { name })),
+        "kick" => return needs_rest("<name>").map(ChatCommand::Kick),
+        "accept" => return Ok(ChatCommand::Accept),
+        "decline" => return Ok(ChatCommand::Decline),
+        "leave" => return Ok(ChatCommand::Send(ClientMessage::PartyLeave)),
+        "trade" => return parse_trade_command(rest).map(ChatCommand::Trade),
+        "m" | "map" => ChatChannel::Map,
+        "p" | "party" => ChatChannel::Party,
+        "g" | "global" => ChatChannel::Global,
+        "gu" | "guild" => ChatChannel::Guild,
+        _ => return Err(format!("Unknown command /{} (try /w, /p, /gu, /g or /invite)", name)),
+    };
+    let text = needs_rest("<message>")?;
+    Ok(ChatCommand::Send(ClientMessage::Chat { channel, to: None, text }))
+}
+
+/// The message a command sends, using the party for names and invites
+/// and the open trade and our bag for trade offers
+fn resolve_command(
+    command: ChatCommand,
+    party: &mut PartyStatus,
+    trade: &mut TradeStatus,
+    bag: &Inventory,
+) -> Result<Option<ClientMessage>, String> {
+    match command {
+        ChatCommand::Send(msg) => Ok(Some(msg)),
+        ChatCommand::Accept => {
+            let (inviter_id, _) = party.invite.take().ok_or("You have no party invite")?;
+            Ok(Some(ClientMessage::PartyAccept { inviter_id }))
+        }
+        ChatCommand::Decline => {
+            party.invite.take().ok_or("You have no party invite")?;
+            Ok(None)
+        }
+        ChatCommand::Kick(name) => {
+            let member = party.member_named(&name).ok_or_else(|| format!("{} is not in your party", name))?;
+            Ok(Some(ClientMessage::PartyKick { member_id: member.id.clone() }))
+        }
+        ChatCommand::Trade(command) => resolve_trade_command(command, trade, bag),
+    }
+}
+
+/// Spawn the chat window in the bottom-left corner
+pub fn spawn_chat_window(
+    mut commands: Commands,
+    assets: Res<GameAssets>,
+    mut log: ResMut<ChatLog>,
+    mut input: ResMut<ChatInput>,
+) {
+    *log = ChatLog::default();
+    *input = ChatInput::default();
+    log.push_system("Press Enter to chat. /w <name> whispers, /p party, /gu guild, /g global, /invite <name> forms a party, /trade <name> trades.");
+
+    commands.spawn((
+        Node {
+            position_type: PositionType::Absolute,
+            left: Val::Px(20.0),
+            bottom: Val::Px(20.0),
+            width: Val::Px(480.0),
+            flex_direction: FlexDirection::Column,
+            padding: UiRect::all(Val::Px(8.0)),
+            ..default()
+        },
+        BackgroundColor(WINDOW_BG),
+        BorderRadius::all(Val::Px(6.0)),
+        HudUI,
+    ))
+    .with_children(|window| {
+        window.spawn((
+            Node {
+                flex_direction: FlexDirection::Column,
+                ..default()
+            },
+            ChatLinesUI,
+        ));
+
+        window.spawn((
+            Node {
+                margin: UiRect::top(Val::Px(6.0)),
+                padding: UiRect::axes(Val::Px(8.0), Val::Px(4.0)),
+                ..default()
+            },
+            BackgroundColor(INPUT_BG),
+            Visibility::Hidden,
+            ChatInputUI,
+        ))
+        .with_children(|field| {
+            field.spawn((
+                Text::new(""),
+                TextFont {
+                    font: assets.ui_font.clone(),
+                    font_size: 16.0,
+                    ..default()
+                },
+                TextColor(MAP_COLOR),
+                ChatInputText,
+            ));
+        });
+    });
+}
+
+/// Open, edit, send and close the input box
+pub fn chat_input(
+    mut events: EventReader<KeyboardInput>,
+    mut input: ResMut<ChatInput>,
+    mut log: ResMut<ChatLog>,
+    mut party: ResMut<PartyStatus>,
+    mut trade: ResMut<TradeStatus>,
+    player_query: Query<&Player, With<PlayerComponent>>,
+    conn: Option<Res<ServerConnection>>,
+) {
+    for event in events.read() {
+        if event.state != ButtonState::Pressed {
+            continue;
+        }
+
+        if !input.open {
+            if event.key_code == KeyCode::Enter {
+                input.open = true;
+            }
+            continue;
+        }
+
+        match &event.logical_key {
+            Key::Enter => {
+                let text = std::mem::take(&mut input.text);
+                input.open = false;
+                if text.trim().is_empty() {
+                    continue;
+                }
+                let Some(conn) = conn.as_deref().filter(|c| c.entity_id.is_some()) else {
+                    log.push_error("Chat needs a server connection");
+                    continue;
+                };
+                let empty = Inventory::new();
+                let bag = player_query.get_single().map(|p| &p.inventory).unwrap_or(&empty);
+                match parse_chat_input(&text).and_then(|command| resolve_command(command, &mut party, &mut trade, bag)) {
+                    Ok(Some(msg)) => conn.send(msg),
+                    Ok(None) => {}
+                    Err(reason) => log.push_error(reason),
+                }
+            }
+            Key::Escape => {
+                input.text.clear();
+                input.open = false;
+            }
+            Key::Backspace => {
+                input.text.pop();
+            }
+            Key::Space if input.text.chars().count() < MAX_INPUT_LEN => {
+                input.text.push(' ');
+            }
+            Key::Character(chars) => {
+                for c in chars.chars().filter(|c| !c.is_control()) {
+                    if input.text.chars().count() < MAX_INPUT_LEN {
+                        input.text.push(c);
+                    }
+                }
+            }
+            _ => {}
+        }
+    }
+}
+
+/// Redraw the window when the log or the input box changes
+pub fn update_chat_window(
+    mut commands: Commands,
+    assets: Res<GameAssets>,
+    log: Res<ChatLog>,
+    input: Res<ChatInput>,
+    lines_ui: Query<Entity, With<ChatLinesUI>>,
+    mut input_ui: Query<&mut Visibility, With<ChatInputUI>>,
+    mut input_text: Query<&mut Text, With<ChatInputText>>,
+) {
+    if log.is_changed()
+        && let Ok(container) = lines_ui.get_single()
+    {
+        let skip = log.lines.len().saturating_sub(VISIBLE_LINES);
+        commands.entity(container).despawn_descendants().with_children(|lines| {
+            for line in log.lines.iter().skip(skip) {
+                lines.spawn((
+                    Text::new(line.text.clone()),
+                    TextFont {
+                        font: assets.ui_font.clone(),
+                        font_size: 16.0,
+                        ..default()
+                    },
+                    TextColor(line.color),
+                ));
+            }
+        });
+    }
+
+    if input.is_changed() {
+        if let Ok(mut visibility) = input_ui.get_single_mut() {
+            *visibility = if input.open { Visibility::Inherited } else { Visibility::Hidden };
+        }
+        if let Ok(mut text) = input_text.get_single_mut() {
+            **text = format!("> {}_", input.text);
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn chat(channel: ChatChannel, to: Option<&str>, text: &str) -> ChatCommand {
+        ChatCommand::Send(ClientMessage::Chat { channel, to: to.map(str::to_string), text: text.to_string() })
+    }
+
+    #[test]
+    fn test_parse_chat_input() {
+        assert_eq!(parse_chat_input(" hello all "), Ok(chat(ChatChannel::Map, None, "hello all")));
+        assert_eq!(parse_chat_input("/w Bob  see you soon"), Ok(chat(ChatChannel::Whisper, Some("Bob"), "see you soon")));
+        assert_eq!(parse_chat_input("/P ready?"), Ok(chat(ChatChannel::Party, None, "ready?")));
+        assert_eq!(parse_chat_input("/global sale at the shop"), Ok(chat(ChatChannel::Global, None, "sale at the shop")));
+        assert_eq!(parse_chat_input("/gu raid at 9"), Ok(chat(ChatChannel::Guild, None, "raid at 9")));
+
+        assert!(parse_chat_input("/w Bob").is_err());
+        assert!(parse_chat_input("/g").is_err());
+        assert!(parse_chat_input("/dance").is_err());
+    }
+
+    #[test]
+    fn test_party_commands() {
+        assert_eq!(parse_chat_input("/invite Bob"), Ok(ChatCommand::Send(ClientMessage::PartyInvite { name: "Bob".to_string() })));
+        assert_eq!(parse_chat_input("/leave"), Ok(ChatCommand::Send(ClientMessage::PartyLeave)));
+        assert!(parse_chat_input("/kick").is_err());
+
+        let mut party = PartyStatus::default();
+        let resolve = |command, party: &mut PartyStatus| {
+            resolve_command(command, party, &mut TradeStatus::default(), &Inventory::new())
+        };
+        assert!(resolve(ChatCommand::Accept, &mut party).is_err());
+        party.invite = Some(("p1".to_string(), "Alice".to_string()));
+        assert_eq!(
+            resolve(parse_chat_input("/accept").unwrap(), &mut party),
+            Ok(Some(ClientMessage::PartyAccept { inviter_id: "p1".to_string() }))
+        );
+        assert!(party.invite.is_none());
+        assert!(resolve(ChatCommand::Kick("Bob".to_string()), &mut party).is_err());
+
+        assert_eq!(
+            resolve(parse_chat_input("/trade Bob").unwrap(), &mut party),
+            Ok(Some(ClientMessage::TradeRequest { name: "Bob".to_string() }))
+        );
+    }
+
+    #[test]
+    fn test_log_keeps_recent_lines() {
+        let mut log = ChatLog::default();
+        for i in 0..MAX_LINES + 5 {
+            log.push_system(format!("line {}", i));
+        }
+        assert_eq!(log.lines.len(), MAX_LINES);
+        assert_eq!(log.lines.front().unwrap().text, "line 5");
+
+        let whisper = ChatMessage {
+            channel: ChatChannel::Whisper,
+            from_id: "p1".to_string(),
+            from: "Alice".to_string(),
+            to: Some("Bob".to_string()),
+            text: "hi".to_string(),
+        };
+        log.push_message(&whisper, Some("p1"));
+        assert_eq!(log.lines.back().unwrap().text, "To Bob: hi");
+        log.push_message(&whisper, Some("p2"));
+        assert_eq!(log.lines.back().unwrap().text, "From Alice: hi");
+    }
+}
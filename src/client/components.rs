@@ -82,6 +82,30 @@ pub struct MainMenuUI;
 #[derive(Component)]
 pub struct CharacterSelectUI;
 
+/// Marker for login UI
+#[derive(Component)]
+pub struct LoginUI;
+
+/// Status/error line on menu screens
+#[derive(Component)]
+pub struct StatusText;
+
+/// Login username field
+#[derive(Component)]
+pub struct UsernameField;
+
+/// Login password field
+#[derive(Component)]
+pub struct PasswordField;
+
+/// New character name field
+#[derive(Component)]
+pub struct CharacterNameField;
+
+/// Label of the gender toggle button
+#[derive(Component)]
+pub struct GenderLabel;
+
 /// Marker for HUD elements
 #[derive(Component)]
 pub struct HudUI;
@@ -94,6 +118,14 @@ pub enum ButtonAction {
     ConfirmCharacter,
     BackToMenu,
     Quit,
+    Login,
+    Register,
+    PlayOffline,
+    /// Roster entry by index
+    SelectCharacter(usize),
+    ToggleGender,
+    CreateCharacter,
+    DeleteCharacter,
 }
 
 /// HP Bar UI
@@ -179,7 +211,14 @@ pub struct Interactable {
 
 #[derive(Debug, Clone)]
 pub enum InteractionType {
+    #[allow(dead_code)]
     NpcChat(String),
+    /// Open the shop window for the NPC with this id
+    Shop(String),
+    /// Open the inn window for the NPC with this id
+    Inn(String),
+    /// Open the blacksmith window for the NPC with this id
+    Blacksmith(String),
     #[allow(dead_code)]
     Portal { target_map: String, target_pos: GridPosition },
     #[allow(dead_code)]
@@ -6,6 +6,7 @@ use crate::shared::domain::character::models::Player;
 use crate::shared::domain::monster::Monster;
 use crate::shared::domain::skill::models::Skill;
 use crate::shared::domain::shared::models::Position;
+use crate::shared::combat;
 use crate::shared::constants::*;
 
 
@@ -34,35 +35,19 @@ pub fn spawn_game_world(
     _asset_server: Res<AssetServer>,
     assets: Res<GameAssets>,
     monster_defs: Res<MonsterDefinitions>,
+    selected_class: Res<SelectedClass>,
+    session: Option<Res<Session>>,
     manifests: Res<Assets<crate::shared::domain::sprite::SpriteManifest>>,
 ) {
     // =============================================
     // Spawn Tiles using tile atlas or fallback colors
     // =============================================
     
-    // Simple 16x16 grid layout (planned: load from map data)
-    const MAP_SIZE: usize = 16;
-    let map_layout: [[char; MAP_SIZE]; MAP_SIZE] = [
-        ['G','G','G','G','G','G','S','S','S','S','G','G','G','G','G','G'],
-        ['G','G','B','W','G','S','S','S','S','S','G','G','B','W','W','G'],
-        ['G','G','D','W','G','S','S','S','S','S','G','W','D','D','W','G'],
-        ['G','G','G','G','G','G','S','S','S','S','G','G','G','G','G','G'],
-        ['S','S','S','S','S','S','S','S','S','S','S','S','S','S','S','S'],
-        ['S','S','S','S','S','F','F','F','S','S','S','S','S','S','S','S'],
-        ['S','S','S','S','S','F','F','F','S','S','S','S','S','S','S','S'],
-        ['S','S','S','S','S','F','F','F','S','S','S','S','S','S','S','S'],
-        ['S','S','S','S','S','S','S','S','S','S','S','S','S','S','S','S'],
-        ['G','G','G','G','G','G','S','S','S','S','G','G','G','G','G','G'],
-        ['G','B','W','G','G','S','S','S','S','S','G','G','B','W','W','G'],
-        ['G','B','D','D','G','S','S','S','S','S','G','W','W','D','D','G'],
-        ['G','G','G','G','G','G','S','S','S','S','G','G','G','G','G','G'],
-        ['G','G','G','G','G','G','S','S','S','S','G','G','G','G','G','G'],
-        ['T','T','T','T','G','G','S','S','S','S','G','G','T','T','T','T'],
-        ['T','T','T','T','G','G','S','S','S','S','G','G','T','T','T','T'],
-    ];
+    // Milles Village layout shared with the server simulation
+    let map_layout = crate::shared::data::maps::MILLES_VILLAGE_LAYOUT;
     
     for (y, row) in map_layout.iter().enumerate() {
-        for (x, &char) in row.iter().enumerate() {
+        for (x, char) in row.chars().enumerate() {
             let tile_type = match char {
                 'G' => RenderTileType::Grass,
                 'S' => RenderTileType::Stone,
@@ -121,88 +106,113 @@ pub fn spawn_game_world(
         }
     }
 
-    let spawn_pos = project_iso(8.0, 8.0);
-
-    // =============================================  
-    // Spawn Player with sprite or fallback color
-    // =============================================
     // =============================================  
     // Spawn Player with sprite or fallback color
     // =============================================
-    let manifest_id = "warrior_male";
-    let player_sprite = if let Some(sprite_handle) = assets.get_character_sprite("warrior", "male") {
-        let mut s = Sprite {
-            image: sprite_handle,
-            ..default()
-        };
-        
-        // Pre-set initial rect if manifest is available to avoid "box flashing"
-        if let Some(manifest_handle) = assets.manifests.get(manifest_id) {
-            if let Some(manifest) = manifests.get(manifest_handle) {
-                let (index, flip) = manifest.get_frame_index(crate::shared::domain::sprite::AnimationState::Idle, crate::shared::domain::sprite::SpriteDirection::Down, 0);
-                let (rx, ry, rw, rh) = manifest.layout.get_frame_rect(index);
-                s.rect = Some(Rect::new(rx as f32, ry as f32, (rx + rw) as f32, (ry + rh) as f32));
-                s.custom_size = Some(Vec2::new(rw as f32, rh as f32));
-                s.flip_x = flip;
-                s.anchor = bevy::sprite::Anchor::BottomCenter;
-            }
-        }
-        s
-    } else {
-        Sprite {
-            color: PLAYER_COLOR,
-            custom_size: Some(Vec2::new(CHARACTER_RENDER_WIDTH, CHARACTER_RENDER_HEIGHT)),
-            ..default()
+    let class = selected_class.class.unwrap_or(PlayerClass::Warrior);
+    let gender = if selected_class.gender.is_empty() { "male" } else { selected_class.gender.as_str() };
+    let (player_sprite, player_manifest) = character_sprite(&assets, &manifests, class, gender);
+    
+    // Character loaded from the server, or a fresh one when offline
+    let player = match session.as_ref().and_then(|s| s.player.clone()) {
+        Some(player) => player,
+        None => {
+            let mut player = Player::new(selected_class.username.clone(), class);
+            player.gender = gender.to_string();
+            player
         }
     };
+
+    // Resume where the character was saved if that tile is walkable here
+    let saved = (player.position.x as i32, player.position.y as i32);
+    let (start_x, start_y) = match crate::shared::data::maps::layout_tile(map_layout, saved.0, saved.1) {
+        Some(tile) if tile.is_walkable() => saved,
+        _ => crate::shared::data::maps::MILLES_VILLAGE_START,
+    };
+    let spawn_pos = project_iso(start_x as f32, start_y as f32);
     
+    let label = super::nameplate::NameplateLabel::new(player.username.clone(), None);
     commands.spawn((
         player_sprite,
         Transform::from_xyz(spawn_pos.x, spawn_pos.y, 10.0),
         PlayerComponent,
-        Player::new("Player".to_string(), PlayerClass::Warrior),
+        player,
         Facing::default(),
-        GridPosition { x: 8, y: 8 },
-        TargetGridPosition { x: 8, y: 8 },
+        GridPosition { x: start_x, y: start_y },
+        TargetGridPosition { x: start_x, y: start_y },
         MovementProgress {
             timer: Timer::from_seconds(0.2, TimerMode::Once),
-            start_pos: Vec2::new(8.0, 8.0),
+            start_pos: Vec2::new(start_x as f32, start_y as f32),
         },
         super::animation::SpriteAnimator {
-            manifest: assets.manifests.get(manifest_id).cloned(),
+            manifest: player_manifest,
             ..default()
         },
         CombatState::default(),
         ActiveSkills::default(),
         CameraTarget,
+        label,
     ));
     
     // =============================================
     // Spawn Monsters with sprites
     // =============================================
-    let monster_positions = vec![
-        (3, 3, "Giant Rat"),
-        (3, 13, "Vampire Bat"),
-        (13, 3, "Slime"), 
-        (13, 13, "Slime"),
-    ];
-    
-    for (x, y, name) in monster_positions {
-        spawn_monster(&mut commands, x, y, name, &monster_defs, &assets, &manifests);
+    // Offline spawns; replaced by server entities once connected
+    for spawn in crate::shared::data::maps::MILLES_VILLAGE_SPAWNS {
+        let Some(def) = crate::shared::data::monsters::get_monster_by_id(spawn.monster_id) else { continue; };
+        spawn_monster(&mut commands, spawn.x, spawn.y, def.name, &monster_defs, &assets, &manifests);
     }
     
     // =============================================
     // Spawn NPCs
     // =============================================
-    spawn_npc(&mut commands, 2, 2, "Innkeeper", InteractionType::NpcChat("Welcome! Rest here to recover.".to_string()));
-    spawn_npc(&mut commands, 12, 11, "Shopkeeper", InteractionType::NpcChat("Buy and sell items.".to_string()));
+    spawn_npc(&mut commands, 2, 2, "Innkeeper", InteractionType::Inn("innkeeper".to_string()));
+    spawn_npc(&mut commands, 12, 11, "Shopkeeper", InteractionType::Shop("shopkeeper".to_string()));
+    spawn_npc(&mut commands, 13, 2, "Blacksmith", InteractionType::Blacksmith("blacksmith".to_string()));
 
     // =============================================
     // Spawn HUD
     // =============================================
     spawn_hud(&mut commands, assets.ui_font.clone());
     
-    println!("🗺️ Game world spawned with {} tiles", MAP_SIZE * MAP_SIZE);
+    println!("🗺️ Game world spawned with {} tiles", map_layout.iter().map(|row| row.len()).sum::<usize>());
+}
+
+/// Build a character sprite for a class/gender, pre-set to its first idle frame
+pub(crate) fn character_sprite(
+    assets: &GameAssets,
+    manifests: &Assets<crate::shared::domain::sprite::SpriteManifest>,
+    class: PlayerClass,
+    gender: &str,
+) -> (Sprite, Option<Handle<crate::shared::domain::sprite::SpriteManifest>>) {
+    let manifest_id = format!("{}_{}", class.sprite_name(), gender);
+    let manifest_handle = assets.manifests.get(&manifest_id).cloned();
+    
+    let sprite = if let Some(sprite_handle) = assets.get_character_sprite(class.sprite_name(), gender) {
+        let mut s = Sprite {
+            image: sprite_handle,
+            ..default()
+        };
+        
+        // Pre-set initial rect if manifest is available to avoid "box flashing"
+        if let Some(manifest) = manifest_handle.as_ref().and_then(|h| manifests.get(h)) {
+            let (index, flip) = manifest.get_frame_index(crate::shared::domain::sprite::AnimationState::Idle, crate::shared::domain::sprite::SpriteDirection::Down, 0);
+            let (rx, ry, rw, rh) = manifest.layout.get_frame_rect(index);
+            s.rect = Some(Rect::new(rx as f32, ry as f32, (rx + rw) as f32, (ry + rh) as f32));
+            s.custom_size = Some(Vec2::new(rw as f32, rh as f32));
+            s.flip_x = flip;
+            s.anchor = bevy::sprite::Anchor::BottomCenter;
+        }
+        s
+    } else {
+        Sprite {
+            color: PLAYER_COLOR,
+            custom_size: Some(Vec2::new(CHARACTER_RENDER_WIDTH, CHARACTER_RENDER_HEIGHT)),
+            ..default()
+        }
+    };
+    
+    (sprite, manifest_handle)
 }
 
 fn spawn_npc(commands: &mut Commands, x: i32, y: i32, name: &str, interaction: InteractionType) {
@@ -224,7 +234,7 @@ fn spawn_npc(commands: &mut Commands, x: i32, y: i32, name: &str, interaction: I
     ));
 }
 
-fn spawn_monster(
+pub(crate) fn spawn_monster(
     commands: &mut Commands, 
     grid_x: i32, 
     grid_y: i32, 
@@ -232,11 +242,11 @@ fn spawn_monster(
     monster_defs: &MonsterDefinitions,
     assets: &GameAssets,
     manifests: &Assets<crate::shared::domain::sprite::SpriteManifest>,
-) {
+) -> Option<Entity> {
     // Look up monster definition
     let Some(data) = monster_defs.definitions.get(name_key) else {
         println!("❌ Failed to spawn monster: {} (Not found in data module)", name_key);
-        return;
+        return None;
     };
     
     let monster = Monster::new(data, Position::new(grid_x as f64, grid_y as f64));
@@ -258,15 +268,15 @@ fn spawn_monster(
         };
         
         // Pre-set initial rect if manifest is available
-        if let Some(manifest_handle) = assets.manifests.get(&data.sprite_type) {
-            if let Some(manifest) = manifests.get(manifest_handle) {
-                let (index, flip) = manifest.get_frame_index(crate::shared::domain::sprite::AnimationState::Idle, crate::shared::domain::sprite::SpriteDirection::Down, 0);
-                let (rx, ry, rw, rh) = manifest.layout.get_frame_rect(index);
-                s.rect = Some(Rect::new(rx as f32, ry as f32, (rx + rw) as f32, (ry + rh) as f32));
-                s.custom_size = Some(Vec2::new(rw as f32, rh as f32));
-                s.flip_x = flip;
-                s.anchor = bevy::sprite::Anchor::BottomCenter;
-            }
+        if let Some(manifest_handle) = assets.manifests.get(&data.sprite_type)
+            && let Some(manifest) = manifests.get(manifest_handle)
+        {
+            let (index, flip) = manifest.get_frame_index(crate::shared::domain::sprite::AnimationState::Idle, crate::shared::domain::sprite::SpriteDirection::Down, 0);
+            let (rx, ry, rw, rh) = manifest.layout.get_frame_rect(index);
+            s.rect = Some(Rect::new(rx as f32, ry as f32, (rx + rw) as f32, (ry + rh) as f32));
+            s.custom_size = Some(Vec2::new(rw as f32, rh as f32));
+            s.flip_x = flip;
+            s.anchor = bevy::sprite::Anchor::BottomCenter;
         }
         s
     } else {
@@ -279,7 +289,7 @@ fn spawn_monster(
             "skeleton" => Color::srgb(0.8, 0.8, 0.75), // Bone white
             "goblin" => Color::srgb(0.3, 0.5, 0.2),   // Green
             "ghost" => Color::srgba(0.7, 0.7, 0.9, 0.6), // Translucent blue
-            "dragon" => Color::srgb(0.8, 0.2, 0.1),   // Red
+            "shadow_dragon" => Color::srgb(0.8, 0.2, 0.1), // Red
             _ => MONSTER_COLOR,
         };
         Sprite {
@@ -289,7 +299,7 @@ fn spawn_monster(
         }
     };
 
-    commands.spawn((
+    let entity = commands.spawn((
         sprite,
         Transform::from_xyz(iso_pos.x, iso_pos.y, Z_LAYER_ENTITY_BASE + iso_pos.y.abs() / 1000.0),
         MonsterComponent,
@@ -313,9 +323,10 @@ fn spawn_monster(
             manifest: assets.manifests.get(&data.sprite_type).cloned(),
             ..default()
         },
-    ));
+    )).id();
     
     println!("👾 Spawned {} at ({}, {})", name_key, grid_x, grid_y);
+    Some(entity)
 }
 
 fn spawn_hud(commands: &mut Commands, font: Handle<Font>) {
@@ -390,7 +401,7 @@ fn spawn_hud(commands: &mut Commands, font: Handle<Font>) {
         parent.spawn((
             Text::new("💰 100"),
             TextFont {
-                font: font,
+                font,
                 font_size: 18.0,
                 ..default()
             },
@@ -430,8 +441,15 @@ pub fn player_movement(
             }
 
             if move_dir != IVec2::ZERO {
-                target_pos.x = grid_pos.x + move_dir.x;
-                target_pos.y = grid_pos.y + move_dir.y;
+                let (nx, ny) = (grid_pos.x + move_dir.x, grid_pos.y + move_dir.y);
+                let layout = crate::shared::data::maps::MILLES_VILLAGE_LAYOUT;
+                let walkable = crate::shared::data::maps::layout_tile(layout, nx, ny)
+                    .is_some_and(|tile| tile.is_walkable());
+                
+                if walkable {
+                    target_pos.x = nx;
+                    target_pos.y = ny;
+                }
             }
         }
     }
@@ -513,17 +531,17 @@ pub fn camera_follow(
     player_query: Query<&Transform, (With<CameraTarget>, Without<Camera2d>)>,
     mut camera_query: Query<&mut Transform, With<Camera2d>>,
 ) {
-    if let Ok(player_transform) = player_query.get_single() {
-        if let Ok(mut camera_transform) = camera_query.get_single_mut() {
-            // Smooth camera follow
-            let target = player_transform.translation;
-            let current = camera_transform.translation;
-            
-            camera_transform.translation = current.lerp(
-                Vec3::new(target.x, target.y, current.z),
-                0.1,
-            );
-        }
+    if let Ok(player_transform) = player_query.get_single()
+        && let Ok(mut camera_transform) = camera_query.get_single_mut()
+    {
+        // Smooth camera follow
+        let target = player_transform.translation;
+        let current = camera_transform.translation;
+        
+        camera_transform.translation = current.lerp(
+            Vec3::new(target.x, target.y, current.z),
+            0.1,
+        );
     }
 }
 
@@ -551,32 +569,29 @@ pub fn monster_ai(
             let detection_grid_range = ai.detection_range as f64 / GRID_UNIT as f64;
             let attack_grid_range = 1.1; // Close range
             
-            match ai.ai_type {
-                MonsterAIType::Aggressive => {
-                    if distance < detection_grid_range as f32 && distance > attack_grid_range {
-                        // Move towards player (simple pathfinding)
-                        let move_x = if dx.abs() > dy.abs() { dx.signum() } else { 0 };
-                        let move_y = if dx.abs() <= dy.abs() { dy.signum() } else { 0 };
-                        
-                        target_pos.x = grid_pos.x + move_x;
-                        target_pos.y = grid_pos.y + move_y;
-
-                        // Set facing
-                        if move_x > 0 { facing.direction = Direction::Right; }
-                        else if move_x < 0 { facing.direction = Direction::Left; }
-                        else if move_y > 0 { facing.direction = Direction::Down; }
-                        else if move_y < 0 { facing.direction = Direction::Up; }
-                    } else if distance > ai.detection_range {
-                        // Return to spawn or wander (simple return)
-                        let sx = ai.spawn_position.x as i32 - grid_pos.x;
-                        let sy = ai.spawn_position.y as i32 - grid_pos.y;
-                        if sx != 0 || sy != 0 {
-                            target_pos.x = grid_pos.x + sx.signum();
-                            target_pos.y = grid_pos.y + sy.signum();
-                        }
+            if ai.ai_type == MonsterAIType::Aggressive {
+                if distance < detection_grid_range as f32 && distance > attack_grid_range {
+                    // Move towards player (simple pathfinding)
+                    let move_x = if dx.abs() > dy.abs() { dx.signum() } else { 0 };
+                    let move_y = if dx.abs() <= dy.abs() { dy.signum() } else { 0 };
+                    
+                    target_pos.x = grid_pos.x + move_x;
+                    target_pos.y = grid_pos.y + move_y;
+
+                    // Set facing
+                    if move_x > 0 { facing.direction = Direction::Right; }
+                    else if move_x < 0 { facing.direction = Direction::Left; }
+                    else if move_y > 0 { facing.direction = Direction::Down; }
+                    else if move_y < 0 { facing.direction = Direction::Up; }
+                } else if distance > ai.detection_range {
+                    // Return to spawn or wander (simple return)
+                    let sx = ai.spawn_position.x as i32 - grid_pos.x;
+                    let sy = ai.spawn_position.y as i32 - grid_pos.y;
+                    if sx != 0 || sy != 0 {
+                        target_pos.x = grid_pos.x + sx.signum();
+                        target_pos.y = grid_pos.y + sy.signum();
                     }
                 }
-                _ => {}
             }
         }
     }
@@ -589,20 +604,22 @@ pub fn interaction_system(
     player_query: Query<(&GridPosition, &Facing), With<PlayerComponent>>,
     interactable_query: Query<(&GridPosition, &Interactable)>,
     tile_query: Query<(&GridPosition, &TileComponent)>,
+    mut chat_log: ResMut<super::chat::ChatLog>,
+    mut shop: ResMut<super::shop::ShopStatus>,
+    mut inn: ResMut<super::inn::InnStatus>,
+    mut blacksmith: ResMut<super::blacksmith::BlacksmithStatus>,
+    session: Option<Res<Session>>,
+    config: Res<super::net::NetworkConfig>,
 ) {
-    if !keyboard_input.just_pressed(KeyCode::KeyE) && !keyboard_input.just_pressed(KeyCode::Enter) {
+    // Enter belongs to the chat box
+    if !keyboard_input.just_pressed(KeyCode::KeyE) {
         return;
     }
 
     let Ok((player_pos, facing)) = player_query.get_single() else { return; };
     
     // Check tile in front of player
-    let (dx, dy) = match facing.direction {
-        Direction::Up => (0, -1),
-        Direction::Down => (0, 1),
-        Direction::Left => (-1, 0),
-        Direction::Right => (1, 0),
-    };
+    let (dx, dy) = facing.direction.offset();
     
     let target_x = player_pos.x + dx;
     let target_y = player_pos.y + dy;
@@ -612,8 +629,35 @@ pub fn interaction_system(
         if npc_pos.x == target_x && npc_pos.y == target_y {
             match &interactable.interaction_type {
                 InteractionType::NpcChat(msg) => {
-                    println!("💬 {}: \"{}\"", interactable.message, msg);
+                    chat_log.push_system(format!("{}: \"{}\"", interactable.message, msg));
                 }
+                InteractionType::Shop(npc_id) => match &session {
+                    Some(session) => shop.open(&config, session, super::shop::ShopNpc {
+                        id: npc_id.clone(),
+                        name: interactable.message.clone(),
+                        x: npc_pos.x,
+                        y: npc_pos.y,
+                    }),
+                    None => chat_log.push_system(format!("{}: \"The shop is closed while you play offline.\"", interactable.message)),
+                },
+                InteractionType::Inn(npc_id) => match &session {
+                    Some(session) => inn.open(&config, session, super::inn::InnNpc {
+                        id: npc_id.clone(),
+                        name: interactable.message.clone(),
+                        x: npc_pos.x,
+                        y: npc_pos.y,
+                    }),
+                    None => chat_log.push_system(format!("{}: \"Welcome! Rest here to recover.\"", interactable.message)),
+                },
+                InteractionType::Blacksmith(npc_id) => match &session {
+                    Some(session) => blacksmith.open(&config, session, super::blacksmith::BlacksmithNpc {
+                        id: npc_id.clone(),
+                        name: interactable.message.clone(),
+                        x: npc_pos.x,
+                        y: npc_pos.y,
+                    }),
+                    None => chat_log.push_system(format!("{}: \"The forge is cold while you play offline.\"", interactable.message)),
+                },
                 _ => {}
             }
             return;
@@ -622,15 +666,25 @@ pub fn interaction_system(
 
     // 2. Check for Doors/Portals at current or target position
     for (tile_pos, tile) in &tile_query {
-        if tile_pos.x == target_x && tile_pos.y == target_y {
-            if tile.tile_type == RenderTileType::Door {
-                println!("🚪 건물 안으로 들어갑니다... (맵 전환 보류)");
-                return;
-            }
+        if tile_pos.x == target_x && tile_pos.y == target_y
+            && tile.tile_type == RenderTileType::Door
+        {
+            println!("🚪 건물 안으로 들어갑니다... (맵 전환 보류)");
+            return;
         }
     }
 }
 
+/// Hotkeys for the skill bar, in slot order
+pub(crate) const SKILL_KEYS: [KeyCode; 5] = [KeyCode::Digit1, KeyCode::Digit2, KeyCode::Digit3, KeyCode::Digit4, KeyCode::Digit5];
+
+/// Skill in each hotbar slot, in key order
+pub(crate) fn hotbar_skills<'a>(skill_data: &'a SkillData, player: &Player) -> Vec<Option<&'a Skill>> {
+    player.skills.bar.iter()
+        .map(|slot| slot.and_then(|id| skill_data.skills.iter().find(|s| s.id == id)))
+        .collect()
+}
+
 pub fn skill_system(
     time: Res<Time>,
     keyboard_input: Res<ButtonInput<KeyCode>>,
@@ -646,98 +700,100 @@ pub fn skill_system(
         skill_cd.timer.tick(time.delta());
     }
 
-    // Get skills for player's class
-    let player_class_id = player.class.id();
-    let available_skills: Vec<&Skill> = skill_data.skills.iter()
-        .filter(|s| (s.class_id == Some(player_class_id) || s.class_id.is_none()) && s.req_level <= player.level)
-        .collect();
-
-    let keys = [KeyCode::Digit1, KeyCode::Digit2, KeyCode::Digit3, KeyCode::Digit4, KeyCode::Digit5];
+    let hotbar = hotbar_skills(&skill_data, &player);
     
-    for (i, key) in keys.iter().enumerate() {
-        if keyboard_input.just_pressed(*key) {
-            if let Some(skill) = available_skills.get(i) {
-                let skill_name = &skill.name;
-                
-                // Check cooldown
-                if let Some(skill_cd) = active_skills.skills.iter().find(|s| s.skill_id == skill.id) {
-                    if !skill_cd.timer.finished() {
-                        println!("⏳ {} is on cooldown", skill_name);
-                        continue;
-                    }
-                }
+    for (i, key) in SKILL_KEYS.iter().enumerate() {
+        if keyboard_input.just_pressed(*key)
+            && let Some(Some(skill)) = hotbar.get(i)
+        {
+            let skill_name = &skill.name;
+            
+            // Check cooldown
+            if let Some(skill_cd) = active_skills.skills.iter().find(|s| s.skill_id == skill.id)
+                && !skill_cd.timer.finished()
+            {
+                println!("⏳ {} is on cooldown", skill_name);
+                continue;
+            }
 
-                // Check MP
-                if player.combat_stats.mp < skill.mp_cost {
-                    println!("❌ Not enough MP for {}", skill_name);
-                    continue;
-                }
+            // Check MP
+            if player.combat_stats.mp < skill.mp_cost {
+                println!("❌ Not enough MP for {}", skill_name);
+                continue;
+            }
 
-                // Execute skill
-                println!("🔥 Skill activated: {}", skill_name);
-                player.combat_stats.mp -= skill.mp_cost;
-
-                // Set cooldown
-                if let Some(skill_cd) = active_skills.skills.iter_mut().find(|s| s.skill_id == skill.id) {
-                    skill_cd.timer.set_duration(std::time::Duration::from_millis(skill.cooldown_ms as u64));
-                    skill_cd.timer.reset();
-                } else {
-                    active_skills.skills.push(SkillCooldown {
-                        skill_id: skill.id,
-                        timer: Timer::new(std::time::Duration::from_millis(skill.cooldown_ms as u64), TimerMode::Once),
-                    });
-                }
+            // Execute skill
+            println!("🔥 Skill activated: {}", skill_name);
+            player.combat_stats.mp -= skill.mp_cost;
 
-                // Skill Effect handling
-                let (dx, dy) = match facing.direction {
-                    Direction::Up => (0, -1),
-                    Direction::Down => (0, 1),
-                    Direction::Left => (-1, 0),
-                    Direction::Right => (1, 0),
-                };
+            // Set cooldown
+            if let Some(skill_cd) = active_skills.skills.iter_mut().find(|s| s.skill_id == skill.id) {
+                skill_cd.timer.set_duration(std::time::Duration::from_millis(skill.cooldown_ms as u64));
+                skill_cd.timer.reset();
+            } else {
+                active_skills.skills.push(SkillCooldown {
+                    skill_id: skill.id,
+                    timer: Timer::new(std::time::Duration::from_millis(skill.cooldown_ms as u64), TimerMode::Once),
+                });
+            }
 
-                let tx = player_pos.x + dx;
-                let ty = player_pos.y + dy;
-
-                match skill.effect_type.as_deref() {
-                    Some("damage") => {
-                        for (entity, m_pos, mut monster) in &mut monster_query {
-                            if m_pos.x == tx && m_pos.y == ty {
-                                let damage = skill.base_value + (player.combat_stats.attack_max / 2);
-                                monster.take_damage(damage);
-                                println!("💥 {} took {} damage from {}!", monster.name, damage, skill_name);
-                                
-                                if monster.is_dead() { 
-                                    // Handle Loot and Rewards
-                                    let (gold_reward, _item_rewards) = monster.calculate_loot();
-                                    player.gold += gold_reward as i64;
-                                    player.exp += monster.exp_reward as i64;
-                                    
-                                    println!("💰 +{} Gold! (Total: {})", gold_reward, player.gold);
-                                    println!("📈 +{} EXP! (Total: {})", monster.exp_reward, player.exp);
-                                    
-                                    // Level Up Check
-                                    if player.exp >= player.exp_to_next_level {
-                                        player.level += 1;
-                                        player.exp -= player.exp_to_next_level;
-                                        player.exp_to_next_level += 100; // Simplified scale
-                                        println!("🎉 레벨 업! 현재 레벨: {}", player.level);
+            // Skill Effect handling
+            let (dx, dy) = facing.direction.offset();
+
+            let tx = player_pos.x + dx;
+            let ty = player_pos.y + dy;
+
+            match skill.effect_type.as_deref() {
+                Some("damage") => {
+                    for (entity, m_pos, mut monster) in &mut monster_query {
+                        if m_pos.x == tx && m_pos.y == ty {
+                            let def = crate::shared::data::skills::get_skill_by_id(skill.id);
+                            let kind = def.map(combat::skill_attack_kind).unwrap_or(combat::AttackKind::Physical);
+                            let result = combat::resolve_attack(
+                                &mut rand::thread_rng(),
+                                &combat::Combatant::from(&player.combat_stats),
+                                &combat::Combatant::from(&*monster),
+                                kind,
+                                Some(skill.base_value),
+                            );
+                            monster.take_damage(result.damage);
+                            match result.outcome {
+                                combat::HitOutcome::Miss => println!("💨 {} missed {}!", skill_name, monster.name),
+                                combat::HitOutcome::Critical => println!("💥 Critical! {} took {} damage from {}!", monster.name, result.damage, skill_name),
+                                combat::HitOutcome::Hit => println!("💥 {} took {} damage from {}!", monster.name, result.damage, skill_name),
+                            }
+                            
+                            if monster.is_dead() { 
+                                // Offline play rolls rewards locally; online kills are rewarded by the server
+                                if let Some(def) = crate::shared::data::monsters::get_monster_by_id(monster.monster_id) {
+                                    let rewards = crate::shared::data::monsters::roll_kill_rewards(&mut rand::thread_rng(), def, player.level);
+                                    let level_before = player.level;
+                                    player.add_exp(rewards.exp);
+                                    player.gold += rewards.gold;
+                                    for item in &rewards.items {
+                                        player.inventory.add_item(item.item_id, item.quantity);
                                     }
 
-                                    commands.entity(entity).despawn(); 
+                                    println!("💰 +{} Gold! (Total: {})", rewards.gold, player.gold);
+                                    println!("📈 +{} EXP! (Total: {})", rewards.exp, player.exp);
+                                    if player.level > level_before {
+                                        println!("🎉 레벨 업! 현재 레벨: {}", player.level);
+                                    }
                                 }
-                                break;
+
+                                commands.entity(entity).despawn(); 
                             }
+                            break;
                         }
                     }
-                    Some("heal") => {
-                        let heal_amt = skill.base_value;
-                        player.heal(heal_amt);
-                        println!("✨ {} 회복! (+{})", skill.name, heal_amt);
-                    }
-                    _ => {
-                        println!("ℹ️ {} 스킬이 사용되었습니다. (효과 미구현)", skill.name);
-                    }
+                }
+                Some("heal") => {
+                    let heal_amt = skill.base_value;
+                    player.heal(heal_amt);
+                    println!("✨ {} 회복! (+{})", skill.name, heal_amt);
+                }
+                _ => {
+                    println!("ℹ️ {} 스킬이 사용되었습니다. (효과 미구현)", skill.name);
                 }
             }
         }
@@ -751,9 +807,28 @@ pub fn cleanup_game_world(
     tiles: Query<Entity, With<TileComponent>>,
     players: Query<Entity, With<PlayerComponent>>,
     monsters: Query<Entity, With<MonsterComponent>>,
+    remote: Query<Entity, (With<super::net::NetworkEntity>, Without<MonsterComponent>)>,
     hud: Query<Entity, With<super::components::HudUI>>,
+    player_state: Query<(&Player, &GridPosition), With<PlayerComponent>>,
+    session: Option<ResMut<Session>>,
+    config: Res<super::net::NetworkConfig>,
 ) {
-    for entity in tiles.iter().chain(players.iter()).chain(monsters.iter()).chain(hud.iter()) {
+    // Keep the latest state for the next session and ask the server to save it.
+    // The server also saves when the socket closes; this covers a lingering connection.
+    if let Some(mut session) = session
+        && let Ok((player, grid)) = player_state.get_single()
+    {
+        let mut player = player.clone();
+        player.position = Position::new(grid.x as f64, grid.y as f64);
+        session.player = Some(player);
+        super::api::post::<_, crate::shared::api::SaveCharacterResponse>(&config, "/characters/save", Some(&session.token), &())
+            .detach();
+    }
+
+    for entity in tiles.iter().chain(players.iter()).chain(monsters.iter()).chain(remote.iter()).chain(hud.iter()) {
         commands.entity(entity).despawn_recursive();
     }
+    
+    // Dropping the connection closes the socket
+    commands.remove_resource::<super::net::ServerConnection>();
 }
@@ -0,0 +1,269 @@
+//! Inn - the window opened by talking to an innkeeper
+//!
+//! Pressing E at an innkeeper asks the server for the price of a rest and
+//! where we are bound. Rest restores HP and MP for that price; Bind makes
+//! the inn's town the place we come back to after dying. Our new HP and MP
+//! arrive with the next snapshot; gold comes back in the answer. Escape,
+//! the Close button or walking away closes the window.
+
+use bevy::prelude::*;
+
+use super::api::{self, ApiTask};
+use super::chat::ChatLog;
+use super::components::{GridPosition, HudUI, PlayerComponent};
+use super::net::NetworkConfig;
+use super::resources::{GameAssets, Session};
+use crate::shared::api::InnResponse;
+use crate::shared::data::maps::{get_map_by_id, NPC_REACH};
+use crate::shared::domain::Player;
+
+const WINDOW_BG: Color = Color::srgba(0.0, 0.0, 0.0, 0.6);
+const TITLE_COLOR: Color = Color::srgb(1.0, 0.85, 0.4);
+const TEXT_COLOR: Color = Color::srgb(0.95, 0.95, 0.95);
+const HINT_COLOR: Color = Color::srgb(0.6, 0.6, 0.65);
+const BUTTON_NORMAL: Color = Color::srgb(0.25, 0.25, 0.3);
+const BUTTON_HOVER: Color = Color::srgb(0.35, 0.35, 0.42);
+
+/// The innkeeper being visited
+#[derive(Debug, Clone)]
+pub struct InnNpc {
+    pub id: String,
+    pub name: String,
+    pub x: i32,
+    pub y: i32,
+}
+
+/// The open inn and the request in flight, if any
+#[derive(Resource, Default)]
+pub struct InnStatus {
+    pub npc: Option<InnNpc>,
+    /// Price, gold and bind point as the server last reported them
+    pub inn: Option<InnResponse>,
+    request: Option<ApiTask<InnResponse>>,
+}
+
+impl InnStatus {
+    /// Ask the server about an inn; the window opens with the answer
+    pub fn open(&mut self, config: &NetworkConfig, session: &Session, npc: InnNpc) {
+        self.request = Some(api::get(config, &format!("/inns/{}", npc.id), Some(&session.token)));
+        self.npc = Some(npc);
+        self.inn = None;
+    }
+
+    pub fn close(&mut self) {
+        *self = Self::default();
+    }
+}
+
+/// Where an inn answer says we are bound, for the window
+pub fn bind_label(inn: &InnResponse) -> String {
+    if inn.bound_here {
+        return "You are bound here".to_string();
+    }
+    match &inn.bind_point {
+        Some(bind) => {
+            let town = get_map_by_id(&bind.map_id).map(|m| m.name).unwrap_or("an unknown place");
+            format!("You are bound to {}", town)
+        }
+        None => "You are not bound anywhere".to_string(),
+    }
+}
+
+/// An inn window button
+#[derive(Component, Debug, Clone, Copy)]
+pub enum InnButton {
+    Rest,
+    Bind,
+    Close,
+}
+
+/// Container of the inn window (hidden while no inn is open)
+#[derive(Component)]
+pub struct InnWindowUI;
+
+pub fn spawn_inn_window(mut commands: Commands, mut status: ResMut<InnStatus>) {
+    status.close();
+    commands.spawn((
+        Node {
+            position_type: PositionType::Absolute,
+            // Same spot as the shop window; only one NPC is visited at a time
+            left: Val::Percent(50.0),
+            top: Val::Px(120.0),
+            margin: UiRect::left(Val::Px(-150.0)),
+            width: Val::Px(300.0),
+            flex_direction: FlexDirection::Column,
+            row_gap: Val::Px(4.0),
+            padding: UiRect::all(Val::Px(8.0)),
+            ..default()
+        },
+        BackgroundColor(WINDOW_BG),
+        BorderRadius::all(Val::Px(6.0)),
+        Visibility::Hidden,
+        HudUI,
+        InnWindowUI,
+    ));
+}
+
+/// Send button presses to the server, one request at a time
+pub fn inn_buttons(
+    mut buttons: Query<(&Interaction, &InnButton, &mut BackgroundColor), Changed<Interaction>>,
+    mut status: ResMut<InnStatus>,
+    session: Option<Res<Session>>,
+    config: Res<NetworkConfig>,
+) {
+    for (interaction, button, mut color) in &mut buttons {
+        match interaction {
+            Interaction::Pressed => {}
+            Interaction::Hovered => {
+                *color = BackgroundColor(BUTTON_HOVER);
+                continue;
+            }
+            Interaction::None => {
+                *color = BackgroundColor(BUTTON_NORMAL);
+                continue;
+            }
+        }
+        let action = match button {
+            InnButton::Rest => "rest",
+            InnButton::Bind => "bind",
+            InnButton::Close => {
+                status.close();
+                return;
+            }
+        };
+        let (Some(session), Some(npc)) = (&session, &status.npc) else { continue; };
+        if status.request.is_some() {
+            continue;
+        }
+        let path = format!("/inns/{}/{}", npc.id, action);
+        status.request = Some(api::post(&config, &path, Some(&session.token), &()));
+    }
+}
+
+/// Apply inn answers to our character; refusals go to the chat log
+pub fn poll_inn_requests(
+    mut status: ResMut<InnStatus>,
+    mut player: Query<&mut Player, With<PlayerComponent>>,
+    mut chat_log: ResMut<ChatLog>,
+) {
+    // Polling must not count as a change, or the window would rebuild every frame
+    let Some(result) = status.bypass_change_detection().request.as_mut().and_then(|t| t.poll()) else { return; };
+    status.request = None;
+    match result {
+        Ok(inn) => {
+            if let Ok(mut player) = player.get_single_mut() {
+                player.gold = inn.gold;
+                player.bind_point = inn.bind_point.clone();
+            }
+            status.inn = Some(inn);
+        }
+        Err(e) => {
+            chat_log.push_system(e.to_string());
+            // An inn that never opened has nothing to show
+            if status.inn.is_none() {
+                status.close();
+            }
+        }
+    }
+}
+
+/// Close the inn on Escape or once we walk away from the innkeeper
+pub fn close_inn(
+    keyboard_input: Res<ButtonInput<KeyCode>>,
+    player: Query<&GridPosition, With<PlayerComponent>>,
+    mut status: ResMut<InnStatus>,
+) {
+    let Some(npc) = &status.npc else { return; };
+    let away = player.get_single()
+        .is_ok_and(|pos| (pos.x - npc.x).abs().max((pos.y - npc.y).abs()) > NPC_REACH);
+    if away || keyboard_input.just_pressed(KeyCode::Escape) {
+        status.close();
+    }
+}
+
+fn spawn_button(parent: &mut ChildBuilder, font: TextFont, label: &str, button: InnButton) {
+    parent.spawn((
+        Button,
+        Node {
+            padding: UiRect::axes(Val::Px(8.0), Val::Px(2.0)),
+            ..default()
+        },
+        BackgroundColor(BUTTON_NORMAL),
+        BorderRadius::all(Val::Px(4.0)),
+        button,
+    )).with_children(|btn| {
+        btn.spawn((Text::new(label), font, TextColor(TEXT_COLOR)));
+    });
+}
+
+/// Rebuild the window whenever the inn changes
+pub fn update_inn_window(
+    mut commands: Commands,
+    assets: Res<GameAssets>,
+    status: Res<InnStatus>,
+    mut container: Query<(Entity, &mut Visibility), With<InnWindowUI>>,
+) {
+    if !status.is_changed() {
+        return;
+    }
+    let Ok((container, mut visibility)) = container.get_single_mut() else { return; };
+    commands.entity(container).despawn_descendants();
+    let (Some(npc), Some(inn)) = (&status.npc, &status.inn) else {
+        *visibility = Visibility::Hidden;
+        return;
+    };
+    *visibility = Visibility::Inherited;
+
+    let font = |size: f32| TextFont {
+        font: assets.ui_font.clone(),
+        font_size: size,
+        ..default()
+    };
+    let row = || Node {
+        justify_content: JustifyContent::SpaceBetween,
+        align_items: AlignItems::Center,
+        ..default()
+    };
+    commands.entity(container).with_children(|window| {
+        window.spawn((Text::new(npc.name.clone()), font(16.0), TextColor(TITLE_COLOR)));
+        window.spawn(row()).with_children(|row| {
+            row.spawn((Text::new(format!("Rest - {}g", inn.rest_cost)), font(14.0), TextColor(TEXT_COLOR)));
+            spawn_button(row, font(14.0), "Rest", InnButton::Rest);
+        });
+        window.spawn(row()).with_children(|row| {
+            row.spawn((Text::new(bind_label(inn)), font(14.0), TextColor(TEXT_COLOR)));
+            if !inn.bound_here {
+                spawn_button(row, font(14.0), "Bind", InnButton::Bind);
+            }
+        });
+        window.spawn(row()).with_children(|row| {
+            row.spawn((Text::new(format!("{} gold", inn.gold)), font(14.0), TextColor(TEXT_COLOR)));
+            spawn_button(row, font(14.0), "Close", InnButton::Close);
+        });
+        window.spawn((Text::new("Resting restores HP and MP in full"), font(12.0), TextColor(HINT_COLOR)));
+    });
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::shared::domain::BindPoint;
+
+    #[test]
+    fn test_bind_label_names_the_bound_town() {
+        let mut inn = InnResponse {
+            npc_id: "innkeeper".to_string(),
+            rest_cost: 10,
+            gold: 100,
+            bind_point: None,
+            bound_here: false,
+        };
+        assert_eq!(bind_label(&inn), "You are not bound anywhere");
+
+        inn.bind_point = Some(BindPoint { map_id: "milles_village".to_string(), x: 8, y: 8 });
+        let town = get_map_by_id("milles_village").unwrap().name;
+        assert_eq!(bind_label(&inn), format!("You are bound to {}", town));
+        inn.bound_here = true;
+        assert_eq!(bind_label(&inn), "You are bound here");
+    }
+}
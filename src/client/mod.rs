@@ -8,8 +8,17 @@ mod systems;
 mod states;
 mod components;
 mod resources;
+mod net;
+mod api;
+mod text_input;
+mod chat;
+mod party;
+mod trade;
+mod shop;
+mod inn;
+mod blacksmith;
+mod nameplate;
 pub mod animation;
-pub mod inventory;
 pub mod equipment;
 
 use bevy::prelude::*;
@@ -33,10 +42,19 @@ impl Plugin for LegendGamePlugin {
             .insert_resource(resources::GameAssets::default())
             .insert_resource(resources::SelectedClass::default())
             .insert_resource(resources::SkillData::default())
-            .insert_resource(resources::TextResource::default())
+            .insert_resource(resources::TextResource)
             .insert_resource(resources::MonsterDefinitions::default())
             .insert_resource(resources::SpriteAtlases::default())
             .insert_resource(systems::LoadingState::default())
+            .insert_resource(net::NetworkConfig::default())
+            .insert_resource(text_input::FocusedField::default())
+            .insert_resource(chat::ChatLog::default())
+            .insert_resource(chat::ChatInput::default())
+            .insert_resource(party::PartyStatus::default())
+            .insert_resource(trade::TradeStatus::default())
+            .insert_resource(shop::ShopStatus::default())
+            .insert_resource(inn::InnStatus::default())
+            .insert_resource(blacksmith::BlacksmithStatus::default())
             
             // Startup systems
             .add_systems(Startup, (
@@ -54,28 +72,90 @@ impl Plugin for LegendGamePlugin {
             .add_systems(Update, ui::main_menu_interaction.run_if(in_state(GameState::MainMenu)))
             .add_systems(OnExit(GameState::MainMenu), ui::cleanup_main_menu)
             
+            // Login state
+            .add_systems(OnEnter(GameState::Login), ui::spawn_login_screen)
+            .add_systems(Update, (
+                ui::login_interaction,
+                ui::poll_login_requests,
+            ).run_if(in_state(GameState::Login)))
+            .add_systems(OnExit(GameState::Login), ui::cleanup_login)
+            
+            // Text fields (login and character creation)
+            .add_systems(Update, (
+                text_input::focus_text_fields,
+                text_input::text_field_input,
+                text_input::update_text_field_visuals,
+            ).chain().run_if(in_state(GameState::Login).or(in_state(GameState::CharacterSelect))))
+            
             // Character select state
             .add_systems(OnEnter(GameState::CharacterSelect), ui::spawn_character_select)
             .add_systems(Update, (
                 ui::character_select_interaction,
+                ui::poll_roster_requests,
                 ui::update_character_select_visuals,
             ).run_if(in_state(GameState::CharacterSelect)))
             .add_systems(OnExit(GameState::CharacterSelect), ui::cleanup_character_select)
             
             // Playing state
-            .add_systems(OnEnter(GameState::Playing), game::spawn_game_world)
+            .add_systems(OnEnter(GameState::Playing), (
+                game::spawn_game_world,
+                chat::spawn_chat_window,
+                party::spawn_party_frames,
+                trade::spawn_trade_window,
+                shop::spawn_shop_window,
+                inn::spawn_inn_window,
+                blacksmith::spawn_blacksmith_window,
+                net::connect_to_server,
+            ))
             .add_systems(Update, (
                 animation::initialize_new_sprites,  // Must run first to set initial frame
-                game::player_movement,
+                game::player_movement.run_if(not(chat::is_typing)),
                 game::character_grid_movement,
                 game::sync_character_animation,
                 game::camera_follow,
-                game::monster_ai,
-                game::skill_system,
-                game::interaction_system,
+                game::interaction_system.run_if(not(chat::is_typing)),
                 animation::update_animations,
+                nameplate::update_nameplates,
                 ui::update_hud,
             ).run_if(in_state(GameState::Playing)))
+            // Chat (claims the keyboard while typing)
+            .add_systems(Update, (
+                chat::chat_input,
+                chat::update_chat_window,
+                party::update_party_frames,
+                trade::update_trade_window,
+            ).chain().run_if(in_state(GameState::Playing)))
+            // Shop window
+            .add_systems(Update, (
+                shop::shop_buttons,
+                shop::poll_shop_requests,
+                shop::close_shop.run_if(not(chat::is_typing)),
+                shop::update_shop_window,
+            ).chain().run_if(in_state(GameState::Playing)))
+            // Inn window
+            .add_systems(Update, (
+                inn::inn_buttons,
+                inn::poll_inn_requests,
+                inn::close_inn.run_if(not(chat::is_typing)),
+                inn::update_inn_window,
+            ).chain().run_if(in_state(GameState::Playing)))
+            // Blacksmith window
+            .add_systems(Update, (
+                blacksmith::blacksmith_buttons,
+                blacksmith::poll_blacksmith_requests,
+                blacksmith::close_blacksmith.run_if(not(chat::is_typing)),
+                blacksmith::update_blacksmith_window,
+            ).chain().run_if(in_state(GameState::Playing)))
+            // Offline simulation (the server owns monsters and skills once joined)
+            .add_systems(Update, (
+                game::monster_ai,
+                game::skill_system.run_if(not(chat::is_typing)),
+            ).run_if(in_state(GameState::Playing).and(not(net::is_online))))
+            // Online
+            .add_systems(Update, (
+                net::receive_server_messages,
+                net::send_player_input.run_if(net::is_online.and(not(chat::is_typing))),
+            ).chain().run_if(in_state(GameState::Playing)))
             .add_systems(OnExit(GameState::Playing), game::cleanup_game_world);
     }
 }
@@ -0,0 +1,89 @@
+//! Nameplates - character names above their heads
+//!
+//! Characters carry a [`NameplateLabel`]; its text child is spawned on
+//! first sight and rewritten whenever the label changes, e.g. when the
+//! server reports that a player joined or left a guild.
+
+use bevy::prelude::*;
+use bevy::sprite::Anchor;
+
+use super::resources::GameAssets;
+use crate::shared::constants::CHARACTER_RENDER_HEIGHT;
+
+const NAME_COLOR: Color = Color::srgb(0.95, 0.95, 0.95);
+
+/// Name and optional guild shown above a character
+#[derive(Component, Debug, Clone, PartialEq)]
+pub struct NameplateLabel {
+    pub name: String,
+    pub guild: Option<String>,
+}
+
+impl NameplateLabel {
+    pub fn new(name: impl Into<String>, guild: Option<String>) -> Self {
+        Self { name: name.into(), guild }
+    }
+
+    /// Name, with the guild in angle brackets on a second line
+    pub fn text(&self) -> String {
+        match &self.guild {
+            Some(guild) => format!("{}\n<{}>", self.name, guild),
+            None => self.name.clone(),
+        }
+    }
+}
+
+/// Text child rendering its parent's [`NameplateLabel`]
+#[derive(Component)]
+pub struct Nameplate;
+
+pub fn update_nameplates(
+    mut commands: Commands,
+    labels: Query<(Entity, &NameplateLabel, Option<&Children>), Changed<NameplateLabel>>,
+    mut plates: Query<&mut Text2d, With<Nameplate>>,
+    assets: Res<GameAssets>,
+) {
+    for (entity, label, children) in &labels {
+        let existing = children
+            .and_then(|children| children.iter().find(|child| plates.contains(**child)).copied());
+
+        match existing {
+            Some(plate) => {
+                if let Ok(mut text) = plates.get_mut(plate) {
+                    text.0 = label.text();
+                }
+            }
+            None => {
+                commands.entity(entity).with_children(|parent| {
+                    parent.spawn((
+                        Text2d::new(label.text()),
+                        TextFont {
+                            font: assets.ui_font.clone(),
+                            font_size: 12.0,
+                            ..default()
+                        },
+                        TextColor(NAME_COLOR),
+                        TextLayout::new_with_justify(JustifyText::Center),
+                        Anchor::BottomCenter,
+                        Transform::from_xyz(0.0, CHARACTER_RENDER_HEIGHT + 4.0, 1.0),
+                        Nameplate,
+                    ));
+                });
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_label_text() {
+        assert_eq!(NameplateLabel::new("Aria", None).text(), "Aria");
+        assert_eq!(
+            NameplateLabel::new("Aria", Some("Knights".to_string())).text(),
+            "Aria\n<Knights>"
+        );
+    }
+}
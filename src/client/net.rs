@@ -0,0 +1,434 @@
+//! Network Client
+//!
+//! Connects to the authoritative game server over WebSocket.
+//! While connected, the local monster AI and skill simulation are
+//! disabled: the client sends intents and mirrors server snapshots.
+
+use bevy::prelude::*;
+use std::collections::HashMap;
+use std::sync::{mpsc, Mutex};
+
+use super::chat::ChatLog;
+use super::components::*;
+use super::nameplate::NameplateLabel;
+use super::party::PartyStatus;
+use super::resources::*;
+use super::trade::{apply_trade, TradeStatus};
+use crate::shared::combat::HitOutcome;
+use crate::shared::data::monsters::get_monster_by_id;
+use crate::shared::domain::character::models::Player;
+use crate::shared::domain::monster::Monster;
+use crate::shared::domain::PlayerClass;
+use crate::shared::protocol::{ClientMessage, EntityKind, EntityState, PlayerVitals, ServerMessage, WS_PATH};
+
+/// Snapshots the local player may disagree with the server before snapping back
+const RECONCILE_THRESHOLD: u32 = 3;
+
+/// Server address configuration
+#[derive(Resource)]
+pub struct NetworkConfig {
+    /// REST API root (no trailing slash)
+    pub api_url: String,
+    /// Real-time WebSocket endpoint
+    pub server_url: String,
+}
+
+impl Default for NetworkConfig {
+    fn default() -> Self {
+        let api_url = crate::shared::api::api_url();
+        let server_url = std::env::var("LEGEND_SERVER_URL")
+            .unwrap_or_else(|_| format!("ws://localhost:3000{}", WS_PATH));
+        Self { api_url, server_url }
+    }
+}
+
+/// Live connection to the game server
+#[derive(Resource)]
+pub struct ServerConnection {
+    outgoing: mpsc::Sender<ClientMessage>,
+    incoming: Mutex<mpsc::Receiver<ServerMessage>>,
+    /// Our entity id, set once the server accepts the join
+    pub entity_id: Option<String>,
+    mismatch_count: u32,
+}
+
+impl ServerConnection {
+    pub fn send(&self, msg: ClientMessage) {
+        // If the socket thread has exited, `receive_server_messages` drops the connection.
+        let _ = self.outgoing.send(msg);
+    }
+}
+
+/// Entity mirrored from server snapshots
+#[derive(Component)]
+pub struct NetworkEntity {
+    pub id: String,
+}
+
+/// Run condition: the server has accepted our join
+pub fn is_online(conn: Option<Res<ServerConnection>>) -> bool {
+    conn.is_some_and(|c| c.entity_id.is_some())
+}
+
+/// Open the connection and request to join the world
+pub fn connect_to_server(
+    mut commands: Commands,
+    config: Res<NetworkConfig>,
+    selected_class: Res<SelectedClass>,
+    session: Option<Res<Session>>,
+) {
+    // A selected character plays as itself; otherwise the server creates a guest
+    let url = match session.as_ref().filter(|s| s.player.is_some()) {
+        Some(session) => format!("{}?token={}", config.server_url, session.token),
+        None => config.server_url.clone(),
+    };
+    let conn = open_connection(&url);
+    conn.send(ClientMessage::Join {
+        name: selected_class.username.clone(),
+        class_id: selected_class.class.unwrap_or(PlayerClass::Warrior).id(),
+        gender: if selected_class.gender.is_empty() { "male".to_string() } else { selected_class.gender.clone() },
+    });
+    commands.insert_resource(conn);
+    info!("🌐 Connecting to {}", config.server_url);
+}
+
+#[cfg(not(target_arch = "wasm32"))]
+fn open_connection(url: &str) -> ServerConnection {
+    use tungstenite::{Message, stream::MaybeTlsStream};
+
+    let (out_tx, out_rx) = mpsc::channel::<ClientMessage>();
+    let (in_tx, in_rx) = mpsc::channel::<ServerMessage>();
+    let url = url.to_string();
+
+    // Socket runs on its own thread; dropping either channel end shuts it down.
+    std::thread::spawn(move || {
+        let mut socket = match tungstenite::connect(url.as_str()) {
+            Ok((socket, _)) => socket,
+            Err(e) => {
+                warn!("🌐 Server unavailable ({}), playing offline", e);
+                return;
+            }
+        };
+
+        // Short read timeout so one thread can interleave reads and writes
+        if let MaybeTlsStream::Plain(stream) = socket.get_mut() {
+            let _ = stream.set_read_timeout(Some(std::time::Duration::from_millis(10)));
+        }
+
+        loop {
+            loop {
+                match out_rx.try_recv() {
+                    Ok(msg) => {
+                        let text = serde_json::to_string(&msg).expect("ClientMessage serializes");
+                        if socket.send(Message::Text(text.into())).is_err() {
+                            return;
+                        }
+                    }
+                    Err(mpsc::TryRecvError::Empty) => break,
+                    Err(mpsc::TryRecvError::Disconnected) => {
+                        let _ = socket.close(None);
+                        return;
+                    }
+                }
+            }
+
+            match socket.read() {
+                Ok(Message::Text(text)) => {
+                    if let Ok(msg) = serde_json::from_str::<ServerMessage>(text.as_str())
+                        && in_tx.send(msg).is_err()
+                    {
+                        return;
+                    }
+                }
+                Ok(Message::Close(_)) => return,
+                Ok(_) => {}
+                Err(tungstenite::Error::Io(e))
+                    if matches!(e.kind(), std::io::ErrorKind::WouldBlock | std::io::ErrorKind::TimedOut) => {}
+                Err(_) => return,
+            }
+        }
+    });
+
+    ServerConnection {
+        outgoing: out_tx,
+        incoming: Mutex::new(in_rx),
+        entity_id: None,
+        mismatch_count: 0,
+    }
+}
+
+#[cfg(target_arch = "wasm32")]
+fn open_connection(_url: &str) -> ServerConnection {
+    // Browser sockets need web-sys bindings; web builds stay offline for now.
+    warn!("🌐 Real-time connection is not available on web builds yet, playing offline");
+    let (out_tx, _) = mpsc::channel();
+    let (_, in_rx) = mpsc::channel();
+    ServerConnection {
+        outgoing: out_tx,
+        incoming: Mutex::new(in_rx),
+        entity_id: None,
+        mismatch_count: 0,
+    }
+}
+
+/// Apply everything the server sent since last frame
+pub fn receive_server_messages(
+    mut commands: Commands,
+    conn: Option<ResMut<ServerConnection>>,
+    mut player_query: Query<
+        (&mut Player, &mut GridPosition, &mut TargetGridPosition, Option<&mut NameplateLabel>),
+        With<PlayerComponent>,
+    >,
+    mut remote_query: Query<
+        (Entity, &NetworkEntity, &mut TargetGridPosition, &mut Facing, Option<&mut Monster>, Option<&mut NameplateLabel>),
+        Without<PlayerComponent>,
+    >,
+    offline_monsters: Query<Entity, (With<MonsterComponent>, Without<NetworkEntity>)>,
+    assets: Res<GameAssets>,
+    monster_defs: Res<MonsterDefinitions>,
+    manifests: Res<Assets<crate::shared::domain::sprite::SpriteManifest>>,
+    mut chat_log: ResMut<ChatLog>,
+    mut party_status: ResMut<PartyStatus>,
+    mut trade_status: ResMut<TradeStatus>,
+) {
+    let Some(mut conn) = conn else { return; };
+
+    let mut messages = Vec::new();
+    let disconnected = {
+        let incoming = conn.incoming.lock().unwrap();
+        loop {
+            match incoming.try_recv() {
+                Ok(msg) => messages.push(msg),
+                Err(mpsc::TryRecvError::Empty) => break false,
+                Err(mpsc::TryRecvError::Disconnected) => break true,
+            }
+        }
+    };
+
+    for msg in messages {
+        match msg {
+            ServerMessage::Welcome { entity_id, map_id, .. } => {
+                info!("🌐 Joined {} as {}", map_id, entity_id);
+                conn.entity_id = Some(entity_id);
+                // The server owns monsters from now on
+                for entity in &offline_monsters {
+                    commands.entity(entity).despawn_recursive();
+                }
+            }
+            ServerMessage::Snapshot { you, entities, party, .. } => {
+                let Some(own_id) = conn.entity_id.clone() else { continue; };
+                if party_status.party != party {
+                    party_status.party = party;
+                }
+
+                if let Ok((mut player, mut grid_pos, mut target_pos, label)) = player_query.get_single_mut() {
+                    apply_vitals(&mut player, &you);
+
+                    if let Some(own) = entities.iter().find(|e| e.id == own_id) {
+                        if let Some(mut label) = label {
+                            set_label(&mut label, own);
+                        }
+
+                        let idle = grid_pos.x == target_pos.x && grid_pos.y == target_pos.y;
+                        if idle && (grid_pos.x, grid_pos.y) != (own.x, own.y) {
+                            conn.mismatch_count += 1;
+                        } else {
+                            conn.mismatch_count = 0;
+                        }
+
+                        // Server rejected our predicted step (or teleported us)
+                        if conn.mismatch_count >= RECONCILE_THRESHOLD {
+                            *grid_pos = GridPosition { x: own.x, y: own.y };
+                            *target_pos = TargetGridPosition { x: own.x, y: own.y };
+                            conn.mismatch_count = 0;
+                        }
+                    }
+                }
+
+                let mut known: HashMap<String, EntityState> = entities.into_iter()
+                    .filter(|e| e.id != own_id)
+                    .map(|e| (e.id.clone(), e))
+                    .collect();
+
+                for (entity, net, mut target_pos, mut facing, monster, label) in &mut remote_query {
+                    match known.remove(&net.id) {
+                        Some(state) => {
+                            if (target_pos.x, target_pos.y) != (state.x, state.y) {
+                                *target_pos = TargetGridPosition { x: state.x, y: state.y };
+                            }
+                            facing.direction = state.direction;
+                            if let Some(mut monster) = monster {
+                                monster.hp = state.hp;
+                            }
+                            if let Some(mut label) = label {
+                                set_label(&mut label, &state);
+                            }
+                        }
+                        None => commands.entity(entity).despawn_recursive(),
+                    }
+                }
+
+                for state in known.into_values() {
+                    spawn_network_entity(&mut commands, state, &assets, &monster_defs, &manifests);
+                }
+            }
+            ServerMessage::MapChanged { map_id, x, y } => {
+                info!("🌐 Entered {} at ({}, {})", map_id, x, y);
+                if let Ok((mut player, mut grid_pos, mut target_pos, _)) = player_query.get_single_mut() {
+                    player.current_map = map_id;
+                    *grid_pos = GridPosition { x, y };
+                    *target_pos = TargetGridPosition { x, y };
+                }
+                conn.mismatch_count = 0;
+            }
+            ServerMessage::Combat(event) => {
+                if event.amount < 0 {
+                    println!("✨ {} recovered {} HP", event.target_id, -event.amount);
+                } else if event.outcome == HitOutcome::Miss {
+                    println!("💨 {} missed {}", event.attacker_id, event.target_id);
+                } else if event.outcome == HitOutcome::Critical {
+                    println!("💥 Critical! {} took {} damage from {}", event.target_id, event.amount, event.attacker_id);
+                } else {
+                    println!("💥 {} took {} damage from {}", event.target_id, event.amount, event.attacker_id);
+                }
+                if event.killed {
+                    println!("☠️ {} was defeated", event.target_id);
+                }
+            }
+            ServerMessage::Rewards(rewards) => {
+                println!("💰 +{} Gold, 📈 +{} EXP", rewards.gold, rewards.exp);
+                // Gold, exp and level arrive with the next snapshot; items only here
+                if let Ok((mut player, _, _, _)) = player_query.get_single_mut() {
+                    for item in &rewards.items {
+                        player.inventory.add_item(item.item_id, item.quantity);
+                        println!("🎁 Received item {} x{}", item.item_id, item.quantity);
+                    }
+                }
+            }
+            ServerMessage::Pong { .. } => {}
+            ServerMessage::Chat(msg) => {
+                chat_log.push_message(&msg, conn.entity_id.as_deref());
+            }
+            ServerMessage::PartyInvite { from_id, from } => {
+                chat_log.push_system(format!("{} invited you to a party. Type /accept to join or /decline.", from));
+                party_status.invite = Some((from_id, from));
+            }
+            ServerMessage::TradeRequest { from_id, from } => {
+                chat_log.push_system(format!("{} wants to trade. Type /trade accept or /trade decline.", from));
+                trade_status.request = Some((from_id, from));
+            }
+            ServerMessage::Trade(state) => {
+                trade_status.trade = Some(state);
+            }
+            ServerMessage::TradeClosed { completed, reason } => {
+                if let Some(trade) = trade_status.trade.take()
+                    && completed
+                    && let Ok((mut player, _, _, _)) = player_query.get_single_mut()
+                {
+                    apply_trade(&mut player.inventory, &trade);
+                }
+                chat_log.push_system(reason);
+            }
+            ServerMessage::Notice { message } => {
+                chat_log.push_system(message);
+            }
+            ServerMessage::Error { message } => {
+                warn!("🌐 Server: {}", message);
+                chat_log.push_error(message);
+            }
+        }
+    }
+
+    if disconnected {
+        warn!("🌐 Disconnected from server");
+        commands.remove_resource::<ServerConnection>();
+    }
+}
+
+fn apply_vitals(player: &mut Player, you: &PlayerVitals) {
+    player.combat_stats.hp = you.hp;
+    player.combat_stats.max_hp = you.max_hp;
+    player.combat_stats.mp = you.mp;
+    player.combat_stats.max_mp = you.max_mp;
+    player.level = you.level;
+    player.exp = you.exp;
+    player.gold = you.gold;
+}
+
+/// Only touch the label when it differs so the nameplate is not rebuilt every snapshot
+fn set_label(label: &mut Mut<NameplateLabel>, state: &EntityState) {
+    let EntityKind::Player { guild, .. } = &state.kind else { return; };
+    if label.name != state.name || label.guild != *guild {
+        **label = NameplateLabel::new(state.name.clone(), guild.clone());
+    }
+}
+
+fn spawn_network_entity(
+    commands: &mut Commands,
+    state: EntityState,
+    assets: &GameAssets,
+    monster_defs: &MonsterDefinitions,
+    manifests: &Assets<crate::shared::domain::sprite::SpriteManifest>,
+) {
+    let net = NetworkEntity { id: state.id.clone() };
+
+    match state.kind {
+        EntityKind::Monster { monster_id } => {
+            let Some(def) = get_monster_by_id(monster_id) else { return; };
+            if let Some(entity) = super::game::spawn_monster(commands, state.x, state.y, def.name, monster_defs, assets, manifests) {
+                commands.entity(entity).insert(net);
+            }
+        }
+        EntityKind::Player { class_id, gender, guild, .. } => {
+            let class = PlayerClass::from_id(class_id).unwrap_or(PlayerClass::Warrior);
+            let (sprite, manifest) = super::game::character_sprite(assets, manifests, class, &gender);
+            commands.spawn((
+                sprite,
+                Transform::default(),
+                net,
+                Facing { direction: state.direction },
+                GridPosition { x: state.x, y: state.y },
+                TargetGridPosition { x: state.x, y: state.y },
+                MovementProgress {
+                    timer: Timer::from_seconds(0.2, TimerMode::Once),
+                    start_pos: Vec2::new(state.x as f32, state.y as f32),
+                },
+                super::animation::SpriteAnimator {
+                    manifest,
+                    ..default()
+                },
+                NameplateLabel::new(state.name, guild),
+            ));
+        }
+    }
+}
+
+/// Forward local input to the server as intents
+pub fn send_player_input(
+    keyboard_input: Res<ButtonInput<KeyCode>>,
+    conn: Res<ServerConnection>,
+    skill_data: Res<SkillData>,
+    moved_query: Query<(&GridPosition, &TargetGridPosition, &Facing), (With<PlayerComponent>, Changed<TargetGridPosition>)>,
+    player_query: Query<&Player, With<PlayerComponent>>,
+) {
+    // A new predicted step started this frame
+    if let Ok((grid_pos, target_pos, facing)) = moved_query.get_single()
+        && (grid_pos.x != target_pos.x || grid_pos.y != target_pos.y)
+    {
+        conn.send(ClientMessage::Move { direction: facing.direction });
+    }
+
+    if keyboard_input.just_pressed(KeyCode::Space) {
+        conn.send(ClientMessage::Attack);
+    }
+
+    let Ok(player) = player_query.get_single() else { return; };
+    let hotbar = super::game::hotbar_skills(&skill_data, player);
+    for (i, key) in super::game::SKILL_KEYS.iter().enumerate() {
+        if keyboard_input.just_pressed(*key)
+            && let Some(Some(skill)) = hotbar.get(i)
+        {
+            conn.send(ClientMessage::UseSkill { skill_id: skill.id });
+        }
+    }
+}
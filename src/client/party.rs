@@ -0,0 +1,127 @@
+//! Party - pending invite and the member frames
+//!
+//! The server sends the party with every snapshot; the frames under the
+//! HUD show each other member's name, level, HP and MP. Members on
+//! another map are dimmed. Party commands are typed in the chat box.
+
+use bevy::prelude::*;
+
+use super::components::{HudUI, PlayerComponent};
+use super::net::ServerConnection;
+use super::resources::GameAssets;
+use crate::shared::domain::character::models::Player;
+use crate::shared::protocol::{PartyMember, PartyState};
+
+const FRAME_BG: Color = Color::srgba(0.0, 0.0, 0.0, 0.45);
+const BAR_BG: Color = Color::srgb(0.2, 0.2, 0.2);
+const HP_FG: Color = Color::srgb(0.8, 0.1, 0.1);
+const MP_FG: Color = Color::srgb(0.1, 0.3, 0.8);
+const NAME_COLOR: Color = Color::srgb(0.95, 0.95, 0.95);
+const AWAY_COLOR: Color = Color::srgb(0.55, 0.55, 0.6);
+
+/// The player's party as last reported by the server
+#[derive(Resource, Default)]
+pub struct PartyStatus {
+    pub party: Option<PartyState>,
+    /// Latest unanswered invite: inviter id and name
+    pub invite: Option<(String, String)>,
+}
+
+impl PartyStatus {
+    pub fn member_named(&self, name: &str) -> Option<&PartyMember> {
+        self.party.as_ref()?.members.iter().find(|m| m.name.eq_ignore_ascii_case(name))
+    }
+}
+
+/// Container of the member frames
+#[derive(Component)]
+pub struct PartyFramesUI;
+
+pub fn spawn_party_frames(mut commands: Commands, mut status: ResMut<PartyStatus>) {
+    *status = PartyStatus::default();
+    commands.spawn((
+        Node {
+            position_type: PositionType::Absolute,
+            left: Val::Px(20.0),
+            top: Val::Px(150.0),
+            flex_direction: FlexDirection::Column,
+            row_gap: Val::Px(6.0),
+            ..default()
+        },
+        HudUI,
+        PartyFramesUI,
+    ));
+}
+
+fn percent(value: i32, max: i32) -> Val {
+    Val::Percent((value as f32 / max.max(1) as f32 * 100.0).clamp(0.0, 100.0))
+}
+
+fn spawn_bar(frame: &mut ChildBuilder, height: f32, fill: Val, color: Color) {
+    frame.spawn((
+        Node {
+            width: Val::Px(140.0),
+            height: Val::Px(height),
+            margin: UiRect::top(Val::Px(2.0)),
+            ..default()
+        },
+        BackgroundColor(BAR_BG),
+    ))
+    .with_children(|bar| {
+        bar.spawn((
+            Node {
+                width: fill,
+                height: Val::Percent(100.0),
+                ..default()
+            },
+            BackgroundColor(color),
+        ));
+    });
+}
+
+/// Rebuild the frames whenever the party changes
+pub fn update_party_frames(
+    mut commands: Commands,
+    assets: Res<GameAssets>,
+    status: Res<PartyStatus>,
+    conn: Option<Res<ServerConnection>>,
+    container: Query<Entity, With<PartyFramesUI>>,
+    player_query: Query<&Player, With<PlayerComponent>>,
+) {
+    if !status.is_changed() {
+        return;
+    }
+    let Ok(container) = container.get_single() else { return; };
+    let own_id = conn.as_ref().and_then(|c| c.entity_id.clone());
+    let own_map = player_query.get_single().ok().map(|p| p.current_map.clone());
+
+    commands.entity(container).despawn_descendants();
+    let Some(party) = &status.party else { return; };
+    commands.entity(container).with_children(|frames| {
+        for member in party.members.iter().filter(|m| Some(&m.id) != own_id.as_ref()) {
+            let leader = if member.id == party.leader_id { "★ " } else { "" };
+            let color = if Some(&member.map_id) == own_map.as_ref() { NAME_COLOR } else { AWAY_COLOR };
+            frames.spawn((
+                Node {
+                    flex_direction: FlexDirection::Column,
+                    padding: UiRect::all(Val::Px(4.0)),
+                    ..default()
+                },
+                BackgroundColor(FRAME_BG),
+            ))
+            .with_children(|frame| {
+                frame.spawn((
+                    Text::new(format!("{}{} Lv.{}", leader, member.name, member.level)),
+                    TextFont {
+                        font: assets.ui_font.clone(),
+                        font_size: 14.0,
+                        ..default()
+                    },
+                    TextColor(color),
+                ));
+                spawn_bar(frame, 8.0, percent(member.hp, member.max_hp), HP_FG);
+                spawn_bar(frame, 5.0, percent(member.mp, member.max_mp), MP_FG);
+            });
+        }
+    });
+}
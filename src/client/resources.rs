@@ -3,7 +3,7 @@
 use bevy::prelude::*;
 use std::collections::HashMap;
 use crate::shared::domain::monster::MonsterData;
-use crate::shared::data::{monsters, skills};
+use crate::shared::data::monsters;
 
 /// Game configuration
 #[derive(Resource)]
@@ -72,6 +72,15 @@ pub struct SelectedClass {
     pub username: String,
 }
 
+/// Logged-in account (absent when playing offline)
+#[derive(Resource)]
+pub struct Session {
+    pub token: String,
+    pub characters: Vec<crate::shared::api::CharacterSummary>,
+    /// Character loaded from the server for the current game
+    pub player: Option<crate::shared::domain::character::models::Player>,
+}
+
 /// Skill definitions from data module
 #[derive(Resource)]
 pub struct SkillData {
@@ -80,28 +89,8 @@ pub struct SkillData {
 
 impl Default for SkillData {
     fn default() -> Self {
-        // Load from static data
-        let skills = skills::ALL_SKILLS.iter().map(|s| {
-            crate::shared::domain::skill::models::Skill {
-                id: s.id,
-                name: s.name.to_string(),
-                class_id: s.class_id,
-                req_level: s.req_level,
-                mp_cost: s.mp_cost,
-                cooldown_ms: s.cooldown_ms,
-                description: Some(s.description_key.to_string()),
-                effect_type: Some(match s.effect_type {
-                    skills::SkillEffectType::Damage => "damage".to_string(),
-                    skills::SkillEffectType::Heal => "heal".to_string(),
-                    skills::SkillEffectType::Buff => "buff".to_string(),
-                    skills::SkillEffectType::Debuff => "debuff".to_string(),
-                    skills::SkillEffectType::DamageOverTime => "damage_over_time".to_string(),
-                    skills::SkillEffectType::HealOverTime => "heal_over_time".to_string(),
-                }),
-                base_value: s.base_value,
-                icon_path: Some(s.icon_path.to_string()),
-            }
-        }).collect();
+        // Same query the server answers, over the static data
+        let skills = crate::shared::domain::skill::SkillQuery::default().run();
         
         Self { skills }
     }
@@ -117,42 +106,8 @@ impl Default for MonsterDefinitions {
     fn default() -> Self {
         let mut definitions = HashMap::new();
         
-        for monster_def in monsters::ALL_MONSTERS {
-            let loot_drops: Vec<crate::shared::domain::monster::LootDrop> = 
-                monsters::get_monster_drops(monster_def.id)
-                    .into_iter()
-                    .map(|d| crate::shared::domain::monster::LootDrop {
-                        item_id: d.item_id,
-                        probability: d.probability,
-                        min_quantity: d.min_quantity,
-                        max_quantity: d.max_quantity,
-                    })
-                    .collect();
-            
-            let monster_data = MonsterData {
-                id: monster_def.id,
-                name: monster_def.name.to_string(),
-                level: monster_def.level,
-                max_hp: monster_def.hp_max,
-                attack_min: monster_def.attack_min,
-                attack_max: monster_def.attack_max,
-                defense: monster_def.defense,
-                exp_reward: monster_def.exp_reward,
-                gold_min: monster_def.gold_min,
-                gold_max: monster_def.gold_max,
-                ai_type: monster_def.ai_type,
-                detection_range: monster_def.detection_range,
-                attack_range: monster_def.attack_range,
-                move_speed: monster_def.move_speed,
-                sprite_path: monster_def.sprite_path(),
-                sprite_type: monster_def.sprite_type.to_string(),
-                sprite_size: monster_def.sprite_size,
-                description: format!("{}.desc", monster_def.name_key),
-                metadata: None,
-                loot_table: loot_drops,
-            };
-            
-            definitions.insert(monster_def.name.to_string(), monster_data);
+        for monster_def in monsters::all_monsters() {
+            definitions.insert(monster_def.name.to_string(), MonsterData::from(monster_def));
         }
         
         Self { definitions }
@@ -181,6 +136,13 @@ impl TextResource {
             "ui.start_game" => "Start Game",
             "ui.quit" => "Quit",
             
+            // Login
+            "ui.login" => "Login",
+            "ui.register" => "Register",
+            "ui.offline" => "Play Offline",
+            "ui.username" => "Username",
+            "ui.password" => "Password",
+            
             // Character Select
             "ui.character_select" => "Select Your Class",
             "ui.warrior" => "Warrior",
@@ -190,6 +152,15 @@ impl TextResource {
             "ui.martial_artist" => "Martial Artist",
             "ui.select" => "Confirm",
             "ui.back" => "Back",
+            "ui.select_character" => "Select Character",
+            "ui.no_characters" => "No characters yet. Create one below.",
+            "ui.new_character" => "New Character",
+            "ui.character_name" => "Character name",
+            "ui.male" => "Male",
+            "ui.female" => "Female",
+            "ui.create" => "Create",
+            "ui.delete" => "Delete",
+            "ui.play" => "Play",
             
             // HUD
             "ui.level" => "Level",
@@ -0,0 +1,277 @@
+//! Shop - the window opened by talking to a shopkeeper
+//!
+//! Pressing E at a shopkeeper asks the server for its wares. The window
+//! lists them with a Buy button each, and every stack in the bag that the
+//! shop buys back with a Sell button; each press trades one item. The
+//! server answers every request with our bag and gold, which replace the
+//! local copies. Escape, the Close button or walking away closes it.
+
+use bevy::prelude::*;
+
+use super::api::{self, ApiTask};
+use super::chat::ChatLog;
+use super::components::{GridPosition, HudUI, PlayerComponent};
+use super::net::NetworkConfig;
+use super::resources::{GameAssets, Session};
+use crate::shared::api::{ShopBuyRequest, ShopResponse, ShopSellRequest};
+use crate::shared::data::items::get_item_by_id;
+use crate::shared::data::maps::NPC_REACH;
+use crate::shared::domain::item::inventory::{Inventory, ItemStack};
+use crate::shared::domain::Player;
+
+const WINDOW_BG: Color = Color::srgba(0.0, 0.0, 0.0, 0.6);
+const TITLE_COLOR: Color = Color::srgb(1.0, 0.85, 0.4);
+const TEXT_COLOR: Color = Color::srgb(0.95, 0.95, 0.95);
+const HINT_COLOR: Color = Color::srgb(0.6, 0.6, 0.65);
+const BUTTON_NORMAL: Color = Color::srgb(0.25, 0.25, 0.3);
+const BUTTON_HOVER: Color = Color::srgb(0.35, 0.35, 0.42);
+
+/// The shopkeeper being visited
+#[derive(Debug, Clone)]
+pub struct ShopNpc {
+    pub id: String,
+    pub name: String,
+    pub x: i32,
+    pub y: i32,
+}
+
+/// The open shop and the request in flight, if any
+#[derive(Resource, Default)]
+pub struct ShopStatus {
+    pub npc: Option<ShopNpc>,
+    /// Wares, bag and gold as the server last reported them
+    pub shop: Option<ShopResponse>,
+    request: Option<ApiTask<ShopResponse>>,
+}
+
+impl ShopStatus {
+    /// Ask the server for a shopkeeper's wares; the window opens with the answer
+    pub fn open(&mut self, config: &NetworkConfig, session: &Session, npc: ShopNpc) {
+        self.request = Some(api::get(config, &format!("/shops/{}", npc.id), Some(&session.token)));
+        self.npc = Some(npc);
+        self.shop = None;
+    }
+
+    pub fn close(&mut self) {
+        *self = Self::default();
+    }
+}
+
+/// Bag slots the shop would buy, with what one item fetches
+pub fn sellable(bag: &Inventory) -> Vec<(usize, &ItemStack, i64)> {
+    bag.slots.iter().enumerate()
+        .filter_map(|(i, slot)| slot.as_ref().map(|stack| (i, stack)))
+        .filter_map(|(i, stack)| {
+            let price = stack.get_def()?.price_sell;
+            (price > 0).then_some((i, stack, price))
+        })
+        .collect()
+}
+
+/// A shop window button
+#[derive(Component, Debug, Clone, Copy)]
+pub enum ShopButton {
+    /// Buy one of an item, by id
+    Buy(i32),
+    /// Sell one item from a bag slot
+    Sell(usize),
+    Close,
+}
+
+/// Container of the shop window (hidden while no shop is open)
+#[derive(Component)]
+pub struct ShopWindowUI;
+
+pub fn spawn_shop_window(mut commands: Commands, mut status: ResMut<ShopStatus>) {
+    status.close();
+    commands.spawn((
+        Node {
+            position_type: PositionType::Absolute,
+            // Centered, clear of the party frames and the trade window
+            left: Val::Percent(50.0),
+            top: Val::Px(120.0),
+            margin: UiRect::left(Val::Px(-150.0)),
+            width: Val::Px(300.0),
+            flex_direction: FlexDirection::Column,
+            row_gap: Val::Px(4.0),
+            padding: UiRect::all(Val::Px(8.0)),
+            ..default()
+        },
+        BackgroundColor(WINDOW_BG),
+        BorderRadius::all(Val::Px(6.0)),
+        Visibility::Hidden,
+        HudUI,
+        ShopWindowUI,
+    ));
+}
+
+/// Send button presses to the server, one request at a time
+pub fn shop_buttons(
+    mut buttons: Query<(&Interaction, &ShopButton, &mut BackgroundColor), Changed<Interaction>>,
+    mut status: ResMut<ShopStatus>,
+    session: Option<Res<Session>>,
+    config: Res<NetworkConfig>,
+) {
+    for (interaction, button, mut color) in &mut buttons {
+        match interaction {
+            Interaction::Pressed => {}
+            Interaction::Hovered => {
+                *color = BackgroundColor(BUTTON_HOVER);
+                continue;
+            }
+            Interaction::None => {
+                *color = BackgroundColor(BUTTON_NORMAL);
+                continue;
+            }
+        }
+        if let ShopButton::Close = button {
+            status.close();
+            return;
+        }
+        let (Some(session), Some(npc)) = (&session, &status.npc) else { continue; };
+        if status.request.is_some() {
+            continue;
+        }
+        let token = Some(session.token.as_str());
+        let request = match *button {
+            ShopButton::Buy(item_id) => {
+                let path = format!("/shops/{}/buy", npc.id);
+                api::post(&config, &path, token, &ShopBuyRequest { item_id, quantity: 1 })
+            }
+            ShopButton::Sell(slot_index) => {
+                let path = format!("/shops/{}/sell", npc.id);
+                api::post(&config, &path, token, &ShopSellRequest { slot_index, quantity: 1 })
+            }
+            ShopButton::Close => continue,
+        };
+        status.request = Some(request);
+    }
+}
+
+/// Apply shop answers to our character; refusals go to the chat log
+pub fn poll_shop_requests(
+    mut status: ResMut<ShopStatus>,
+    mut player: Query<&mut Player, With<PlayerComponent>>,
+    mut chat_log: ResMut<ChatLog>,
+) {
+    // Polling must not count as a change, or the window would rebuild every frame
+    let Some(result) = status.bypass_change_detection().request.as_mut().and_then(|t| t.poll()) else { return; };
+    status.request = None;
+    match result {
+        Ok(shop) => {
+            if let Ok(mut player) = player.get_single_mut() {
+                player.inventory = shop.inventory.clone();
+                player.gold = shop.gold;
+            }
+            status.shop = Some(shop);
+        }
+        Err(e) => {
+            chat_log.push_system(e.to_string());
+            // A shop that never opened has nothing to show
+            if status.shop.is_none() {
+                status.close();
+            }
+        }
+    }
+}
+
+/// Close the shop on Escape or once we walk away from the shopkeeper
+pub fn close_shop(
+    keyboard_input: Res<ButtonInput<KeyCode>>,
+    player: Query<&GridPosition, With<PlayerComponent>>,
+    mut status: ResMut<ShopStatus>,
+) {
+    let Some(npc) = &status.npc else { return; };
+    let away = player.get_single()
+        .is_ok_and(|pos| (pos.x - npc.x).abs().max((pos.y - npc.y).abs()) > NPC_REACH);
+    if away || keyboard_input.just_pressed(KeyCode::Escape) {
+        status.close();
+    }
+}
+
+fn spawn_button(parent: &mut ChildBuilder, font: TextFont, label: &str, button: ShopButton) {
+    parent.spawn((
+        Button,
+        Node {
+            padding: UiRect::axes(Val::Px(8.0), Val::Px(2.0)),
+            ..default()
+        },
+        BackgroundColor(BUTTON_NORMAL),
+        BorderRadius::all(Val::Px(4.0)),
+        button,
+    )).with_children(|btn| {
+        btn.spawn((Text::new(label), font, TextColor(TEXT_COLOR)));
+    });
+}
+
+/// Rebuild the window whenever the shop changes
+pub fn update_shop_window(
+    mut commands: Commands,
+    assets: Res<GameAssets>,
+    status: Res<ShopStatus>,
+    mut container: Query<(Entity, &mut Visibility), With<ShopWindowUI>>,
+) {
+    if !status.is_changed() {
+        return;
+    }
+    let Ok((container, mut visibility)) = container.get_single_mut() else { return; };
+    commands.entity(container).despawn_descendants();
+    let (Some(npc), Some(shop)) = (&status.npc, &status.shop) else {
+        *visibility = Visibility::Hidden;
+        return;
+    };
+    *visibility = Visibility::Inherited;
+
+    let font = |size: f32| TextFont {
+        font: assets.ui_font.clone(),
+        font_size: size,
+        ..default()
+    };
+    let row = || Node {
+        justify_content: JustifyContent::SpaceBetween,
+        align_items: AlignItems::Center,
+        ..default()
+    };
+    commands.entity(container).with_children(|window| {
+        window.spawn((Text::new(npc.name.clone()), font(16.0), TextColor(TITLE_COLOR)));
+        for listing in &shop.items {
+            let name = get_item_by_id(listing.item_id).map(|d| d.name).unwrap_or("Unknown item");
+            window.spawn(row()).with_children(|row| {
+                row.spawn((Text::new(format!("{} - {}g", name, listing.price)), font(14.0), TextColor(TEXT_COLOR)));
+                spawn_button(row, font(14.0), "Buy", ShopButton::Buy(listing.item_id));
+            });
+        }
+
+        window.spawn((Text::new("Your bag"), font(16.0), TextColor(TITLE_COLOR)));
+        for (slot, stack, price) in sellable(&shop.inventory) {
+            window.spawn(row()).with_children(|row| {
+                let label = format!("{} x{} - {}g", stack.display_name(), stack.quantity, price);
+                row.spawn((Text::new(label), font(14.0), TextColor(TEXT_COLOR)));
+                spawn_button(row, font(14.0), "Sell", ShopButton::Sell(slot));
+            });
+        }
+
+        window.spawn(row()).with_children(|row| {
+            row.spawn((Text::new(format!("{} gold", shop.gold)), font(14.0), TextColor(TEXT_COLOR)));
+            spawn_button(row, font(14.0), "Close", ShopButton::Close);
+        });
+        window.spawn((Text::new("Each press buys or sells one"), font(12.0), TextColor(HINT_COLOR)));
+    });
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::shared::data::items::{IRON_SWORD, RED_POTION};
+
+    #[test]
+    fn test_sellable_lists_bag_slots_with_a_price() {
+        let mut bag = Inventory::new();
+        bag.add_item(RED_POTION.id, 3);
+        bag.add_item(IRON_SWORD.id, 1);
+        bag.slots[5] = Some(ItemStack::new(9999, 1));
+
+        let sellable: Vec<_> = sellable(&bag).into_iter().map(|(slot, _, price)| (slot, price)).collect();
+        assert_eq!(sellable, vec![(0, RED_POTION.price_sell), (1, IRON_SWORD.price_sell)]);
+    }
+}
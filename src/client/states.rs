@@ -8,6 +8,7 @@ pub enum GameState {
     #[default]
     Loading,
     MainMenu,
+    Login,
     CharacterSelect,
     Playing,
 }
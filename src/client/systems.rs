@@ -59,19 +59,19 @@ pub fn load_assets(
     game_assets.torch_sprite = Some(asset_server.load("tiles/decorations/torch.png"));
     
     // Load monster sprites and create default manifests
-    let monster_types = ["rat", "bat", "slime", "wolf", "skeleton", "goblin", "ghost", "dragon"];
-    for monster_type in monster_types {
+    for &monster_type in crate::shared::data::monsters::PRELOADED_MONSTER_SPRITES {
         let path = format!("monsters/{}/spritesheet.png", monster_type);
         let handle = asset_server.load(&path);
         game_assets.monster_sprites.insert(monster_type.to_string(), handle.clone());
         
-        // Populate library with default manifests for now
-        let size = match monster_type {
-            "rat" | "bat" | "slime" => crate::shared::domain::sprite::MonsterSpriteSize::Small,
-            "wolf" | "skeleton" | "goblin" => crate::shared::domain::sprite::MonsterSpriteSize::Medium,
-            "ghost" => crate::shared::domain::sprite::MonsterSpriteSize::Large,
-            "dragon" => crate::shared::domain::sprite::MonsterSpriteSize::Boss,
-            _ => crate::shared::domain::sprite::MonsterSpriteSize::Medium,
+        // Populate library with default manifests sized from MONSTER_SPRITES
+        let frame_width = crate::shared::data::monsters::get_monster_sprite_config(monster_type)
+            .map_or(48, |c| c.frame_width);
+        let size = match frame_width {
+            32 => crate::shared::domain::sprite::MonsterSpriteSize::Small,
+            48 => crate::shared::domain::sprite::MonsterSpriteSize::Medium,
+            64 => crate::shared::domain::sprite::MonsterSpriteSize::Large,
+            _ => crate::shared::domain::sprite::MonsterSpriteSize::Boss,
         };
         
         let manifest = crate::shared::domain::sprite::SpriteManifest::new_monster(monster_type, monster_type, &path, size);
@@ -138,10 +138,10 @@ pub fn check_assets_loaded(
     }
 
     // Check optional assets (just for logging, don't block too long)
-    if let Some(atlas) = &game_assets.tile_atlas {
-        if !matches!(asset_server.get_load_state(atlas), Some(LoadState::Loaded)) {
-            // checking...
-        }
+    if let Some(atlas) = &game_assets.tile_atlas
+        && !matches!(asset_server.get_load_state(atlas), Some(LoadState::Loaded))
+    {
+        // checking...
     }
 
     // 4. Decision logic
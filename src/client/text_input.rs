@@ -0,0 +1,178 @@
+//! Text Input - focusable single-line fields for menus
+//!
+//! Click a field (or press Tab) to focus it, then type.
+//! Masked fields render their value as asterisks.
+
+use bevy::input::keyboard::{Key, KeyboardInput};
+use bevy::input::ButtonState;
+use bevy::prelude::*;
+
+const FIELD_BG: Color = Color::srgb(0.1, 0.1, 0.16);
+const FIELD_BORDER: Color = Color::srgb(0.3, 0.3, 0.45);
+const FIELD_FOCUSED: Color = Color::srgb(1.0, 0.8, 0.2);
+const PLACEHOLDER_COLOR: Color = Color::srgb(0.5, 0.5, 0.55);
+const VALUE_COLOR: Color = Color::srgb(0.95, 0.95, 0.95);
+
+/// Editable text value
+#[derive(Component)]
+pub struct TextField {
+    pub value: String,
+    pub placeholder: String,
+    pub masked: bool,
+    pub max_len: usize,
+}
+
+impl TextField {
+    pub fn new(placeholder: &str, max_len: usize) -> Self {
+        Self {
+            value: String::new(),
+            placeholder: placeholder.to_string(),
+            masked: false,
+            max_len,
+        }
+    }
+
+    pub fn masked(mut self) -> Self {
+        self.masked = true;
+        self
+    }
+
+    fn display(&self) -> String {
+        if self.value.is_empty() {
+            self.placeholder.clone()
+        } else if self.masked {
+            "*".repeat(self.value.chars().count())
+        } else {
+            self.value.clone()
+        }
+    }
+}
+
+/// Text child that renders a field's value
+#[derive(Component)]
+pub struct TextFieldLabel;
+
+/// Field receiving keyboard input
+#[derive(Resource, Default)]
+pub struct FocusedField(pub Option<Entity>);
+
+/// Spawn a field with `marker` on the field entity
+pub fn spawn_text_field(
+    parent: &mut ChildBuilder,
+    field: TextField,
+    font: Handle<Font>,
+    marker: impl Bundle,
+) -> Entity {
+    let display = field.display();
+    parent
+        .spawn((
+            Button,
+            Node {
+                width: Val::Px(320.0),
+                height: Val::Px(56.0),
+                margin: UiRect::all(Val::Px(8.0)),
+                padding: UiRect::horizontal(Val::Px(14.0)),
+                align_items: AlignItems::Center,
+                border: UiRect::all(Val::Px(2.0)),
+                ..default()
+            },
+            BackgroundColor(FIELD_BG),
+            BorderColor(FIELD_BORDER),
+            BorderRadius::all(Val::Px(8.0)),
+            field,
+            marker,
+        ))
+        .with_children(|f| {
+            f.spawn((
+                Text::new(display),
+                TextFont {
+                    font,
+                    font_size: 24.0,
+                    ..default()
+                },
+                TextColor(PLACEHOLDER_COLOR),
+                TextFieldLabel,
+            ));
+        })
+        .id()
+}
+
+/// Focus a field when clicked
+pub fn focus_text_fields(
+    query: Query<(Entity, &Interaction), (Changed<Interaction>, With<TextField>)>,
+    mut focused: ResMut<FocusedField>,
+) {
+    for (entity, interaction) in &query {
+        if *interaction == Interaction::Pressed {
+            focused.0 = Some(entity);
+        }
+    }
+}
+
+/// Apply typed characters to the focused field
+pub fn text_field_input(
+    mut events: EventReader<KeyboardInput>,
+    mut focused: ResMut<FocusedField>,
+    mut fields: Query<(Entity, &mut TextField)>,
+) {
+    for event in events.read() {
+        if event.state != ButtonState::Pressed {
+            continue;
+        }
+
+        if event.key_code == KeyCode::Tab {
+            // Cycle focus in spawn order
+            let mut entities: Vec<Entity> = fields.iter().map(|(e, _)| e).collect();
+            entities.sort();
+            if entities.is_empty() {
+                continue;
+            }
+            let next = match focused.0.and_then(|f| entities.iter().position(|e| *e == f)) {
+                Some(i) => entities[(i + 1) % entities.len()],
+                None => entities[0],
+            };
+            focused.0 = Some(next);
+            continue;
+        }
+
+        let Some(target) = focused.0 else { continue; };
+        let Ok((_, mut field)) = fields.get_mut(target) else { continue; };
+
+        match &event.logical_key {
+            Key::Backspace => {
+                field.value.pop();
+            }
+            Key::Character(chars) => {
+                for c in chars.chars().filter(|c| !c.is_control()) {
+                    if field.value.chars().count() < field.max_len {
+                        field.value.push(c);
+                    }
+                }
+            }
+            _ => {}
+        }
+    }
+}
+
+/// Refresh labels and the focus border
+pub fn update_text_field_visuals(
+    focused: Res<FocusedField>,
+    mut fields: Query<(Entity, Ref<TextField>, &Children, &mut BorderColor)>,
+    mut labels: Query<(&mut Text, &mut TextColor), With<TextFieldLabel>>,
+) {
+    for (entity, field, children, mut border) in &mut fields {
+        if focused.is_changed() {
+            *border = BorderColor(if focused.0 == Some(entity) { FIELD_FOCUSED } else { FIELD_BORDER });
+        }
+
+        if !field.is_changed() {
+            continue;
+        }
+        for child in children.iter() {
+            if let Ok((mut text, mut color)) = labels.get_mut(*child) {
+                **text = field.display();
+                *color = TextColor(if field.value.is_empty() { PLACEHOLDER_COLOR } else { VALUE_COLOR });
+            }
+        }
+    }
+}
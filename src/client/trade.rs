@@ -0,0 +1,270 @@
+//! Trade - the open trade window and `/trade` commands
+//!
+//! `/trade <name>` asks a nearby player to trade and `/trade accept`
+//! answers the latest request. While a trade is open, `/trade add <slot>
+//! [quantity]`, `/trade remove <slot>` and `/trade gold <amount>` change
+//! our offer (bag slots count from 1), `/trade lock` fixes it and
+//! `/trade confirm` agrees once both sides are locked. The window on the
+//! right shows both offers as the server last reported them.
+
+use bevy::prelude::*;
+
+use super::components::HudUI;
+use super::resources::GameAssets;
+use crate::shared::domain::item::inventory::{Inventory, INVENTORY_SIZE};
+use crate::shared::protocol::{ClientMessage, TradeOffer, TradeSlot, TradeState};
+
+const WINDOW_BG: Color = Color::srgba(0.0, 0.0, 0.0, 0.6);
+const TITLE_COLOR: Color = Color::srgb(1.0, 0.85, 0.4);
+const TEXT_COLOR: Color = Color::srgb(0.95, 0.95, 0.95);
+const HINT_COLOR: Color = Color::srgb(0.6, 0.6, 0.65);
+
+/// The open trade and the latest unanswered request
+#[derive(Resource, Default)]
+pub struct TradeStatus {
+    pub trade: Option<TradeState>,
+    /// Requester id and name
+    pub request: Option<(String, String)>,
+}
+
+/// What a `/trade` command asks for
+#[derive(Debug, Clone, PartialEq)]
+pub enum TradeCommand {
+    Request(String),
+    Accept,
+    Decline,
+    /// Bag slot (0-based) and quantity; the whole stack if None
+    Add { slot: usize, quantity: Option<i32> },
+    Remove(usize),
+    Gold(i64),
+    Lock,
+    Confirm,
+    Cancel,
+}
+
+/// Parse what follows `/trade`
+pub fn parse_trade_command(args: &str) -> Result<TradeCommand, String> {
+    let mut words = args.split_whitespace();
+    let slot = |word: Option<&str>| {
+        word.and_then(|w| w.parse::<usize>().ok())
+            .filter(|n| (1..=INVENTORY_SIZE).contains(n))
+            .map(|n| n - 1)
+            .ok_or_else(|| format!("Bag slots are 1 to {}", INVENTORY_SIZE))
+    };
+
+    match words.next().map(str::to_lowercase).as_deref() {
+        None => Err("Usage: /trade <name>".to_string()),
+        Some("accept") => Ok(TradeCommand::Accept),
+        Some("decline") => Ok(TradeCommand::Decline),
+        Some("add") => {
+            let slot = slot(words.next())?;
+            let quantity = match words.next() {
+                Some(q) => Some(q.parse().ok().filter(|q| *q > 0).ok_or("Usage: /trade add <slot> [quantity]")?),
+                None => None,
+            };
+            Ok(TradeCommand::Add { slot, quantity })
+        }
+        Some("remove") => slot(words.next()).map(TradeCommand::Remove),
+        Some("gold") => words.next()
+            .and_then(|g| g.parse().ok())
+            .filter(|g| *g >= 0)
+            .map(TradeCommand::Gold)
+            .ok_or_else(|| "Usage: /trade gold <amount>".to_string()),
+        Some("lock") => Ok(TradeCommand::Lock),
+        Some("confirm") => Ok(TradeCommand::Confirm),
+        Some("cancel") => Ok(TradeCommand::Cancel),
+        Some(_) => Ok(TradeCommand::Request(args.trim().to_string())),
+    }
+}
+
+/// Our current offer as slots, for editing
+fn offered_slots(offer: &TradeOffer) -> Vec<TradeSlot> {
+    offer.items.iter()
+        .map(|i| TradeSlot { slot_index: i.slot_index, quantity: i.item.quantity })
+        .collect()
+}
+
+/// The message a command sends; offers are rebuilt from what the server
+/// last reported, with the change applied
+pub fn resolve_trade_command(
+    command: TradeCommand,
+    status: &mut TradeStatus,
+    bag: &Inventory,
+) -> Result<Option<ClientMessage>, String> {
+    let mine = status.trade.as_ref().map(|t| &t.mine);
+    let open = || mine.ok_or_else(|| "You are not trading".to_string());
+
+    match command {
+        TradeCommand::Request(name) => Ok(Some(ClientMessage::TradeRequest { name })),
+        TradeCommand::Accept => {
+            let (from_id, _) = status.request.take().ok_or("You have no trade request")?;
+            Ok(Some(ClientMessage::TradeAccept { from_id }))
+        }
+        TradeCommand::Decline => {
+            status.request.take().ok_or("You have no trade request")?;
+            Ok(None)
+        }
+        TradeCommand::Add { slot, quantity } => {
+            let mine = open()?;
+            let stack = bag.slots[slot].as_ref().ok_or("That bag slot is empty")?;
+            let mut items: Vec<TradeSlot> = offered_slots(mine).into_iter().filter(|s| s.slot_index != slot).collect();
+            items.push(TradeSlot { slot_index: slot, quantity: quantity.unwrap_or(stack.quantity) });
+            Ok(Some(ClientMessage::TradeOffer { items, gold: mine.gold }))
+        }
+        TradeCommand::Remove(slot) => {
+            let mine = open()?;
+            let items = offered_slots(mine).into_iter().filter(|s| s.slot_index != slot).collect();
+            Ok(Some(ClientMessage::TradeOffer { items, gold: mine.gold }))
+        }
+        TradeCommand::Gold(gold) => {
+            let mine = open()?;
+            Ok(Some(ClientMessage::TradeOffer { items: offered_slots(mine), gold }))
+        }
+        TradeCommand::Lock => open().map(|_| Some(ClientMessage::TradeLock)),
+        TradeCommand::Confirm => open().map(|_| Some(ClientMessage::TradeConfirm)),
+        TradeCommand::Cancel => open().map(|_| Some(ClientMessage::TradeCancel)),
+    }
+}
+
+/// Mirror a completed exchange in our bag; gold arrives with the next snapshot
+pub fn apply_trade(bag: &mut Inventory, trade: &TradeState) {
+    for offered in &trade.mine.items {
+        let _ = bag.take(offered.slot_index, offered.item.quantity);
+    }
+    for received in &trade.theirs.items {
+        let _ = bag.insert_stack(received.item.clone());
+    }
+}
+
+/// Container of the trade window (hidden while not trading)
+#[derive(Component)]
+pub struct TradeWindowUI;
+
+pub fn spawn_trade_window(mut commands: Commands, mut status: ResMut<TradeStatus>) {
+    *status = TradeStatus::default();
+    commands.spawn((
+        Node {
+            position_type: PositionType::Absolute,
+            right: Val::Px(20.0),
+            top: Val::Px(150.0),
+            width: Val::Px(260.0),
+            flex_direction: FlexDirection::Column,
+            row_gap: Val::Px(4.0),
+            padding: UiRect::all(Val::Px(8.0)),
+            ..default()
+        },
+        BackgroundColor(WINDOW_BG),
+        BorderRadius::all(Val::Px(6.0)),
+        Visibility::Hidden,
+        HudUI,
+        TradeWindowUI,
+    ));
+}
+
+fn offer_lines(name: &str, offer: &TradeOffer) -> Vec<String> {
+    let state = if offer.confirmed {
+        " (confirmed)"
+    } else if offer.locked {
+        " (locked)"
+    } else {
+        ""
+    };
+    let mut lines = vec![format!("{}{}", name, state)];
+    for offered in &offer.items {
+        lines.push(format!("  {} x{}", offered.item.display_name(), offered.item.quantity));
+    }
+    lines.push(format!("  {} gold", offer.gold));
+    lines
+}
+
+/// Rebuild the window whenever the trade changes
+pub fn update_trade_window(
+    mut commands: Commands,
+    assets: Res<GameAssets>,
+    status: Res<TradeStatus>,
+    mut container: Query<(Entity, &mut Visibility), With<TradeWindowUI>>,
+) {
+    if !status.is_changed() {
+        return;
+    }
+    let Ok((container, mut visibility)) = container.get_single_mut() else { return; };
+    commands.entity(container).despawn_descendants();
+    let Some(trade) = &status.trade else {
+        *visibility = Visibility::Hidden;
+        return;
+    };
+    *visibility = Visibility::Inherited;
+
+    let font = |size: f32| TextFont {
+        font: assets.ui_font.clone(),
+        font_size: size,
+        ..default()
+    };
+    commands.entity(container).with_children(|window| {
+        window.spawn((Text::new(format!("Trading with {}", trade.partner)), font(16.0), TextColor(TITLE_COLOR)));
+        for line in offer_lines("You", &trade.mine).into_iter().chain(offer_lines(&trade.partner, &trade.theirs)) {
+            window.spawn((Text::new(line), font(14.0), TextColor(TEXT_COLOR)));
+        }
+        window.spawn((
+            Text::new("/trade add <slot> [qty], gold <n>, lock, confirm, cancel"),
+            font(12.0),
+            TextColor(HINT_COLOR),
+        ));
+    });
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::shared::data::items::RED_POTION;
+    use crate::shared::domain::item::inventory::ItemStack;
+    use crate::shared::protocol::TradeItem;
+
+    #[test]
+    fn test_parse_trade_command() {
+        assert_eq!(parse_trade_command("Bob"), Ok(TradeCommand::Request("Bob".to_string())));
+        assert_eq!(parse_trade_command("ACCEPT"), Ok(TradeCommand::Accept));
+        assert_eq!(parse_trade_command("add 3"), Ok(TradeCommand::Add { slot: 2, quantity: None }));
+        assert_eq!(parse_trade_command("add 1 5"), Ok(TradeCommand::Add { slot: 0, quantity: Some(5) }));
+        assert_eq!(parse_trade_command("gold 250"), Ok(TradeCommand::Gold(250)));
+        assert!(parse_trade_command("").is_err());
+        assert!(parse_trade_command("add 0").is_err());
+        assert!(parse_trade_command("add 1 -2").is_err());
+        assert!(parse_trade_command("gold lots").is_err());
+    }
+
+    #[test]
+    fn test_offers_are_rebuilt_from_the_open_trade() {
+        let mut bag = Inventory::new();
+        bag.add_item(RED_POTION.id, 10);
+        let mut status = TradeStatus::default();
+        assert!(resolve_trade_command(TradeCommand::Lock, &mut status, &bag).is_err());
+
+        status.trade = Some(TradeState {
+            partner_id: "p2".to_string(),
+            partner: "Bob".to_string(),
+            mine: TradeOffer { gold: 30, ..Default::default() },
+            theirs: TradeOffer::default(),
+        });
+        assert_eq!(
+            resolve_trade_command(TradeCommand::Add { slot: 0, quantity: None }, &mut status, &bag),
+            Ok(Some(ClientMessage::TradeOffer { items: vec![TradeSlot { slot_index: 0, quantity: 10 }], gold: 30 }))
+        );
+        assert!(resolve_trade_command(TradeCommand::Add { slot: 1, quantity: None }, &mut status, &bag).is_err());
+
+        // Once the server reports the potions on offer, they can be taken back out
+        let trade = status.trade.as_mut().unwrap();
+        trade.mine.items = vec![TradeItem { slot_index: 0, item: ItemStack::new(RED_POTION.id, 4) }];
+        assert_eq!(
+            resolve_trade_command(TradeCommand::Gold(0), &mut status, &bag),
+            Ok(Some(ClientMessage::TradeOffer { items: vec![TradeSlot { slot_index: 0, quantity: 4 }], gold: 0 }))
+        );
+        assert_eq!(
+            resolve_trade_command(TradeCommand::Remove(0), &mut status, &bag),
+            Ok(Some(ClientMessage::TradeOffer { items: Vec::new(), gold: 30 }))
+        );
+
+        apply_trade(&mut bag, status.trade.as_ref().unwrap());
+        assert_eq!(bag.count_item(RED_POTION.id), 6);
+    }
+}
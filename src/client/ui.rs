@@ -4,8 +4,13 @@ use bevy::prelude::*;
 use super::components::*;
 use super::resources::*;
 use super::states::GameState;
+use super::api::{self, ApiTask};
+use super::net::NetworkConfig;
+use super::text_input::{spawn_text_field, FocusedField, TextField};
+use crate::shared::api::*;
 use crate::shared::domain::PlayerClass;
 use crate::shared::domain::character::models::Player;
+use crate::shared::validation::validate_registration;
 
 // ============ Color Constants ============
 
@@ -147,7 +152,7 @@ pub fn main_menu_interaction(
                 
                 match action {
                     ButtonAction::CharacterSelect => {
-                        next_state.set(GameState::CharacterSelect);
+                        next_state.set(GameState::Login);
                     }
                     ButtonAction::Quit => {
                         exit.send(AppExit::Success);
@@ -176,13 +181,272 @@ pub fn cleanup_main_menu(
     }
 }
 
+// ============ Login ============
+
+/// In-flight account requests
+#[derive(Resource, Default)]
+pub struct LoginRequests {
+    login: Option<ApiTask<LoginResponse>>,
+    register: Option<ApiTask<RegisterResponse>>,
+}
+
+pub fn spawn_login_screen(
+    mut commands: Commands,
+    text: Res<TextResource>,
+    assets: Res<GameAssets>,
+    mut focused: ResMut<FocusedField>,
+) {
+    commands.insert_resource(LoginRequests::default());
+
+    commands
+        .spawn((
+            Node {
+                width: Val::Percent(100.0),
+                height: Val::Percent(100.0),
+                flex_direction: FlexDirection::Column,
+                justify_content: JustifyContent::Center,
+                align_items: AlignItems::Center,
+                ..default()
+            },
+            BackgroundColor(DARK_BG),
+            ZIndex(100),
+            LoginUI,
+        ))
+        .with_children(|parent| {
+            parent.spawn((
+                Text::new(text.get("ui.login")),
+                TextFont {
+                    font: assets.ui_font.clone(),
+                    font_size: 40.0,
+                    ..default()
+                },
+                TextColor(GOLD),
+                Node {
+                    margin: UiRect::bottom(Val::Px(30.0)),
+                    ..default()
+                },
+            ));
+
+            let username = spawn_text_field(parent, TextField::new(text.get("ui.username"), 20), assets.ui_font.clone(), UsernameField);
+            spawn_text_field(parent, TextField::new(text.get("ui.password"), 64).masked(), assets.ui_font.clone(), PasswordField);
+            focused.0 = Some(username);
+
+            spawn_status_text(parent, assets.ui_font.clone());
+
+            let button_style = Node {
+                width: Val::Px(180.0),
+                height: Val::Px(60.0),
+                justify_content: JustifyContent::Center,
+                align_items: AlignItems::Center,
+                ..default()
+            };
+
+            parent.spawn((
+                Node {
+                    column_gap: Val::Px(20.0),
+                    margin: UiRect::top(Val::Px(10.0)),
+                    ..default()
+                },
+            )).with_children(|row| {
+                spawn_styled_button(row, text.get("ui.back"), ButtonAction::BackToMenu, assets.ui_font.clone(), button_style.clone());
+                spawn_styled_button(row, text.get("ui.register"), ButtonAction::Register, assets.ui_font.clone(), button_style.clone());
+                spawn_styled_button(row, text.get("ui.login"), ButtonAction::Login, assets.ui_font.clone(), button_style.clone());
+            });
+
+            spawn_styled_button(parent, text.get("ui.offline"), ButtonAction::PlayOffline, assets.ui_font.clone(), Node {
+                width: Val::Px(240.0),
+                margin: UiRect::top(Val::Px(20.0)),
+                ..button_style
+            });
+        });
+}
+
+fn spawn_status_text(parent: &mut ChildBuilder, font: Handle<Font>) {
+    parent.spawn((
+        Text::new(""),
+        TextFont {
+            font,
+            font_size: 20.0,
+            ..default()
+        },
+        TextColor(BLOOD_RED),
+        Node {
+            margin: UiRect::vertical(Val::Px(10.0)),
+            ..default()
+        },
+        StatusText,
+    ));
+}
+
+fn set_status(status_query: &mut Query<&mut Text, With<StatusText>>, message: &str) {
+    if let Ok(mut text) = status_query.get_single_mut() {
+        **text = message.to_string();
+    }
+}
+
+pub fn login_interaction(
+    mut commands: Commands,
+    mut interaction_query: Query<
+        (&Interaction, &ButtonAction, &mut BackgroundColor, &mut Node),
+        (Changed<Interaction>, With<Button>),
+    >,
+    keyboard_input: Res<ButtonInput<KeyCode>>,
+    username_query: Query<&TextField, With<UsernameField>>,
+    password_query: Query<&TextField, With<PasswordField>>,
+    mut status_query: Query<&mut Text, With<StatusText>>,
+    config: Res<NetworkConfig>,
+    selected_class: Res<SelectedClass>,
+    mut requests: ResMut<LoginRequests>,
+    mut next_state: ResMut<NextState<GameState>>,
+) {
+    let mut action_pressed = None;
+    if keyboard_input.just_pressed(KeyCode::Enter) {
+        action_pressed = Some(ButtonAction::Login);
+    }
+
+    for (interaction, action, mut bg_color, mut node) in &mut interaction_query {
+        match *interaction {
+            Interaction::Pressed => {
+                *bg_color = BackgroundColor(BUTTON_PRESSED);
+                node.top = Val::Px(2.0);
+                action_pressed = Some(action.clone());
+            }
+            Interaction::Hovered => {
+                *bg_color = BackgroundColor(BUTTON_HOVER);
+                node.top = Val::Px(0.0);
+            }
+            Interaction::None => {
+                *bg_color = BackgroundColor(BUTTON_NORMAL);
+                node.top = Val::Px(0.0);
+            }
+        }
+    }
+
+    let Some(action) = action_pressed else { return; };
+    let (Ok(username), Ok(password)) = (username_query.get_single(), password_query.get_single()) else { return; };
+
+    match action {
+        ButtonAction::Login | ButtonAction::Register => {
+            if requests.login.is_some() || requests.register.is_some() {
+                return;
+            }
+            if username.value.trim().is_empty() || password.value.is_empty() {
+                set_status(&mut status_query, "Enter a username and password");
+                return;
+            }
+
+            if matches!(action, ButtonAction::Login) {
+                requests.login = Some(api::post(&config, "/login", None, &LoginRequest {
+                    username: username.value.trim().to_string(),
+                    password: password.value.clone(),
+                }));
+            } else {
+                // The first character is named after the account
+                let req = RegisterRequest {
+                    username: username.value.trim().to_string(),
+                    password: password.value.clone(),
+                    class_idx: selected_class.class.unwrap_or(PlayerClass::Warrior).id(),
+                    gender: if selected_class.gender.is_empty() { "male".to_string() } else { selected_class.gender.clone() },
+                };
+                if let Some(error) = validate_registration(&req).first() {
+                    set_status(&mut status_query, &error.message);
+                    return;
+                }
+                requests.register = Some(api::post(&config, "/register", None, &req));
+            }
+            set_status(&mut status_query, "...");
+        }
+        ButtonAction::PlayOffline => {
+            commands.remove_resource::<Session>();
+            next_state.set(GameState::CharacterSelect);
+        }
+        ButtonAction::BackToMenu => {
+            next_state.set(GameState::MainMenu);
+        }
+        _ => {}
+    }
+}
+
+/// Finish login/register once the server answers
+pub fn poll_login_requests(
+    mut commands: Commands,
+    mut requests: ResMut<LoginRequests>,
+    username_query: Query<&TextField, With<UsernameField>>,
+    password_query: Query<&TextField, With<PasswordField>>,
+    mut status_query: Query<&mut Text, With<StatusText>>,
+    config: Res<NetworkConfig>,
+    mut next_state: ResMut<NextState<GameState>>,
+) {
+    if let Some(result) = requests.register.as_mut().and_then(|task| task.poll()) {
+        requests.register = None;
+        match result {
+            Ok(res) if res.success => {
+                // Log straight into the new account
+                if let (Ok(username), Ok(password)) = (username_query.get_single(), password_query.get_single()) {
+                    requests.login = Some(api::post(&config, "/login", None, &LoginRequest {
+                        username: username.value.trim().to_string(),
+                        password: password.value.clone(),
+                    }));
+                }
+            }
+            Ok(res) => set_status(&mut status_query, &res.message),
+            Err(e) => set_status(&mut status_query, &e.to_string()),
+        }
+    }
+
+    if let Some(result) = requests.login.as_mut().and_then(|task| task.poll()) {
+        requests.login = None;
+        match result {
+            Ok(LoginResponse { success: true, token: Some(token), characters, .. }) => {
+                commands.insert_resource(Session {
+                    token,
+                    characters,
+                    player: None,
+                });
+                next_state.set(GameState::CharacterSelect);
+            }
+            Ok(res) => set_status(&mut status_query, res.error.as_deref().unwrap_or("Login failed")),
+            Err(e) => set_status(&mut status_query, &e.to_string()),
+        }
+    }
+}
+
+pub fn cleanup_login(
+    mut commands: Commands,
+    query: Query<Entity, With<LoginUI>>,
+    mut focused: ResMut<FocusedField>,
+) {
+    for entity in &query {
+        commands.entity(entity).despawn_recursive();
+    }
+    commands.remove_resource::<LoginRequests>();
+    focused.0 = None;
+}
+
 // ============ Character Select ============
 
+/// Roster selection and in-flight roster requests
+#[derive(Resource, Default)]
+pub struct RosterState {
+    pub selected: Option<usize>,
+    create: Option<ApiTask<CharacterResponse>>,
+    delete: Option<ApiTask<CharacterResponse>>,
+    refresh: Option<ApiTask<CharacterListResponse>>,
+    select: Option<ApiTask<SelectCharacterResponse>>,
+}
+
+impl RosterState {
+    fn is_busy(&self) -> bool {
+        self.create.is_some() || self.delete.is_some() || self.refresh.is_some() || self.select.is_some()
+    }
+}
+
 pub fn spawn_character_select(
     mut commands: Commands,
     mut selected_class: ResMut<SelectedClass>,
     text: Res<TextResource>,
     assets: Res<GameAssets>,
+    session: Option<Res<Session>>,
 ) {
     // Set default selection
     if selected_class.class.is_none() {
@@ -191,6 +455,21 @@ pub fn spawn_character_select(
         selected_class.username = "Player".to_string();
     }
     
+    let selected = session.as_ref().filter(|s| !s.characters.is_empty()).map(|_| 0);
+    commands.insert_resource(RosterState { selected, ..default() });
+    
+    build_character_select(&mut commands, &text, &assets, &selected_class, session.as_deref(), selected);
+}
+
+/// Roster (online) or class picker (offline)
+fn build_character_select(
+    commands: &mut Commands,
+    text: &TextResource,
+    assets: &GameAssets,
+    selected_class: &SelectedClass,
+    session: Option<&Session>,
+    selected: Option<usize>,
+) {
     commands
         .spawn((
             Node {
@@ -208,8 +487,9 @@ pub fn spawn_character_select(
         ))
         .with_children(|parent| {
             // Header
+            let header = if session.is_some() { "ui.select_character" } else { "ui.character_select" };
             parent.spawn((
-                Text::new(text.get("ui.character_select")),
+                Text::new(text.get(header)),
                 TextFont {
                     font: assets.ui_font.clone(),
                     font_size: 40.0,
@@ -222,6 +502,77 @@ pub fn spawn_character_select(
                 },
             ));
             
+            if let Some(session) = session {
+                // Roster
+                parent.spawn((
+                    Node {
+                        flex_direction: FlexDirection::Column,
+                        align_items: AlignItems::Center,
+                        margin: UiRect::bottom(Val::Px(20.0)),
+                        ..default()
+                    },
+                )).with_children(|list| {
+                    if session.characters.is_empty() {
+                        list.spawn((
+                            Text::new(text.get("ui.no_characters")),
+                            TextFont {
+                                font: assets.ui_font.clone(),
+                                font_size: 20.0,
+                                ..default()
+                            },
+                            TextColor(TEXT_WHITE),
+                        ));
+                    }
+                    for (i, character) in session.characters.iter().enumerate() {
+                        spawn_roster_entry(list, i, character, assets.ui_font.clone(), selected == Some(i));
+                    }
+                });
+                
+                // New character: name + gender
+                parent.spawn((
+                    Text::new(text.get("ui.new_character")),
+                    TextFont {
+                        font: assets.ui_font.clone(),
+                        font_size: 24.0,
+                        ..default()
+                    },
+                    TextColor(MAGIC_PURPLE),
+                ));
+                parent.spawn((
+                    Node {
+                        align_items: AlignItems::Center,
+                        column_gap: Val::Px(10.0),
+                        ..default()
+                    },
+                )).with_children(|row| {
+                    spawn_text_field(row, TextField::new(text.get("ui.character_name"), 12), assets.ui_font.clone(), CharacterNameField);
+                    row.spawn((
+                        Button,
+                        Node {
+                            width: Val::Px(120.0),
+                            height: Val::Px(56.0),
+                            justify_content: JustifyContent::Center,
+                            align_items: AlignItems::Center,
+                            ..default()
+                        },
+                        BackgroundColor(BUTTON_NORMAL),
+                        BorderRadius::all(Val::Px(10.0)),
+                        ButtonAction::ToggleGender,
+                    )).with_children(|btn| {
+                        btn.spawn((
+                            Text::new(gender_label(text, &selected_class.gender)),
+                            TextFont {
+                                font: assets.ui_font.clone(),
+                                font_size: 22.0,
+                                ..default()
+                            },
+                            TextColor(TEXT_WHITE),
+                            GenderLabel,
+                        ));
+                    });
+                });
+            }
+            
             // Class Grid
             parent.spawn((
                 Node {
@@ -230,18 +581,20 @@ pub fn spawn_character_select(
                     grid_template_rows: vec![GridTrack::auto(); 2],    // 2 rows
                     row_gap: Val::Px(20.0),
                     column_gap: Val::Px(20.0),
-                    margin: UiRect::bottom(Val::Px(40.0)),
+                    margin: UiRect::vertical(Val::Px(20.0)),
                     ..default()
                 },
             ))
             .with_children(|grid| {
-                spawn_class_button(grid, text.get("ui.warrior"), PlayerClass::Warrior, assets.ui_font.clone(), &selected_class);
-                spawn_class_button(grid, text.get("ui.rogue"), PlayerClass::Rogue, assets.ui_font.clone(), &selected_class);
-                spawn_class_button(grid, text.get("ui.mage"), PlayerClass::Mage, assets.ui_font.clone(), &selected_class);
-                spawn_class_button(grid, text.get("ui.cleric"), PlayerClass::Cleric, assets.ui_font.clone(), &selected_class);
-                spawn_class_button(grid, text.get("ui.martial_artist"), PlayerClass::MartialArtist, assets.ui_font.clone(), &selected_class);
+                spawn_class_button(grid, text.get("ui.warrior"), PlayerClass::Warrior, assets.ui_font.clone(), selected_class);
+                spawn_class_button(grid, text.get("ui.rogue"), PlayerClass::Rogue, assets.ui_font.clone(), selected_class);
+                spawn_class_button(grid, text.get("ui.mage"), PlayerClass::Mage, assets.ui_font.clone(), selected_class);
+                spawn_class_button(grid, text.get("ui.cleric"), PlayerClass::Cleric, assets.ui_font.clone(), selected_class);
+                spawn_class_button(grid, text.get("ui.martial_artist"), PlayerClass::MartialArtist, assets.ui_font.clone(), selected_class);
             });
             
+            spawn_status_text(parent, assets.ui_font.clone());
+            
             // Footer Actions
             parent.spawn((
                 Node {
@@ -251,39 +604,82 @@ pub fn spawn_character_select(
                     ..default()
                 },
             )).with_children(|footer| {
+                let footer_button = |width: f32| Node {
+                    width: Val::Px(width),
+                    height: Val::Px(60.0),
+                    justify_content: JustifyContent::Center,
+                    align_items: AlignItems::Center,
+                    ..default()
+                };
+                
                 // Back Button
-                spawn_styled_button(
-                    footer, 
-                    text.get("ui.back"), 
-                    ButtonAction::BackToMenu, 
-                    assets.ui_font.clone(),
-                    Node {
-                        width: Val::Px(150.0),
-                        height: Val::Px(60.0),
-                        justify_content: JustifyContent::Center,
-                        align_items: AlignItems::Center,
-                        ..default()
-                    }
-                );
+                spawn_styled_button(footer, text.get("ui.back"), ButtonAction::BackToMenu, assets.ui_font.clone(), footer_button(150.0));
                 
-                // Confirm Button
-                spawn_styled_button(
-                    footer, 
-                    text.get("ui.select"), 
-                    ButtonAction::ConfirmCharacter, 
-                    assets.ui_font.clone(),
-                    Node {
-                        width: Val::Px(200.0),
-                        height: Val::Px(60.0),
-                        justify_content: JustifyContent::Center,
-                        align_items: AlignItems::Center,
-                        ..default()
-                    }
-                );
+                if session.is_some() {
+                    spawn_styled_button(footer, text.get("ui.delete"), ButtonAction::DeleteCharacter, assets.ui_font.clone(), footer_button(150.0));
+                    spawn_styled_button(footer, text.get("ui.create"), ButtonAction::CreateCharacter, assets.ui_font.clone(), footer_button(150.0));
+                    spawn_styled_button(footer, text.get("ui.play"), ButtonAction::ConfirmCharacter, assets.ui_font.clone(), footer_button(200.0));
+                } else {
+                    // Confirm Button
+                    spawn_styled_button(footer, text.get("ui.select"), ButtonAction::ConfirmCharacter, assets.ui_font.clone(), footer_button(200.0));
+                }
             });
         });
 }
 
+fn gender_label(text: &TextResource, gender: &str) -> &'static str {
+    if gender == "female" { text.get("ui.female") } else { text.get("ui.male") }
+}
+
+fn spawn_roster_entry(
+    parent: &mut ChildBuilder,
+    index: usize,
+    character: &CharacterSummary,
+    font: Handle<Font>,
+    is_selected: bool,
+) {
+    let class_name = PlayerClass::from_id(character.class_id).map(|c| c.name().to_string()).unwrap_or_default();
+
+    parent
+        .spawn((
+            Button,
+            Node {
+                width: Val::Px(420.0),
+                height: Val::Px(56.0),
+                margin: UiRect::all(Val::Px(4.0)),
+                padding: UiRect::horizontal(Val::Px(16.0)),
+                justify_content: JustifyContent::SpaceBetween,
+                align_items: AlignItems::Center,
+                border: UiRect::all(Val::Px(if is_selected { 3.0 } else { 1.0 })),
+                ..default()
+            },
+            BackgroundColor(if is_selected { BUTTON_HOVER } else { DARK_PANEL }),
+            BorderColor(if is_selected { GOLD } else { MAGIC_PURPLE }),
+            BorderRadius::all(Val::Px(10.0)),
+            ButtonAction::SelectCharacter(index),
+        ))
+        .with_children(|btn| {
+            btn.spawn((
+                Text::new(character.name.clone()),
+                TextFont {
+                    font: font.clone(),
+                    font_size: 24.0,
+                    ..default()
+                },
+                TextColor(TEXT_WHITE),
+            ));
+            btn.spawn((
+                Text::new(format!("Lv.{} {}", character.level, class_name)),
+                TextFont {
+                    font,
+                    font_size: 18.0,
+                    ..default()
+                },
+                TextColor(GOLD),
+            ));
+        });
+}
+
 fn spawn_class_button(
     parent: &mut ChildBuilder, 
     text: &str, 
@@ -351,26 +747,75 @@ pub fn character_select_interaction(
         (&Interaction, &ButtonAction, &mut BackgroundColor, &mut Node),
         (Changed<Interaction>, With<Button>),
     >,
+    mut commands: Commands,
     mut next_state: ResMut<NextState<GameState>>,
     mut selected_class: ResMut<SelectedClass>,
+    mut roster: ResMut<RosterState>,
+    session: Option<Res<Session>>,
+    config: Res<NetworkConfig>,
+    name_query: Query<&TextField, With<CharacterNameField>>,
+    mut status_query: Query<&mut Text, With<StatusText>>,
 ) {
     for (interaction, action, mut bg_color, mut node) in &mut interaction_query {
+        let is_toggle = matches!(action, ButtonAction::SelectClass(_) | ButtonAction::SelectCharacter(_));
         match *interaction {
             Interaction::Pressed => {
                 // Button press effect
                 node.top = Val::Px(2.0);
                 *bg_color = BackgroundColor(BUTTON_PRESSED);
 
+                if roster.is_busy() {
+                    continue;
+                }
+
                 match action {
                     ButtonAction::SelectClass(class) => {
                         selected_class.class = Some(*class);
                     }
-                    ButtonAction::ConfirmCharacter => {
-                        if selected_class.class.is_some() {
+                    ButtonAction::SelectCharacter(index) => {
+                        roster.selected = Some(*index);
+                    }
+                    ButtonAction::ToggleGender => {
+                        selected_class.gender = if selected_class.gender == "female" { "male" } else { "female" }.to_string();
+                    }
+                    ButtonAction::ConfirmCharacter => match &session {
+                        Some(session) => {
+                            let Some(character) = roster.selected.and_then(|i| session.characters.get(i)) else {
+                                set_status(&mut status_query, "Select a character first");
+                                continue;
+                            };
+                            let path = format!("/characters/{}/select", character.id);
+                            roster.select = Some(api::post(&config, &path, Some(&session.token), &()));
+                        }
+                        None if selected_class.class.is_some() => {
                             next_state.set(GameState::Playing);
                         }
+                        None => {}
+                    },
+                    ButtonAction::CreateCharacter => {
+                        let (Some(session), Ok(name)) = (&session, name_query.get_single()) else { continue; };
+                        if name.value.trim().is_empty() {
+                            set_status(&mut status_query, "Enter a character name");
+                            continue;
+                        }
+                        roster.create = Some(api::post(&config, "/characters", Some(&session.token), &CreateCharacterRequest {
+                            name: name.value.trim().to_string(),
+                            class_id: selected_class.class.unwrap_or(PlayerClass::Warrior).id(),
+                            gender: selected_class.gender.clone(),
+                        }));
+                    }
+                    ButtonAction::DeleteCharacter => {
+                        let Some(session) = &session else { continue; };
+                        let Some(character) = roster.selected.and_then(|i| session.characters.get(i)) else {
+                            set_status(&mut status_query, "Select a character first");
+                            continue;
+                        };
+                        let path = format!("/characters/{}", character.id);
+                        roster.delete = Some(api::delete(&config, &path, Some(&session.token)));
                     }
                     ButtonAction::BackToMenu => {
+                        // Leaving the roster logs out
+                        commands.remove_resource::<Session>();
                         next_state.set(GameState::MainMenu);
                     }
                     _ => {}
@@ -378,14 +823,14 @@ pub fn character_select_interaction(
             }
             Interaction::Hovered => {
                 node.top = Val::Px(0.0);
-                // Hover color logic is handled in visual system for class buttons
-                 if !matches!(action, ButtonAction::SelectClass(_)) {
+                // Hover color logic is handled in visual system for selectable buttons
+                if !is_toggle {
                     *bg_color = BackgroundColor(BUTTON_HOVER);
                 }
             }
             Interaction::None => {
                 node.top = Val::Px(0.0);
-                 if !matches!(action, ButtonAction::SelectClass(_)) {
+                if !is_toggle {
                     *bg_color = BackgroundColor(BUTTON_NORMAL);
                 }
             }
@@ -393,26 +838,100 @@ pub fn character_select_interaction(
     }
 }
 
+/// Apply roster request results (refreshing the list or entering the game)
+pub fn poll_roster_requests(
+    mut commands: Commands,
+    mut roster: ResMut<RosterState>,
+    session: Option<ResMut<Session>>,
+    config: Res<NetworkConfig>,
+    mut selected_class: ResMut<SelectedClass>,
+    mut status_query: Query<&mut Text, With<StatusText>>,
+    ui_query: Query<Entity, With<CharacterSelectUI>>,
+    text: Res<TextResource>,
+    assets: Res<GameAssets>,
+    mut next_state: ResMut<NextState<GameState>>,
+) {
+    let Some(mut session) = session else { return; };
+
+    let changed = [roster.create.as_mut().and_then(|t| t.poll()), roster.delete.as_mut().and_then(|t| t.poll())];
+    for (i, result) in changed.into_iter().enumerate() {
+        let Some(result) = result else { continue; };
+        if i == 0 { roster.create = None; } else { roster.delete = None; }
+
+        match result {
+            Ok(res) if res.success => {
+                roster.refresh = Some(api::get(&config, "/characters", Some(&session.token)));
+            }
+            Ok(res) => set_status(&mut status_query, res.error.as_deref().unwrap_or("Request failed")),
+            Err(e) => set_status(&mut status_query, &e.to_string()),
+        }
+    }
+
+    if let Some(result) = roster.refresh.as_mut().and_then(|t| t.poll()) {
+        roster.refresh = None;
+        match result {
+            Ok(res) if res.success => {
+                session.characters = res.characters;
+                roster.selected = if session.characters.is_empty() { None } else { Some(session.characters.len() - 1) };
+
+                for entity in &ui_query {
+                    commands.entity(entity).despawn_recursive();
+                }
+                build_character_select(&mut commands, &text, &assets, &selected_class, Some(&session), roster.selected);
+            }
+            Ok(res) => set_status(&mut status_query, res.error.as_deref().unwrap_or("Request failed")),
+            Err(e) => set_status(&mut status_query, &e.to_string()),
+        }
+    }
+
+    if let Some(result) = roster.select.as_mut().and_then(|t| t.poll()) {
+        roster.select = None;
+        match result {
+            Ok(SelectCharacterResponse { success: true, player: Some(player), token: Some(token), .. }) => {
+                selected_class.class = Some(player.class);
+                selected_class.gender = player.gender.clone();
+                selected_class.username = player.username.clone();
+                session.token = token;
+                session.player = Some(player);
+                next_state.set(GameState::Playing);
+            }
+            Ok(res) => set_status(&mut status_query, res.error.as_deref().unwrap_or("Failed to load character")),
+            Err(e) => set_status(&mut status_query, &e.to_string()),
+        }
+    }
+}
+
 pub fn update_character_select_visuals(
     selected_class: Res<SelectedClass>,
+    roster: Res<RosterState>,
+    text: Res<TextResource>,
     mut button_query: Query<(&ButtonAction, &mut BorderColor, &mut BackgroundColor, &Interaction), With<Button>>,
+    mut gender_query: Query<&mut Text, With<GenderLabel>>,
 ) {
-    if !selected_class.is_changed() {
+    if !selected_class.is_changed() && !roster.is_changed() {
         return;
     }
 
+    for mut label in &mut gender_query {
+        **label = gender_label(&text, &selected_class.gender).to_string();
+    }
+
     for (action, mut border, mut bg, interaction) in &mut button_query {
-        if let ButtonAction::SelectClass(class) = action {
-            if Some(*class) == selected_class.class {
-                *border = BorderColor(GOLD);
-                if *interaction != Interaction::Pressed {
-                    *bg = BackgroundColor(BUTTON_HOVER);
-                }
-            } else {
-                *border = BorderColor(MAGIC_PURPLE);
-                if *interaction == Interaction::None {
-                    *bg = BackgroundColor(DARK_PANEL);
-                }
+        let is_selected = match action {
+            ButtonAction::SelectClass(class) => Some(*class) == selected_class.class,
+            ButtonAction::SelectCharacter(index) => Some(*index) == roster.selected,
+            _ => continue,
+        };
+
+        if is_selected {
+            *border = BorderColor(GOLD);
+            if *interaction != Interaction::Pressed {
+                *bg = BackgroundColor(BUTTON_HOVER);
+            }
+        } else {
+            *border = BorderColor(MAGIC_PURPLE);
+            if *interaction == Interaction::None {
+                *bg = BackgroundColor(DARK_PANEL);
             }
         }
     }
@@ -421,10 +940,13 @@ pub fn update_character_select_visuals(
 pub fn cleanup_character_select(
     mut commands: Commands,
     query: Query<Entity, With<CharacterSelectUI>>,
+    mut focused: ResMut<FocusedField>,
 ) {
     for entity in &query {
         commands.entity(entity).despawn_recursive();
     }
+    commands.remove_resource::<RosterState>();
+    focused.0 = None;
 }
 
 // ============ HUD ============
@@ -14,6 +14,21 @@ fn main() {
         console_error_panic_hook::set_once();
     }
 
+    // Native builds can use the same content files as the server
+    #[cfg(not(target_arch = "wasm32"))]
+    if let Ok(dir) = std::env::var("LEGEND_CONTENT_DIR") {
+        use legend_client::shared::data::{install, GameContent};
+        match GameContent::load_dir(std::path::Path::new(&dir)) {
+            Ok(content) => install(content),
+            Err(errors) => {
+                for e in errors {
+                    eprintln!("Invalid game content: {}", e);
+                }
+                std::process::exit(1);
+            }
+        }
+    }
+
     App::new()
         .add_plugins(DefaultPlugins
             .set(WindowPlugin {
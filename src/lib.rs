@@ -2,6 +2,9 @@
 //!
 //! This crate contains shared code between client and server.
 
+// Bevy systems take their inputs as (often long, nested) query parameters
+#![allow(clippy::type_complexity, clippy::too_many_arguments)]
+
 pub mod shared;
 
 #[cfg(feature = "server")]
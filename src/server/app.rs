@@ -0,0 +1,794 @@
+//! API router and the state its handlers share
+
+use axum::extract::FromRef;
+use axum::routing::{delete, get, post};
+use axum::Router;
+
+use super::data::DataCatalog;
+use super::repo::Repos;
+use super::session::SessionKeys;
+use super::throttle::LoginThrottle;
+use super::world::WorldHandle;
+use super::{auth, blacksmith, characters, data, gm, guild, inn, inventory, monsters, realtime, shop, skills};
+
+/// Router state; handlers extract the parts they need with `State<T>`
+#[derive(Clone)]
+pub struct AppState {
+    pub repos: Repos,
+    pub keys: SessionKeys,
+    pub throttle: LoginThrottle,
+    pub world: WorldHandle,
+    pub data: DataCatalog,
+}
+
+impl FromRef<AppState> for Repos {
+    fn from_ref(state: &AppState) -> Self {
+        state.repos.clone()
+    }
+}
+
+impl FromRef<AppState> for SessionKeys {
+    fn from_ref(state: &AppState) -> Self {
+        state.keys.clone()
+    }
+}
+
+impl FromRef<AppState> for LoginThrottle {
+    fn from_ref(state: &AppState) -> Self {
+        state.throttle.clone()
+    }
+}
+
+impl FromRef<AppState> for WorldHandle {
+    fn from_ref(state: &AppState) -> Self {
+        state.world.clone()
+    }
+}
+
+impl FromRef<AppState> for DataCatalog {
+    fn from_ref(state: &AppState) -> Self {
+        state.data.clone()
+    }
+}
+
+/// Routes served under `/api`
+pub fn api_router(state: AppState) -> Router {
+    Router::new()
+        .route("/health", get(health_check))
+        .route("/login", post(auth::login_handler))
+        .route("/register", post(auth::register_handler))
+        .route("/session", get(auth::session_handler))
+        .route("/characters", get(characters::list_characters_handler)
+            .post(characters::create_character_handler))
+        .route("/characters/save", post(characters::save_character_handler))
+        .route("/characters/{id}", delete(characters::delete_character_handler))
+        .route("/characters/{id}/select", post(characters::select_character_handler))
+        .route("/inventory", get(inventory::get_inventory_handler))
+        .route("/inventory/move", post(inventory::move_item_handler))
+        .route("/inventory/equip", post(inventory::equip_item_handler))
+        .route("/inventory/unequip", post(inventory::unequip_item_handler))
+        .route("/inventory/discard", post(inventory::discard_item_handler))
+        .route("/monsters", get(monsters::get_monsters))
+        .route("/monsters/{id}", get(monsters::get_monster_by_id))
+        .route("/skills", get(skills::get_skills))
+        .route("/skills/learn", post(skills::learn_skill_handler))
+        .route("/skills/bar", post(skills::assign_skill_handler))
+        .route("/guild", get(guild::get_guild_handler)
+            .post(guild::create_guild_handler)
+            .delete(guild::disband_guild_handler))
+        .route("/guild/leave", post(guild::leave_guild_handler))
+        .route("/guild/notice", post(guild::set_notice_handler))
+        .route("/guild/invites", get(guild::list_invites_handler)
+            .post(guild::invite_handler))
+        .route("/guild/invites/{id}/accept", post(guild::accept_invite_handler))
+        .route("/guild/members/{id}", delete(guild::kick_handler))
+        .route("/guild/members/{id}/rank", post(guild::set_rank_handler))
+        .route("/guild/bank", get(guild::get_bank_handler))
+        .route("/guild/bank/deposit", post(guild::deposit_item_handler))
+        .route("/guild/bank/withdraw", post(guild::withdraw_item_handler))
+        .route("/guild/bank/gold/deposit", post(guild::deposit_gold_handler))
+        .route("/guild/bank/gold/withdraw", post(guild::withdraw_gold_handler))
+        .route("/shops/{id}", get(shop::get_shop_handler))
+        .route("/shops/{id}/buy", post(shop::buy_handler))
+        .route("/shops/{id}/sell", post(shop::sell_handler))
+        .route("/inns/{id}", get(inn::get_inn_handler))
+        .route("/inns/{id}/rest", post(inn::rest_handler))
+        .route("/inns/{id}/bind", post(inn::bind_handler))
+        .route("/blacksmiths/{id}", get(blacksmith::get_blacksmith_handler))
+        .route("/blacksmiths/{id}/enhance", post(blacksmith::enhance_handler))
+        .route("/data/version", get(data::get_version))
+        .route("/data/{name}", get(data::get_dataset))
+        .route("/gm/characters/{id}", get(gm::inspect_character_handler))
+        .route("/gm/characters/{id}/teleport", post(gm::teleport_handler))
+        .route("/gm/characters/{id}/grant", post(gm::grant_handler))
+        .route("/gm/characters/{id}/level", post(gm::set_level_handler))
+        .route("/gm/users/{id}/ban", post(gm::ban_handler))
+        .route("/gm/users/{id}/suspend", post(gm::suspend_handler))
+        .route("/gm/users/{id}/unban", post(gm::unban_handler))
+        .route("/gm/users/{id}/role", post(gm::set_role_handler))
+        .route("/gm/audit", get(gm::audit_log_handler))
+        .route("/gm/chat", get(gm::chat_log_handler))
+        .route("/ws", get(realtime::ws_handler))
+        .with_state(state)
+}
+
+async fn health_check() -> &'static str {
+    "OK"
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use axum::body::Body;
+    use axum::extract::ConnectInfo;
+    use axum::http::{header, Method, Request, StatusCode};
+    use serde::de::DeserializeOwned;
+    use serde::Serialize;
+    use std::net::SocketAddr;
+    use tower::ServiceExt;
+    use uuid::Uuid;
+
+    use crate::shared::api::*;
+    use crate::shared::data::enhancements::{EnhanceOutcome, ENHANCEMENT_STONE_ID};
+    use crate::shared::data::items::{RED_POTION, WOODEN_SWORD};
+    use crate::shared::data::monsters::ItemGrant;
+    use crate::shared::domain::guild::GuildRank;
+    use crate::shared::protocol::{ChatChannel, ClientMessage, ServerMessage, TradeSlot};
+    use crate::shared::domain::item::inventory::Inventory;
+    use crate::shared::domain::Player;
+    use crate::server::world::World;
+
+    fn test_state() -> AppState {
+        AppState {
+            repos: Repos::in_memory(),
+            keys: SessionKeys::new(b"test-secret"),
+            throttle: LoginThrottle::default(),
+            world: World::with_seed(1).into_handle(),
+            data: DataCatalog::build(),
+        }
+    }
+
+    async fn call<B: Serialize, T: DeserializeOwned>(
+        state: &AppState,
+        method: Method,
+        uri: &str,
+        token: Option<&str>,
+        body: Option<&B>,
+    ) -> (StatusCode, T) {
+        let mut builder = Request::builder().method(method).uri(uri);
+        if let Some(token) = token {
+            builder = builder.header(header::AUTHORIZATION, format!("Bearer {}", token));
+        }
+        let body = match body {
+            Some(b) => {
+                builder = builder.header(header::CONTENT_TYPE, "application/json");
+                Body::from(serde_json::to_vec(b).unwrap())
+            }
+            None => Body::empty(),
+        };
+        let mut request = builder.body(body).unwrap();
+        request.extensions_mut().insert(ConnectInfo(SocketAddr::from(([127, 0, 0, 1], 4000))));
+
+        let response = api_router(state.clone()).oneshot(request).await.unwrap();
+        let status = response.status();
+        let bytes = axum::body::to_bytes(response.into_body(), usize::MAX).await.unwrap();
+        let bytes = if bytes.is_empty() { b"null".as_slice() } else { &bytes };
+        (status, serde_json::from_slice(bytes).unwrap())
+    }
+
+    async fn register(state: &AppState, username: &str) -> StatusCode {
+        let req = RegisterRequest {
+            username: username.to_string(),
+            password: "secret123".to_string(),
+            class_idx: 1,
+            gender: "male".to_string(),
+        };
+        let (status, _): (_, serde_json::Value) = call(state, Method::POST, "/register", None, Some(&req)).await;
+        status
+    }
+
+    async fn login(state: &AppState, username: &str, password: &str) -> (StatusCode, serde_json::Value) {
+        let req = LoginRequest { username: username.to_string(), password: password.to_string() };
+        call(state, Method::POST, "/login", None, Some(&req)).await
+    }
+
+    #[tokio::test]
+    async fn test_register_and_login() {
+        let state = test_state();
+        assert_eq!(register(&state, "hero").await, StatusCode::OK);
+
+        let (status, body) = login(&state, "hero", "secret123").await;
+        assert_eq!(status, StatusCode::OK);
+        let res: LoginResponse = serde_json::from_value(body).unwrap();
+        assert_eq!(res.characters.len(), 1);
+        assert_eq!(res.characters[0].name, "hero");
+
+        let (status, body) = login(&state, "hero", "wrong1234").await;
+        assert_eq!(status, StatusCode::UNAUTHORIZED);
+        let error: ApiErrorBody = serde_json::from_value(body).unwrap();
+        assert_eq!(error.code, ApiErrorCode::InvalidCredentials);
+
+        // Unknown users fail exactly like wrong passwords
+        let (_, unknown) = login(&state, "nobody", "secret123").await;
+        assert_eq!(serde_json::from_value::<ApiErrorBody>(unknown).unwrap(), error);
+    }
+
+    #[tokio::test]
+    async fn test_register_rejects_taken_and_invalid() {
+        let state = test_state();
+        assert_eq!(register(&state, "hero").await, StatusCode::OK);
+        assert_eq!(register(&state, "hero").await, StatusCode::CONFLICT);
+        assert_eq!(register(&state, "x").await, StatusCode::UNPROCESSABLE_ENTITY);
+    }
+
+    #[tokio::test]
+    async fn test_roster_and_inventory() {
+        let state = test_state();
+        register(&state, "hero").await;
+        let (_, body) = login(&state, "hero", "secret123").await;
+        let token = serde_json::from_value::<LoginResponse>(body).unwrap().token.unwrap();
+
+        // Create a second character and see it in the roster
+        let create = CreateCharacterRequest { name: "mage".to_string(), class_id: 3, gender: "female".to_string() };
+        let (_, created): (_, CharacterResponse) = call(&state, Method::POST, "/characters", Some(&token), Some(&create)).await;
+        let mage = created.character.unwrap();
+        let (_, roster): (_, CharacterListResponse) = call::<(), _>(&state, Method::GET, "/characters", Some(&token), None).await;
+        assert_eq!(roster.characters.iter().map(|c| c.name.as_str()).collect::<Vec<_>>(), ["hero", "mage"]);
+
+        // Refusals come back as error statuses, without internal details
+        let (status, error): (_, ApiErrorBody) = call(&state, Method::POST, "/characters", Some(&token), Some(&create)).await;
+        assert_eq!((status, error.code), (StatusCode::CONFLICT, ApiErrorCode::NameTaken));
+        let (status, error): (_, ApiErrorBody) =
+            call(&state, Method::POST, "/inventory/move", Some(&token), Some(&MoveItemRequest { from: 0, to: 5 })).await;
+        assert_eq!((status, error.message.as_str()), (StatusCode::BAD_REQUEST, "No character selected"));
+
+        // Give the mage a potion, then select it and move the potion
+        let mage_id: Uuid = mage.id.parse().unwrap();
+        let user_id = SessionKeys::new(b"test-secret").verify(&token).unwrap().sub;
+        let record = state.repos.characters.load(user_id, mage_id).await.unwrap().unwrap();
+        let mut inventory = Inventory::new();
+        inventory.add_item(RED_POTION.id, 3);
+        state.repos.characters.save(&record, &inventory, &Default::default()).await.unwrap();
+
+        let uri = format!("/characters/{}/select", mage.id);
+        let (_, selected): (_, SelectCharacterResponse) = call::<(), _>(&state, Method::POST, &uri, Some(&token), None).await;
+        let token = selected.token.unwrap();
+
+        let (status, error): (_, ApiErrorBody) =
+            call(&state, Method::POST, "/inventory/move", Some(&token), Some(&MoveItemRequest { from: 7, to: 5 })).await;
+        assert_eq!((status, error.message.as_str()), (StatusCode::BAD_REQUEST, "No item in slot"));
+        let (_, moved): (_, InventoryResponse) =
+            call(&state, Method::POST, "/inventory/move", Some(&token), Some(&MoveItemRequest { from: 0, to: 5 })).await;
+        let slots = moved.inventory.unwrap().slots;
+        assert!(slots[0].is_none());
+        assert_eq!(slots[5].as_ref().map(|s| (s.item_id, s.quantity)), Some((RED_POTION.id, 3)));
+
+        // The move was written through
+        assert!(state.repos.inventory.load(mage_id).await.unwrap().slots[5].is_some());
+
+        let uri = format!("/characters/{}", mage.id);
+        let (_, deleted): (_, CharacterResponse) = call::<(), _>(&state, Method::DELETE, &uri, Some(&token), None).await;
+        assert!(deleted.success);
+        let (_, roster): (_, CharacterListResponse) = call::<(), _>(&state, Method::GET, "/characters", Some(&token), None).await;
+        assert_eq!(roster.characters.len(), 1);
+    }
+
+    async fn get_data(state: &AppState, uri: &str, if_none_match: Option<&str>) -> axum::response::Response {
+        let mut builder = Request::builder().uri(uri);
+        if let Some(tag) = if_none_match {
+            builder = builder.header(header::IF_NONE_MATCH, tag);
+        }
+        api_router(state.clone()).oneshot(builder.body(Body::empty()).unwrap()).await.unwrap()
+    }
+
+    #[tokio::test]
+    async fn test_data_etags_and_version() {
+        let state = test_state();
+        let (status, version): (_, DataVersionResponse) = call::<(), _>(&state, Method::GET, "/data/version", None, None).await;
+        assert_eq!(status, StatusCode::OK);
+        for name in ["items", "maps", "classes", "exp_table", "spawns", "npcs", "portals", "shops", "enhancements", "inns"] {
+            assert!(version.datasets.contains_key(name), "missing {}", name);
+        }
+
+        let response = get_data(&state, "/data/items", None).await;
+        assert_eq!(response.status(), StatusCode::OK);
+        let etag = response.headers()[header::ETAG].to_str().unwrap().to_string();
+        assert_eq!(etag, version.datasets["items"]);
+        let bytes = axum::body::to_bytes(response.into_body(), usize::MAX).await.unwrap();
+        let items: Vec<serde_json::Value> = serde_json::from_slice(&bytes).unwrap();
+        assert_eq!(items[0]["id"], RED_POTION.id);
+
+        // A current copy is not sent again
+        let response = get_data(&state, "/data/items", Some(&format!("\"stale\", {}", etag))).await;
+        assert_eq!(response.status(), StatusCode::NOT_MODIFIED);
+        assert_eq!(response.headers()[header::ETAG], etag.as_str());
+        assert_eq!(get_data(&state, "/data/items", Some("\"stale\"")).await.status(), StatusCode::OK);
+
+        assert_eq!(get_data(&state, "/data/nope", None).await.status(), StatusCode::NOT_FOUND);
+    }
+
+    /// Register and log in; returns the account token and user id
+    async fn account(state: &AppState, username: &str, role: Role) -> (String, Uuid) {
+        register(state, username).await;
+        let user_id = state.repos.users.find_by_username(username).await.unwrap().unwrap().id;
+        state.repos.users.set_role(user_id, role).await.unwrap();
+        let (_, body) = login(state, username, "secret123").await;
+        (serde_json::from_value::<LoginResponse>(body).unwrap().token.unwrap(), user_id)
+    }
+
+    async fn first_character(state: &AppState, user_id: Uuid) -> String {
+        state.repos.characters.roster(user_id).await.unwrap()[0].id.clone()
+    }
+
+    #[tokio::test]
+    async fn test_gm_character_tools() {
+        let state = test_state();
+        let (gm, _) = account(&state, "boss", Role::Gm).await;
+        let (player, hero_id) = account(&state, "hero", Role::Player).await;
+        let hero = first_character(&state, hero_id).await;
+
+        let uri = format!("/gm/characters/{}", hero);
+        let (status, body): (_, ApiErrorBody) = call::<(), _>(&state, Method::GET, &uri, Some(&player), None).await;
+        assert_eq!(status, StatusCode::FORBIDDEN);
+        assert_eq!(body.code, ApiErrorCode::Forbidden);
+
+        let grant = GmGrantRequest {
+            items: vec![ItemGrant { item_id: RED_POTION.id, quantity: 5 }],
+            gold: 500,
+            exp: 0,
+        };
+        let (status, granted): (_, GmCharacterResponse) =
+            call(&state, Method::POST, &format!("{}/grant", uri), Some(&gm), Some(&grant)).await;
+        assert_eq!(status, StatusCode::OK);
+        assert!(!granted.online);
+        assert_eq!(granted.owner.username, "hero");
+        assert_eq!(granted.character.gold, 600);
+        assert_eq!(granted.character.inventory.count_item(RED_POTION.id), 5);
+
+        // Gold that would overflow is refused and nothing is given
+        let grant = GmGrantRequest { gold: i64::MAX, ..grant };
+        let (status, _): (_, ApiErrorBody) = call(&state, Method::POST, &format!("{}/grant", uri), Some(&gm), Some(&grant)).await;
+        assert_eq!(status, StatusCode::BAD_REQUEST);
+
+        let (_, leveled): (_, GmCharacterResponse) =
+            call(&state, Method::POST, &format!("{}/level", uri), Some(&gm), Some(&GmSetLevelRequest { level: 10 })).await;
+        assert_eq!((leveled.character.level, leveled.character.stat_points), (10, 18));
+
+        let teleport = GmTeleportRequest { map_id: "milles_village".to_string(), x: 0, y: 15 };
+        let (status, _): (_, ApiErrorBody) =
+            call(&state, Method::POST, &format!("{}/teleport", uri), Some(&gm), Some(&teleport)).await;
+        assert_eq!(status, StatusCode::BAD_REQUEST);
+
+        let teleport = GmTeleportRequest { map_id: "milles_plains".to_string(), x: 5, y: 6 };
+        call::<_, GmCharacterResponse>(&state, Method::POST, &format!("{}/teleport", uri), Some(&gm), Some(&teleport)).await;
+
+        // Everything was saved
+        let (_, inspected): (_, GmCharacterResponse) = call::<(), _>(&state, Method::GET, &uri, Some(&gm), None).await;
+        let c = inspected.character;
+        assert_eq!((c.level, c.gold, c.current_map.as_str(), c.position.x, c.position.y), (10, 600, "milles_plains", 5.0, 6.0));
+
+        let (_, audit): (_, GmAuditResponse) = call::<(), _>(&state, Method::GET, "/gm/audit", Some(&gm), None).await;
+        let actions: Vec<&str> = audit.entries.iter().map(|e| e.action.as_str()).collect();
+        // Logged before they run, so the refused grant is there too
+        assert_eq!(actions, ["inspect_character", "teleport", "set_level", "grant", "grant"]);
+        assert_eq!(audit.entries[1].target_character_id.as_deref(), Some(hero.as_str()));
+    }
+
+    #[tokio::test]
+    async fn test_gm_moderation() {
+        let state = test_state();
+        let (gm, gm_id) = account(&state, "boss", Role::Gm).await;
+        let (admin, _) = account(&state, "owner", Role::Admin).await;
+        let (_, hero_id) = account(&state, "hero", Role::Player).await;
+        let hero = first_character(&state, hero_id).await;
+
+        // The hero is online and gets teleported, then kicked by the ban
+        let player = crate::server::characters::load_player(&state.repos, hero_id, hero.parse().unwrap()).await.unwrap().unwrap();
+        let (tx, mut rx) = tokio::sync::mpsc::unbounded_channel();
+        state.world.lock().unwrap().join(player, tx, true).unwrap();
+        let teleport = GmTeleportRequest { map_id: "milles_plains".to_string(), x: 3, y: 3 };
+        let uri = format!("/gm/characters/{}/teleport", hero);
+        let (_, moved): (_, GmCharacterResponse) = call(&state, Method::POST, &uri, Some(&gm), Some(&teleport)).await;
+        assert!(moved.online);
+
+        let suspend = GmSuspendRequest { duration_secs: 3600, reason: "spam".to_string() };
+        let (status, suspended): (_, GmAccountResponse) =
+            call(&state, Method::POST, &format!("/gm/users/{}/suspend", hero_id), Some(&gm), Some(&suspend)).await;
+        assert_eq!(status, StatusCode::OK);
+        assert!(matches!(suspended.status, AccountStatus::Suspended { .. }));
+        assert_eq!(state.world.lock().unwrap().player_count(), 0);
+        let mut messages = Vec::new();
+        while let Ok(msg) = rx.try_recv() {
+            messages.push(msg);
+        }
+        assert!(matches!(messages[..], [.., ServerMessage::MapChanged { x: 3, y: 3, .. }, ServerMessage::Error { .. }]));
+
+        // A suspended account with the right password is told why
+        let (status, body) = login(&state, "hero", "secret123").await;
+        assert_eq!(status, StatusCode::FORBIDDEN);
+        assert_eq!(serde_json::from_value::<ApiErrorBody>(body).unwrap().code, ApiErrorCode::AccountDisabled);
+        let (status, _) = login(&state, "hero", "wrong1234").await;
+        assert_eq!(status, StatusCode::UNAUTHORIZED);
+
+        call::<(), GmAccountResponse>(&state, Method::POST, &format!("/gm/users/{}/unban", hero_id), Some(&gm), None).await;
+        assert_eq!(login(&state, "hero", "secret123").await.0, StatusCode::OK);
+
+        let ban = GmBanRequest { reason: "botting".to_string() };
+        call::<_, GmAccountResponse>(&state, Method::POST, &format!("/gm/users/{}/ban", hero_id), Some(&gm), Some(&ban)).await;
+        assert_eq!(login(&state, "hero", "secret123").await.0, StatusCode::FORBIDDEN);
+
+        // GMs can't touch staff or hand out roles; admins can
+        let (status, _): (_, ApiErrorBody) =
+            call(&state, Method::POST, &format!("/gm/users/{}/role", hero_id), Some(&gm), Some(&GmSetRoleRequest { role: Role::Gm })).await;
+        assert_eq!(status, StatusCode::FORBIDDEN);
+        let demote = GmSetRoleRequest { role: Role::Player };
+        let (status, demoted): (_, GmAccountResponse) =
+            call(&state, Method::POST, &format!("/gm/users/{}/role", gm_id), Some(&admin), Some(&demote)).await;
+        assert_eq!((status, demoted.role), (StatusCode::OK, Role::Player));
+        let (status, _): (_, ApiErrorBody) = call::<(), _>(&state, Method::GET, "/gm/audit", Some(&gm), None).await;
+        assert_eq!(status, StatusCode::FORBIDDEN);
+
+        let audit = state.repos.audit.recent(10).await.unwrap();
+        let actions: Vec<&str> = audit.iter().map(|e| e.action.as_str()).collect();
+        assert_eq!(actions, ["set_role", "ban", "unban", "suspend", "teleport"]);
+    }
+
+    #[tokio::test]
+    async fn test_gm_reads_chat_log() {
+        let state = test_state();
+        let (gm, _) = account(&state, "boss", Role::Gm).await;
+        let (player_token, hero_id) = account(&state, "hero", Role::Player).await;
+        let hero = first_character(&state, hero_id).await;
+
+        // What the autosave loop does with the world's chat
+        let player = crate::server::characters::load_player(&state.repos, hero_id, hero.parse().unwrap()).await.unwrap().unwrap();
+        let (tx, _rx) = tokio::sync::mpsc::unbounded_channel();
+        let log = {
+            let mut world = state.world.lock().unwrap();
+            let id = world.join(player, tx, true).unwrap();
+            for text in ["hello", "anyone here?"] {
+                world.push_input(&id, ClientMessage::Chat { channel: ChatChannel::Global, to: None, text: text.to_string() });
+            }
+            world.tick(0.1);
+            world.take_chat_log()
+        };
+        state.repos.chat.append(&log).await.unwrap();
+
+        let uri = format!("/gm/chat?character_id={}&limit=1", hero);
+        let (status, body): (_, GmChatLogResponse) = call::<(), _>(&state, Method::GET, &uri, Some(&gm), None).await;
+        assert_eq!(status, StatusCode::OK);
+        assert_eq!(body.entries.len(), 1);
+        assert_eq!(body.entries[0].message, "anyone here?");
+        assert_eq!(body.entries[0].sender_character_id.as_deref(), Some(hero.as_str()));
+
+        let (status, _): (_, ApiErrorBody) = call::<(), _>(&state, Method::GET, "/gm/chat", Some(&player_token), None).await;
+        assert_eq!(status, StatusCode::FORBIDDEN);
+        assert_eq!(state.repos.audit.recent(1).await.unwrap()[0].action, "read_chat_log");
+    }
+
+    /// Account with its first character selected; returns the token and character id
+    async fn playing(state: &AppState, username: &str) -> (String, Uuid) {
+        let (token, user_id) = account(state, username, Role::Player).await;
+        let uri = format!("/characters/{}/select", first_character(state, user_id).await);
+        let (_, selected): (_, SelectCharacterResponse) = call::<(), _>(state, Method::POST, &uri, Some(&token), None).await;
+        let token = selected.token.unwrap();
+        let claims = SessionKeys::new(b"test-secret").verify(&token).unwrap();
+        (token, claims.cid.unwrap())
+    }
+
+    /// Bring a stored character into the world at `(x, y)`, after `setup`;
+    /// returns its world id
+    async fn join_at(state: &AppState, character_id: Uuid, x: f64, y: f64, setup: impl FnOnce(&mut Player)) -> String {
+        let owner = state.repos.characters.owner(character_id).await.unwrap().unwrap();
+        let mut player = crate::server::characters::load_player(&state.repos, owner, character_id).await.unwrap().unwrap();
+        player.position.x = x;
+        player.position.y = y;
+        setup(&mut player);
+        let (tx, _rx) = tokio::sync::mpsc::unbounded_channel();
+        state.world.lock().unwrap().join(player, tx, true).unwrap()
+    }
+
+    #[tokio::test]
+    async fn test_online_character_cannot_be_deleted() {
+        let state = test_state();
+        let (hero, hero_id) = playing(&state, "hero").await;
+        let user_id = state.repos.characters.owner(hero_id).await.unwrap().unwrap();
+        let player = crate::server::characters::load_player(&state.repos, user_id, hero_id).await.unwrap().unwrap();
+        let (tx, _rx) = tokio::sync::mpsc::unbounded_channel();
+        let id = state.world.lock().unwrap().join(player, tx, true).unwrap();
+
+        let uri = format!("/characters/{}", hero_id);
+        let (status, error): (_, ApiErrorBody) = call::<(), _>(&state, Method::DELETE, &uri, Some(&hero), None).await;
+        assert_eq!((status, error.code), (StatusCode::CONFLICT, ApiErrorCode::CharacterOnline));
+        assert!(state.repos.characters.load(user_id, hero_id).await.unwrap().is_some());
+
+        state.world.lock().unwrap().leave(&id);
+        let (status, deleted): (_, CharacterResponse) = call::<(), _>(&state, Method::DELETE, &uri, Some(&hero), None).await;
+        assert_eq!(status, StatusCode::OK);
+        assert!(deleted.success);
+    }
+
+    #[tokio::test]
+    async fn test_guild_membership_and_ranks() {
+        let state = test_state();
+        let (hero, _) = playing(&state, "hero").await;
+        let (mage, mage_id) = playing(&state, "mage").await;
+        let (rogue, rogue_id) = playing(&state, "rogue").await;
+
+        let create = CreateGuildRequest { name: "Night Watch".to_string() };
+        let (status, guild): (_, GuildResponse) = call(&state, Method::POST, "/guild", Some(&hero), Some(&create)).await;
+        assert_eq!(status, StatusCode::OK);
+        assert_eq!(guild.my_rank, GuildRank::Leader);
+        let (status, body): (_, ApiErrorBody) =
+            call(&state, Method::POST, "/guild", Some(&mage), Some(&CreateGuildRequest { name: "night watch".to_string() })).await;
+        assert_eq!((status, body.code), (StatusCode::CONFLICT, ApiErrorCode::GuildNameTaken));
+
+        for name in ["mage", "rogue"] {
+            let invite = GuildInviteRequest { name: name.to_string() };
+            call::<_, GuildResponse>(&state, Method::POST, "/guild/invites", Some(&hero), Some(&invite)).await;
+        }
+        let (_, invites): (_, GuildInvitesResponse) = call::<(), _>(&state, Method::GET, "/guild/invites", Some(&mage), None).await;
+        assert_eq!(invites.invites[0].guild_name, "Night Watch");
+        assert_eq!(invites.invites[0].invited_by, "hero");
+        for token in [&mage, &rogue] {
+            let uri = format!("/guild/invites/{}/accept", guild.id);
+            let (status, _): (_, GuildResponse) = call::<(), _>(&state, Method::POST, &uri, Some(token), None).await;
+            assert_eq!(status, StatusCode::OK);
+        }
+
+        // Members can't invite, kick or change the notice
+        let (status, _): (_, ApiErrorBody) =
+            call(&state, Method::POST, "/guild/notice", Some(&mage), Some(&GuildNoticeRequest { notice: "hi".to_string() })).await;
+        assert_eq!(status, StatusCode::FORBIDDEN);
+        let (status, _): (_, ApiErrorBody) =
+            call::<(), _>(&state, Method::DELETE, &format!("/guild/members/{}", rogue_id), Some(&mage), None).await;
+        assert_eq!(status, StatusCode::FORBIDDEN);
+
+        // An officer can, but only below their own rank
+        let promote = GuildSetRankRequest { rank: GuildRank::Officer };
+        call::<_, GuildResponse>(&state, Method::POST, &format!("/guild/members/{}/rank", mage_id), Some(&hero), Some(&promote)).await;
+        let notice = GuildNoticeRequest { notice: "Raid at nine".to_string() };
+        let (_, updated): (_, GuildResponse) = call(&state, Method::POST, "/guild/notice", Some(&mage), Some(&notice)).await;
+        assert_eq!(updated.notice, "Raid at nine");
+        let (_, kicked): (_, GuildResponse) =
+            call::<(), _>(&state, Method::DELETE, &format!("/guild/members/{}", rogue_id), Some(&mage), None).await;
+        let names: Vec<&str> = kicked.members.iter().map(|m| m.name.as_str()).collect();
+        assert_eq!(names, ["hero", "mage"]);
+        let (status, _): (_, ApiErrorBody) = call::<(), _>(&state, Method::GET, "/guild", Some(&rogue), None).await;
+        assert_eq!(status, StatusCode::NOT_FOUND);
+
+        // Handing over leadership demotes the old leader, who may then leave
+        let lead = GuildSetRankRequest { rank: GuildRank::Leader };
+        let (_, handed): (_, GuildResponse) =
+            call(&state, Method::POST, &format!("/guild/members/{}/rank", mage_id), Some(&hero), Some(&lead)).await;
+        assert_eq!(handed.my_rank, GuildRank::Officer);
+        assert_eq!(handed.members[0].name, "mage");
+        let (status, _): (_, ApiErrorBody) = call::<(), _>(&state, Method::POST, "/guild/leave", Some(&mage), None).await;
+        assert_eq!(status, StatusCode::BAD_REQUEST);
+        let (status, ()) = call::<(), _>(&state, Method::POST, "/guild/leave", Some(&hero), None).await;
+        assert_eq!(status, StatusCode::NO_CONTENT);
+
+        let (status, ()) = call::<(), _>(&state, Method::DELETE, "/guild", Some(&mage), None).await;
+        assert_eq!(status, StatusCode::NO_CONTENT);
+        assert!(state.repos.guilds.membership(mage_id).await.unwrap().is_none());
+    }
+
+    #[tokio::test]
+    async fn test_guild_bank_at_guild_hall() {
+        let state = test_state();
+        let (hero, hero_id) = playing(&state, "hero").await;
+        call::<_, GuildResponse>(&state, Method::POST, "/guild", Some(&hero), Some(&CreateGuildRequest { name: "Vault".to_string() })).await;
+
+        let user_id = state.repos.characters.owner(hero_id).await.unwrap().unwrap();
+        join_at(&state, hero_id, 8.0, 8.0, |p| {
+            p.inventory = Inventory::new();
+            p.inventory.add_item(RED_POTION.id, 5);
+        }).await;
+
+        // Too far from the hall
+        let deposit = GuildDepositRequest { slot_index: 0, quantity: 3 };
+        let (status, _): (_, ApiErrorBody) = call(&state, Method::POST, "/guild/bank/deposit", Some(&hero), Some(&deposit)).await;
+        assert_eq!(status, StatusCode::BAD_REQUEST);
+
+        state.world.lock().unwrap().with_player(&hero_id.to_string(), |p| {
+            p.position.x = 2.0;
+            p.position.y = 11.0;
+        });
+        let (status, banked): (_, GuildBankResponse) =
+            call(&state, Method::POST, "/guild/bank/deposit", Some(&hero), Some(&deposit)).await;
+        assert_eq!(status, StatusCode::OK);
+        assert_eq!(banked.bank.slots[0].as_ref().map(|s| s.quantity), Some(3));
+        assert_eq!(banked.inventory.count_item(RED_POTION.id), 2);
+
+        let (_, banked): (_, GuildBankResponse) =
+            call(&state, Method::POST, "/guild/bank/gold/deposit", Some(&hero), Some(&GuildGoldRequest { amount: 40 })).await;
+        assert_eq!((banked.bank.gold, banked.gold), (40, 60));
+        // The bank and the character are written together
+        let (guild, _) = state.repos.guilds.membership(hero_id).await.unwrap().unwrap();
+        assert_eq!(state.repos.guilds.bank(guild.id).await.unwrap().gold, 40);
+        assert_eq!(state.repos.characters.load(user_id, hero_id).await.unwrap().unwrap().gold, 60);
+        let (status, _): (_, ApiErrorBody) =
+            call(&state, Method::POST, "/guild/bank/gold/withdraw", Some(&hero), Some(&GuildGoldRequest { amount: 41 })).await;
+        assert_eq!(status, StatusCode::BAD_REQUEST);
+
+        // Nothing is lost with a disbanded guild
+        let (status, _): (_, ApiErrorBody) = call::<(), _>(&state, Method::DELETE, "/guild", Some(&hero), None).await;
+        assert_eq!(status, StatusCode::BAD_REQUEST);
+        let withdraw = GuildWithdrawRequest { bank_slot: 0, quantity: 3 };
+        call::<_, GuildBankResponse>(&state, Method::POST, "/guild/bank/withdraw", Some(&hero), Some(&withdraw)).await;
+        let (_, banked): (_, GuildBankResponse) =
+            call(&state, Method::POST, "/guild/bank/gold/withdraw", Some(&hero), Some(&GuildGoldRequest { amount: 40 })).await;
+        assert!(banked.bank.is_empty());
+        assert_eq!((banked.gold, banked.inventory.count_item(RED_POTION.id)), (100, 5));
+
+        // Written through to the character
+        assert_eq!(state.repos.inventory.load(hero_id).await.unwrap().count_item(RED_POTION.id), 5);
+        let (status, ()) = call::<(), _>(&state, Method::DELETE, "/guild", Some(&hero), None).await;
+        assert_eq!(status, StatusCode::NO_CONTENT);
+    }
+
+    #[tokio::test]
+    async fn test_trade_is_saved_for_both_characters() {
+        let state = test_state();
+        let mut ids = Vec::new();
+        for name in ["hero", "mage"] {
+            let (_, user_id) = account(&state, name, Role::Player).await;
+            let character_id = first_character(&state, user_id).await.parse().unwrap();
+            ids.push(join_at(&state, character_id, 8.0, 8.0, |p| {
+                p.inventory.add_item(RED_POTION.id, 5);
+            }).await);
+        }
+
+        let trades = {
+            let mut world = state.world.lock().unwrap();
+            let (hero, mage) = (&ids[0], &ids[1]);
+            world.push_input(hero, ClientMessage::TradeRequest { name: "mage".to_string() });
+            world.tick(0.1);
+            world.push_input(mage, ClientMessage::TradeAccept { from_id: hero.clone() });
+            world.tick(0.1);
+            let items = vec![TradeSlot { slot_index: 0, quantity: 5 }];
+            world.push_input(hero, ClientMessage::TradeOffer { items, gold: 25 });
+            world.tick(0.1);
+            for message in [ClientMessage::TradeLock, ClientMessage::TradeConfirm] {
+                for id in [hero, mage] {
+                    world.push_input(id, message.clone());
+                }
+                world.tick(0.1);
+            }
+            world.take_completed_trades()
+        };
+        assert_eq!(trades.len(), 1);
+        crate::server::persistence::save_trades(&state.repos, trades).await;
+
+        let [hero, mage] = [&ids[0], &ids[1]].map(|id| id.parse::<Uuid>().unwrap());
+        assert_eq!(state.repos.inventory.load(hero).await.unwrap().count_item(RED_POTION.id), 0);
+        assert_eq!(state.repos.inventory.load(mage).await.unwrap().count_item(RED_POTION.id), 10);
+        let owner = state.repos.characters.owner(mage).await.unwrap().unwrap();
+        assert_eq!(state.repos.characters.load(owner, mage).await.unwrap().unwrap().gold, 125);
+    }
+
+    #[tokio::test]
+    async fn test_shop_buys_and_sells_at_the_shopkeeper() {
+        let state = test_state();
+        let (hero, hero_id) = playing(&state, "hero").await;
+        let user_id = state.repos.characters.owner(hero_id).await.unwrap().unwrap();
+        join_at(&state, hero_id, 8.0, 8.0, |p| p.inventory = Inventory::new()).await;
+
+        // Too far from the shopkeeper
+        let buy = ShopBuyRequest { item_id: RED_POTION.id, quantity: 2 };
+        let (status, _): (_, ApiErrorBody) = call(&state, Method::POST, "/shops/shopkeeper/buy", Some(&hero), Some(&buy)).await;
+        assert_eq!(status, StatusCode::BAD_REQUEST);
+
+        state.world.lock().unwrap().with_player(&hero_id.to_string(), |p| {
+            p.position.x = 12.0;
+            p.position.y = 10.0;
+        });
+        let (status, shop): (_, ShopResponse) = call::<(), _>(&state, Method::GET, "/shops/shopkeeper", Some(&hero), None).await;
+        assert_eq!(status, StatusCode::OK);
+        assert_eq!(shop.items[0], ShopListing { item_id: RED_POTION.id, price: RED_POTION.price_buy });
+        let (status, _): (_, ApiErrorBody) = call::<(), _>(&state, Method::GET, "/shops/blacksmith", Some(&hero), None).await;
+        assert_eq!(status, StatusCode::NOT_FOUND);
+
+        let (status, shop): (_, ShopResponse) = call(&state, Method::POST, "/shops/shopkeeper/buy", Some(&hero), Some(&buy)).await;
+        assert_eq!(status, StatusCode::OK);
+        assert_eq!((shop.gold, shop.inventory.count_item(RED_POTION.id)), (0, 2));
+        let (status, body): (_, ApiErrorBody) = call(&state, Method::POST, "/shops/shopkeeper/buy", Some(&hero), Some(&buy)).await;
+        assert_eq!(status, StatusCode::BAD_REQUEST);
+        assert_eq!(body.message, "Not enough gold");
+
+        let sell = ShopSellRequest { slot_index: 0, quantity: 1 };
+        let (_, shop): (_, ShopResponse) = call(&state, Method::POST, "/shops/shopkeeper/sell", Some(&hero), Some(&sell)).await;
+        assert_eq!((shop.gold, shop.inventory.count_item(RED_POTION.id)), (RED_POTION.price_sell, 1));
+
+        // Written through to the character
+        assert_eq!(state.repos.inventory.load(hero_id).await.unwrap().count_item(RED_POTION.id), 1);
+        assert_eq!(state.repos.characters.load(user_id, hero_id).await.unwrap().unwrap().gold, RED_POTION.price_sell);
+    }
+
+    #[tokio::test]
+    async fn test_inn_rest_and_bind_point() {
+        let state = test_state();
+        let (hero, hero_id) = playing(&state, "hero").await;
+        let user_id = state.repos.characters.owner(hero_id).await.unwrap().unwrap();
+        join_at(&state, hero_id, 8.0, 8.0, |_| {}).await;
+
+        // Too far from the innkeeper
+        let (status, _): (_, ApiErrorBody) = call::<(), _>(&state, Method::POST, "/inns/innkeeper/rest", Some(&hero), None).await;
+        assert_eq!(status, StatusCode::BAD_REQUEST);
+
+        state.world.lock().unwrap().with_player(&hero_id.to_string(), |p| {
+            p.position.x = 3.0;
+            p.position.y = 3.0;
+            p.combat_stats.hp = p.combat_stats.max_hp;
+            p.combat_stats.mp = p.combat_stats.max_mp;
+        });
+        let (status, inn): (_, InnResponse) = call::<(), _>(&state, Method::GET, "/inns/innkeeper", Some(&hero), None).await;
+        assert_eq!(status, StatusCode::OK);
+        assert_eq!((inn.rest_cost, inn.bind_point, inn.bound_here), (10, None, false));
+        let (status, _): (_, ApiErrorBody) = call::<(), _>(&state, Method::GET, "/inns/shopkeeper", Some(&hero), None).await;
+        assert_eq!(status, StatusCode::NOT_FOUND);
+
+        let (status, body): (_, ApiErrorBody) = call::<(), _>(&state, Method::POST, "/inns/innkeeper/rest", Some(&hero), None).await;
+        assert_eq!((status, body.message.as_str()), (StatusCode::BAD_REQUEST, "You are already fully rested"));
+        state.world.lock().unwrap().with_player(&hero_id.to_string(), |p| p.take_damage(5));
+        let (status, inn): (_, InnResponse) = call::<(), _>(&state, Method::POST, "/inns/innkeeper/rest", Some(&hero), None).await;
+        assert_eq!((status, inn.gold), (StatusCode::OK, 90));
+        let rested = state.world.lock().unwrap().player_state(&hero_id.to_string()).unwrap();
+        assert_eq!(rested.combat_stats.hp, rested.combat_stats.max_hp);
+
+        let (_, inn): (_, InnResponse) = call::<(), _>(&state, Method::POST, "/inns/innkeeper/bind", Some(&hero), None).await;
+        assert!(inn.bound_here);
+        let stored = state.repos.characters.load(user_id, hero_id).await.unwrap().unwrap();
+        assert_eq!(stored.bind_point, inn.bind_point);
+        assert_eq!(stored.bind_point.map(|b| b.map_id), Some("milles_village".to_string()));
+        assert_eq!(stored.gold, 90);
+    }
+
+    #[tokio::test]
+    async fn test_blacksmith_enhances_bag_equipment() {
+        let state = test_state();
+        let (hero, hero_id) = playing(&state, "hero").await;
+        join_at(&state, hero_id, 8.0, 8.0, |p| {
+            p.inventory = Inventory::new();
+            p.inventory.add_item(WOODEN_SWORD.id, 1);
+            p.inventory.add_item(ENHANCEMENT_STONE_ID, 1);
+        }).await;
+
+        // Too far from the blacksmith
+        let req = EnhanceRequest { slot_index: 0 };
+        let (status, body): (_, ApiErrorBody) = call(&state, Method::POST, "/blacksmiths/blacksmith/enhance", Some(&hero), Some(&req)).await;
+        assert_eq!((status, body.message.as_str()), (StatusCode::BAD_REQUEST, "You must be at the blacksmith"));
+        assert_eq!(state.world.lock().unwrap().player_state(&hero_id.to_string()).unwrap().gold, 100);
+
+        state.world.lock().unwrap().with_player(&hero_id.to_string(), |p| {
+            p.position.x = 13.0;
+            p.position.y = 3.0;
+        });
+        let (status, _): (_, ApiErrorBody) = call::<(), _>(&state, Method::GET, "/blacksmiths/shopkeeper", Some(&hero), None).await;
+        assert_eq!(status, StatusCode::NOT_FOUND);
+        let (status, smith): (_, BlacksmithResponse) = call::<(), _>(&state, Method::GET, "/blacksmiths/blacksmith", Some(&hero), None).await;
+        assert_eq!(status, StatusCode::OK);
+        assert_eq!(smith.options.iter().map(|o| (o.slot_index, o.level)).collect::<Vec<_>>(), vec![(0, 1)]);
+
+        // The stone is not equipment; +1 never fails
+        let req = EnhanceRequest { slot_index: 1 };
+        let (status, _): (_, ApiErrorBody) = call(&state, Method::POST, "/blacksmiths/blacksmith/enhance", Some(&hero), Some(&req)).await;
+        assert_eq!(status, StatusCode::BAD_REQUEST);
+        let req = EnhanceRequest { slot_index: 0 };
+        let (status, smith): (_, BlacksmithResponse) = call(&state, Method::POST, "/blacksmiths/blacksmith/enhance", Some(&hero), Some(&req)).await;
+        assert_eq!(status, StatusCode::OK);
+        assert_eq!(smith.outcome, Some(EnhanceOutcome::Success));
+        assert_eq!((smith.gold, smith.inventory.count_item(ENHANCEMENT_STONE_ID)), (0, 0));
+
+        let bag = state.repos.inventory.load(hero_id).await.unwrap();
+        assert_eq!(bag.slots[0].as_ref().map(|s| s.display_name()), Some("+1 Wooden Sword".to_string()));
+    }
+
+    #[tokio::test]
+    async fn test_requires_token() {
+        let state = test_state();
+        let (status, body): (_, ApiErrorBody) = call::<(), _>(&state, Method::GET, "/inventory", None, None).await;
+        assert_eq!(status, StatusCode::UNAUTHORIZED);
+        assert_eq!(body.code, ApiErrorCode::Unauthorized);
+    }
+}
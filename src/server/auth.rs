@@ -3,217 +3,163 @@
 //! Server feature only
 
 #[cfg(feature = "server")]
-use axum::{Json, Extension};
+use axum::{extract::{ConnectInfo, State}, Json};
 #[cfg(feature = "server")]
-use sqlx::PgPool;
+use std::net::SocketAddr;
+#[cfg(feature = "server")]
+use super::repo::{NewCharacter, Repos};
+#[cfg(feature = "server")]
+use super::session::{AuthUser, SessionKeys};
+#[cfg(feature = "server")]
+use super::throttle::LoginThrottle;
+#[cfg(feature = "server")]
+use super::error::ApiError;
+#[cfg(feature = "server")]
+use crate::shared::api::AccountStatus;
 
-use serde::{Deserialize, Serialize};
-use crate::shared::domain::Player;
+use serde::Serialize;
 
-// Request/Response DTOs
-#[derive(Debug, Deserialize)]
-pub struct LoginRequest {
-    pub username: String,
-    pub password: String,
-}
+// Request/Response DTOs (shared with the client)
+pub use crate::shared::api::{LoginRequest, LoginResponse, RegisterRequest, RegisterResponse};
 
 #[derive(Debug, Serialize)]
-pub struct LoginResponse {
-    pub success: bool,
-    pub player: Option<Player>,
-    pub error: Option<String>,
+pub struct SessionResponse {
+    pub user_id: String,
+    pub character_id: Option<String>,
 }
 
-#[derive(Debug, Deserialize)]
-pub struct RegisterRequest {
-    pub username: String,
-    pub password: String,
-    pub class_idx: i32,
-    pub gender: String,
-}
+// --- Server Handlers ---
 
-#[derive(Debug, Serialize)]
-pub struct RegisterResponse {
-    pub success: bool,
-    pub message: String,
+/// Reject banned and suspended accounts
+#[cfg(feature = "server")]
+pub fn check_account_status(status: &AccountStatus) -> Result<(), ApiError> {
+    match status {
+        AccountStatus::Active => Ok(()),
+        AccountStatus::Banned => Err(ApiError::AccountBanned),
+        AccountStatus::Suspended { until } => Err(ApiError::AccountSuspended { until: *until }),
+    }
 }
 
-// --- Server Handlers ---
+/// Failure message for bad credentials and locked accounts alike, so
+/// responses don't reveal which usernames exist
+pub const LOGIN_FAILED: &str = "Invalid username or password, or too many attempts. Try again later.";
 
+/// Hash checked when the username doesn't exist, so unknown and known
+/// accounts take the same time to reject
+#[cfg(feature = "server")]
+static DUMMY_HASH: std::sync::LazyLock<String> = std::sync::LazyLock::new(|| {
+    bcrypt::hash("legend-dummy-password", bcrypt::DEFAULT_COST).unwrap_or_default()
+});
+
+/// Verify credentials and return the account's roster with an account token
 #[cfg(feature = "server")]
 pub async fn login_handler(
-    Extension(pool): Extension<PgPool>,
+    State(repos): State<Repos>,
+    State(keys): State<SessionKeys>,
+    State(throttle): State<LoginThrottle>,
+    ConnectInfo(addr): ConnectInfo<SocketAddr>,
     Json(req): Json<LoginRequest>,
-) -> Json<LoginResponse> {
-    use bcrypt::verify;
-    use sqlx::Row;
-    use crate::shared::domain::character::models::PlayerClass;
-    use crate::shared::domain::shared::models::{Stats, CombatStats, Position, Direction};
-    use crate::shared::data::characters::get_class_by_id;
+) -> Result<Json<LoginResponse>, ApiError> {
+    use std::time::Instant;
+    
+    let ip = addr.ip();
+    if let Err(retry_after) = throttle.check(ip, Instant::now()) {
+        return Err(ApiError::TooManyAttempts { retry_after_secs: retry_after.as_secs().max(1) });
+    }
     
     // 1. Get User
-    let row: Option<(uuid::Uuid, String)> = sqlx::query_as(
-        "SELECT id, password_hash FROM users WHERE username = $1"
-    )
-    .bind(&req.username)
-    .fetch_optional(&pool)
-    .await
-    .unwrap_or(None);
+    let user = repos.users.find_by_username(&req.username).await?;
     
-    let (user_id, password_hash) = match row {
-        Some(u) => u,
-        None => return Json(LoginResponse {
-            success: false,
-            player: None,
-            error: Some("User not found".to_string()),
-        }),
+    // 2. Verify Password (bcrypt is slow; keep it off the async workers,
+    // including the first use of the dummy hash, which computes it)
+    let password_hash = user.as_ref().map(|u| u.password_hash.clone());
+    let password = req.password.clone();
+    let verified = tokio::task::spawn_blocking(move || {
+        let hash = password_hash.as_deref().unwrap_or(&DUMMY_HASH);
+        bcrypt::verify(&password, hash).unwrap_or(false)
+    })
+        .await
+        .map_err(|e| ApiError::Internal(format!("Password check failed: {}", e)))?;
+    
+    let user_id = match user {
+        Some(user) if verified && !user.locked => {
+            // Only reveal a ban to someone who knows the password
+            check_account_status(&user.status)?;
+            user.id
+        }
+        Some(user) => {
+            throttle.record_failure(ip, Instant::now());
+            // A locked account doesn't extend its lock on further attempts
+            if !user.locked {
+                let limits = throttle.limits();
+                repos.users.record_login_failure(user.id, limits.max_account_failures, limits.account_lockout_secs as f64).await?;
+            }
+            return Err(ApiError::InvalidCredentials);
+        }
+        None => {
+            throttle.record_failure(ip, Instant::now());
+            return Err(ApiError::InvalidCredentials);
+        }
     };
     
-    // 2. Verify Password
-    if !verify(&req.password, &password_hash).unwrap_or(false) {
-        return Json(LoginResponse {
-            success: false,
-            player: None,
-            error: Some("Invalid password".to_string()),
-        });
-    }
+    repos.users.record_login(user_id).await?;
     
-    // 3. Get Character
-    let char_row = sqlx::query(
-        r#"
-        SELECT id, name, gender, class_id, level, exp, gold, current_map, pos_x, pos_y,
-               bonus_str_stat, bonus_dex_stat, bonus_int_stat, bonus_wis_stat, bonus_con_stat, stat_points
-        FROM characters
-        WHERE user_id = $1
-        LIMIT 1
-        "#
-    )
-    .bind(user_id)
-    .fetch_optional(&pool)
-    .await
-    .unwrap_or(None);
+    // 3. Get Roster (the player picks a character next)
+    let characters = repos.characters.roster(user_id).await?;
     
-    if let Some(c) = char_row {
-        let class_id: i32 = c.get("class_id");
-        let player_class = match class_id {
-            1 => PlayerClass::Warrior,
-            2 => PlayerClass::Rogue,
-            3 => PlayerClass::Mage,
-            4 => PlayerClass::Cleric,
-            5 => PlayerClass::MartialArtist,
-            _ => PlayerClass::Warrior,
-        };
-        
-        let bonus_stats = Stats {
-            str_stat: c.try_get::<i32, _>("bonus_str_stat").unwrap_or(0),
-            dex_stat: c.try_get::<i32, _>("bonus_dex_stat").unwrap_or(0),
-            int_stat: c.try_get::<i32, _>("bonus_int_stat").unwrap_or(0),
-            wis_stat: c.try_get::<i32, _>("bonus_wis_stat").unwrap_or(0),
-            con_stat: c.try_get::<i32, _>("bonus_con_stat").unwrap_or(0),
-        };
-        
-        // Load base stats from class definition
-        let class_def = get_class_by_id(class_id).expect("Invalid class ID in DB");
-        let final_stats = class_def.base_stats + bonus_stats;
-        
-        let level: i32 = c.try_get("level").unwrap_or(1);
-        let combat_stats = CombatStats::from_stats(&final_stats, level);
-
-        let char_id: uuid::Uuid = c.get("id");
-        let player = Player {
-            id: char_id.to_string(),
-            username: c.get("name"),
-            gender: c.get("gender"),
-            class: player_class,
-            level,
-            exp: c.try_get("exp").unwrap_or(0),
-            exp_to_next_level: crate::shared::data::characters::exp_to_next_level(level),
-            stats: final_stats,
-            stat_points: c.try_get("stat_points").unwrap_or(0),
-            combat_stats,
-            equipment: std::collections::HashMap::new(),
-            inventory: vec![None; 24],
-            current_map: c.get("current_map"),
-            position: Position { 
-                x: c.try_get::<f64, _>("pos_x").unwrap_or(400.0), 
-                y: c.try_get::<f64, _>("pos_y").unwrap_or(300.0) 
-            },
-            direction: Direction::Down,
-            gold: c.try_get("gold").unwrap_or(0),
-            is_moving: false,
-            is_attacking: false,
-            target_monster_id: None,
-            last_attack_time: 0.0,
-            attack_cooldown: 1000.0,
-        };
-        
-        return Json(LoginResponse {
-            success: true,
-            player: Some(player),
-            error: None,
-        });
-    }
+    let token = keys.issue(user_id, None)
+        .map_err(|e| ApiError::Internal(format!("Failed to issue token: {}", e)))?;
     
-    Json(LoginResponse {
-        success: false,
-        player: None,
-        error: Some("Character not found".to_string()),
-    })
+    Ok(Json(LoginResponse {
+        success: true,
+        token: Some(token),
+        characters,
+        error: None,
+    }))
 }
 
+/// Create an account and its first character (named after the account)
+/// in one transaction
 #[cfg(feature = "server")]
 pub async fn register_handler(
-    Extension(pool): Extension<PgPool>,
+    State(repos): State<Repos>,
     Json(req): Json<RegisterRequest>,
-) -> Json<RegisterResponse> {
+) -> Result<Json<RegisterResponse>, ApiError> {
     use bcrypt::{hash, DEFAULT_COST};
-    use uuid::Uuid;
-    
-    // Hash password
-    let hashed = match hash(&req.password, DEFAULT_COST) {
-        Ok(h) => h,
-        Err(_) => return Json(RegisterResponse {
-            success: false,
-            message: "Failed to hash password".to_string(),
-        }),
-    };
     
-    // Create User
-    let user_id = Uuid::new_v4();
-    if let Err(e) = sqlx::query(
-        "INSERT INTO users (id, username, password_hash) VALUES ($1, $2, $3)"
-    )
-    .bind(user_id)
-    .bind(&req.username)
-    .bind(&hashed)
-    .execute(&pool)
-    .await {
-        return Json(RegisterResponse {
-            success: false,
-            message: format!("Failed to create user: {}", e),
-        });
+    let errors = crate::shared::validation::validate_registration(&req);
+    if !errors.is_empty() {
+        return Err(ApiError::Validation(errors));
     }
+    let username = req.username.trim().to_string();
     
-    // Create Character with default starting values
-    let char_id = Uuid::new_v4();
-    if let Err(e) = sqlx::query(
-        "INSERT INTO characters (id, user_id, name, class_id, gender) VALUES ($1, $2, $3, $4, $5)"
-    )
-    .bind(char_id)
-    .bind(user_id)
-    .bind(&req.username)
-    .bind(req.class_idx)
-    .bind(&req.gender)
-    .execute(&pool)
-    .await {
-        return Json(RegisterResponse {
-            success: false,
-            message: format!("Failed to create character: {}", e),
-        });
-    }
+    // Hash password
+    let password = req.password.clone();
+    let hashed = tokio::task::spawn_blocking(move || hash(&password, DEFAULT_COST))
+        .await
+        .map_err(|e| ApiError::Internal(format!("Failed to hash password: {}", e)))?
+        .map_err(|e| ApiError::Internal(format!("Failed to hash password: {}", e)))?;
     
-    Json(RegisterResponse {
+    let (hp, mp) = super::characters::starting_vitals(req.class_idx);
+    repos.users.register(&username, &hashed, NewCharacter {
+        name: username.clone(),
+        class_id: req.class_idx,
+        gender: req.gender.clone(),
+        hp,
+        mp,
+    }).await?;
+    
+    Ok(Json(RegisterResponse {
         success: true,
         message: "Registration successful".to_string(),
+    }))
+}
+
+/// Identity of the caller's access token
+#[cfg(feature = "server")]
+pub async fn session_handler(user: AuthUser) -> Json<SessionResponse> {
+    Json(SessionResponse {
+        user_id: user.user_id.to_string(),
+        character_id: user.character_id.map(|id| id.to_string()),
     })
 }
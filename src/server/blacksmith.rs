@@ -0,0 +1,180 @@
+//! Blacksmith handlers - Axum REST API
+//!
+//! Blacksmiths are NPCs of type `NpcType::Blacksmith`. They enhance
+//! equipment in the bag one level at a time, for the gold and materials
+//! the enhancement table asks (see `shared::data::enhancements`). Every
+//! roll happens here, never on the client.
+
+use axum::extract::{Path, State};
+use axum::Json;
+use rand::Rng;
+
+use crate::shared::api::{BlacksmithResponse, EnhanceOption, EnhanceRequest};
+use crate::shared::data::enhancements::{next_enhancement, roll_enhancement, EnhanceOutcome, EnhancementDef};
+use crate::shared::data::items::{get_item_by_id, ItemCategory, ItemDef};
+use crate::shared::data::maps::{find_npc, NpcDef, NpcType};
+use crate::shared::domain::item::inventory::{Inventory, ItemStack};
+use crate::shared::domain::Player;
+use super::error::ApiError;
+use super::persistence::{at_place, save_player, Place};
+use super::repo::Repos;
+use super::session::AuthUser;
+use super::world::WorldHandle;
+
+/// Only equipment can be enhanced
+pub fn enhanceable(def: &ItemDef) -> bool {
+    matches!(def.category, ItemCategory::Weapon | ItemCategory::Armor | ItemCategory::Accessory)
+}
+
+/// The next enhancement of a stack, if it is equipment below the top level
+fn next_for(stack: &ItemStack) -> Option<&'static EnhancementDef> {
+    stack.get_def().filter(|def| enhanceable(def))?;
+    next_enhancement(stack.enhancement)
+}
+
+/// Every bag slot a blacksmith could enhance, with its next step
+pub fn enhance_options(bag: &Inventory) -> Vec<EnhanceOption> {
+    bag.slots.iter().enumerate()
+        .filter_map(|(i, slot)| Some((i, next_for(slot.as_ref()?)?)))
+        .map(|(slot_index, step)| EnhanceOption {
+            slot_index,
+            level: step.level,
+            gold_cost: step.gold_cost,
+            material_id: step.material_id,
+            material_quantity: step.material_quantity,
+            success_chance: step.success_chance,
+            destroy_chance: step.destroy_chance,
+        })
+        .collect()
+}
+
+/// Pay for one enhancement of the equipment in a bag slot and roll it.
+/// The cost is spent whatever the outcome; nothing changes if it can't be paid.
+pub fn enhance<R: Rng + ?Sized>(player: &mut Player, slot_index: usize, rng: &mut R) -> Result<EnhanceOutcome, String> {
+    let stack = player.inventory.slots.get(slot_index)
+        .and_then(|s| s.as_ref())
+        .ok_or("No item in slot")?;
+    let def = stack.get_def().ok_or("Unknown item")?;
+    if !enhanceable(def) {
+        return Err(format!("{} cannot be enhanced", def.name));
+    }
+    let step = next_enhancement(stack.enhancement)
+        .ok_or_else(|| format!("{} cannot be enhanced any further", stack.display_name()))?;
+    if step.gold_cost > player.gold {
+        return Err("Not enough gold".to_string());
+    }
+    if !player.inventory.has_item(step.material_id, step.material_quantity) {
+        let material = get_item_by_id(step.material_id).map(|d| d.name).unwrap_or("materials");
+        return Err(format!("You need {} {}", step.material_quantity, material));
+    }
+
+    player.gold -= step.gold_cost;
+    player.inventory.remove_item(step.material_id, step.material_quantity);
+    let outcome = roll_enhancement(rng, step);
+    let slot = &mut player.inventory.slots[slot_index];
+    match outcome {
+        EnhanceOutcome::Success => {
+            if let Some(stack) = slot.as_mut() {
+                stack.enhancement = step.level;
+            }
+        }
+        EnhanceOutcome::Destroyed => *slot = None,
+        EnhanceOutcome::Failure => {}
+    }
+    Ok(outcome)
+}
+
+/// A blacksmith NPC on any map, with the id of its map
+fn find_blacksmith(npc_id: &str) -> Result<(&'static str, &'static NpcDef), ApiError> {
+    find_npc(npc_id)
+        .filter(|(_, npc)| npc.npc_type == NpcType::Blacksmith)
+        .ok_or(ApiError::NotFound("Blacksmith"))
+}
+
+fn blacksmith_response(smith: &NpcDef, player: Player, outcome: Option<EnhanceOutcome>) -> Json<BlacksmithResponse> {
+    Json(BlacksmithResponse {
+        npc_id: smith.id.to_string(),
+        options: enhance_options(&player.inventory),
+        inventory: player.inventory,
+        gold: player.gold,
+        outcome,
+    })
+}
+
+// --- Server Handlers ---
+
+pub async fn get_blacksmith_handler(
+    State(world): State<WorldHandle>,
+    user: AuthUser,
+    Path(npc_id): Path<String>,
+) -> Result<Json<BlacksmithResponse>, ApiError> {
+    let character_id = user.character()?;
+    let (map_id, smith) = find_blacksmith(&npc_id)?;
+    let ((), player) = at_place(&world, character_id, Place::Npc { map_id, npc_id: smith.id }, "the blacksmith", |_| Ok(()))
+        .map_err(ApiError::BadRequest)?;
+    Ok(blacksmith_response(smith, player, None))
+}
+
+pub async fn enhance_handler(
+    State(repos): State<Repos>,
+    State(world): State<WorldHandle>,
+    user: AuthUser,
+    Path(npc_id): Path<String>,
+    Json(req): Json<EnhanceRequest>,
+) -> Result<Json<BlacksmithResponse>, ApiError> {
+    let character_id = user.character()?;
+    let (map_id, smith) = find_blacksmith(&npc_id)?;
+    let (outcome, player) = at_place(&world, character_id, Place::Npc { map_id, npc_id: smith.id }, "the blacksmith", |p| {
+        enhance(p, req.slot_index, &mut rand::thread_rng())
+    }).map_err(ApiError::BadRequest)?;
+    save_player(&repos, &player).await?;
+    Ok(blacksmith_response(smith, player, Some(outcome)))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use rand::rngs::mock::StepRng;
+    use crate::shared::data::enhancements::ENHANCEMENT_STONE_ID;
+    use crate::shared::data::items::{IRON_SWORD, RED_POTION};
+    use crate::shared::domain::PlayerClass;
+
+    /// An rng whose every float roll lands at `fraction` of the range
+    fn rolling(fraction: f64) -> StepRng {
+        StepRng::new((fraction * u64::MAX as f64) as u64, 0)
+    }
+
+    #[test]
+    fn test_enhance_spends_the_cost_and_applies_the_roll() {
+        let mut alice = Player::new("alice".to_string(), PlayerClass::Warrior);
+        alice.inventory = Inventory::new();
+        alice.inventory.add_item(IRON_SWORD.id, 1);
+        alice.inventory.add_item(RED_POTION.id, 1);
+        alice.gold = 1_000;
+
+        assert_eq!(enhance(&mut alice, 1, &mut rolling(0.0)), Err("Red Potion cannot be enhanced".to_string()));
+        assert_eq!(enhance(&mut alice, 0, &mut rolling(0.0)), Err("You need 1 Enhancement Stone".to_string()));
+        assert_eq!(alice.gold, 1_000);
+
+        alice.inventory.add_item(ENHANCEMENT_STONE_ID, 3);
+        assert_eq!(enhance(&mut alice, 0, &mut rolling(0.0)), Ok(EnhanceOutcome::Success));
+        assert_eq!(alice.inventory.slots[0].as_ref().unwrap().display_name(), "+1 Iron Sword");
+        assert_eq!((alice.gold, alice.inventory.count_item(ENHANCEMENT_STONE_ID)), (900, 2));
+
+        // +1 -> +2 succeeds 95% of the time; a failed roll still costs
+        assert_eq!(enhance(&mut alice, 0, &mut rolling(0.99)), Ok(EnhanceOutcome::Failure));
+        assert_eq!(alice.inventory.slots[0].as_ref().unwrap().enhancement, 1);
+        assert_eq!((alice.gold, alice.inventory.count_item(ENHANCEMENT_STONE_ID)), (700, 1));
+
+        let options = enhance_options(&alice.inventory);
+        assert_eq!(options.iter().map(|o| (o.slot_index, o.level)).collect::<Vec<_>>(), vec![(0, 2)]);
+
+        // Past +5 a bad roll destroys the item
+        alice.inventory.slots[0].as_mut().unwrap().enhancement = 6;
+        alice.inventory.add_item(ENHANCEMENT_STONE_ID, 2);
+        alice.gold = 2_200;
+        assert_eq!(enhance(&mut alice, 0, &mut rolling(0.55)), Ok(EnhanceOutcome::Destroyed));
+        assert_eq!(alice.inventory.count_item(IRON_SWORD.id), 0);
+        assert_eq!(alice.gold, 0);
+    }
+}
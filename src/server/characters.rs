@@ -0,0 +1,219 @@
+//! Character roster handlers - Axum REST API
+//!
+//! List, create, delete and select characters on the caller's account.
+
+use axum::extract::{Path, State};
+use axum::Json;
+use uuid::Uuid;
+
+use crate::shared::api::{
+    CharacterListResponse, CharacterResponse, CreateCharacterRequest,
+    SaveCharacterResponse, SelectCharacterResponse, MAX_CHARACTERS_PER_ACCOUNT,
+};
+use crate::shared::data::characters::{exp_to_next_level, get_class_by_id};
+use crate::shared::domain::character::models::PlayerClass;
+use crate::shared::domain::guild::GuildRank;
+use crate::shared::validation::{validate_character_name, validate_class_id, validate_gender};
+use crate::shared::domain::item::inventory::Inventory;
+use crate::shared::domain::shared::models::{CombatStats, Direction, Position, Stats};
+use crate::shared::domain::skill::models::SkillBook;
+use crate::shared::domain::Player;
+use super::error::ApiError;
+use super::persistence::save_player;
+use super::repo::{CharacterRecord, NewCharacter, RepoError, Repos};
+use super::session::{AuthUser, SessionKeys};
+use super::world::WorldHandle;
+
+/// Load a character owned by `user_id` as a playable `Player`
+pub async fn load_player(repos: &Repos, user_id: Uuid, character_id: Uuid) -> Result<Option<Player>, RepoError> {
+    let Some(c) = repos.characters.load(user_id, character_id).await? else { return Ok(None); };
+
+    let inventory = repos.inventory.load(character_id).await?;
+    let skills = repos.skills.load(character_id, c.class_id).await?;
+    Ok(Some(player_from_record(c, inventory, skills)))
+}
+
+/// Rebuild a `Player` from its stored row
+fn player_from_record(c: CharacterRecord, inventory: Inventory, skills: SkillBook) -> Player {
+    let player_class = PlayerClass::from_id(c.class_id).unwrap_or(PlayerClass::Warrior);
+
+    // Load base stats from class definition
+    let class_def = get_class_by_id(c.class_id).expect("Invalid class ID in DB");
+    let final_stats = class_def.base_stats + c.bonus_stats;
+
+    let mut combat_stats = CombatStats::from_stats(&final_stats, c.level);
+
+    // Resume with the stored vitals; a character saved dead comes back with 1 HP
+    combat_stats.hp = c.hp.clamp(1, combat_stats.max_hp);
+    combat_stats.mp = c.mp.clamp(0, combat_stats.max_mp);
+
+    Player {
+        id: c.id.to_string(),
+        username: c.name,
+        gender: c.gender,
+        class: player_class,
+        level: c.level,
+        exp: c.exp,
+        exp_to_next_level: exp_to_next_level(c.level),
+        stats: final_stats,
+        stat_points: c.stat_points,
+        combat_stats,
+        inventory,
+        skills,
+        current_map: c.current_map,
+        bind_point: c.bind_point,
+        position: Position { x: c.pos_x, y: c.pos_y },
+        direction: Direction::Down,
+        gold: c.gold,
+        is_moving: false,
+        is_attacking: false,
+        target_monster_id: None,
+        last_attack_time: 0.0,
+        attack_cooldown: 1000.0,
+    }
+}
+
+/// The stored row for a `Player`. Only allocated points are stored;
+/// base stats come from the class definition.
+pub fn character_record(player: &Player) -> Result<CharacterRecord, uuid::Error> {
+    let base = get_class_by_id(player.class.id()).map(|c| c.base_stats).unwrap_or_default();
+    Ok(CharacterRecord {
+        id: Uuid::parse_str(&player.id)?,
+        name: player.username.clone(),
+        class_id: player.class.id(),
+        gender: player.gender.clone(),
+        level: player.level,
+        exp: player.exp,
+        hp: player.combat_stats.hp,
+        mp: player.combat_stats.mp,
+        gold: player.gold,
+        current_map: player.current_map.clone(),
+        pos_x: player.position.x,
+        pos_y: player.position.y,
+        bind_point: player.bind_point.clone(),
+        bonus_stats: Stats {
+            str_stat: player.stats.str_stat - base.str_stat,
+            dex_stat: player.stats.dex_stat - base.dex_stat,
+            int_stat: player.stats.int_stat - base.int_stat,
+            wis_stat: player.stats.wis_stat - base.wis_stat,
+            con_stat: player.stats.con_stat - base.con_stat,
+        },
+        stat_points: player.stat_points,
+    })
+}
+
+/// Full HP/MP for a new level 1 character of a class
+pub fn starting_vitals(class_id: i32) -> (i32, i32) {
+    let base = get_class_by_id(class_id).map(|c| c.base_stats).unwrap_or_default();
+    let combat = CombatStats::from_stats(&base, 1);
+    (combat.max_hp, combat.max_mp)
+}
+
+// --- Server Handlers ---
+
+pub async fn list_characters_handler(
+    State(repos): State<Repos>,
+    user: AuthUser,
+) -> Result<Json<CharacterListResponse>, ApiError> {
+    let characters = repos.characters.roster(user.user_id).await?;
+    Ok(Json(CharacterListResponse {
+        success: true,
+        characters,
+        error: None,
+    }))
+}
+
+pub async fn create_character_handler(
+    State(repos): State<Repos>,
+    user: AuthUser,
+    Json(req): Json<CreateCharacterRequest>,
+) -> Result<Json<CharacterResponse>, ApiError> {
+    let name = req.name.trim().to_string();
+    validate_character_name(&name)
+        .and_then(|_| validate_class_id(req.class_id))
+        .and_then(|_| validate_gender(&req.gender))
+        .map_err(ApiError::BadRequest)?;
+
+    if repos.characters.roster(user.user_id).await?.len() >= MAX_CHARACTERS_PER_ACCOUNT {
+        return Err(ApiError::BadRequest(format!("An account can have at most {} characters", MAX_CHARACTERS_PER_ACCOUNT)));
+    }
+
+    let (hp, mp) = starting_vitals(req.class_id);
+    let character = repos.characters.create(user.user_id, NewCharacter {
+        name,
+        class_id: req.class_id,
+        gender: req.gender,
+        hp,
+        mp,
+    }).await?;
+    Ok(Json(CharacterResponse {
+        success: true,
+        character: Some(character),
+        error: None,
+    }))
+}
+
+pub async fn delete_character_handler(
+    State(repos): State<Repos>,
+    State(world): State<WorldHandle>,
+    user: AuthUser,
+    Path(character_id): Path<Uuid>,
+) -> Result<Json<CharacterResponse>, ApiError> {
+    if repos.characters.owner(character_id).await? != Some(user.user_id) {
+        return Err(ApiError::NotFound("Character"));
+    }
+    // The world would keep saving it, and its trade or party would point nowhere
+    if world.lock().unwrap().is_online(&character_id.to_string()) {
+        return Err(ApiError::CharacterOnline);
+    }
+    // A guild is never left without a leader
+    if let Some((guild, GuildRank::Leader)) = repos.guilds.membership(character_id).await? {
+        return Err(ApiError::BadRequest(format!("Hand over leadership of {} or disband it first", guild.name)));
+    }
+
+    if !repos.characters.delete(user.user_id, character_id).await? {
+        return Err(ApiError::NotFound("Character"));
+    }
+    Ok(Json(CharacterResponse {
+        success: true,
+        character: None,
+        error: None,
+    }))
+}
+
+/// Pick the character to play; returns it with a token bound to it
+pub async fn select_character_handler(
+    State(repos): State<Repos>,
+    State(keys): State<SessionKeys>,
+    user: AuthUser,
+    Path(character_id): Path<Uuid>,
+) -> Result<Json<SelectCharacterResponse>, ApiError> {
+    let player = load_player(&repos, user.user_id, character_id).await?
+        .ok_or(ApiError::NotFound("Character"))?;
+    let token = keys.issue(user.user_id, Some(character_id))
+        .map_err(|e| ApiError::Internal(format!("Failed to issue token: {}", e)))?;
+
+    if let Err(e) = repos.characters.mark_played(character_id).await {
+        tracing::warn!("Failed to mark {} as played: {}", character_id, e);
+    }
+
+    Ok(Json(SelectCharacterResponse {
+        success: true,
+        player: Some(player),
+        token: Some(token),
+        error: None,
+    }))
+}
+
+/// Write the active character's in-world state to the database now
+pub async fn save_character_handler(
+    State(repos): State<Repos>,
+    State(world): State<WorldHandle>,
+    user: AuthUser,
+) -> Result<Json<SaveCharacterResponse>, ApiError> {
+    let character_id = user.character()?;
+    let player = world.lock().unwrap().player_state(&character_id.to_string())
+        .ok_or_else(|| ApiError::BadRequest("Character is not in the world".to_string()))?;
+    save_player(&repos, &player).await?;
+    Ok(Json(SaveCharacterResponse { success: true, error: None }))
+}
@@ -0,0 +1,153 @@
+//! Chat - rate limiting, word filter and the moderation log
+//!
+//! Chat travels over the real-time connection: `ClientMessage::Chat` is
+//! routed by the `World` to the sender's map, one whisper recipient, the
+//! sender's party or everyone. Each sender may send `max_messages` per
+//! `window_secs`; banned words are masked with `*` before delivery.
+//! Every delivered message is queued for the `chat_log` table with its
+//! original text.
+
+use std::collections::VecDeque;
+
+use chrono::{DateTime, Utc};
+use serde::Deserialize;
+use uuid::Uuid;
+
+use crate::shared::protocol::{ChatChannel, MAX_CHAT_LEN};
+
+/// Chat limits and filter (the `chat` section of the server config)
+#[derive(Debug, Clone, PartialEq, Deserialize)]
+#[serde(default, deny_unknown_fields)]
+pub struct ChatSettings {
+    /// Messages one sender may send per window
+    pub max_messages: u32,
+    pub window_secs: f64,
+    /// Masked in delivered messages, ignoring case
+    pub banned_words: Vec<String>,
+}
+
+impl Default for ChatSettings {
+    fn default() -> Self {
+        Self {
+            max_messages: 5,
+            window_secs: 5.0,
+            banned_words: Vec::new(),
+        }
+    }
+}
+
+/// Send times of one sender's recent messages
+#[derive(Debug, Clone, Default)]
+pub struct ChatLimiter {
+    sent: VecDeque<f64>,
+}
+
+impl ChatLimiter {
+    /// Count a message sent at `now` (seconds); false if over the limit
+    pub fn allow(&mut self, now: f64, settings: &ChatSettings) -> bool {
+        while self.sent.front().is_some_and(|t| now - t >= settings.window_secs) {
+            self.sent.pop_front();
+        }
+        if self.sent.len() >= settings.max_messages as usize {
+            return false;
+        }
+        self.sent.push_back(now);
+        true
+    }
+}
+
+/// Masks banned words, matching case-insensitively anywhere in the text
+#[derive(Debug, Clone, Default)]
+pub struct WordFilter {
+    words: Vec<Vec<char>>,
+}
+
+fn fold(c: char) -> char {
+    c.to_lowercase().next().unwrap_or(c)
+}
+
+impl WordFilter {
+    pub fn new(words: &[String]) -> Self {
+        let words = words.iter()
+            .map(|w| w.trim().chars().map(fold).collect::<Vec<_>>())
+            .filter(|w| !w.is_empty())
+            .collect();
+        Self { words }
+    }
+
+    /// `text` with each banned word replaced by `*`s; None if nothing matched
+    pub fn apply(&self, text: &str) -> Option<String> {
+        let chars: Vec<char> = text.chars().collect();
+        let folded: Vec<char> = chars.iter().copied().map(fold).collect();
+        let mut masked = vec![false; chars.len()];
+        for word in &self.words {
+            for start in 0..folded.len().saturating_sub(word.len() - 1) {
+                if folded[start..start + word.len()] == word[..] {
+                    masked[start..start + word.len()].fill(true);
+                }
+            }
+        }
+        masked.contains(&true).then(|| {
+            chars.iter().zip(&masked).map(|(c, m)| if *m { '*' } else { *c }).collect()
+        })
+    }
+}
+
+/// Trimmed text without control characters; Err if empty or too long
+pub fn clean_message(text: &str) -> Result<String, String> {
+    let text: String = text.chars().filter(|c| !c.is_control()).collect::<String>().trim().to_string();
+    if text.is_empty() {
+        return Err("Message is empty".to_string());
+    }
+    if text.chars().count() > MAX_CHAT_LEN {
+        return Err(format!("Messages are limited to {} characters", MAX_CHAT_LEN));
+    }
+    Ok(text)
+}
+
+/// A delivered message waiting to be written to `chat_log`
+#[derive(Debug, Clone, PartialEq)]
+pub struct NewChatLog {
+    pub channel: ChatChannel,
+    /// None for guests
+    pub sender_character_id: Option<Uuid>,
+    pub sender_name: String,
+    pub recipient_name: Option<String>,
+    pub map_id: String,
+    /// As typed, before the word filter
+    pub message: String,
+    pub filtered: bool,
+    pub sent_at: DateTime<Utc>,
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_limiter_allows_a_burst_per_window() {
+        let settings = ChatSettings { max_messages: 2, window_secs: 1.0, ..Default::default() };
+        let mut limiter = ChatLimiter::default();
+        assert!(limiter.allow(0.0, &settings));
+        assert!(limiter.allow(0.1, &settings));
+        assert!(!limiter.allow(0.5, &settings));
+        // The first message left the window
+        assert!(limiter.allow(1.0, &settings));
+        assert!(!limiter.allow(1.05, &settings));
+    }
+
+    #[test]
+    fn test_filter_masks_words_ignoring_case() {
+        let filter = WordFilter::new(&["darn".to_string(), "바보".to_string(), " ".to_string()]);
+        assert_eq!(filter.apply("Well DARN it, 바보야").as_deref(), Some("Well **** it, **야"));
+        assert_eq!(filter.apply("hello there"), None);
+        assert_eq!(WordFilter::default().apply("darn"), None);
+    }
+
+    #[test]
+    fn test_clean_message() {
+        assert_eq!(clean_message("  hi\u{7}  ").unwrap(), "hi");
+        assert!(clean_message(" \n ").is_err());
+        assert!(clean_message(&"a".repeat(MAX_CHAT_LEN + 1)).is_err());
+    }
+}
@@ -0,0 +1,362 @@
+//! Server configuration - RON file plus environment overrides
+//!
+//! Settings are read from `LEGEND_CONFIG` (default `config/server.ron`,
+//! which may be absent), then environment variables override single
+//! values. Every value is checked before the server starts.
+
+use std::net::SocketAddr;
+use std::path::{Path, PathBuf};
+use std::str::FromStr;
+
+use axum::http::HeaderValue;
+use serde::Deserialize;
+use tower_http::cors::{AllowOrigin, Any, CorsLayer};
+
+use super::chat::ChatSettings;
+use super::throttle::LoginLimits;
+use crate::shared::protocol::TICK_RATE;
+
+/// Config file read when `LEGEND_CONFIG` is not set
+pub const DEFAULT_CONFIG_PATH: &str = "config/server.ron";
+
+/// Shortest accepted JWT secret (bytes)
+pub const MIN_JWT_SECRET_LEN: usize = 16;
+
+/// Publicly known signing secret, accepted only in dev mode
+pub const DEV_JWT_SECRET: &str = "legend-dev-secret";
+
+#[derive(Debug, thiserror::Error)]
+pub enum ConfigError {
+    #[error("cannot read config file {path}: {source}")]
+    Read { path: PathBuf, source: std::io::Error },
+    #[error("cannot parse config file {path}: {source}")]
+    Parse { path: PathBuf, source: ron::error::SpannedError },
+    #[error("environment variable {var}={value:?} is invalid: {reason}")]
+    Env { var: &'static str, value: String, reason: String },
+    #[error("invalid config value `{field}`: {reason}")]
+    Invalid { field: &'static str, reason: String },
+}
+
+#[derive(Debug, Clone, PartialEq, Deserialize)]
+#[serde(default, deny_unknown_fields)]
+pub struct DatabaseConfig {
+    pub url: String,
+    pub max_connections: u32,
+    pub min_connections: u32,
+    /// How long a request waits for a free connection
+    pub acquire_timeout_secs: u64,
+}
+
+impl Default for DatabaseConfig {
+    fn default() -> Self {
+        Self {
+            url: "postgresql://legend:legend@db:5432/legend".to_string(),
+            max_connections: 5,
+            min_connections: 0,
+            acquire_timeout_secs: 30,
+        }
+    }
+}
+
+#[derive(Debug, Clone, PartialEq, Deserialize)]
+#[serde(default, deny_unknown_fields)]
+pub struct CorsConfig {
+    /// Exact origins such as `https://play.example.com`; `"*"` allows any
+    pub allowed_origins: Vec<String>,
+}
+
+impl Default for CorsConfig {
+    fn default() -> Self {
+        Self { allowed_origins: vec!["*".to_string()] }
+    }
+}
+
+#[derive(Debug, Clone, PartialEq, Deserialize)]
+#[serde(default, deny_unknown_fields)]
+pub struct ServerConfig {
+    pub listen_addr: SocketAddr,
+    pub database: DatabaseConfig,
+    pub cors: CorsConfig,
+    /// Token signing secret; required unless `dev_mode` is on
+    pub jwt_secret: Option<String>,
+    /// Local development: sign tokens with `DEV_JWT_SECRET` when no secret is set
+    pub dev_mode: bool,
+    /// Served under `/assets`
+    pub asset_root: PathBuf,
+    /// World simulation ticks per second
+    pub tick_rate: u32,
+    pub rate_limits: LoginLimits,
+    pub chat: ChatSettings,
+    /// Data files overriding the built-in game content
+    pub content_dir: Option<PathBuf>,
+    /// How often `content_dir` is checked for edits; 0 turns reloading off
+    pub content_reload_secs: u64,
+    /// Reloads allowed before `content_dir` stops being watched. Each one
+    /// keeps its copy of the content in memory until the server restarts.
+    pub content_reload_limit: u32,
+}
+
+impl Default for ServerConfig {
+    fn default() -> Self {
+        Self {
+            listen_addr: SocketAddr::from(([0, 0, 0, 0], 3000)),
+            database: DatabaseConfig::default(),
+            cors: CorsConfig::default(),
+            jwt_secret: None,
+            dev_mode: false,
+            asset_root: PathBuf::from("public/assets"),
+            tick_rate: TICK_RATE,
+            rate_limits: LoginLimits::default(),
+            chat: ChatSettings::default(),
+            content_dir: None,
+            content_reload_secs: 2,
+            content_reload_limit: 50,
+        }
+    }
+}
+
+fn parse_env<T: FromStr>(var: &'static str, value: String) -> Result<T, ConfigError>
+where
+    T::Err: std::fmt::Display,
+{
+    value.trim().parse().map_err(|e: T::Err| ConfigError::Env { var, value, reason: e.to_string() })
+}
+
+fn invalid(field: &'static str, reason: impl Into<String>) -> ConfigError {
+    ConfigError::Invalid { field, reason: reason.into() }
+}
+
+impl ServerConfig {
+    /// Load the file, apply the process environment and validate
+    pub fn load() -> Result<Self, ConfigError> {
+        let (path, required) = match std::env::var("LEGEND_CONFIG") {
+            Ok(path) => (PathBuf::from(path), true),
+            Err(_) => (PathBuf::from(DEFAULT_CONFIG_PATH), false),
+        };
+
+        let mut config = if required || path.exists() {
+            Self::from_file(&path)?
+        } else {
+            Self::default()
+        };
+        config.apply_env(|var| std::env::var(var).ok())?;
+        config.validate()?;
+        Ok(config)
+    }
+
+    pub fn from_file(path: &Path) -> Result<Self, ConfigError> {
+        let text = std::fs::read_to_string(path)
+            .map_err(|source| ConfigError::Read { path: path.to_path_buf(), source })?;
+        ron::from_str(&text).map_err(|source| ConfigError::Parse { path: path.to_path_buf(), source })
+    }
+
+    /// Override single values from environment variables
+    pub fn apply_env(&mut self, var: impl Fn(&str) -> Option<String>) -> Result<(), ConfigError> {
+        if let Some(v) = var("LEGEND_LISTEN_ADDR") {
+            self.listen_addr = parse_env("LEGEND_LISTEN_ADDR", v)?;
+        }
+        if let Some(v) = var("DATABASE_URL") {
+            self.database.url = v;
+        }
+        if let Some(v) = var("LEGEND_DB_MAX_CONNECTIONS") {
+            self.database.max_connections = parse_env("LEGEND_DB_MAX_CONNECTIONS", v)?;
+        }
+        if let Some(v) = var("LEGEND_DB_MIN_CONNECTIONS") {
+            self.database.min_connections = parse_env("LEGEND_DB_MIN_CONNECTIONS", v)?;
+        }
+        if let Some(v) = var("LEGEND_CORS_ORIGINS") {
+            self.cors.allowed_origins = v.split(',').map(|o| o.trim().to_string()).filter(|o| !o.is_empty()).collect();
+        }
+        if let Some(v) = var("JWT_SECRET") {
+            self.jwt_secret = Some(v).filter(|s| !s.is_empty());
+        }
+        if let Some(v) = var("LEGEND_DEV") {
+            self.dev_mode = match v.trim() {
+                "1" | "true" => true,
+                "" | "0" | "false" => false,
+                _ => return Err(ConfigError::Env { var: "LEGEND_DEV", value: v, reason: "expected 1 or 0".to_string() }),
+            };
+        }
+        if let Some(v) = var("LEGEND_ASSET_ROOT") {
+            self.asset_root = PathBuf::from(v);
+        }
+        if let Some(v) = var("LEGEND_CONTENT_DIR") {
+            self.content_dir = Some(PathBuf::from(v)).filter(|p| !p.as_os_str().is_empty());
+        }
+        if let Some(v) = var("LEGEND_TICK_RATE") {
+            self.tick_rate = parse_env("LEGEND_TICK_RATE", v)?;
+        }
+        if let Some(v) = var("LEGEND_LOGIN_MAX_IP_FAILURES") {
+            self.rate_limits.max_ip_failures = parse_env("LEGEND_LOGIN_MAX_IP_FAILURES", v)?;
+        }
+        if let Some(v) = var("LEGEND_LOGIN_MAX_ACCOUNT_FAILURES") {
+            self.rate_limits.max_account_failures = parse_env("LEGEND_LOGIN_MAX_ACCOUNT_FAILURES", v)?;
+        }
+        Ok(())
+    }
+
+    pub fn validate(&self) -> Result<(), ConfigError> {
+        let db = &self.database;
+        if db.url.is_empty() {
+            return Err(invalid("database.url", "must not be empty"));
+        }
+        if db.max_connections == 0 {
+            return Err(invalid("database.max_connections", "must be at least 1"));
+        }
+        if db.min_connections > db.max_connections {
+            return Err(invalid("database.min_connections", format!("must not exceed max_connections ({})", db.max_connections)));
+        }
+
+        if self.cors.allowed_origins.is_empty() {
+            return Err(invalid("cors.allowed_origins", "list at least one origin, or \"*\""));
+        }
+        for origin in &self.cors.allowed_origins {
+            let valid = origin == "*"
+                || ((origin.starts_with("http://") || origin.starts_with("https://")) && HeaderValue::from_str(origin).is_ok());
+            if !valid {
+                return Err(invalid("cors.allowed_origins", format!("{:?} is not \"*\" or an http(s) origin", origin)));
+            }
+        }
+        if self.cors.allowed_origins.len() > 1 && self.cors.allowed_origins.iter().any(|o| o == "*") {
+            return Err(invalid("cors.allowed_origins", "\"*\" cannot be combined with other origins"));
+        }
+
+        match &self.jwt_secret {
+            None if !self.dev_mode => {
+                return Err(invalid("jwt_secret", "must be set (or JWT_SECRET); LEGEND_DEV=1 allows the development secret"));
+            }
+            Some(secret) if secret.len() < MIN_JWT_SECRET_LEN => {
+                return Err(invalid("jwt_secret", format!("must be at least {} bytes", MIN_JWT_SECRET_LEN)));
+            }
+            Some(secret) if secret == DEV_JWT_SECRET && !self.dev_mode => {
+                return Err(invalid("jwt_secret", "is the public development secret; only LEGEND_DEV=1 accepts it"));
+            }
+            _ => {}
+        }
+
+        // A missing directory only means no assets yet; a file is a mistake
+        if self.asset_root.exists() && !self.asset_root.is_dir() {
+            return Err(invalid("asset_root", format!("{} is not a directory", self.asset_root.display())));
+        }
+
+        if let Some(dir) = &self.content_dir
+            && !dir.is_dir()
+        {
+            return Err(invalid("content_dir", format!("{} is not a directory", dir.display())));
+        }
+
+        if !(1..=60).contains(&self.tick_rate) {
+            return Err(invalid("tick_rate", "must be between 1 and 60"));
+        }
+
+        let limits = &self.rate_limits;
+        if limits.max_ip_failures == 0 || limits.ip_window_secs == 0 {
+            return Err(invalid("rate_limits", "max_ip_failures and ip_window_secs must be at least 1"));
+        }
+        if limits.max_account_failures < 1 || limits.account_lockout_secs == 0 {
+            return Err(invalid("rate_limits", "max_account_failures and account_lockout_secs must be at least 1"));
+        }
+
+        if self.chat.max_messages == 0 || self.chat.window_secs.is_nan() || self.chat.window_secs <= 0.0 {
+            return Err(invalid("chat", "max_messages and window_secs must be positive"));
+        }
+        Ok(())
+    }
+
+    /// Token signing secret: `jwt_secret`, or the development secret in dev
+    /// mode. `validate` refuses configurations that have neither.
+    pub fn signing_secret(&self) -> Option<&str> {
+        match &self.jwt_secret {
+            Some(secret) => Some(secret),
+            None => self.dev_mode.then_some(DEV_JWT_SECRET),
+        }
+    }
+
+    pub fn cors_layer(&self) -> CorsLayer {
+        let origins = &self.cors.allowed_origins;
+        let allow_origin = if origins.iter().any(|o| o == "*") {
+            AllowOrigin::any()
+        } else {
+            // Checked by `validate`
+            AllowOrigin::list(origins.iter().filter_map(|o| HeaderValue::from_str(o).ok()))
+        };
+        CorsLayer::new()
+            .allow_origin(allow_origin)
+            .allow_methods(Any)
+            .allow_headers(Any)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::collections::HashMap;
+
+    fn valid() -> ServerConfig {
+        ServerConfig { jwt_secret: Some("a-test-secret-of-32-bytes-length".to_string()), ..Default::default() }
+    }
+
+    #[test]
+    fn test_file_sections_default_individually() {
+        let config: ServerConfig = ron::from_str("(tick_rate: 20, database: (max_connections: 10))").unwrap();
+        assert_eq!(config.tick_rate, 20);
+        assert_eq!(config.database.max_connections, 10);
+        assert_eq!(config.database.url, DatabaseConfig::default().url);
+
+        assert!(ron::from_str::<ServerConfig>("(tick_rat: 20)").is_err());
+    }
+
+    #[test]
+    fn test_env_overrides_and_rejects_bad_numbers() {
+        let env = HashMap::from([
+            ("LEGEND_LISTEN_ADDR", "127.0.0.1:8080"),
+            ("LEGEND_CORS_ORIGINS", "https://a.example, https://b.example"),
+            ("JWT_SECRET", ""),
+            ("LEGEND_DEV", "1"),
+        ]);
+        let mut config = valid();
+        config.apply_env(|v| env.get(v).map(|s| s.to_string())).unwrap();
+        assert_eq!(config.listen_addr, SocketAddr::from(([127, 0, 0, 1], 8080)));
+        assert_eq!(config.cors.allowed_origins, ["https://a.example", "https://b.example"]);
+        assert_eq!(config.jwt_secret, None);
+        assert!(config.dev_mode);
+
+        let err = valid().apply_env(|v| (v == "LEGEND_DEV").then(|| "yes".to_string())).unwrap_err();
+        assert!(err.to_string().contains("LEGEND_DEV"));
+        let err = valid().apply_env(|v| (v == "LEGEND_TICK_RATE").then(|| "fast".to_string())).unwrap_err();
+        assert!(err.to_string().contains("LEGEND_TICK_RATE"));
+    }
+
+    #[test]
+    fn test_validation_names_the_bad_field() {
+        assert!(valid().validate().is_ok());
+
+        let field = |config: ServerConfig| match config.validate() {
+            Err(ConfigError::Invalid { field, .. }) => field,
+            other => panic!("expected an invalid field, got {:?}", other),
+        };
+        assert_eq!(field(ServerConfig { tick_rate: 0, ..valid() }), "tick_rate");
+        assert_eq!(field(ServerConfig { jwt_secret: Some("short".to_string()), ..valid() }), "jwt_secret");
+        assert_eq!(field(ServerConfig { jwt_secret: None, ..valid() }), "jwt_secret");
+        assert_eq!(field(ServerConfig { jwt_secret: Some(DEV_JWT_SECRET.to_string()), ..valid() }), "jwt_secret");
+        assert_eq!(field(ServerConfig { asset_root: PathBuf::from("Cargo.toml"), ..valid() }), "asset_root");
+        assert_eq!(field(ServerConfig { content_dir: Some(PathBuf::from("no/such/dir")), ..valid() }), "content_dir");
+        let cors = CorsConfig { allowed_origins: vec!["example.com".to_string()] };
+        assert_eq!(field(ServerConfig { cors, ..valid() }), "cors.allowed_origins");
+        let database = DatabaseConfig { min_connections: 9, ..Default::default() };
+        assert_eq!(field(ServerConfig { database, ..valid() }), "database.min_connections");
+        let chat = ChatSettings { window_secs: f64::NAN, ..Default::default() };
+        assert_eq!(field(ServerConfig { chat, ..valid() }), "chat");
+    }
+
+    #[test]
+    fn test_only_dev_mode_falls_back_to_the_dev_secret() {
+        assert_eq!(ServerConfig::default().signing_secret(), None);
+        assert!(ServerConfig::default().validate().is_err());
+
+        let dev = ServerConfig { dev_mode: true, ..Default::default() };
+        assert!(dev.validate().is_ok());
+        assert_eq!(dev.signing_secret(), Some(DEV_JWT_SECRET));
+        assert_eq!(valid().signing_secret(), Some("a-test-secret-of-32-bytes-length"));
+    }
+}
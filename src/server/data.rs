@@ -0,0 +1,205 @@
+//! Read-only game data API
+//!
+//! Every dataset from `shared::data` is serialized once and served with
+//! a content-hash ETag, so clients and external tools can revalidate with
+//! `If-None-Match` instead of downloading it again. `/data/version`
+//! hashes all ETags together to detect any change. When a content
+//! directory is configured, edits to it are picked up while running.
+
+use std::collections::BTreeMap;
+use std::path::PathBuf;
+use std::sync::{Arc, RwLock};
+use std::time::{Duration, SystemTime};
+
+use axum::body::Bytes;
+use axum::extract::{Path, State};
+use axum::http::{header, HeaderMap, HeaderValue, StatusCode};
+use axum::response::{IntoResponse, Response};
+use serde::Serialize;
+use sha2::{Digest, Sha256};
+
+use crate::shared::api::DataVersionResponse;
+use crate::shared::data::characters::{ALL_CLASSES, EXP_TABLE};
+use crate::shared::data::content::{self, section_files, GameContent, SECTIONS};
+use crate::shared::data::items::all_items;
+use crate::shared::data::maps::{all_maps, get_map_npcs, get_map_portals, get_map_spawns};
+use crate::shared::domain::skill::SkillQuery;
+use super::error::ApiError;
+use super::monsters::all_monster_dtos;
+
+/// Clients may cache, but must revalidate before each use
+const CACHE_CONTROL: &str = "public, no-cache";
+
+/// One serialized dataset and its strong ETag
+#[derive(Debug, Clone)]
+pub struct Dataset {
+    pub etag: String,
+    pub body: Bytes,
+}
+
+impl Dataset {
+    fn from_json(body: Vec<u8>) -> Self {
+        let etag = format!("\"{:x}\"", Sha256::digest(&body));
+        Self { etag, body: Bytes::from(body) }
+    }
+
+    fn new<T: Serialize + ?Sized>(value: &T) -> Self {
+        Self::from_json(serde_json::to_vec(value).expect("game data serializes to JSON"))
+    }
+
+    /// `If-None-Match` lists this ETag (or is `*`)
+    fn matches(&self, headers: &HeaderMap) -> bool {
+        headers.get_all(header::IF_NONE_MATCH).iter()
+            .filter_map(|v| v.to_str().ok())
+            .flat_map(|v| v.split(','))
+            .map(|tag| tag.trim())
+            .any(|tag| tag == "*" || tag.strip_prefix("W/").unwrap_or(tag) == self.etag)
+    }
+
+    fn respond(&self, headers: &HeaderMap) -> Response {
+        let etag = HeaderValue::from_str(&self.etag).expect("hex ETag is a valid header");
+        let cache = [(header::ETAG, etag), (header::CACHE_CONTROL, HeaderValue::from_static(CACHE_CONTROL))];
+        if self.matches(headers) {
+            return (StatusCode::NOT_MODIFIED, cache).into_response();
+        }
+        let content_type = (header::CONTENT_TYPE, HeaderValue::from_static("application/json"));
+        (cache, [content_type], self.body.clone()).into_response()
+    }
+}
+
+/// Per-map lists keyed by map id; maps without entries are left out
+fn per_map<T: Serialize>(lookup: fn(&str) -> &'static [T]) -> BTreeMap<&'static str, &'static [T]> {
+    all_maps().iter()
+        .map(|m| (m.id, lookup(m.id)))
+        .filter(|(_, entries)| !entries.is_empty())
+        .collect()
+}
+
+/// All datasets by name, plus the combined version
+#[derive(Debug)]
+struct Snapshot {
+    datasets: BTreeMap<&'static str, Dataset>,
+    version: Dataset,
+}
+
+impl Snapshot {
+    fn build() -> Self {
+        let datasets = BTreeMap::from([
+            ("items", Dataset::new(all_items())),
+            ("maps", Dataset::new(all_maps())),
+            ("classes", Dataset::new(ALL_CLASSES)),
+            ("exp_table", Dataset::new(&EXP_TABLE[..])),
+            ("monsters", Dataset::new(&all_monster_dtos())),
+            ("skills", Dataset::new(&SkillQuery::default().run())),
+            ("spawns", Dataset::new(&per_map(get_map_spawns))),
+            ("npcs", Dataset::new(&per_map(get_map_npcs))),
+            ("portals", Dataset::new(&per_map(get_map_portals))),
+            ("shops", Dataset::new(&content::content().shops)),
+            ("enhancements", Dataset::new(&content::content().enhancements)),
+            ("inns", Dataset::new(&content::content().inns)),
+        ]);
+
+        let mut hasher = Sha256::new();
+        for (name, dataset) in &datasets {
+            hasher.update(name.as_bytes());
+            hasher.update(dataset.etag.as_bytes());
+        }
+        let version = DataVersionResponse {
+            version: format!("{:x}", hasher.finalize()),
+            datasets: datasets.iter().map(|(name, d)| (name.to_string(), d.etag.clone())).collect(),
+        };
+
+        Self { datasets, version: Dataset::new(&version) }
+    }
+}
+
+/// Serialized game data, rebuilt when the content changes
+#[derive(Debug, Clone)]
+pub struct DataCatalog {
+    snapshot: Arc<RwLock<Arc<Snapshot>>>,
+}
+
+impl DataCatalog {
+    /// Serialize the active game content
+    pub fn build() -> Self {
+        Self { snapshot: Arc::new(RwLock::new(Arc::new(Snapshot::build()))) }
+    }
+
+    /// Re-serialize after new content was installed
+    pub fn rebuild(&self) {
+        *self.snapshot.write().unwrap() = Arc::new(Snapshot::build());
+    }
+
+    fn current(&self) -> Arc<Snapshot> {
+        self.snapshot.read().unwrap().clone()
+    }
+
+    pub fn get(&self, name: &str) -> Option<Dataset> {
+        self.current().datasets.get(name).cloned()
+    }
+
+    pub fn version(&self) -> Dataset {
+        self.current().version.clone()
+    }
+}
+
+/// Modification times of every section file, to notice edits
+fn content_stamp(dir: &std::path::Path) -> Vec<(PathBuf, Option<SystemTime>)> {
+    SECTIONS.iter()
+        .flat_map(|section| section_files(dir, section))
+        .map(|path| {
+            let modified = std::fs::metadata(&path).and_then(|m| m.modified()).ok();
+            (path, modified)
+        })
+        .collect()
+}
+
+/// Poll `dir` and install its content whenever a file changes
+///
+/// Content that fails to load is logged and the running data is kept.
+/// Installed content is never freed (see `content::install`), so polling
+/// stops after `limit` reloads.
+pub fn spawn_content_reload_loop(dir: PathBuf, interval: Duration, limit: u32, catalog: DataCatalog) {
+    tokio::spawn(async move {
+        let mut ticker = tokio::time::interval(interval);
+        let mut last = content_stamp(&dir);
+        let mut reloads = 0;
+        while reloads < limit {
+            ticker.tick().await;
+            let stamp = content_stamp(&dir);
+            if stamp == last {
+                continue;
+            }
+            last = stamp;
+            match GameContent::load_dir(&dir) {
+                Ok(loaded) => {
+                    content::install(loaded);
+                    catalog.rebuild();
+                    reloads += 1;
+                    tracing::info!("🔄 Reloaded game content from {}", dir.display());
+                }
+                Err(errors) => {
+                    for e in errors {
+                        tracing::error!("Game content not reloaded: {}", e);
+                    }
+                }
+            }
+        }
+        tracing::warn!("Game content reloaded {} times; restart the server to pick up further edits", limit);
+    });
+}
+
+/// `GET /data/{name}` - one dataset, or 304 if the client's copy is current
+pub async fn get_dataset(
+    State(catalog): State<DataCatalog>,
+    Path(name): Path<String>,
+    headers: HeaderMap,
+) -> Result<Response, ApiError> {
+    let dataset = catalog.get(&name).ok_or(ApiError::NotFound("Unknown dataset"))?;
+    Ok(dataset.respond(&headers))
+}
+
+/// `GET /data/version` - ETags of every dataset and their combined hash
+pub async fn get_version(State(catalog): State<DataCatalog>, headers: HeaderMap) -> Response {
+    catalog.version().respond(&headers)
+}
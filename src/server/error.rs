@@ -0,0 +1,151 @@
+//! API errors - typed failures with HTTP status codes
+//!
+//! Handlers return `Result<Json<T>, ApiError>`. Each variant maps to a
+//! status and an `ApiErrorCode`; internal details are logged, never sent.
+
+use axum::http::{header, StatusCode};
+use axum::response::{IntoResponse, Response};
+use axum::Json;
+
+use crate::shared::api::{ApiErrorBody, ApiErrorCode, FieldError};
+use super::repo::RepoError;
+
+#[derive(Debug, thiserror::Error)]
+pub enum ApiError {
+    #[error("{0}")]
+    BadRequest(String),
+    #[error("Some fields are invalid")]
+    Validation(Vec<FieldError>),
+    #[error("{}", super::auth::LOGIN_FAILED)]
+    InvalidCredentials,
+    #[error("Too many login attempts. Try again in {retry_after_secs} seconds.")]
+    TooManyAttempts { retry_after_secs: u64 },
+    #[error("{0}")]
+    Unauthorized(&'static str),
+    #[error("{0}")]
+    Forbidden(&'static str),
+    #[error("This account has been banned")]
+    AccountBanned,
+    #[error("This account is suspended until {}", until.format("%Y-%m-%d %H:%M UTC"))]
+    AccountSuspended { until: chrono::DateTime<chrono::Utc> },
+    #[error("{0} not found")]
+    NotFound(&'static str),
+    #[error("Username is already taken")]
+    UsernameTaken,
+    #[error("Character name is already taken")]
+    NameTaken,
+    #[error("Guild name is already taken")]
+    GuildNameTaken,
+    #[error("Log out of this character first")]
+    CharacterOnline,
+    #[error("Database error: {0}")]
+    Database(#[from] sqlx::Error),
+    #[error("{0}")]
+    Internal(String),
+}
+
+impl ApiError {
+    pub fn status(&self) -> StatusCode {
+        match self {
+            Self::BadRequest(_) => StatusCode::BAD_REQUEST,
+            Self::Validation(_) => StatusCode::UNPROCESSABLE_ENTITY,
+            Self::InvalidCredentials | Self::Unauthorized(_) => StatusCode::UNAUTHORIZED,
+            Self::TooManyAttempts { .. } => StatusCode::TOO_MANY_REQUESTS,
+            Self::Forbidden(_) | Self::AccountBanned | Self::AccountSuspended { .. } => StatusCode::FORBIDDEN,
+            Self::NotFound(_) => StatusCode::NOT_FOUND,
+            Self::UsernameTaken | Self::NameTaken | Self::GuildNameTaken | Self::CharacterOnline => StatusCode::CONFLICT,
+            Self::Database(_) | Self::Internal(_) => StatusCode::INTERNAL_SERVER_ERROR,
+        }
+    }
+
+    pub fn code(&self) -> ApiErrorCode {
+        match self {
+            Self::BadRequest(_) => ApiErrorCode::BadRequest,
+            Self::Validation(_) => ApiErrorCode::ValidationFailed,
+            Self::InvalidCredentials => ApiErrorCode::InvalidCredentials,
+            Self::TooManyAttempts { .. } => ApiErrorCode::TooManyAttempts,
+            Self::Unauthorized(_) => ApiErrorCode::Unauthorized,
+            Self::Forbidden(_) => ApiErrorCode::Forbidden,
+            Self::AccountBanned | Self::AccountSuspended { .. } => ApiErrorCode::AccountDisabled,
+            Self::NotFound(_) => ApiErrorCode::NotFound,
+            Self::UsernameTaken => ApiErrorCode::UsernameTaken,
+            Self::NameTaken => ApiErrorCode::NameTaken,
+            Self::GuildNameTaken => ApiErrorCode::GuildNameTaken,
+            Self::CharacterOnline => ApiErrorCode::CharacterOnline,
+            Self::Database(_) | Self::Internal(_) => ApiErrorCode::Internal,
+        }
+    }
+
+    fn body(&self) -> ApiErrorBody {
+        let message = match self {
+            Self::Database(_) | Self::Internal(_) => "Internal server error".to_string(),
+            other => other.to_string(),
+        };
+        ApiErrorBody {
+            code: self.code(),
+            message,
+            retry_after_secs: match self {
+                Self::TooManyAttempts { retry_after_secs } => Some(*retry_after_secs),
+                _ => None,
+            },
+            fields: match self {
+                Self::Validation(fields) => fields.clone(),
+                _ => Vec::new(),
+            },
+        }
+    }
+}
+
+impl From<RepoError> for ApiError {
+    fn from(e: RepoError) -> Self {
+        match e {
+            RepoError::UsernameTaken => Self::UsernameTaken,
+            RepoError::NameTaken => Self::NameTaken,
+            RepoError::GuildNameTaken => Self::GuildNameTaken,
+            RepoError::Database(e) => Self::Database(e),
+        }
+    }
+}
+
+impl IntoResponse for ApiError {
+    fn into_response(self) -> Response {
+        if self.status().is_server_error() {
+            tracing::error!("API error: {}", self);
+        }
+        let mut response = (self.status(), Json(self.body())).into_response();
+        if let Self::TooManyAttempts { retry_after_secs } = self {
+            response.headers_mut().insert(header::RETRY_AFTER, retry_after_secs.into());
+        }
+        response
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_internal_details_are_not_sent() {
+        let error = ApiError::Database(sqlx::Error::Protocol("relation \"users\" does not exist".to_string()));
+        assert_eq!(error.status(), StatusCode::INTERNAL_SERVER_ERROR);
+
+        let body = error.body();
+        assert_eq!(body.code, ApiErrorCode::Internal);
+        assert!(!body.message.contains("users"));
+    }
+
+    #[test]
+    fn test_throttle_sets_retry_after() {
+        let response = ApiError::TooManyAttempts { retry_after_secs: 30 }.into_response();
+        assert_eq!(response.status(), StatusCode::TOO_MANY_REQUESTS);
+        assert_eq!(response.headers()[header::RETRY_AFTER], "30");
+    }
+
+    #[test]
+    fn test_body_round_trips() {
+        let body = ApiError::UsernameTaken.body();
+        let json = serde_json::to_string(&body).unwrap();
+        assert_eq!(json, r#"{"code":"username_taken","message":"Username is already taken"}"#);
+        assert_eq!(serde_json::from_str::<ApiErrorBody>(&json).unwrap(), body);
+    }
+}
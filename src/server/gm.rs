@@ -0,0 +1,399 @@
+//! GM tools - moderation and character administration
+//!
+//! Every route takes a `GmUser`: a valid token whose account has the `gm`
+//! or `admin` role. The role is read from the database on each request,
+//! so revoking it takes effect at once. Every action, including looking
+//! at a character, is written to the audit log once its checks pass and
+//! before it is carried out; if the entry cannot be written the action
+//! is refused, so nothing a GM does goes unlogged.
+//!
+//! Online characters are changed in the world and saved; offline ones
+//! are loaded, changed and saved.
+
+use axum::extract::{FromRef, FromRequestParts, Path, Query, State};
+use axum::http::request::Parts;
+use axum::Json;
+use serde::{Deserialize, Serialize};
+use uuid::Uuid;
+
+use crate::shared::api::{
+    AccountStatus, GmAccountResponse, GmAuditResponse, GmBanRequest, GmCharacterResponse, GmChatLogResponse, GmGrantRequest,
+    GmSetLevelRequest, GmSetRoleRequest, GmSuspendRequest, GmTeleportRequest, Role,
+};
+use crate::shared::data::characters::MAX_LEVEL;
+use crate::shared::data::items::get_item_by_id;
+use crate::shared::data::maps::{get_map_by_id, is_walkable_at};
+use crate::shared::domain::shared::models::Position;
+use crate::shared::domain::Player;
+use super::characters::load_player;
+use super::error::ApiError;
+use super::persistence::save_player;
+use super::repo::{NewAuditEntry, Repos, UserRecord};
+use super::session::{AuthUser, SessionKeys};
+use super::world::WorldHandle;
+
+/// Log entries returned when no limit is given
+const DEFAULT_LOG_LIMIT: i64 = 100;
+const MAX_LOG_LIMIT: i64 = 1000;
+
+/// Caller with at least the GM role
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct GmUser {
+    pub user_id: Uuid,
+    pub role: Role,
+}
+
+impl<S> FromRequestParts<S> for GmUser
+where
+    S: Send + Sync,
+    SessionKeys: FromRef<S>,
+    Repos: FromRef<S>,
+{
+    type Rejection = ApiError;
+
+    async fn from_request_parts(parts: &mut Parts, state: &S) -> Result<Self, Self::Rejection> {
+        let user = AuthUser::from_request_parts(parts, state).await?;
+        let account = Repos::from_ref(state).users.find_by_id(user.user_id).await?
+            .ok_or(ApiError::Unauthorized("Unknown account"))?;
+
+        if account.role < Role::Gm || account.status != AccountStatus::Active {
+            return Err(ApiError::Forbidden("GM role required"));
+        }
+        Ok(Self { user_id: account.id, role: account.role })
+    }
+}
+
+impl GmUser {
+    /// Write an action to the audit log; `details` is usually the request
+    async fn audit(
+        &self,
+        repos: &Repos,
+        action: &'static str,
+        target_user_id: Option<Uuid>,
+        target_character_id: Option<Uuid>,
+        details: &impl Serialize,
+    ) -> Result<(), ApiError> {
+        repos.audit.record(NewAuditEntry {
+            gm_user_id: self.user_id,
+            action,
+            target_user_id,
+            target_character_id,
+            details: serde_json::to_string(details).unwrap_or_default(),
+        }).await?;
+        Ok(())
+    }
+
+    /// Accounts a GM may ban, suspend or reinstate: not their own, and
+    /// only admins may act on staff
+    fn check_can_moderate(&self, target: &UserRecord) -> Result<(), ApiError> {
+        if target.id == self.user_id {
+            return Err(ApiError::BadRequest("You cannot moderate your own account".to_string()));
+        }
+        if target.role >= Role::Gm && self.role < Role::Admin {
+            return Err(ApiError::Forbidden("Only admins can moderate staff accounts"));
+        }
+        Ok(())
+    }
+}
+
+fn account_view(account: &UserRecord) -> GmAccountResponse {
+    GmAccountResponse {
+        id: account.id.to_string(),
+        username: account.username.clone(),
+        role: account.role,
+        status: account.status.clone(),
+    }
+}
+
+async fn find_account(repos: &Repos, user_id: Uuid) -> Result<UserRecord, ApiError> {
+    repos.users.find_by_id(user_id).await?.ok_or(ApiError::NotFound("Account"))
+}
+
+async fn find_owner(repos: &Repos, character_id: Uuid) -> Result<UserRecord, ApiError> {
+    let owner = repos.characters.owner(character_id).await?.ok_or(ApiError::NotFound("Character"))?;
+    find_account(repos, owner).await
+}
+
+/// Load a stored character regardless of who owns it
+async fn load_stored(repos: &Repos, owner: &UserRecord, character_id: Uuid) -> Result<Player, ApiError> {
+    load_player(repos, owner.id, character_id).await?.ok_or(ApiError::NotFound("Character"))
+}
+
+/// Apply a change to any character and save it
+async fn modify_character(
+    repos: &Repos,
+    world: &WorldHandle,
+    owner: &UserRecord,
+    character_id: Uuid,
+    change: impl FnOnce(&mut Player) -> Result<(), ApiError>,
+) -> Result<GmCharacterResponse, ApiError> {
+    // The closure runs at most once; it moves into whichever path applies
+    let mut change = Some(change);
+    let online = world.lock().unwrap().with_player(&character_id.to_string(), |p| {
+        (change.take().expect("change runs once"))(p).map(|_| p.clone())
+    });
+
+    let (player, online) = match online {
+        Some(result) => (result?, true),
+        None => {
+            let mut player = load_stored(repos, owner, character_id).await?;
+            (change.take().expect("change runs once"))(&mut player)?;
+            (player, false)
+        }
+    };
+    save_player(repos, &player).await?;
+
+    Ok(GmCharacterResponse { owner: account_view(owner), online, character: player })
+}
+
+/// Disconnect every character of an account, saving each
+async fn kick_account(repos: &Repos, world: &WorldHandle, user_id: Uuid, reason: &str) -> Result<(), ApiError> {
+    let roster = repos.characters.roster(user_id).await?;
+    let kicked: Vec<Player> = {
+        let mut world = world.lock().unwrap();
+        roster.iter().filter_map(|c| world.kick(&c.id, reason)).collect()
+    };
+    for player in kicked {
+        tracing::info!("👢 Kicked {}: {}", player.id, reason);
+        save_player(repos, &player).await?;
+    }
+    Ok(())
+}
+
+/// Change an account's status after the usual moderation checks, logged as `action`
+async fn moderate(
+    gm: GmUser,
+    repos: &Repos,
+    user_id: Uuid,
+    status: AccountStatus,
+    action: &'static str,
+    details: &impl Serialize,
+) -> Result<UserRecord, ApiError> {
+    let target = find_account(repos, user_id).await?;
+    gm.check_can_moderate(&target)?;
+    gm.audit(repos, action, Some(user_id), None, details).await?;
+    if !repos.users.set_status(user_id, &status).await? {
+        return Err(ApiError::NotFound("Account"));
+    }
+    Ok(UserRecord { status, ..target })
+}
+
+fn require_reason(reason: &str) -> Result<(), ApiError> {
+    if reason.trim().is_empty() {
+        return Err(ApiError::BadRequest("A reason is required".to_string()));
+    }
+    Ok(())
+}
+
+// --- Server Handlers ---
+
+/// `GET /gm/characters/{id}` - full state, live if the character is online
+pub async fn inspect_character_handler(
+    gm: GmUser,
+    State(repos): State<Repos>,
+    State(world): State<WorldHandle>,
+    Path(character_id): Path<Uuid>,
+) -> Result<Json<GmCharacterResponse>, ApiError> {
+    let owner = find_owner(&repos, character_id).await?;
+    gm.audit(&repos, "inspect_character", Some(owner.id), Some(character_id), &()).await?;
+
+    let live = world.lock().unwrap().player_state(&character_id.to_string());
+    let online = live.is_some();
+    let character = match live {
+        Some(player) => player,
+        None => load_stored(&repos, &owner, character_id).await?,
+    };
+    Ok(Json(GmCharacterResponse { owner: account_view(&owner), online, character }))
+}
+
+/// `POST /gm/characters/{id}/teleport` - move to any walkable tile of any map
+pub async fn teleport_handler(
+    gm: GmUser,
+    State(repos): State<Repos>,
+    State(world): State<WorldHandle>,
+    Path(character_id): Path<Uuid>,
+    Json(req): Json<GmTeleportRequest>,
+) -> Result<Json<GmCharacterResponse>, ApiError> {
+    let map = get_map_by_id(&req.map_id).ok_or(ApiError::NotFound("Map"))?;
+    if !is_walkable_at(map, req.x, req.y) {
+        return Err(ApiError::BadRequest(format!("({}, {}) on {} is not walkable", req.x, req.y, map.id)));
+    }
+
+    let owner = find_owner(&repos, character_id).await?;
+    gm.audit(&repos, "teleport", Some(owner.id), Some(character_id), &req).await?;
+
+    // Online players are moved by the world so their client follows
+    let moved = world.lock().unwrap().teleport(&character_id.to_string(), map.id, req.x, req.y);
+    let response = match moved {
+        Some(player) => {
+            save_player(&repos, &player).await?;
+            GmCharacterResponse { owner: account_view(&owner), online: true, character: player }
+        }
+        None => modify_character(&repos, &world, &owner, character_id, |p| {
+            p.current_map = map.id.to_string();
+            p.position = Position::new(req.x as f64, req.y as f64);
+            Ok(())
+        }).await?,
+    };
+    Ok(Json(response))
+}
+
+/// `POST /gm/characters/{id}/grant` - add items, gold and exp
+pub async fn grant_handler(
+    gm: GmUser,
+    State(repos): State<Repos>,
+    State(world): State<WorldHandle>,
+    Path(character_id): Path<Uuid>,
+    Json(req): Json<GmGrantRequest>,
+) -> Result<Json<GmCharacterResponse>, ApiError> {
+    if req.gold < 0 || req.exp < 0 {
+        return Err(ApiError::BadRequest("Gold and exp cannot be negative".to_string()));
+    }
+    for item in &req.items {
+        if get_item_by_id(item.item_id).is_none() {
+            return Err(ApiError::BadRequest(format!("Unknown item: {}", item.item_id)));
+        }
+        if item.quantity <= 0 {
+            return Err(ApiError::BadRequest(format!("Quantity of item {} must be positive", item.item_id)));
+        }
+    }
+
+    let owner = find_owner(&repos, character_id).await?;
+    gm.audit(&repos, "grant", Some(owner.id), Some(character_id), &req).await?;
+
+    let response = modify_character(&repos, &world, &owner, character_id, |p| {
+        let gold = p.gold.checked_add(req.gold)
+            .ok_or_else(|| ApiError::BadRequest("That much gold would overflow the character's purse".to_string()))?;
+        // All items fit or none are given
+        let mut inventory = p.inventory.clone();
+        for item in &req.items {
+            if inventory.add_item(item.item_id, item.quantity) > 0 {
+                return Err(ApiError::BadRequest("Not enough room in the inventory".to_string()));
+            }
+        }
+        p.inventory = inventory;
+        p.gold = gold;
+        p.add_exp(req.exp);
+        Ok(())
+    }).await?;
+    Ok(Json(response))
+}
+
+/// `POST /gm/characters/{id}/level` - set the level directly
+pub async fn set_level_handler(
+    gm: GmUser,
+    State(repos): State<Repos>,
+    State(world): State<WorldHandle>,
+    Path(character_id): Path<Uuid>,
+    Json(req): Json<GmSetLevelRequest>,
+) -> Result<Json<GmCharacterResponse>, ApiError> {
+    if !(1..=MAX_LEVEL).contains(&req.level) {
+        return Err(ApiError::BadRequest(format!("Level must be between 1 and {}", MAX_LEVEL)));
+    }
+
+    let owner = find_owner(&repos, character_id).await?;
+    gm.audit(&repos, "set_level", Some(owner.id), Some(character_id), &req).await?;
+
+    let response = modify_character(&repos, &world, &owner, character_id, |p| {
+        p.set_level(req.level);
+        Ok(())
+    }).await?;
+    Ok(Json(response))
+}
+
+/// `POST /gm/users/{id}/ban` - block logins until reinstated
+pub async fn ban_handler(
+    gm: GmUser,
+    State(repos): State<Repos>,
+    State(world): State<WorldHandle>,
+    Path(user_id): Path<Uuid>,
+    Json(req): Json<GmBanRequest>,
+) -> Result<Json<GmAccountResponse>, ApiError> {
+    require_reason(&req.reason)?;
+    let account = moderate(gm, &repos, user_id, AccountStatus::Banned, "ban", &req).await?;
+    kick_account(&repos, &world, user_id, "This account has been banned").await?;
+    Ok(Json(account_view(&account)))
+}
+
+/// `POST /gm/users/{id}/suspend` - block logins for a while
+pub async fn suspend_handler(
+    gm: GmUser,
+    State(repos): State<Repos>,
+    State(world): State<WorldHandle>,
+    Path(user_id): Path<Uuid>,
+    Json(req): Json<GmSuspendRequest>,
+) -> Result<Json<GmAccountResponse>, ApiError> {
+    require_reason(&req.reason)?;
+    let duration = i64::try_from(req.duration_secs).ok()
+        .filter(|secs| *secs > 0)
+        .and_then(chrono::Duration::try_seconds)
+        .ok_or_else(|| ApiError::BadRequest("Duration must be positive".to_string()))?;
+    let until = chrono::Utc::now() + duration;
+
+    let account = moderate(gm, &repos, user_id, AccountStatus::Suspended { until }, "suspend", &req).await?;
+    kick_account(&repos, &world, user_id, &ApiError::AccountSuspended { until }.to_string()).await?;
+    Ok(Json(account_view(&account)))
+}
+
+/// `POST /gm/users/{id}/unban` - lift a ban or suspension
+pub async fn unban_handler(
+    gm: GmUser,
+    State(repos): State<Repos>,
+    Path(user_id): Path<Uuid>,
+) -> Result<Json<GmAccountResponse>, ApiError> {
+    let account = moderate(gm, &repos, user_id, AccountStatus::Active, "unban", &()).await?;
+    Ok(Json(account_view(&account)))
+}
+
+/// `POST /gm/users/{id}/role` - grant or revoke GM and admin (admins only)
+pub async fn set_role_handler(
+    gm: GmUser,
+    State(repos): State<Repos>,
+    Path(user_id): Path<Uuid>,
+    Json(req): Json<GmSetRoleRequest>,
+) -> Result<Json<GmAccountResponse>, ApiError> {
+    if gm.role < Role::Admin {
+        return Err(ApiError::Forbidden("Only admins can change roles"));
+    }
+    if user_id == gm.user_id {
+        return Err(ApiError::BadRequest("You cannot change your own role".to_string()));
+    }
+    let account = find_account(&repos, user_id).await?;
+    gm.audit(&repos, "set_role", Some(user_id), None, &req).await?;
+    repos.users.set_role(user_id, req.role).await?;
+    Ok(Json(account_view(&UserRecord { role: req.role, ..account })))
+}
+
+#[derive(Debug, Serialize, Deserialize)]
+pub struct LogQuery {
+    pub limit: Option<i64>,
+    /// Chat log only: what one character sent
+    pub character_id: Option<Uuid>,
+}
+
+impl LogQuery {
+    fn limit(&self) -> i64 {
+        self.limit.unwrap_or(DEFAULT_LOG_LIMIT).clamp(1, MAX_LOG_LIMIT)
+    }
+}
+
+/// `GET /gm/audit?limit=` - most recent GM actions
+pub async fn audit_log_handler(
+    _gm: GmUser,
+    State(repos): State<Repos>,
+    Query(query): Query<LogQuery>,
+) -> Result<Json<GmAuditResponse>, ApiError> {
+    let entries = repos.audit.recent(query.limit()).await?;
+    Ok(Json(GmAuditResponse { entries }))
+}
+
+/// `GET /gm/chat?character_id=&limit=` - most recent chat, as typed
+pub async fn chat_log_handler(
+    gm: GmUser,
+    State(repos): State<Repos>,
+    Query(query): Query<LogQuery>,
+) -> Result<Json<GmChatLogResponse>, ApiError> {
+    gm.audit(&repos, "read_chat_log", None, query.character_id, &query).await?;
+    let entries = repos.chat.recent(query.character_id, query.limit()).await?;
+    Ok(Json(GmChatLogResponse { entries }))
+}
@@ -0,0 +1,437 @@
+//! Guild handlers - Axum REST API
+//!
+//! Guilds are stored in `guild_*` tables (see `repo::GuildRepo`); the
+//! world only keeps each online member's `GuildTag` for nameplates and
+//! guild chat, and is told whenever membership changes.
+//!
+//! Guild bank deposits and withdrawals change the bank and the character
+//! together and write both in one transaction; the in-world character is
+//! put back if that write fails.
+
+use std::sync::{Arc, Mutex};
+
+use axum::extract::{Path, State};
+use axum::http::StatusCode;
+use axum::Json;
+use uuid::Uuid;
+
+use crate::shared::api::{
+    CreateGuildRequest, GuildBankResponse, GuildDepositRequest, GuildGoldRequest, GuildInviteInfo, GuildInviteRequest,
+    GuildInvitesResponse, GuildMemberInfo, GuildNoticeRequest, GuildResponse, GuildSetRankRequest, GuildWithdrawRequest,
+};
+use crate::shared::domain::guild::{GuildBank, GuildRank, GUILD_NOTICE_MAX, MAX_GUILD_MEMBERS};
+use crate::shared::domain::map::models::ObjectType;
+use crate::shared::domain::Player;
+use crate::shared::validation::validate_guild_name;
+use super::characters::load_player;
+use super::error::ApiError;
+use super::persistence::{at_place, Place};
+use super::repo::{GuildMemberRecord, GuildRecord, Repos, TradeSide};
+use super::session::AuthUser;
+use super::world::WorldHandle;
+
+/// An online member's guild, for nameplates and guild chat
+#[derive(Debug, Clone, PartialEq)]
+pub struct GuildTag {
+    pub id: Uuid,
+    pub name: String,
+}
+
+impl From<&GuildRecord> for GuildTag {
+    fn from(guild: &GuildRecord) -> Self {
+        Self { id: guild.id, name: guild.name.clone() }
+    }
+}
+
+/// The caller's guild and rank
+async fn my_guild(repos: &Repos, character_id: Uuid) -> Result<(GuildRecord, GuildRank), ApiError> {
+    repos.guilds.membership(character_id).await?.ok_or(ApiError::NotFound("Guild"))
+}
+
+fn require(allowed: bool, message: &'static str) -> Result<(), ApiError> {
+    if allowed { Ok(()) } else { Err(ApiError::Forbidden(message)) }
+}
+
+async fn find_member(repos: &Repos, guild_id: Uuid, character_id: Uuid) -> Result<GuildMemberRecord, ApiError> {
+    repos.guilds.members(guild_id).await?
+        .into_iter()
+        .find(|m| m.character_id == character_id)
+        .ok_or(ApiError::NotFound("Guild member"))
+}
+
+async fn guild_view(repos: &Repos, world: &WorldHandle, guild: GuildRecord, my_rank: GuildRank) -> Result<Json<GuildResponse>, ApiError> {
+    let members = repos.guilds.members(guild.id).await?;
+    let world = world.lock().unwrap();
+    Ok(Json(GuildResponse {
+        id: guild.id.to_string(),
+        name: guild.name,
+        notice: guild.notice,
+        my_rank,
+        members: members.into_iter()
+            .map(|m| GuildMemberInfo {
+                online: world.is_online(&m.character_id.to_string()),
+                character_id: m.character_id.to_string(),
+                name: m.name,
+                class_id: m.class_id,
+                level: m.level,
+                rank: m.rank,
+            })
+            .collect(),
+    }))
+}
+
+/// Move gold or items between the caller's character and its guild bank.
+///
+/// `change` sees the in-world character and the locked bank; the bank and
+/// the character's gold and bag are written in one transaction. If that
+/// write fails the character is put back, unless it changed since.
+async fn exchange(
+    repos: &Repos,
+    world: &WorldHandle,
+    guild_id: Uuid,
+    character_id: Uuid,
+    change: impl FnOnce(&mut Player, &mut GuildBank) -> Result<(), &'static str> + Send + 'static,
+) -> Result<Json<GuildBankResponse>, ApiError> {
+    // The character before and after, for the response and for undoing
+    let changed = Arc::new(Mutex::new(None));
+    let slot = changed.clone();
+    let in_world = world.clone();
+    let result = repos.guilds.transfer(guild_id, Box::new(move |bank| {
+        let (before, after) = at_place(&in_world, character_id, Place::Building(ObjectType::GuildHall), "a guild hall", |p| {
+            // On a copy, so a refused change leaves the character as it was
+            let mut after = p.clone();
+            change(&mut after, bank)?;
+            Ok(std::mem::replace(p, after))
+        })?;
+        let member = TradeSide { character_id, gold: after.gold, inventory: after.inventory.clone() };
+        *slot.lock().unwrap() = Some((before, after));
+        Ok(member)
+    })).await;
+
+    let bank = match result {
+        Ok(outcome) => outcome.map_err(ApiError::BadRequest)?,
+        Err(e) => {
+            if let Some((before, after)) = changed.lock().unwrap().take() {
+                let undone = world.lock().unwrap().with_player(&character_id.to_string(), |p| {
+                    let unchanged = p.gold == after.gold && p.inventory == after.inventory;
+                    if unchanged {
+                        p.gold = before.gold;
+                        p.inventory = before.inventory;
+                    }
+                    unchanged
+                });
+                if undone != Some(true) {
+                    tracing::error!("Could not undo a guild bank transfer for {} after a failed save", character_id);
+                }
+            }
+            return Err(e.into());
+        }
+    };
+    let (_, player) = changed.lock().unwrap().take().expect("transfer succeeded");
+    Ok(Json(GuildBankResponse {
+        bank,
+        inventory: player.inventory,
+        gold: player.gold,
+    }))
+}
+
+// --- Server Handlers ---
+
+pub async fn create_guild_handler(
+    State(repos): State<Repos>,
+    State(world): State<WorldHandle>,
+    user: AuthUser,
+    Json(req): Json<CreateGuildRequest>,
+) -> Result<Json<GuildResponse>, ApiError> {
+    let character_id = user.character()?;
+    let name = req.name.trim().to_string();
+    validate_guild_name(&name).map_err(ApiError::BadRequest)?;
+    if repos.guilds.membership(character_id).await?.is_some() {
+        return Err(ApiError::BadRequest("You are already in a guild".to_string()));
+    }
+
+    let guild = repos.guilds.create(&name, character_id).await?;
+    world.lock().unwrap().set_guild(&character_id.to_string(), Some(GuildTag::from(&guild)));
+    guild_view(&repos, &world, guild, GuildRank::Leader).await
+}
+
+pub async fn get_guild_handler(
+    State(repos): State<Repos>,
+    State(world): State<WorldHandle>,
+    user: AuthUser,
+) -> Result<Json<GuildResponse>, ApiError> {
+    let (guild, rank) = my_guild(&repos, user.character()?).await?;
+    guild_view(&repos, &world, guild, rank).await
+}
+
+/// Only an empty bank can be disbanded, so nothing is lost with it
+pub async fn disband_guild_handler(
+    State(repos): State<Repos>,
+    State(world): State<WorldHandle>,
+    user: AuthUser,
+) -> Result<StatusCode, ApiError> {
+    let (guild, rank) = my_guild(&repos, user.character()?).await?;
+    require(rank.can_disband(), "Only the guild leader can disband the guild")?;
+    if !repos.guilds.bank(guild.id).await?.is_empty() {
+        return Err(ApiError::BadRequest("Empty the guild bank before disbanding".to_string()));
+    }
+
+    let members = repos.guilds.members(guild.id).await?;
+    repos.guilds.disband(guild.id).await?;
+
+    let mut world = world.lock().unwrap();
+    world.notify_guild(guild.id, &format!("{} has been disbanded", guild.name));
+    for m in members {
+        world.set_guild(&m.character_id.to_string(), None);
+    }
+    Ok(StatusCode::NO_CONTENT)
+}
+
+/// Leaders hand over leadership (or disband) instead of leaving
+pub async fn leave_guild_handler(
+    State(repos): State<Repos>,
+    State(world): State<WorldHandle>,
+    user: AuthUser,
+) -> Result<StatusCode, ApiError> {
+    let character_id = user.character()?;
+    let (guild, rank) = my_guild(&repos, character_id).await?;
+    if rank == GuildRank::Leader {
+        return Err(ApiError::BadRequest("The leader must hand over leadership or disband the guild".to_string()));
+    }
+    let me = find_member(&repos, guild.id, character_id).await?;
+    repos.guilds.remove_member(guild.id, character_id).await?;
+
+    let mut world = world.lock().unwrap();
+    world.set_guild(&character_id.to_string(), None);
+    world.notify(&character_id.to_string(), &format!("You left {}", guild.name));
+    world.notify_guild(guild.id, &format!("{} left the guild", me.name));
+    Ok(StatusCode::NO_CONTENT)
+}
+
+pub async fn set_notice_handler(
+    State(repos): State<Repos>,
+    State(world): State<WorldHandle>,
+    user: AuthUser,
+    Json(req): Json<GuildNoticeRequest>,
+) -> Result<Json<GuildResponse>, ApiError> {
+    let (mut guild, rank) = my_guild(&repos, user.character()?).await?;
+    require(rank.can_edit_notice(), "Only officers can change the guild notice")?;
+    let notice = req.notice.trim().to_string();
+    if notice.chars().count() > GUILD_NOTICE_MAX {
+        return Err(ApiError::BadRequest(format!("The notice can be at most {} characters", GUILD_NOTICE_MAX)));
+    }
+
+    repos.guilds.set_notice(guild.id, &notice).await?;
+    if !notice.is_empty() {
+        world.lock().unwrap().notify_guild(guild.id, &format!("[Guild notice] {}", notice));
+    }
+    guild.notice = notice;
+    guild_view(&repos, &world, guild, rank).await
+}
+
+/// Invite a character by name; they join with `accept_invite_handler`
+pub async fn invite_handler(
+    State(repos): State<Repos>,
+    State(world): State<WorldHandle>,
+    user: AuthUser,
+    Json(req): Json<GuildInviteRequest>,
+) -> Result<Json<GuildResponse>, ApiError> {
+    let character_id = user.character()?;
+    let (guild, rank) = my_guild(&repos, character_id).await?;
+    require(rank.can_invite(), "Only officers can invite")?;
+
+    let target = repos.characters.find_by_name(req.name.trim()).await?.ok_or(ApiError::NotFound("Character"))?;
+    if repos.guilds.membership(target).await?.is_some() {
+        return Err(ApiError::BadRequest(format!("{} is already in a guild", req.name.trim())));
+    }
+    let members = repos.guilds.members(guild.id).await?;
+    if members.len() >= MAX_GUILD_MEMBERS {
+        return Err(ApiError::BadRequest("The guild is full".to_string()));
+    }
+    let inviter = members.iter().find(|m| m.character_id == character_id).map(|m| m.name.clone()).unwrap_or_default();
+
+    repos.guilds.invite(guild.id, target, &inviter).await?;
+    world.lock().unwrap().notify(&target.to_string(), &format!("{} invited you to join {}", inviter, guild.name));
+    guild_view(&repos, &world, guild, rank).await
+}
+
+/// Open invites to the caller
+pub async fn list_invites_handler(
+    State(repos): State<Repos>,
+    user: AuthUser,
+) -> Result<Json<GuildInvitesResponse>, ApiError> {
+    let invites = repos.guilds.invites(user.character()?).await?;
+    Ok(Json(GuildInvitesResponse {
+        invites: invites.into_iter()
+            .map(|i| GuildInviteInfo {
+                guild_id: i.guild_id.to_string(),
+                guild_name: i.guild_name,
+                invited_by: i.invited_by,
+            })
+            .collect(),
+    }))
+}
+
+pub async fn accept_invite_handler(
+    State(repos): State<Repos>,
+    State(world): State<WorldHandle>,
+    user: AuthUser,
+    Path(guild_id): Path<Uuid>,
+) -> Result<Json<GuildResponse>, ApiError> {
+    let character_id = user.character()?;
+    if repos.guilds.membership(character_id).await?.is_some() {
+        return Err(ApiError::BadRequest("You are already in a guild".to_string()));
+    }
+    if repos.guilds.members(guild_id).await?.len() >= MAX_GUILD_MEMBERS {
+        return Err(ApiError::BadRequest("The guild is full".to_string()));
+    }
+    if !repos.guilds.accept_invite(guild_id, character_id).await? {
+        return Err(ApiError::NotFound("Guild invite"));
+    }
+
+    let (guild, rank) = my_guild(&repos, character_id).await?;
+    let me = find_member(&repos, guild.id, character_id).await?;
+    {
+        let mut world = world.lock().unwrap();
+        world.notify_guild(guild.id, &format!("{} joined the guild", me.name));
+        world.set_guild(&character_id.to_string(), Some(GuildTag::from(&guild)));
+        world.notify(&character_id.to_string(), &format!("You joined {}", guild.name));
+        if !guild.notice.is_empty() {
+            world.notify(&character_id.to_string(), &format!("[Guild notice] {}", guild.notice));
+        }
+    }
+    guild_view(&repos, &world, guild, rank).await
+}
+
+/// Promote or demote a member; making someone leader hands over leadership
+pub async fn set_rank_handler(
+    State(repos): State<Repos>,
+    State(world): State<WorldHandle>,
+    user: AuthUser,
+    Path(member_id): Path<Uuid>,
+    Json(req): Json<GuildSetRankRequest>,
+) -> Result<Json<GuildResponse>, ApiError> {
+    let character_id = user.character()?;
+    let (guild, rank) = my_guild(&repos, character_id).await?;
+    require(rank.can_set_ranks(), "Only the guild leader can change ranks")?;
+    if member_id == character_id {
+        return Err(ApiError::BadRequest("You cannot change your own rank".to_string()));
+    }
+    let member = find_member(&repos, guild.id, member_id).await?;
+
+    repos.guilds.set_rank(guild.id, member_id, req.rank).await?;
+    world.lock().unwrap().notify_guild(guild.id, &format!("{} is now {}", member.name, req.rank.name()));
+
+    let my_rank = if req.rank == GuildRank::Leader { GuildRank::Officer } else { rank };
+    guild_view(&repos, &world, guild, my_rank).await
+}
+
+pub async fn kick_handler(
+    State(repos): State<Repos>,
+    State(world): State<WorldHandle>,
+    user: AuthUser,
+    Path(member_id): Path<Uuid>,
+) -> Result<Json<GuildResponse>, ApiError> {
+    let character_id = user.character()?;
+    let (guild, rank) = my_guild(&repos, character_id).await?;
+    let member = find_member(&repos, guild.id, member_id).await?;
+    require(rank.can_kick(member.rank), "You can only remove members of a lower rank")?;
+
+    repos.guilds.remove_member(guild.id, member_id).await?;
+    {
+        let mut world = world.lock().unwrap();
+        world.set_guild(&member_id.to_string(), None);
+        world.notify(&member_id.to_string(), &format!("You were removed from {}", guild.name));
+        world.notify_guild(guild.id, &format!("{} was removed from the guild", member.name));
+    }
+    guild_view(&repos, &world, guild, rank).await
+}
+
+// --- Guild Bank ---
+
+pub async fn get_bank_handler(
+    State(repos): State<Repos>,
+    State(world): State<WorldHandle>,
+    user: AuthUser,
+) -> Result<Json<GuildBankResponse>, ApiError> {
+    let character_id = user.character()?;
+    let (guild, _) = my_guild(&repos, character_id).await?;
+    let bank = repos.guilds.bank(guild.id).await?;
+
+    let online = world.lock().unwrap().player_state(&character_id.to_string());
+    let player = match online {
+        Some(player) => player,
+        None => load_player(&repos, user.user_id, character_id).await?
+            .ok_or(ApiError::NotFound("Character"))?,
+    };
+    Ok(Json(GuildBankResponse {
+        bank,
+        inventory: player.inventory,
+        gold: player.gold,
+    }))
+}
+
+pub async fn deposit_item_handler(
+    State(repos): State<Repos>,
+    State(world): State<WorldHandle>,
+    user: AuthUser,
+    Json(req): Json<GuildDepositRequest>,
+) -> Result<Json<GuildBankResponse>, ApiError> {
+    let character_id = user.character()?;
+    let (guild, _) = my_guild(&repos, character_id).await?;
+    exchange(&repos, &world, guild.id, character_id, move |p, bank| {
+        bank.deposit(p.inventory.take(req.slot_index, req.quantity)?)
+    }).await
+}
+
+pub async fn withdraw_item_handler(
+    State(repos): State<Repos>,
+    State(world): State<WorldHandle>,
+    user: AuthUser,
+    Json(req): Json<GuildWithdrawRequest>,
+) -> Result<Json<GuildBankResponse>, ApiError> {
+    let character_id = user.character()?;
+    let (guild, rank) = my_guild(&repos, character_id).await?;
+    require(rank.can_withdraw(), "Only officers can take from the guild bank")?;
+    exchange(&repos, &world, guild.id, character_id, move |p, bank| {
+        p.inventory.insert_stack(bank.withdraw(req.bank_slot, req.quantity)?)
+    }).await
+}
+
+pub async fn deposit_gold_handler(
+    State(repos): State<Repos>,
+    State(world): State<WorldHandle>,
+    user: AuthUser,
+    Json(req): Json<GuildGoldRequest>,
+) -> Result<Json<GuildBankResponse>, ApiError> {
+    let character_id = user.character()?;
+    let (guild, _) = my_guild(&repos, character_id).await?;
+    let amount = req.amount;
+    if amount <= 0 {
+        return Err(ApiError::BadRequest("Invalid amount".to_string()));
+    }
+    exchange(&repos, &world, guild.id, character_id, move |p, bank| {
+        if p.gold < amount {
+            return Err("Not enough gold");
+        }
+        p.gold -= amount;
+        bank.deposit_gold(amount)
+    }).await
+}
+
+pub async fn withdraw_gold_handler(
+    State(repos): State<Repos>,
+    State(world): State<WorldHandle>,
+    user: AuthUser,
+    Json(req): Json<GuildGoldRequest>,
+) -> Result<Json<GuildBankResponse>, ApiError> {
+    let character_id = user.character()?;
+    let (guild, rank) = my_guild(&repos, character_id).await?;
+    require(rank.can_withdraw(), "Only officers can take from the guild bank")?;
+    let amount = req.amount;
+    exchange(&repos, &world, guild.id, character_id, move |p, bank| {
+        bank.withdraw_gold(amount)?;
+        p.gold = p.gold.checked_add(amount).ok_or("You cannot carry that much gold")?;
+        Ok(())
+    }).await
+}
@@ -0,0 +1,134 @@
+//! Inn handlers - Axum REST API
+//!
+//! Innkeepers are NPCs of type `NpcType::Inn`; their prices and bind
+//! tiles are game data (see `shared::data::inns`). The bind point is
+//! saved with the character and is where the world brings them back
+//! after they die.
+
+use axum::extract::{Path, State};
+use axum::Json;
+
+use crate::shared::api::InnResponse;
+use crate::shared::data::inns::{get_inn, InnDef};
+use crate::shared::data::maps::{get_npc, NpcType};
+use crate::shared::domain::{BindPoint, Player};
+use super::error::ApiError;
+use super::persistence::{at_place, save_player, Place};
+use super::repo::Repos;
+use super::session::AuthUser;
+use super::world::WorldHandle;
+
+/// Pay to restore HP and MP in full; returns what it cost
+pub fn rest(player: &mut Player, inn: &InnDef) -> Result<i64, String> {
+    let stats = &mut player.combat_stats;
+    if stats.hp >= stats.max_hp && stats.mp >= stats.max_mp {
+        return Err("You are already fully rested".to_string());
+    }
+    let cost = inn.rest_cost(player.level);
+    if cost > player.gold {
+        return Err("Not enough gold".to_string());
+    }
+    player.gold -= cost;
+    stats.hp = stats.max_hp;
+    stats.mp = stats.max_mp;
+    Ok(cost)
+}
+
+/// Where characters bound at an inn come back to
+pub fn inn_bind_point(inn: &InnDef) -> BindPoint {
+    BindPoint { map_id: inn.map_id.to_string(), x: inn.bind_x, y: inn.bind_y }
+}
+
+/// The inn an innkeeper keeps
+fn find_inn(npc_id: &str) -> Result<&'static InnDef, ApiError> {
+    get_inn(npc_id)
+        .filter(|inn| get_npc(inn.map_id, inn.npc_id).is_some_and(|npc| npc.npc_type == NpcType::Inn))
+        .ok_or(ApiError::NotFound("Inn"))
+}
+
+/// The innkeeper's spot, for `at_place`
+fn inn_place(inn: &InnDef) -> Place<'_> {
+    Place::Npc { map_id: inn.map_id, npc_id: inn.npc_id }
+}
+
+fn inn_response(inn: &InnDef, player: Player) -> Json<InnResponse> {
+    Json(InnResponse {
+        npc_id: inn.npc_id.to_string(),
+        rest_cost: inn.rest_cost(player.level),
+        gold: player.gold,
+        bound_here: player.bind_point.as_ref() == Some(&inn_bind_point(inn)),
+        bind_point: player.bind_point,
+    })
+}
+
+// --- Server Handlers ---
+
+pub async fn get_inn_handler(
+    State(world): State<WorldHandle>,
+    user: AuthUser,
+    Path(npc_id): Path<String>,
+) -> Result<Json<InnResponse>, ApiError> {
+    let character_id = user.character()?;
+    let inn = find_inn(&npc_id)?;
+    let ((), player) = at_place(&world, character_id, inn_place(inn), "the inn", |_| Ok(()))
+        .map_err(ApiError::BadRequest)?;
+    Ok(inn_response(inn, player))
+}
+
+pub async fn rest_handler(
+    State(repos): State<Repos>,
+    State(world): State<WorldHandle>,
+    user: AuthUser,
+    Path(npc_id): Path<String>,
+) -> Result<Json<InnResponse>, ApiError> {
+    let character_id = user.character()?;
+    let inn = find_inn(&npc_id)?;
+    let (_, player) = at_place(&world, character_id, inn_place(inn), "the inn", |p| rest(p, inn))
+        .map_err(ApiError::BadRequest)?;
+    save_player(&repos, &player).await?;
+    Ok(inn_response(inn, player))
+}
+
+pub async fn bind_handler(
+    State(repos): State<Repos>,
+    State(world): State<WorldHandle>,
+    user: AuthUser,
+    Path(npc_id): Path<String>,
+) -> Result<Json<InnResponse>, ApiError> {
+    let character_id = user.character()?;
+    let inn = find_inn(&npc_id)?;
+    let ((), player) = at_place(&world, character_id, inn_place(inn), "the inn", |p| {
+        p.bind_point = Some(inn_bind_point(inn));
+        Ok(())
+    }).map_err(ApiError::BadRequest)?;
+    save_player(&repos, &player).await?;
+    Ok(inn_response(inn, player))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::shared::data::inns::NOVICE_MAX_LEVEL;
+    use crate::shared::domain::PlayerClass;
+
+    #[test]
+    fn test_rest_restores_everything_for_a_level_based_fee() {
+        let inn = get_inn("innkeeper").unwrap();
+        let mut player = Player::new("alice".to_string(), PlayerClass::Warrior);
+        assert!(rest(&mut player, inn).is_err());
+
+        player.take_damage(10);
+        player.combat_stats.mp = 0;
+        assert_eq!(rest(&mut player, inn), Ok(inn.novice_rest_cost));
+        assert_eq!(player.combat_stats.hp, player.combat_stats.max_hp);
+        assert_eq!(player.combat_stats.mp, player.combat_stats.max_mp);
+        assert_eq!(player.gold, 100 - inn.novice_rest_cost);
+
+        player.set_level(NOVICE_MAX_LEVEL + 1);
+        player.take_damage(10);
+        player.gold = inn.rest_cost - 1;
+        assert_eq!(rest(&mut player, inn), Err("Not enough gold".to_string()));
+        player.gold = inn.rest_cost;
+        assert_eq!(rest(&mut player, inn), Ok(inn.rest_cost));
+    }
+}
@@ -0,0 +1,119 @@
+//! Inventory handlers - Axum REST API
+//!
+//! Bag slots and equipped items are stored with the character (see
+//! `repo::InventoryRepo`). Changes apply to the in-world character when it is online (so autosave
+//! keeps them) and are written through to the database immediately.
+
+use axum::extract::State;
+use axum::Json;
+
+use crate::shared::api::{
+    DiscardItemRequest, EquipItemRequest, InventoryResponse, MoveItemRequest, UnequipItemRequest,
+};
+use crate::shared::data::items::{get_item_by_id, ItemDef};
+use crate::shared::domain::item::inventory::Inventory;
+use crate::shared::domain::Player;
+use super::characters::load_player;
+use super::error::ApiError;
+use super::persistence::update_character;
+use super::repo::Repos;
+use super::session::AuthUser;
+use super::world::WorldHandle;
+
+fn inventory_response(inventory: Inventory) -> Json<InventoryResponse> {
+    Json(InventoryResponse {
+        success: true,
+        inventory: Some(inventory),
+        error: None,
+    })
+}
+
+/// Definition of the item in a bag slot
+fn slot_item(inventory: &Inventory, slot_index: usize) -> Result<&'static ItemDef, String> {
+    let stack = inventory.slots.get(slot_index)
+        .and_then(|s| s.as_ref())
+        .ok_or("No item in slot")?;
+    get_item_by_id(stack.item_id).ok_or_else(|| format!("Unknown item: {}", stack.item_id))
+}
+
+/// Apply a change to the caller's character and reply with the new inventory
+async fn update_inventory(
+    repos: &Repos,
+    world: &WorldHandle,
+    user: AuthUser,
+    change: impl Fn(&mut Player) -> Result<(), String>,
+) -> Result<Json<InventoryResponse>, ApiError> {
+    let player = update_character(repos, world, user, change).await?;
+    Ok(inventory_response(player.inventory))
+}
+
+// --- Server Handlers ---
+
+pub async fn get_inventory_handler(
+    State(repos): State<Repos>,
+    State(world): State<WorldHandle>,
+    user: AuthUser,
+) -> Result<Json<InventoryResponse>, ApiError> {
+    let character_id = user.character()?;
+
+    if let Some(player) = world.lock().unwrap().player_state(&character_id.to_string()) {
+        return Ok(inventory_response(player.inventory));
+    }
+
+    let player = load_player(&repos, user.user_id, character_id).await?
+        .ok_or(ApiError::NotFound("Character"))?;
+    Ok(inventory_response(player.inventory))
+}
+
+pub async fn move_item_handler(
+    State(repos): State<Repos>,
+    State(world): State<WorldHandle>,
+    user: AuthUser,
+    Json(req): Json<MoveItemRequest>,
+) -> Result<Json<InventoryResponse>, ApiError> {
+    update_inventory(&repos, &world, user, |p| {
+        slot_item(&p.inventory, req.from)?;
+        p.inventory.move_item(req.from, req.to).map_err(str::to_string)
+    }).await
+}
+
+pub async fn equip_item_handler(
+    State(repos): State<Repos>,
+    State(world): State<WorldHandle>,
+    user: AuthUser,
+    Json(req): Json<EquipItemRequest>,
+) -> Result<Json<InventoryResponse>, ApiError> {
+    update_inventory(&repos, &world, user, |p| {
+        let def = slot_item(&p.inventory, req.slot_index)?;
+        if def.req_level > p.level {
+            return Err(format!("{} requires level {}", def.name, def.req_level));
+        }
+        if def.req_class.is_some_and(|c| c != p.class.id()) {
+            return Err(format!("{} cannot be used by a {}", def.name, p.class.name()));
+        }
+        p.inventory.equip(req.slot_index, req.equip_slot).map_err(str::to_string)
+    }).await
+}
+
+pub async fn unequip_item_handler(
+    State(repos): State<Repos>,
+    State(world): State<WorldHandle>,
+    user: AuthUser,
+    Json(req): Json<UnequipItemRequest>,
+) -> Result<Json<InventoryResponse>, ApiError> {
+    update_inventory(&repos, &world, user, |p| {
+        p.inventory.unequip(req.equip_slot).map_err(str::to_string)
+    }).await
+}
+
+pub async fn discard_item_handler(
+    State(repos): State<Repos>,
+    State(world): State<WorldHandle>,
+    user: AuthUser,
+    Json(req): Json<DiscardItemRequest>,
+) -> Result<Json<InventoryResponse>, ApiError> {
+    update_inventory(&repos, &world, user, |p| {
+        slot_item(&p.inventory, req.slot_index)?;
+        p.inventory.discard(req.slot_index, req.quantity).map(|_| ()).map_err(str::to_string)
+    }).await
+}
@@ -4,10 +4,67 @@
 pub mod auth;
 
 #[cfg(feature = "server")]
-pub mod db;
+pub mod characters;
+
+#[cfg(feature = "server")]
+pub mod session;
 
 #[cfg(feature = "server")]
 pub mod monsters;
 
 #[cfg(feature = "server")]
 pub mod skills;
+
+#[cfg(feature = "server")]
+pub mod world;
+
+#[cfg(feature = "server")]
+pub mod realtime;
+
+#[cfg(feature = "server")]
+pub mod persistence;
+
+#[cfg(feature = "server")]
+pub mod inventory;
+
+#[cfg(feature = "server")]
+pub mod throttle;
+
+#[cfg(feature = "server")]
+pub mod error;
+
+#[cfg(feature = "server")]
+pub mod repo;
+
+#[cfg(feature = "server")]
+pub mod app;
+
+#[cfg(feature = "server")]
+pub mod config;
+
+#[cfg(feature = "server")]
+pub mod data;
+
+#[cfg(feature = "server")]
+pub mod gm;
+
+#[cfg(feature = "server")]
+pub mod chat;
+
+#[cfg(feature = "server")]
+pub mod party;
+
+#[cfg(feature = "server")]
+pub mod guild;
+
+#[cfg(feature = "server")]
+pub mod trade;
+
+#[cfg(feature = "server")]
+pub mod shop;
+
+#[cfg(feature = "server")]
+pub mod inn;
+
+#[cfg(feature = "server")]
+pub mod blacksmith;
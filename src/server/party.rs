@@ -0,0 +1,123 @@
+//! Parties - membership rules and the shared exp split
+//!
+//! Parties live only in the `World`: they form by invite, survive map
+//! changes and dissolve when fewer than two members remain. The first
+//! member is the leader; when the leader leaves, the next member in
+//! join order takes over. Kill exp is split between members near the
+//! killer, and party-targeted skills reach members near the caster.
+
+use crate::shared::data::monsters::{calculate_exp_reward, MonsterDef};
+
+/// Most members in one party
+pub const MAX_PARTY_SIZE: usize = 6;
+
+/// Seconds an unanswered invite stays valid
+pub const INVITE_TIMEOUT_SECS: f64 = 60.0;
+
+/// Tiles (on the same map) within which members share exp and party skills
+pub const PARTY_RANGE: i32 = 12;
+
+/// Extra exp for the whole party per sharing member beyond the first
+pub const PARTY_EXP_BONUS: f64 = 0.1;
+
+/// Members by entity id, in join order (the leader first)
+#[derive(Debug, Clone, PartialEq)]
+pub struct Party {
+    members: Vec<String>,
+}
+
+impl Party {
+    pub fn new(leader: String, member: String) -> Self {
+        Self { members: vec![leader, member] }
+    }
+
+    pub fn leader(&self) -> &str {
+        &self.members[0]
+    }
+
+    pub fn members(&self) -> &[String] {
+        &self.members
+    }
+
+    pub fn contains(&self, id: &str) -> bool {
+        self.members.iter().any(|m| m == id)
+    }
+
+    pub fn is_full(&self) -> bool {
+        self.members.len() >= MAX_PARTY_SIZE
+    }
+
+    pub fn add(&mut self, id: String) {
+        if !self.contains(&id) {
+            self.members.push(id);
+        }
+    }
+
+    /// Remove a member; the next in line leads if it was the leader
+    pub fn remove(&mut self, id: &str) {
+        self.members.retain(|m| m != id);
+    }
+
+    /// A party of one is no party
+    pub fn is_disbanded(&self) -> bool {
+        self.members.len() < 2
+    }
+}
+
+/// Whether two tiles on the same map are close enough to share
+pub fn in_range(a: (i32, i32), b: (i32, i32)) -> bool {
+    (a.0 - b.0).abs().max((a.1 - b.1).abs()) <= PARTY_RANGE
+}
+
+/// Exp each sharing member gets for a kill, in `levels` order.
+///
+/// Every member's base is `calculate_exp_reward` at their own level, so
+/// low-level members are not carried at the killer's rate. The bases
+/// are scaled by the party bonus and divided between the members.
+pub fn exp_shares(monster: &MonsterDef, levels: &[i32]) -> Vec<i64> {
+    let n = levels.len().max(1) as f64;
+    let scale = (1.0 + PARTY_EXP_BONUS * (n - 1.0)) / n;
+    levels.iter()
+        .map(|level| (calculate_exp_reward(monster, *level) as f64 * scale).round() as i64)
+        .collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::shared::data::monsters::SLIME;
+
+    #[test]
+    fn test_leader_passes_on_and_party_disbands() {
+        let mut party = Party::new("a".to_string(), "b".to_string());
+        party.add("c".to_string());
+        party.add("b".to_string());
+        assert_eq!(party.members(), ["a", "b", "c"]);
+
+        party.remove("a");
+        assert_eq!(party.leader(), "b");
+        assert!(!party.is_disbanded());
+        party.remove("c");
+        assert!(party.is_disbanded());
+    }
+
+    #[test]
+    fn test_exp_shares() {
+        let solo = calculate_exp_reward(&SLIME, SLIME.level) as i64;
+        assert_eq!(exp_shares(&SLIME, &[SLIME.level]), [solo]);
+
+        // Two equal members get 55% each
+        let pair = exp_shares(&SLIME, &[SLIME.level, SLIME.level]);
+        assert_eq!(pair, [(solo as f64 * 0.55).round() as i64; 2]);
+
+        // A higher-level member earns less from the same kill
+        let mixed = exp_shares(&SLIME, &[SLIME.level, SLIME.level + 5]);
+        assert!(mixed[1] < mixed[0]);
+    }
+
+    #[test]
+    fn test_range_is_square() {
+        assert!(in_range((0, 0), (PARTY_RANGE, -PARTY_RANGE)));
+        assert!(!in_range((0, 0), (PARTY_RANGE + 1, 0)));
+    }
+}
@@ -0,0 +1,174 @@
+//! Character persistence - write in-world state back to `characters`
+//!
+//! Players are saved when they leave the world, when they change map
+//! and on a periodic autosave so a crash loses at most one interval.
+//! Chat delivered by the world is written to the log on the same flush.
+//! Completed trades are written one transaction per trade, so both sides
+//! land together. Saves queued for a trader before the exchange are
+//! replaced with their post-trade state when it completes (see
+//! `World::complete_trade`), so no flush writes the goods back.
+
+use std::time::Duration;
+
+use uuid::Uuid;
+
+use crate::shared::data::maps::{near_building, near_npc};
+use crate::shared::domain::map::models::ObjectType;
+use crate::shared::domain::Player;
+use super::characters::{character_record, load_player};
+use super::error::ApiError;
+use super::repo::{RepoError, Repos, TradeSide};
+use super::session::AuthUser;
+use super::trade::CompletedTrade;
+use super::world::WorldHandle;
+
+/// Seconds between full autosaves of every connected character
+pub const AUTOSAVE_INTERVAL_SECS: u64 = 60;
+
+/// How often queued saves (map changes) are flushed
+const FLUSH_INTERVAL_SECS: u64 = 1;
+
+/// Write a character's progress, vitals, location, inventory and skills
+pub async fn save_player(repos: &Repos, player: &Player) -> Result<(), RepoError> {
+    let record = character_record(player).map_err(|e| sqlx::Error::Decode(Box::new(e)))?;
+    repos.characters.save(&record, &player.inventory, &player.skills).await
+}
+
+/// Apply a change to the caller's active character and save it.
+///
+/// The in-world copy is authoritative while the character is online;
+/// otherwise the stored character is loaded, changed and saved. A refused
+/// change is a `BadRequest` carrying its message.
+pub async fn update_character(
+    repos: &Repos,
+    world: &WorldHandle,
+    user: AuthUser,
+    change: impl Fn(&mut Player) -> Result<(), String>,
+) -> Result<Player, ApiError> {
+    let character_id = user.character()?;
+
+    let online = world.lock().unwrap()
+        .with_player(&character_id.to_string(), |p| change(p).map(|_| p.clone()));
+
+    let player = match online {
+        Some(result) => result.map_err(ApiError::BadRequest)?,
+        None => {
+            let mut player = load_player(repos, user.user_id, character_id).await?
+                .ok_or(ApiError::NotFound("Character"))?;
+            change(&mut player).map_err(ApiError::BadRequest)?;
+            player
+        }
+    };
+
+    save_player(repos, &player).await?;
+    Ok(player)
+}
+
+/// Where a character has to stand to use a service
+#[derive(Debug, Clone, Copy)]
+pub enum Place<'a> {
+    /// Within reach of an NPC on its map
+    Npc { map_id: &'a str, npc_id: &'a str },
+    /// Within reach of a building of this type on the character's map
+    Building(ObjectType),
+}
+
+/// Apply a change to the caller's character while it stands at `place`.
+///
+/// Shops, inns, blacksmiths and the guild bank work on the in-world
+/// character, never on what the client reports, so it has to be online
+/// and within reach; `what` names the place in the refusal. Returns the
+/// change's result and the character after it, for saving and replying.
+pub fn at_place<T>(
+    world: &WorldHandle,
+    character_id: Uuid,
+    place: Place,
+    what: &str,
+    change: impl FnOnce(&mut Player) -> Result<T, String>,
+) -> Result<(T, Player), String> {
+    world.lock().unwrap()
+        .with_player(&character_id.to_string(), |p| {
+            let (x, y) = (p.position.x as i32, p.position.y as i32);
+            let near = match place {
+                Place::Npc { map_id, npc_id } => p.current_map == map_id && near_npc(map_id, npc_id, x, y),
+                Place::Building(obj_type) => near_building(&p.current_map, obj_type, x, y),
+            };
+            if !near {
+                return Err(format!("You must be at {what}"));
+            }
+            change(p).map(|value| (value, p.clone()))
+        })
+        .ok_or_else(|| format!("You must be in the world to use {what}"))?
+}
+
+/// Write both characters' gold and inventory after a trade
+pub async fn save_trade(repos: &Repos, trade: &CompletedTrade) -> Result<(), RepoError> {
+    let sides = trade.players.iter()
+        .map(|p| Ok(TradeSide { character_id: Uuid::parse_str(&p.id)?, gold: p.gold, inventory: p.inventory.clone() }))
+        .collect::<Result<Vec<_>, uuid::Error>>()
+        .map_err(|e| sqlx::Error::Decode(Box::new(e)))?;
+    repos.characters.save_trade(&sides).await
+}
+
+/// Save completed trades in order, logging failures
+pub async fn save_trades(repos: &Repos, trades: Vec<CompletedTrade>) {
+    for trade in trades {
+        if let Err(e) = save_trade(repos, &trade).await {
+            let [a, b] = &trade.players;
+            tracing::error!("Failed to save the trade between {} and {}: {}", a.id, b.id, e);
+        }
+    }
+}
+
+/// Save a batch, logging failures instead of stopping at the first one
+async fn save_all(repos: &Repos, players: Vec<Player>) {
+    for player in players {
+        if let Err(e) = save_player(repos, &player).await {
+            tracing::error!("Failed to save {}: {}", player.id, e);
+        }
+    }
+}
+
+/// Write completed trades, queued saves and chat; with `autosave`, every
+/// connected character too
+pub async fn flush(world: &WorldHandle, repos: &Repos, autosave: bool) {
+    // Take the lock only to copy state; saving happens without it
+    let (trades, players, chat) = {
+        let mut world = world.lock().unwrap();
+        let mut players = world.take_pending_saves();
+        if autosave {
+            players.extend(world.persistent_players());
+        }
+        (world.take_completed_trades(), players, world.take_chat_log())
+    };
+    if autosave {
+        tracing::debug!("Autosaving {} characters", players.len());
+    }
+
+    save_trades(repos, trades).await;
+    save_all(repos, players).await;
+    if !chat.is_empty()
+        && let Err(e) = repos.chat.append(&chat).await
+    {
+        tracing::error!("Failed to log {} chat messages: {}", chat.len(), e);
+    }
+}
+
+/// Flush queued saves and chat every second and autosave everyone every minute
+pub fn spawn_autosave_loop(world: WorldHandle, repos: Repos) -> tokio::task::JoinHandle<()> {
+    tokio::spawn(async move {
+        let mut interval = tokio::time::interval(Duration::from_secs(FLUSH_INTERVAL_SECS));
+        interval.set_missed_tick_behavior(tokio::time::MissedTickBehavior::Skip);
+        let mut elapsed = 0;
+
+        loop {
+            interval.tick().await;
+            elapsed += FLUSH_INTERVAL_SECS;
+            let autosave = elapsed >= AUTOSAVE_INTERVAL_SECS;
+            if autosave {
+                elapsed = 0;
+            }
+            flush(&world, &repos, autosave).await;
+        }
+    })
+}
@@ -0,0 +1,206 @@
+//! Real-time WebSocket endpoint
+//!
+//! Each connection joins the shared `World`, forwards decoded
+//! `ClientMessage`s as intents and streams `ServerMessage`s back.
+//!
+//! Connecting with `?token=<access token>` plays the character bound to
+//! the token; it is loaded from and saved back to the database. Without
+//! a token the connection plays a throwaway guest.
+
+use axum::extract::ws::{Message, WebSocket, WebSocketUpgrade};
+use axum::extract::Query;
+use axum::http::StatusCode;
+use axum::response::{IntoResponse, Response};
+use axum::extract::State;
+use serde::Deserialize;
+use tokio::sync::mpsc;
+
+use crate::shared::data::characters::get_class_by_id;
+use crate::shared::data::maps::MILLES_VILLAGE;
+use crate::shared::domain::{Player, PlayerClass};
+use crate::shared::protocol::{ClientMessage, ServerMessage};
+use super::auth::check_account_status;
+use super::characters::load_player;
+use super::guild::GuildTag;
+use super::persistence::{save_player, save_trades};
+use super::repo::Repos;
+use super::session::{AuthUser, SessionKeys};
+use super::world::WorldHandle;
+
+/// Query string of the upgrade request
+#[derive(Debug, Deserialize)]
+pub struct ConnectParams {
+    pub token: Option<String>,
+}
+
+/// Upgrade an HTTP request to a game connection
+pub async fn ws_handler(
+    ws: WebSocketUpgrade,
+    Query(params): Query<ConnectParams>,
+    State(world): State<WorldHandle>,
+    State(repos): State<Repos>,
+    State(keys): State<SessionKeys>,
+) -> Response {
+    let user = match params.token {
+        Some(token) => match keys.verify(&token) {
+            Ok(claims) if claims.cid.is_some() => Some(AuthUser::from(claims)),
+            Ok(_) => return (StatusCode::UNAUTHORIZED, "No character selected").into_response(),
+            Err(_) => return (StatusCode::UNAUTHORIZED, "Invalid access token").into_response(),
+        },
+        None => None,
+    };
+
+    ws.on_upgrade(move |socket| handle_socket(socket, world, repos, user))
+}
+
+async fn handle_socket(mut socket: WebSocket, world: WorldHandle, repos: Repos, user: Option<AuthUser>) {
+    // 1. The first message must be a Join
+    let player = match recv_client_message(&mut socket).await {
+        Some(ClientMessage::Join { name, class_id, gender }) => {
+            let player = match user {
+                Some(user) => character_player(&repos, user).await,
+                None => guest_player(name, class_id, gender),
+            };
+            match player {
+                Ok(p) => p,
+                Err(message) => {
+                    let _ = send_server_message(&mut socket, &ServerMessage::Error { message }).await;
+                    return;
+                }
+            }
+        }
+        _ => {
+            let message = "Expected join message".to_string();
+            let _ = send_server_message(&mut socket, &ServerMessage::Error { message }).await;
+            return;
+        }
+    };
+
+    // 2. Enter the world
+    let (tx, mut rx) = mpsc::unbounded_channel();
+    let persistent = user.is_some();
+    let joined = world.lock().unwrap().join(player, tx, persistent);
+    let id = match joined {
+        Ok(id) => id,
+        Err(message) => {
+            let _ = send_server_message(&mut socket, &ServerMessage::Error { message }).await;
+            return;
+        }
+    };
+    tracing::info!("🟢 {} joined the world", id);
+    if let Some(user) = user {
+        show_guild(&world, &repos, &user, &id).await;
+    }
+
+    // 3. Pump messages until either side closes
+    loop {
+        tokio::select! {
+            incoming = socket.recv() => match incoming {
+                Some(Ok(Message::Text(text))) => match serde_json::from_str::<ClientMessage>(text.as_str()) {
+                    Ok(msg) => world.lock().unwrap().push_input(&id, msg),
+                    Err(e) => {
+                        let error = ServerMessage::Error { message: format!("Invalid message: {}", e) };
+                        if send_server_message(&mut socket, &error).await.is_err() {
+                            break;
+                        }
+                    }
+                },
+                Some(Ok(Message::Close(_))) | Some(Err(_)) | None => break,
+                Some(Ok(_)) => {}
+            },
+            outgoing = rx.recv() => match outgoing {
+                Some(msg) => {
+                    if send_server_message(&mut socket, &msg).await.is_err() {
+                        break;
+                    }
+                }
+                None => break,
+            },
+        }
+    }
+
+    // A trade finished just before leaving must land before the final save
+    let (left, trades) = {
+        let mut world = world.lock().unwrap();
+        (world.leave(&id), world.take_completed_trades())
+    };
+    tracing::info!("🔴 {} left the world", id);
+    save_trades(&repos, trades).await;
+
+    if persistent
+        && let Some(player) = left
+        && let Err(e) = save_player(&repos, &player).await
+    {
+        tracing::error!("Failed to save {} on logout: {}", id, e);
+    }
+}
+
+/// Load the character bound to an authenticated connection
+async fn character_player(repos: &Repos, user: AuthUser) -> Result<Player, String> {
+    let character_id = user.character_id.ok_or("No character selected")?;
+    // Tokens outlive bans, so check the account on every join
+    match repos.users.find_by_id(user.user_id).await {
+        Ok(Some(account)) => check_account_status(&account.status).map_err(|e| e.to_string())?,
+        Ok(None) => return Err("Account not found".to_string()),
+        Err(e) => return Err(format!("Failed to load account: {}", e)),
+    }
+    match load_player(repos, user.user_id, character_id).await {
+        Ok(Some(player)) => Ok(player),
+        Ok(None) => Err("Character not found".to_string()),
+        Err(e) => Err(format!("Failed to load character: {}", e)),
+    }
+}
+
+/// Tag a character with their guild and show them its notice
+async fn show_guild(world: &WorldHandle, repos: &Repos, user: &AuthUser, id: &str) {
+    let Some(character_id) = user.character_id else { return; };
+    let guild = match repos.guilds.membership(character_id).await {
+        Ok(Some((guild, _))) => guild,
+        Ok(None) => return,
+        Err(e) => {
+            tracing::warn!("Failed to load the guild of {}: {}", id, e);
+            return;
+        }
+    };
+    let mut world = world.lock().unwrap();
+    world.set_guild(id, Some(GuildTag::from(&guild)));
+    if !guild.notice.is_empty() {
+        world.notify(id, &format!("[Guild notice] {}", guild.notice));
+    }
+}
+
+/// Build a throwaway character for an unauthenticated session
+fn guest_player(name: String, class_id: i32, gender: String) -> Result<Player, String> {
+    let name = name.trim().to_string();
+    if name.is_empty() || name.chars().count() > 20 {
+        return Err("Name must be 1-20 characters".to_string());
+    }
+    if get_class_by_id(class_id).is_none() {
+        return Err(format!("Unknown class: {}", class_id));
+    }
+    if gender != "male" && gender != "female" {
+        return Err(format!("Unknown gender: {}", gender));
+    }
+
+    let class = PlayerClass::from_id(class_id).unwrap_or(PlayerClass::Warrior);
+    let mut player = Player::new(name, class);
+    player.gender = gender;
+    player.current_map = MILLES_VILLAGE.id.to_string();
+    Ok(player)
+}
+
+async fn recv_client_message(socket: &mut WebSocket) -> Option<ClientMessage> {
+    while let Some(Ok(msg)) = socket.recv().await {
+        match msg {
+            Message::Text(text) => return serde_json::from_str(text.as_str()).ok(),
+            Message::Close(_) => return None,
+            _ => {}
+        }
+    }
+    None
+}
+
+async fn send_server_message(socket: &mut WebSocket, msg: &ServerMessage) -> Result<(), axum::Error> {
+    let text = serde_json::to_string(msg).expect("ServerMessage serializes");
+    socket.send(Message::Text(text.into())).await
+}
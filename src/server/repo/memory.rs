@@ -0,0 +1,519 @@
+//! In-memory repositories - for tests and database-less runs
+//!
+//! All tables sit behind one lock, so multi-table writes are atomic
+//! just like the Postgres transactions.
+
+use std::collections::HashMap;
+use std::sync::Mutex;
+
+use chrono::{DateTime, Utc};
+use uuid::Uuid;
+
+use crate::shared::api::{AccountStatus, AuditEntry, CharacterSummary, ChatLogEntry, Role};
+use crate::shared::data::characters::defaults;
+use crate::shared::domain::guild::{GuildBank, GuildRank};
+use crate::shared::domain::item::inventory::Inventory;
+use crate::shared::domain::shared::models::Stats;
+use crate::shared::domain::skill::models::SkillBook;
+use crate::server::chat::NewChatLog;
+use super::{
+    AuditRepo, BankTransfer, CharacterRecord, ChatRepo, CharacterRepo, GuildInviteRecord, GuildMemberRecord, GuildRecord, GuildRepo,
+    InventoryRepo, NewAuditEntry, NewCharacter, RepoError, RepoFuture, SkillRepo, TradeSide, UserRecord, UserRepo,
+};
+
+#[derive(Debug, Clone)]
+struct StoredUser {
+    id: Uuid,
+    username: String,
+    password_hash: String,
+    failed_login_attempts: i32,
+    locked_until: Option<DateTime<Utc>>,
+    last_login_at: Option<DateTime<Utc>>,
+    role: Role,
+    is_active: bool,
+    suspended_until: Option<DateTime<Utc>>,
+}
+
+impl StoredUser {
+    fn record(&self, now: DateTime<Utc>) -> UserRecord {
+        let status = match (self.is_active, self.suspended_until) {
+            (false, _) => AccountStatus::Banned,
+            (true, Some(until)) if until > now => AccountStatus::Suspended { until },
+            _ => AccountStatus::Active,
+        };
+        UserRecord {
+            id: self.id,
+            username: self.username.clone(),
+            password_hash: self.password_hash.clone(),
+            locked: self.locked_until.is_some_and(|until| until > now),
+            role: self.role,
+            status,
+        }
+    }
+}
+
+#[derive(Debug, Clone)]
+struct StoredGuild {
+    record: GuildRecord,
+    bank: GuildBank,
+}
+
+#[derive(Debug, Clone)]
+struct StoredGuildMember {
+    guild_id: Uuid,
+    character_id: Uuid,
+    rank: GuildRank,
+}
+
+#[derive(Debug, Clone)]
+struct StoredGuildInvite {
+    guild_id: Uuid,
+    character_id: Uuid,
+    invited_by: String,
+}
+
+#[derive(Default)]
+struct Tables {
+    users: Vec<StoredUser>,
+    /// (owner, character) in creation order, like `ORDER BY created_at`
+    characters: Vec<(Uuid, CharacterRecord)>,
+    inventories: HashMap<Uuid, Inventory>,
+    skills: HashMap<Uuid, SkillBook>,
+    audit: Vec<AuditEntry>,
+    chat: Vec<ChatLogEntry>,
+    guilds: Vec<StoredGuild>,
+    /// In join order
+    guild_members: Vec<StoredGuildMember>,
+    guild_invites: Vec<StoredGuildInvite>,
+}
+
+impl Tables {
+    fn insert_character(&mut self, user_id: Uuid, character: NewCharacter) -> Result<CharacterSummary, RepoError> {
+        if self.characters.iter().any(|(_, c)| c.name == character.name) {
+            return Err(RepoError::NameTaken);
+        }
+        let record = CharacterRecord {
+            id: Uuid::new_v4(),
+            name: character.name,
+            class_id: character.class_id,
+            gender: character.gender,
+            level: 1,
+            exp: 0,
+            hp: character.hp,
+            mp: character.mp,
+            gold: defaults::STARTING_GOLD,
+            current_map: defaults::STARTING_MAP.to_string(),
+            pos_x: defaults::STARTING_X,
+            pos_y: defaults::STARTING_Y,
+            bind_point: None,
+            bonus_stats: Stats::default(),
+            stat_points: 0,
+        };
+        let summary = summary(&record);
+        self.characters.push((user_id, record));
+        Ok(summary)
+    }
+
+    fn guild(&mut self, guild_id: Uuid) -> Option<&mut StoredGuild> {
+        self.guilds.iter_mut().find(|g| g.record.id == guild_id)
+    }
+}
+
+fn summary(c: &CharacterRecord) -> CharacterSummary {
+    CharacterSummary {
+        id: c.id.to_string(),
+        name: c.name.clone(),
+        class_id: c.class_id,
+        gender: c.gender.clone(),
+        level: c.level,
+        current_map: c.current_map.clone(),
+    }
+}
+
+#[derive(Default)]
+pub struct MemoryRepo {
+    tables: Mutex<Tables>,
+}
+
+impl MemoryRepo {
+    fn with<T>(&self, f: impl FnOnce(&mut Tables) -> T) -> T {
+        f(&mut self.tables.lock().unwrap())
+    }
+}
+
+impl UserRepo for MemoryRepo {
+    fn find_by_username<'a>(&'a self, username: &'a str) -> RepoFuture<'a, Option<UserRecord>> {
+        let user = self.with(|t| t.users.iter().find(|u| u.username == username).map(|u| u.record(Utc::now())));
+        Box::pin(async move { Ok(user) })
+    }
+
+    fn find_by_id(&self, user_id: Uuid) -> RepoFuture<'_, Option<UserRecord>> {
+        let user = self.with(|t| t.users.iter().find(|u| u.id == user_id).map(|u| u.record(Utc::now())));
+        Box::pin(async move { Ok(user) })
+    }
+
+    fn record_login_failure(&self, user_id: Uuid, max_failures: i32, lockout_secs: f64) -> RepoFuture<'_, ()> {
+        self.with(|t| {
+            if let Some(u) = t.users.iter_mut().find(|u| u.id == user_id) {
+                u.failed_login_attempts += 1;
+                if u.failed_login_attempts >= max_failures {
+                    u.failed_login_attempts = 0;
+                    u.locked_until = Some(Utc::now() + chrono::Duration::milliseconds((lockout_secs * 1000.0) as i64));
+                }
+            }
+        });
+        Box::pin(async { Ok(()) })
+    }
+
+    fn record_login(&self, user_id: Uuid) -> RepoFuture<'_, ()> {
+        self.with(|t| {
+            if let Some(u) = t.users.iter_mut().find(|u| u.id == user_id) {
+                u.failed_login_attempts = 0;
+                u.locked_until = None;
+                u.last_login_at = Some(Utc::now());
+            }
+        });
+        Box::pin(async { Ok(()) })
+    }
+
+    fn register<'a>(&'a self, username: &'a str, password_hash: &'a str, character: NewCharacter) -> RepoFuture<'a, Uuid> {
+        let result = self.with(|t| {
+            if t.users.iter().any(|u| u.username == username) {
+                return Err(RepoError::UsernameTaken);
+            }
+            let id = Uuid::new_v4();
+            t.insert_character(id, character)?;
+            t.users.push(StoredUser {
+                id,
+                username: username.to_string(),
+                password_hash: password_hash.to_string(),
+                failed_login_attempts: 0,
+                locked_until: None,
+                last_login_at: None,
+                role: Role::Player,
+                is_active: true,
+                suspended_until: None,
+            });
+            Ok(id)
+        });
+        Box::pin(async move { result })
+    }
+
+    fn set_role(&self, user_id: Uuid, role: Role) -> RepoFuture<'_, bool> {
+        let found = self.with(|t| t.users.iter_mut().find(|u| u.id == user_id).map(|u| u.role = role).is_some());
+        Box::pin(async move { Ok(found) })
+    }
+
+    fn set_status<'a>(&'a self, user_id: Uuid, status: &'a AccountStatus) -> RepoFuture<'a, bool> {
+        let found = self.with(|t| {
+            let Some(u) = t.users.iter_mut().find(|u| u.id == user_id) else { return false; };
+            (u.is_active, u.suspended_until) = match status {
+                AccountStatus::Active => (true, None),
+                AccountStatus::Banned => (false, None),
+                AccountStatus::Suspended { until } => (true, Some(*until)),
+            };
+            true
+        });
+        Box::pin(async move { Ok(found) })
+    }
+}
+
+impl CharacterRepo for MemoryRepo {
+    fn roster(&self, user_id: Uuid) -> RepoFuture<'_, Vec<CharacterSummary>> {
+        let roster = self.with(|t| t.characters.iter().filter(|(owner, _)| *owner == user_id).map(|(_, c)| summary(c)).collect());
+        Box::pin(async move { Ok(roster) })
+    }
+
+    fn create(&self, user_id: Uuid, character: NewCharacter) -> RepoFuture<'_, CharacterSummary> {
+        let result = self.with(|t| t.insert_character(user_id, character));
+        Box::pin(async move { result })
+    }
+
+    fn delete(&self, user_id: Uuid, character_id: Uuid) -> RepoFuture<'_, bool> {
+        let deleted = self.with(|t| {
+            let before = t.characters.len();
+            t.characters.retain(|(owner, c)| !(c.id == character_id && *owner == user_id));
+            let deleted = t.characters.len() < before;
+            if deleted {
+                t.inventories.remove(&character_id);
+                t.skills.remove(&character_id);
+                t.guild_members.retain(|m| m.character_id != character_id);
+                t.guild_invites.retain(|i| i.character_id != character_id);
+            }
+            deleted
+        });
+        Box::pin(async move { Ok(deleted) })
+    }
+
+    fn load(&self, user_id: Uuid, character_id: Uuid) -> RepoFuture<'_, Option<CharacterRecord>> {
+        let record = self.with(|t| {
+            t.characters.iter()
+                .find(|(owner, c)| c.id == character_id && *owner == user_id)
+                .map(|(_, c)| c.clone())
+        });
+        Box::pin(async move { Ok(record) })
+    }
+
+    fn find_by_name<'a>(&'a self, name: &'a str) -> RepoFuture<'a, Option<Uuid>> {
+        let id = self.with(|t| t.characters.iter().find(|(_, c)| c.name.eq_ignore_ascii_case(name)).map(|(_, c)| c.id));
+        Box::pin(async move { Ok(id) })
+    }
+
+    fn owner(&self, character_id: Uuid) -> RepoFuture<'_, Option<Uuid>> {
+        let owner = self.with(|t| t.characters.iter().find(|(_, c)| c.id == character_id).map(|(owner, _)| *owner));
+        Box::pin(async move { Ok(owner) })
+    }
+
+    fn mark_played(&self, _character_id: Uuid) -> RepoFuture<'_, ()> {
+        Box::pin(async { Ok(()) })
+    }
+
+    fn save<'a>(&'a self, character: &'a CharacterRecord, inventory: &'a Inventory, skills: &'a SkillBook) -> RepoFuture<'a, ()> {
+        self.with(|t| {
+            if let Some((_, stored)) = t.characters.iter_mut().find(|(_, c)| c.id == character.id) {
+                // Names are fixed at creation
+                *stored = CharacterRecord {
+                    name: stored.name.clone(),
+                    ..character.clone()
+                };
+                t.inventories.insert(character.id, inventory.clone());
+                t.skills.insert(character.id, skills.clone());
+            }
+        });
+        Box::pin(async { Ok(()) })
+    }
+
+    fn save_trade<'a>(&'a self, sides: &'a [TradeSide]) -> RepoFuture<'a, ()> {
+        self.with(|t| {
+            for side in sides {
+                if let Some((_, stored)) = t.characters.iter_mut().find(|(_, c)| c.id == side.character_id) {
+                    stored.gold = side.gold;
+                    t.inventories.insert(side.character_id, side.inventory.clone());
+                }
+            }
+        });
+        Box::pin(async { Ok(()) })
+    }
+}
+
+impl InventoryRepo for MemoryRepo {
+    fn load(&self, character_id: Uuid) -> RepoFuture<'_, Inventory> {
+        let inventory = self.with(|t| t.inventories.get(&character_id).cloned().unwrap_or_default());
+        Box::pin(async move { Ok(inventory) })
+    }
+}
+
+impl SkillRepo for MemoryRepo {
+    fn load(&self, character_id: Uuid, class_id: i32) -> RepoFuture<'_, SkillBook> {
+        let book = self.with(|t| t.skills.get(&character_id).cloned());
+        Box::pin(async move { Ok(book.unwrap_or_else(|| SkillBook::starter(class_id))) })
+    }
+}
+
+impl AuditRepo for MemoryRepo {
+    fn record(&self, entry: NewAuditEntry) -> RepoFuture<'_, ()> {
+        self.with(|t| {
+            let id = t.audit.len() as i64 + 1;
+            t.audit.push(AuditEntry {
+                id,
+                gm_user_id: entry.gm_user_id.to_string(),
+                action: entry.action.to_string(),
+                target_user_id: entry.target_user_id.map(|id| id.to_string()),
+                target_character_id: entry.target_character_id.map(|id| id.to_string()),
+                details: entry.details,
+                created_at: Utc::now(),
+            });
+        });
+        Box::pin(async { Ok(()) })
+    }
+
+    fn recent(&self, limit: i64) -> RepoFuture<'_, Vec<AuditEntry>> {
+        let entries = self.with(|t| t.audit.iter().rev().take(limit.max(0) as usize).cloned().collect());
+        Box::pin(async move { Ok(entries) })
+    }
+}
+
+impl ChatRepo for MemoryRepo {
+    fn append<'a>(&'a self, entries: &'a [NewChatLog]) -> RepoFuture<'a, ()> {
+        self.with(|t| {
+            for entry in entries {
+                let id = t.chat.len() as i64 + 1;
+                t.chat.push(ChatLogEntry {
+                    id,
+                    channel: entry.channel,
+                    sender_character_id: entry.sender_character_id.map(|id| id.to_string()),
+                    sender_name: entry.sender_name.clone(),
+                    recipient_name: entry.recipient_name.clone(),
+                    map_id: entry.map_id.clone(),
+                    message: entry.message.clone(),
+                    filtered: entry.filtered,
+                    sent_at: entry.sent_at,
+                });
+            }
+        });
+        Box::pin(async { Ok(()) })
+    }
+
+    fn recent(&self, sender_character_id: Option<Uuid>, limit: i64) -> RepoFuture<'_, Vec<ChatLogEntry>> {
+        let sender = sender_character_id.map(|id| id.to_string());
+        let entries = self.with(|t| {
+            t.chat.iter().rev()
+                .filter(|e| sender.is_none() || e.sender_character_id == sender)
+                .take(limit.max(0) as usize)
+                .cloned()
+                .collect()
+        });
+        Box::pin(async move { Ok(entries) })
+    }
+}
+
+impl GuildRepo for MemoryRepo {
+    fn create<'a>(&'a self, name: &'a str, leader: Uuid) -> RepoFuture<'a, GuildRecord> {
+        let result = self.with(|t| {
+            if t.guilds.iter().any(|g| g.record.name.to_lowercase() == name.to_lowercase()) {
+                return Err(RepoError::GuildNameTaken);
+            }
+            let record = GuildRecord {
+                id: Uuid::new_v4(),
+                name: name.to_string(),
+                notice: String::new(),
+            };
+            t.guilds.push(StoredGuild { record: record.clone(), bank: GuildBank::default() });
+            t.guild_members.push(StoredGuildMember { guild_id: record.id, character_id: leader, rank: GuildRank::Leader });
+            Ok(record)
+        });
+        Box::pin(async move { result })
+    }
+
+    fn membership(&self, character_id: Uuid) -> RepoFuture<'_, Option<(GuildRecord, GuildRank)>> {
+        let membership = self.with(|t| {
+            let member = t.guild_members.iter().find(|m| m.character_id == character_id)?.clone();
+            let guild = t.guild(member.guild_id)?;
+            Some((guild.record.clone(), member.rank))
+        });
+        Box::pin(async move { Ok(membership) })
+    }
+
+    fn members(&self, guild_id: Uuid) -> RepoFuture<'_, Vec<GuildMemberRecord>> {
+        let mut members: Vec<GuildMemberRecord> = self.with(|t| {
+            t.guild_members.iter()
+                .filter(|m| m.guild_id == guild_id)
+                .filter_map(|m| {
+                    let (_, c) = t.characters.iter().find(|(_, c)| c.id == m.character_id)?;
+                    Some(GuildMemberRecord {
+                        character_id: c.id,
+                        name: c.name.clone(),
+                        class_id: c.class_id,
+                        level: c.level,
+                        rank: m.rank,
+                    })
+                })
+                .collect()
+        });
+        // Stable, so join order holds within a rank
+        members.sort_by_key(|m| std::cmp::Reverse(m.rank));
+        Box::pin(async move { Ok(members) })
+    }
+
+    fn invite<'a>(&'a self, guild_id: Uuid, character_id: Uuid, invited_by: &'a str) -> RepoFuture<'a, ()> {
+        self.with(|t| {
+            t.guild_invites.retain(|i| !(i.guild_id == guild_id && i.character_id == character_id));
+            t.guild_invites.push(StoredGuildInvite { guild_id, character_id, invited_by: invited_by.to_string() });
+        });
+        Box::pin(async { Ok(()) })
+    }
+
+    fn invites(&self, character_id: Uuid) -> RepoFuture<'_, Vec<GuildInviteRecord>> {
+        let invites = self.with(|t| {
+            t.guild_invites.iter()
+                .filter(|i| i.character_id == character_id)
+                .filter_map(|i| {
+                    let guild = t.guilds.iter().find(|g| g.record.id == i.guild_id)?;
+                    Some(GuildInviteRecord {
+                        guild_id: i.guild_id,
+                        guild_name: guild.record.name.clone(),
+                        invited_by: i.invited_by.clone(),
+                    })
+                })
+                .collect()
+        });
+        Box::pin(async move { Ok(invites) })
+    }
+
+    fn accept_invite(&self, guild_id: Uuid, character_id: Uuid) -> RepoFuture<'_, bool> {
+        let joined = self.with(|t| {
+            let invited = t.guild_invites.iter().any(|i| i.guild_id == guild_id && i.character_id == character_id);
+            if !invited || t.guild_members.iter().any(|m| m.character_id == character_id) {
+                return false;
+            }
+            t.guild_invites.retain(|i| i.character_id != character_id);
+            t.guild_members.push(StoredGuildMember { guild_id, character_id, rank: GuildRank::Member });
+            true
+        });
+        Box::pin(async move { Ok(joined) })
+    }
+
+    fn remove_member(&self, guild_id: Uuid, character_id: Uuid) -> RepoFuture<'_, bool> {
+        let removed = self.with(|t| {
+            let before = t.guild_members.len();
+            t.guild_members.retain(|m| !(m.guild_id == guild_id && m.character_id == character_id));
+            t.guild_members.len() < before
+        });
+        Box::pin(async move { Ok(removed) })
+    }
+
+    fn set_rank(&self, guild_id: Uuid, character_id: Uuid, rank: GuildRank) -> RepoFuture<'_, bool> {
+        let found = self.with(|t| {
+            if !t.guild_members.iter().any(|m| m.guild_id == guild_id && m.character_id == character_id) {
+                return false;
+            }
+            for m in t.guild_members.iter_mut().filter(|m| m.guild_id == guild_id) {
+                if m.character_id == character_id {
+                    m.rank = rank;
+                } else if rank == GuildRank::Leader && m.rank == GuildRank::Leader {
+                    m.rank = GuildRank::Officer;
+                }
+            }
+            true
+        });
+        Box::pin(async move { Ok(found) })
+    }
+
+    fn set_notice<'a>(&'a self, guild_id: Uuid, notice: &'a str) -> RepoFuture<'a, ()> {
+        self.with(|t| {
+            if let Some(guild) = t.guild(guild_id) {
+                guild.record.notice = notice.to_string();
+            }
+        });
+        Box::pin(async { Ok(()) })
+    }
+
+    fn disband(&self, guild_id: Uuid) -> RepoFuture<'_, ()> {
+        self.with(|t| {
+            t.guilds.retain(|g| g.record.id != guild_id);
+            t.guild_members.retain(|m| m.guild_id != guild_id);
+            t.guild_invites.retain(|i| i.guild_id != guild_id);
+        });
+        Box::pin(async { Ok(()) })
+    }
+
+    fn bank(&self, guild_id: Uuid) -> RepoFuture<'_, GuildBank> {
+        let bank = self.with(|t| t.guild(guild_id).map(|g| g.bank.clone()).unwrap_or_default());
+        Box::pin(async move { Ok(bank) })
+    }
+
+    fn transfer(&self, guild_id: Uuid, transfer: BankTransfer) -> RepoFuture<'_, Result<GuildBank, String>> {
+        let result = self.with(|t| {
+            let guild = t.guild(guild_id).ok_or("Guild not found")?;
+            let mut bank = guild.bank.clone();
+            let member = transfer(&mut bank)?;
+            guild.bank = bank.clone();
+            if let Some((_, stored)) = t.characters.iter_mut().find(|(_, c)| c.id == member.character_id) {
+                stored.gold = member.gold;
+                t.inventories.insert(member.character_id, member.inventory);
+            }
+            Ok(bank)
+        });
+        Box::pin(async move { Ok(result) })
+    }
+}
@@ -0,0 +1,284 @@
+//! Repositories - storage behind the handlers
+//!
+//! Handlers talk to these traits instead of running SQL. `postgres`
+//! implements them on a `PgPool`; `memory` keeps everything in process
+//! so the API can be exercised in tests without a database.
+
+use std::future::Future;
+use std::pin::Pin;
+use std::sync::Arc;
+
+use sqlx::PgPool;
+use uuid::Uuid;
+
+use crate::shared::api::{AccountStatus, AuditEntry, CharacterSummary, ChatLogEntry, Role};
+use crate::shared::domain::character::BindPoint;
+use crate::shared::domain::guild::{GuildBank, GuildRank};
+use crate::shared::domain::item::inventory::Inventory;
+use crate::shared::domain::shared::models::Stats;
+use crate::shared::domain::skill::models::SkillBook;
+use crate::server::chat::NewChatLog;
+
+pub mod memory;
+pub mod postgres;
+
+pub use memory::MemoryRepo;
+pub use postgres::PgRepo;
+
+#[derive(Debug, thiserror::Error)]
+pub enum RepoError {
+    #[error("Username is already taken")]
+    UsernameTaken,
+    #[error("Character name is already taken")]
+    NameTaken,
+    #[error("Guild name is already taken")]
+    GuildNameTaken,
+    #[error(transparent)]
+    Database(#[from] sqlx::Error),
+}
+
+/// Boxed so the traits stay object safe
+pub type RepoFuture<'a, T> = Pin<Box<dyn Future<Output = Result<T, RepoError>> + Send + 'a>>;
+
+/// What login and the GM tools need from an account
+#[derive(Debug, Clone, PartialEq)]
+pub struct UserRecord {
+    pub id: Uuid,
+    pub username: String,
+    pub password_hash: String,
+    /// Locked out after too many failed logins
+    pub locked: bool,
+    pub role: Role,
+    /// Expired suspensions read as `Active`
+    pub status: AccountStatus,
+}
+
+/// A GM action about to be logged
+#[derive(Debug, Clone, PartialEq)]
+pub struct NewAuditEntry {
+    pub gm_user_id: Uuid,
+    pub action: &'static str,
+    pub target_user_id: Option<Uuid>,
+    pub target_character_id: Option<Uuid>,
+    pub details: String,
+}
+
+/// A character about to be created
+#[derive(Debug, Clone, PartialEq)]
+pub struct NewCharacter {
+    pub name: String,
+    pub class_id: i32,
+    pub gender: String,
+    pub hp: i32,
+    pub mp: i32,
+}
+
+/// A stored character row, without inventory and skills
+#[derive(Debug, Clone, PartialEq)]
+pub struct CharacterRecord {
+    pub id: Uuid,
+    pub name: String,
+    pub class_id: i32,
+    pub gender: String,
+    pub level: i32,
+    pub exp: i64,
+    pub hp: i32,
+    pub mp: i32,
+    pub gold: i64,
+    pub current_map: String,
+    pub pos_x: f64,
+    pub pos_y: f64,
+    pub bind_point: Option<BindPoint>,
+    /// Allocated points on top of the class's base stats
+    pub bonus_stats: Stats,
+    pub stat_points: i32,
+}
+
+/// A character's gold and inventory after a trade or a guild bank transfer
+#[derive(Debug, Clone, PartialEq)]
+pub struct TradeSide {
+    pub character_id: Uuid,
+    pub gold: i64,
+    pub inventory: Inventory,
+}
+
+/// A guild without its members and bank
+#[derive(Debug, Clone, PartialEq)]
+pub struct GuildRecord {
+    pub id: Uuid,
+    pub name: String,
+    pub notice: String,
+}
+
+/// A guild member with what the roster shows of their character
+#[derive(Debug, Clone, PartialEq)]
+pub struct GuildMemberRecord {
+    pub character_id: Uuid,
+    pub name: String,
+    pub class_id: i32,
+    pub level: i32,
+    pub rank: GuildRank,
+}
+
+/// An open invite to a guild
+#[derive(Debug, Clone, PartialEq)]
+pub struct GuildInviteRecord {
+    pub guild_id: Uuid,
+    pub guild_name: String,
+    /// Name of the character who sent it
+    pub invited_by: String,
+}
+
+/// Gold or items moved between a guild bank and a member, giving the
+/// member's new gold and inventory; an `Err` leaves both as they were
+pub type BankTransfer = Box<dyn FnOnce(&mut GuildBank) -> Result<TradeSide, String> + Send>;
+
+pub trait UserRepo: Send + Sync {
+    fn find_by_username<'a>(&'a self, username: &'a str) -> RepoFuture<'a, Option<UserRecord>>;
+
+    fn find_by_id(&self, user_id: Uuid) -> RepoFuture<'_, Option<UserRecord>>;
+
+    /// Count a failed login; the account locks for `lockout_secs` once it
+    /// reaches `max_failures` (and the count starts over)
+    fn record_login_failure(&self, user_id: Uuid, max_failures: i32, lockout_secs: f64) -> RepoFuture<'_, ()>;
+
+    /// Clear failures and lockout, stamp `last_login_at`
+    fn record_login(&self, user_id: Uuid) -> RepoFuture<'_, ()>;
+
+    /// Create an account and its first character atomically
+    fn register<'a>(&'a self, username: &'a str, password_hash: &'a str, character: NewCharacter) -> RepoFuture<'a, Uuid>;
+
+    /// `false` if there is no such user
+    fn set_role(&self, user_id: Uuid, role: Role) -> RepoFuture<'_, bool>;
+
+    /// Ban, suspend or reinstate an account; `false` if there is no such user
+    fn set_status<'a>(&'a self, user_id: Uuid, status: &'a AccountStatus) -> RepoFuture<'a, bool>;
+}
+
+pub trait CharacterRepo: Send + Sync {
+    /// Characters owned by a user, oldest first
+    fn roster(&self, user_id: Uuid) -> RepoFuture<'_, Vec<CharacterSummary>>;
+
+    fn create(&self, user_id: Uuid, character: NewCharacter) -> RepoFuture<'_, CharacterSummary>;
+
+    /// `false` if the user owns no such character
+    fn delete(&self, user_id: Uuid, character_id: Uuid) -> RepoFuture<'_, bool>;
+
+    fn load(&self, user_id: Uuid, character_id: Uuid) -> RepoFuture<'_, Option<CharacterRecord>>;
+
+    /// Id of the character with this name, ignoring case
+    fn find_by_name<'a>(&'a self, name: &'a str) -> RepoFuture<'a, Option<Uuid>>;
+
+    /// The user a character belongs to
+    fn owner(&self, character_id: Uuid) -> RepoFuture<'_, Option<Uuid>>;
+
+    fn mark_played(&self, character_id: Uuid) -> RepoFuture<'_, ()>;
+
+    /// Write a character with its inventory and skills atomically
+    fn save<'a>(&'a self, character: &'a CharacterRecord, inventory: &'a Inventory, skills: &'a SkillBook) -> RepoFuture<'a, ()>;
+
+    /// Write the gold and inventory of every side of a trade in one transaction
+    fn save_trade<'a>(&'a self, sides: &'a [TradeSide]) -> RepoFuture<'a, ()>;
+}
+
+pub trait InventoryRepo: Send + Sync {
+    fn load(&self, character_id: Uuid) -> RepoFuture<'_, Inventory>;
+}
+
+pub trait SkillRepo: Send + Sync {
+    /// Characters with no stored skills get their class's starter set
+    fn load(&self, character_id: Uuid, class_id: i32) -> RepoFuture<'_, SkillBook>;
+}
+
+/// Append-only log of GM actions
+pub trait AuditRepo: Send + Sync {
+    fn record(&self, entry: NewAuditEntry) -> RepoFuture<'_, ()>;
+
+    /// Newest first
+    fn recent(&self, limit: i64) -> RepoFuture<'_, Vec<AuditEntry>>;
+}
+
+/// Chat history kept for moderation
+pub trait ChatRepo: Send + Sync {
+    fn append<'a>(&'a self, entries: &'a [NewChatLog]) -> RepoFuture<'a, ()>;
+
+    /// Newest first, optionally only what one character sent
+    fn recent(&self, sender_character_id: Option<Uuid>, limit: i64) -> RepoFuture<'_, Vec<ChatLogEntry>>;
+}
+
+/// Guilds, their members and invites, and guild banks
+pub trait GuildRepo: Send + Sync {
+    /// Found a guild with `leader` as its only member
+    fn create<'a>(&'a self, name: &'a str, leader: Uuid) -> RepoFuture<'a, GuildRecord>;
+
+    /// The guild a character belongs to and their rank in it
+    fn membership(&self, character_id: Uuid) -> RepoFuture<'_, Option<(GuildRecord, GuildRank)>>;
+
+    /// Leader first, then officers, then members, each in join order
+    fn members(&self, guild_id: Uuid) -> RepoFuture<'_, Vec<GuildMemberRecord>>;
+
+    fn invite<'a>(&'a self, guild_id: Uuid, character_id: Uuid, invited_by: &'a str) -> RepoFuture<'a, ()>;
+
+    /// Open invites to a character, oldest first
+    fn invites(&self, character_id: Uuid) -> RepoFuture<'_, Vec<GuildInviteRecord>>;
+
+    /// Join a guild that invited the character and drop their other
+    /// invites; `false` if there was no such invite
+    fn accept_invite(&self, guild_id: Uuid, character_id: Uuid) -> RepoFuture<'_, bool>;
+
+    /// `false` if the character is not in the guild
+    fn remove_member(&self, guild_id: Uuid, character_id: Uuid) -> RepoFuture<'_, bool>;
+
+    /// `false` if the character is not in the guild. Making someone
+    /// leader demotes the current leader to officer.
+    fn set_rank(&self, guild_id: Uuid, character_id: Uuid, rank: GuildRank) -> RepoFuture<'_, bool>;
+
+    fn set_notice<'a>(&'a self, guild_id: Uuid, notice: &'a str) -> RepoFuture<'a, ()>;
+
+    /// Delete a guild with its members, invites and bank
+    fn disband(&self, guild_id: Uuid) -> RepoFuture<'_, ()>;
+
+    fn bank(&self, guild_id: Uuid) -> RepoFuture<'_, GuildBank>;
+
+    /// Apply a transfer and write the bank with the member's gold and
+    /// inventory in one transaction; the result is the bank after it or
+    /// why it was refused
+    fn transfer(&self, guild_id: Uuid, transfer: BankTransfer) -> RepoFuture<'_, Result<GuildBank, String>>;
+}
+
+/// Every repository, shared through the router state
+#[derive(Clone)]
+pub struct Repos {
+    pub users: Arc<dyn UserRepo>,
+    pub characters: Arc<dyn CharacterRepo>,
+    pub inventory: Arc<dyn InventoryRepo>,
+    pub skills: Arc<dyn SkillRepo>,
+    pub audit: Arc<dyn AuditRepo>,
+    pub chat: Arc<dyn ChatRepo>,
+    pub guilds: Arc<dyn GuildRepo>,
+}
+
+impl Repos {
+    pub fn postgres(pool: PgPool) -> Self {
+        Self::from_backend(Arc::new(PgRepo::new(pool)))
+    }
+
+    pub fn in_memory() -> Self {
+        Self::from_backend(Arc::new(MemoryRepo::default()))
+    }
+
+    fn from_backend<B>(backend: Arc<B>) -> Self
+    where
+        B: UserRepo + CharacterRepo + InventoryRepo + SkillRepo + AuditRepo + ChatRepo + GuildRepo + 'static,
+    {
+        Self {
+            users: backend.clone(),
+            characters: backend.clone(),
+            inventory: backend.clone(),
+            skills: backend.clone(),
+            audit: backend.clone(),
+            chat: backend.clone(),
+            guilds: backend,
+        }
+    }
+}
@@ -0,0 +1,936 @@
+//! Postgres repositories
+
+use chrono::{DateTime, Utc};
+use sqlx::{PgConnection, PgPool, Row};
+use uuid::Uuid;
+
+use crate::shared::api::{AccountStatus, AuditEntry, CharacterSummary, ChatLogEntry, Role};
+use crate::shared::data::characters::{defaults, total_exp_for_level};
+use crate::shared::data::items::get_item_by_id;
+use crate::shared::data::skills::get_skill_by_id;
+use crate::shared::domain::character::BindPoint;
+use crate::shared::domain::guild::{GuildBank, GuildRank};
+use crate::shared::domain::item::inventory::{EquipSlot, Inventory, ItemStack, INVENTORY_SIZE};
+use crate::shared::domain::shared::models::Stats;
+use crate::shared::domain::skill::models::{LearnedSkill, SkillBook, SKILL_BAR_SLOTS};
+use crate::shared::protocol::ChatChannel;
+use crate::server::chat::NewChatLog;
+use super::{
+    AuditRepo, BankTransfer, CharacterRecord, ChatRepo, CharacterRepo, GuildInviteRecord, GuildMemberRecord, GuildRecord, GuildRepo,
+    InventoryRepo, NewAuditEntry, NewCharacter, RepoError, RepoFuture, SkillRepo, TradeSide, UserRecord, UserRepo,
+};
+
+/// Postgres `unique_violation`
+const UNIQUE_VIOLATION: &str = "23505";
+
+/// Map a unique-constraint failure to `conflict`
+fn on_unique_violation(e: sqlx::Error, conflict: RepoError) -> RepoError {
+    match e.as_database_error().and_then(|d| d.code()) {
+        Some(code) if code == UNIQUE_VIOLATION => conflict,
+        _ => RepoError::Database(e),
+    }
+}
+
+/// Timestamps cross the driver as unix seconds (sqlx is built without chrono)
+fn from_epoch(secs: f64) -> DateTime<Utc> {
+    DateTime::from_timestamp_micros((secs * 1_000_000.0).round() as i64).unwrap_or_default()
+}
+
+fn to_epoch(at: DateTime<Utc>) -> f64 {
+    at.timestamp_micros() as f64 / 1_000_000.0
+}
+
+/// Account columns in `UserRow` order; only a running suspension is returned
+const USER_COLUMNS: &str = "id, username, password_hash, COALESCE(locked_until > NOW(), FALSE), role, is_active, \
+    CASE WHEN suspended_until > NOW() THEN EXTRACT(EPOCH FROM suspended_until)::float8 END";
+
+type UserRow = (Uuid, String, String, bool, String, bool, Option<f64>);
+
+fn user_record((id, username, password_hash, locked, role, is_active, suspended_until): UserRow) -> UserRecord {
+    let status = match (is_active, suspended_until) {
+        (false, _) => AccountStatus::Banned,
+        (true, Some(until)) => AccountStatus::Suspended { until: from_epoch(until) },
+        (true, None) => AccountStatus::Active,
+    };
+    let role = Role::from_key(&role).unwrap_or_else(|| {
+        tracing::warn!("Unknown role {:?} for {}, treating as player", role, id);
+        Role::Player
+    });
+    UserRecord { id, username, password_hash, locked, role, status }
+}
+
+#[derive(Clone)]
+pub struct PgRepo {
+    pool: PgPool,
+}
+
+impl PgRepo {
+    pub fn new(pool: PgPool) -> Self {
+        Self { pool }
+    }
+}
+
+/// A stored bind point; all three columns are set together
+fn bind_point(row: &sqlx::postgres::PgRow) -> Option<BindPoint> {
+    Some(BindPoint {
+        map_id: row.try_get::<Option<String>, _>("bind_map_id").ok()??,
+        x: row.try_get::<Option<i32>, _>("bind_x").ok()??,
+        y: row.try_get::<Option<i32>, _>("bind_y").ok()??,
+    })
+}
+
+async fn insert_character(conn: &mut PgConnection, user_id: Uuid, character: &NewCharacter) -> Result<CharacterSummary, RepoError> {
+    let id = Uuid::new_v4();
+    let row = sqlx::query(
+        "INSERT INTO characters (id, user_id, name, class_id, gender, hp, mp) VALUES ($1, $2, $3, $4, $5, $6, $7) RETURNING level, current_map"
+    )
+    .bind(id)
+    .bind(user_id)
+    .bind(&character.name)
+    .bind(character.class_id)
+    .bind(&character.gender)
+    .bind(character.hp)
+    .bind(character.mp)
+    .fetch_one(conn)
+    .await
+    .map_err(|e| on_unique_violation(e, RepoError::NameTaken))?;
+
+    Ok(CharacterSummary {
+        id: id.to_string(),
+        name: character.name.clone(),
+        class_id: character.class_id,
+        gender: character.gender.clone(),
+        level: row.try_get("level").unwrap_or(1),
+        current_map: row.try_get("current_map").unwrap_or_default(),
+    })
+}
+
+impl UserRepo for PgRepo {
+    fn find_by_username<'a>(&'a self, username: &'a str) -> RepoFuture<'a, Option<UserRecord>> {
+        Box::pin(async move {
+            let row: Option<UserRow> = sqlx::query_as(&format!("SELECT {} FROM users WHERE username = $1", USER_COLUMNS))
+                .bind(username)
+                .fetch_optional(&self.pool)
+                .await?;
+            Ok(row.map(user_record))
+        })
+    }
+
+    fn find_by_id(&self, user_id: Uuid) -> RepoFuture<'_, Option<UserRecord>> {
+        Box::pin(async move {
+            let row: Option<UserRow> = sqlx::query_as(&format!("SELECT {} FROM users WHERE id = $1", USER_COLUMNS))
+                .bind(user_id)
+                .fetch_optional(&self.pool)
+                .await?;
+            Ok(row.map(user_record))
+        })
+    }
+
+    fn record_login_failure(&self, user_id: Uuid, max_failures: i32, lockout_secs: f64) -> RepoFuture<'_, ()> {
+        Box::pin(async move {
+            sqlx::query(
+                "UPDATE users SET
+                    failed_login_attempts = CASE WHEN failed_login_attempts + 1 >= $2 THEN 0 ELSE failed_login_attempts + 1 END,
+                    locked_until = CASE WHEN failed_login_attempts + 1 >= $2 THEN NOW() + make_interval(secs => $3) ELSE locked_until END,
+                    last_failed_login_at = NOW()
+                 WHERE id = $1"
+            )
+            .bind(user_id)
+            .bind(max_failures)
+            .bind(lockout_secs)
+            .execute(&self.pool)
+            .await?;
+            Ok(())
+        })
+    }
+
+    fn record_login(&self, user_id: Uuid) -> RepoFuture<'_, ()> {
+        Box::pin(async move {
+            sqlx::query(
+                "UPDATE users SET failed_login_attempts = 0, locked_until = NULL, last_login_at = NOW() WHERE id = $1"
+            )
+            .bind(user_id)
+            .execute(&self.pool)
+            .await?;
+            Ok(())
+        })
+    }
+
+    fn register<'a>(&'a self, username: &'a str, password_hash: &'a str, character: NewCharacter) -> RepoFuture<'a, Uuid> {
+        Box::pin(async move {
+            let mut tx = self.pool.begin().await?;
+
+            let user_id = Uuid::new_v4();
+            sqlx::query("INSERT INTO users (id, username, password_hash) VALUES ($1, $2, $3)")
+                .bind(user_id)
+                .bind(username)
+                .bind(password_hash)
+                .execute(&mut *tx)
+                .await
+                .map_err(|e| on_unique_violation(e, RepoError::UsernameTaken))?;
+
+            insert_character(&mut tx, user_id, &character).await?;
+
+            tx.commit().await?;
+            Ok(user_id)
+        })
+    }
+
+    fn set_role(&self, user_id: Uuid, role: Role) -> RepoFuture<'_, bool> {
+        Box::pin(async move {
+            let result = sqlx::query("UPDATE users SET role = $2 WHERE id = $1")
+                .bind(user_id)
+                .bind(role.key())
+                .execute(&self.pool)
+                .await?;
+            Ok(result.rows_affected() > 0)
+        })
+    }
+
+    fn set_status<'a>(&'a self, user_id: Uuid, status: &'a AccountStatus) -> RepoFuture<'a, bool> {
+        Box::pin(async move {
+            let (is_active, suspended_until) = match status {
+                AccountStatus::Active => (true, None),
+                AccountStatus::Banned => (false, None),
+                AccountStatus::Suspended { until } => (true, Some(to_epoch(*until))),
+            };
+            let result = sqlx::query("UPDATE users SET is_active = $2, suspended_until = to_timestamp($3) WHERE id = $1")
+                .bind(user_id)
+                .bind(is_active)
+                .bind(suspended_until)
+                .execute(&self.pool)
+                .await?;
+            Ok(result.rows_affected() > 0)
+        })
+    }
+}
+
+impl CharacterRepo for PgRepo {
+    fn roster(&self, user_id: Uuid) -> RepoFuture<'_, Vec<CharacterSummary>> {
+        Box::pin(async move {
+            let rows = sqlx::query(
+                "SELECT id, name, class_id, gender, level, current_map FROM characters WHERE user_id = $1 ORDER BY created_at"
+            )
+            .bind(user_id)
+            .fetch_all(&self.pool)
+            .await?;
+
+            Ok(rows.iter()
+                .map(|r| CharacterSummary {
+                    id: r.get::<Uuid, _>("id").to_string(),
+                    name: r.get("name"),
+                    class_id: r.try_get("class_id").unwrap_or(1),
+                    gender: r.get("gender"),
+                    level: r.try_get("level").unwrap_or(1),
+                    current_map: r.try_get("current_map").unwrap_or_default(),
+                })
+                .collect())
+        })
+    }
+
+    fn create(&self, user_id: Uuid, character: NewCharacter) -> RepoFuture<'_, CharacterSummary> {
+        Box::pin(async move {
+            let mut conn = self.pool.acquire().await?;
+            insert_character(&mut conn, user_id, &character).await
+        })
+    }
+
+    fn delete(&self, user_id: Uuid, character_id: Uuid) -> RepoFuture<'_, bool> {
+        Box::pin(async move {
+            let result = sqlx::query("DELETE FROM characters WHERE id = $1 AND user_id = $2")
+                .bind(character_id)
+                .bind(user_id)
+                .execute(&self.pool)
+                .await?;
+            Ok(result.rows_affected() > 0)
+        })
+    }
+
+    fn load(&self, user_id: Uuid, character_id: Uuid) -> RepoFuture<'_, Option<CharacterRecord>> {
+        Box::pin(async move {
+            let row = sqlx::query(
+                r#"
+                SELECT id, name, gender, class_id, level, exp, hp, mp, gold, current_map, pos_x, pos_y,
+                       bind_map_id, bind_x, bind_y,
+                       bonus_str_stat, bonus_dex_stat, bonus_int_stat, bonus_wis_stat, bonus_con_stat, stat_points
+                FROM characters
+                WHERE id = $1 AND user_id = $2
+                "#
+            )
+            .bind(character_id)
+            .bind(user_id)
+            .fetch_optional(&self.pool)
+            .await?;
+
+            Ok(row.map(|c| CharacterRecord {
+                id: character_id,
+                name: c.get("name"),
+                class_id: c.get("class_id"),
+                gender: c.get("gender"),
+                level: c.try_get("level").unwrap_or(1),
+                exp: c.try_get("exp").unwrap_or(0),
+                hp: c.try_get("hp").unwrap_or(1),
+                mp: c.try_get("mp").unwrap_or(0),
+                gold: c.try_get("gold").unwrap_or(0),
+                current_map: c.try_get("current_map").unwrap_or_else(|_| defaults::STARTING_MAP.to_string()),
+                pos_x: c.try_get("pos_x").unwrap_or(defaults::STARTING_X),
+                pos_y: c.try_get("pos_y").unwrap_or(defaults::STARTING_Y),
+                bind_point: bind_point(&c),
+                bonus_stats: Stats {
+                    str_stat: c.try_get("bonus_str_stat").unwrap_or(0),
+                    dex_stat: c.try_get("bonus_dex_stat").unwrap_or(0),
+                    int_stat: c.try_get("bonus_int_stat").unwrap_or(0),
+                    wis_stat: c.try_get("bonus_wis_stat").unwrap_or(0),
+                    con_stat: c.try_get("bonus_con_stat").unwrap_or(0),
+                },
+                stat_points: c.try_get("stat_points").unwrap_or(0),
+            }))
+        })
+    }
+
+    fn find_by_name<'a>(&'a self, name: &'a str) -> RepoFuture<'a, Option<Uuid>> {
+        Box::pin(async move {
+            let id: Option<(Uuid,)> = sqlx::query_as("SELECT id FROM characters WHERE LOWER(name) = LOWER($1)")
+                .bind(name)
+                .fetch_optional(&self.pool)
+                .await?;
+            Ok(id.map(|(id,)| id))
+        })
+    }
+
+    fn owner(&self, character_id: Uuid) -> RepoFuture<'_, Option<Uuid>> {
+        Box::pin(async move {
+            let owner: Option<(Uuid,)> = sqlx::query_as("SELECT user_id FROM characters WHERE id = $1")
+                .bind(character_id)
+                .fetch_optional(&self.pool)
+                .await?;
+            Ok(owner.map(|(id,)| id))
+        })
+    }
+
+    fn mark_played(&self, character_id: Uuid) -> RepoFuture<'_, ()> {
+        Box::pin(async move {
+            sqlx::query("UPDATE characters SET last_played_at = CURRENT_TIMESTAMP WHERE id = $1")
+                .bind(character_id)
+                .execute(&self.pool)
+                .await?;
+            Ok(())
+        })
+    }
+
+    fn save<'a>(&'a self, c: &'a CharacterRecord, inventory: &'a Inventory, skills: &'a SkillBook) -> RepoFuture<'a, ()> {
+        Box::pin(async move {
+            let mut tx = self.pool.begin().await?;
+            sqlx::query(
+                r#"
+                UPDATE characters SET
+                    level = $2, exp = $3, total_exp = $4, hp = $5, mp = $6, gold = $7,
+                    current_map = $8, pos_x = $9, pos_y = $10,
+                    bonus_str_stat = $11, bonus_dex_stat = $12, bonus_int_stat = $13,
+                    bonus_wis_stat = $14, bonus_con_stat = $15, stat_points = $16,
+                    bind_map_id = $17, bind_x = $18, bind_y = $19,
+                    last_played_at = CURRENT_TIMESTAMP
+                WHERE id = $1
+                "#
+            )
+            .bind(c.id)
+            .bind(c.level)
+            .bind(c.exp)
+            .bind(total_exp_for_level(c.level) + c.exp)
+            .bind(c.hp)
+            .bind(c.mp)
+            .bind(c.gold)
+            .bind(&c.current_map)
+            .bind(c.pos_x)
+            .bind(c.pos_y)
+            .bind(c.bonus_stats.str_stat)
+            .bind(c.bonus_stats.dex_stat)
+            .bind(c.bonus_stats.int_stat)
+            .bind(c.bonus_stats.wis_stat)
+            .bind(c.bonus_stats.con_stat)
+            .bind(c.stat_points)
+            .bind(c.bind_point.as_ref().map(|b| b.map_id.as_str()))
+            .bind(c.bind_point.as_ref().map(|b| b.x))
+            .bind(c.bind_point.as_ref().map(|b| b.y))
+            .execute(&mut *tx)
+            .await?;
+
+            save_inventory(&mut tx, c.id, inventory).await?;
+            save_skills(&mut tx, c.id, skills).await?;
+            tx.commit().await?;
+            Ok(())
+        })
+    }
+
+    fn save_trade<'a>(&'a self, sides: &'a [TradeSide]) -> RepoFuture<'a, ()> {
+        Box::pin(async move {
+            let mut tx = self.pool.begin().await?;
+            for side in sides {
+                sqlx::query("UPDATE characters SET gold = $2 WHERE id = $1")
+                    .bind(side.character_id)
+                    .bind(side.gold)
+                    .execute(&mut *tx)
+                    .await?;
+                save_inventory(&mut tx, side.character_id, &side.inventory).await?;
+            }
+            tx.commit().await?;
+            Ok(())
+        })
+    }
+}
+
+impl InventoryRepo for PgRepo {
+    fn load(&self, character_id: Uuid) -> RepoFuture<'_, Inventory> {
+        Box::pin(async move {
+            let rows = sqlx::query(
+                r#"
+                SELECT item_id, quantity, slot_index, is_equipped, equipped_slot, enhancement_level
+                FROM character_inventory
+                WHERE character_id = $1
+                ORDER BY slot_index NULLS LAST, created_at
+                "#
+            )
+            .bind(character_id)
+            .fetch_all(&self.pool)
+            .await?;
+
+            let mut inventory = Inventory::new();
+            let mut misplaced = Vec::new();
+
+            for r in rows {
+                let item_id: i32 = r.get("item_id");
+                if get_item_by_id(item_id).is_none() {
+                    tracing::warn!("Dropping unknown item {} from {}", item_id, character_id);
+                    continue;
+                }
+                let stack = ItemStack {
+                    item_id,
+                    quantity: r.try_get("quantity").unwrap_or(1),
+                    enhancement: r.try_get("enhancement_level").unwrap_or(0),
+                };
+
+                let equipped_slot = r.try_get::<Option<String>, _>("equipped_slot").ok().flatten();
+                if r.try_get("is_equipped").unwrap_or(false)
+                    && let Some(slot) = equipped_slot.as_deref().and_then(EquipSlot::from_key)
+                    && !inventory.equipment.contains_key(&slot)
+                {
+                    inventory.equipment.insert(slot, stack);
+                    continue;
+                }
+
+                match r.try_get::<Option<i32>, _>("slot_index").ok().flatten() {
+                    Some(i) if (0..INVENTORY_SIZE as i32).contains(&i) && inventory.slots[i as usize].is_none() => {
+                        inventory.slots[i as usize] = Some(stack);
+                    }
+                    _ => misplaced.push(stack),
+                }
+            }
+
+            // Rows without a valid slot go to the first free ones
+            for stack in misplaced {
+                match inventory.slots.iter_mut().find(|s| s.is_none()) {
+                    Some(slot) => *slot = Some(stack),
+                    None => tracing::warn!("No room for item {} of {}", stack.item_id, character_id),
+                }
+            }
+
+            Ok(inventory)
+        })
+    }
+}
+
+impl SkillRepo for PgRepo {
+    fn load(&self, character_id: Uuid, class_id: i32) -> RepoFuture<'_, SkillBook> {
+        Box::pin(async move {
+            let rows = sqlx::query(
+                "SELECT skill_id, skill_level, slot_index FROM character_skills WHERE character_id = $1 ORDER BY learned_at, skill_id"
+            )
+            .bind(character_id)
+            .fetch_all(&self.pool)
+            .await?;
+
+            if rows.is_empty() {
+                return Ok(SkillBook::starter(class_id));
+            }
+
+            let mut book = SkillBook::default();
+            for r in rows {
+                let skill_id: i32 = r.get("skill_id");
+                if get_skill_by_id(skill_id).is_none() {
+                    tracing::warn!("Dropping unknown skill {} from {}", skill_id, character_id);
+                    continue;
+                }
+                book.learned.push(LearnedSkill {
+                    skill_id,
+                    level: r.try_get("skill_level").unwrap_or(1),
+                });
+
+                // Stored slots are 1-based
+                if let Some(slot) = r.try_get::<Option<i32>, _>("slot_index").ok().flatten()
+                    && (1..=SKILL_BAR_SLOTS as i32).contains(&slot)
+                {
+                    book.bar[slot as usize - 1] = Some(skill_id);
+                }
+            }
+
+            Ok(book)
+        })
+    }
+}
+
+impl AuditRepo for PgRepo {
+    fn record(&self, entry: NewAuditEntry) -> RepoFuture<'_, ()> {
+        Box::pin(async move {
+            sqlx::query(
+                "INSERT INTO gm_audit_log (gm_user_id, action, target_user_id, target_character_id, details) VALUES ($1, $2, $3, $4, $5)"
+            )
+            .bind(entry.gm_user_id)
+            .bind(entry.action)
+            .bind(entry.target_user_id)
+            .bind(entry.target_character_id)
+            .bind(&entry.details)
+            .execute(&self.pool)
+            .await?;
+            Ok(())
+        })
+    }
+
+    fn recent(&self, limit: i64) -> RepoFuture<'_, Vec<AuditEntry>> {
+        Box::pin(async move {
+            let rows = sqlx::query(
+                r#"
+                SELECT id, gm_user_id, action, target_user_id, target_character_id, details,
+                       EXTRACT(EPOCH FROM created_at)::float8 AS created_at
+                FROM gm_audit_log
+                ORDER BY id DESC
+                LIMIT $1
+                "#
+            )
+            .bind(limit)
+            .fetch_all(&self.pool)
+            .await?;
+
+            Ok(rows.iter()
+                .map(|r| AuditEntry {
+                    id: r.get("id"),
+                    gm_user_id: r.get::<Uuid, _>("gm_user_id").to_string(),
+                    action: r.get("action"),
+                    target_user_id: r.get::<Option<Uuid>, _>("target_user_id").map(|id| id.to_string()),
+                    target_character_id: r.get::<Option<Uuid>, _>("target_character_id").map(|id| id.to_string()),
+                    details: r.get("details"),
+                    created_at: from_epoch(r.get("created_at")),
+                })
+                .collect())
+        })
+    }
+}
+
+impl ChatRepo for PgRepo {
+    fn append<'a>(&'a self, entries: &'a [NewChatLog]) -> RepoFuture<'a, ()> {
+        Box::pin(async move {
+            let mut tx = self.pool.begin().await?;
+            for e in entries {
+                sqlx::query(
+                    r#"
+                    INSERT INTO chat_log
+                        (channel, sender_character_id, sender_name, recipient_name, map_id, message, filtered, sent_at)
+                    VALUES ($1, $2, $3, $4, $5, $6, $7, to_timestamp($8))
+                    "#
+                )
+                .bind(e.channel.key())
+                .bind(e.sender_character_id)
+                .bind(&e.sender_name)
+                .bind(&e.recipient_name)
+                .bind(&e.map_id)
+                .bind(&e.message)
+                .bind(e.filtered)
+                .bind(to_epoch(e.sent_at))
+                .execute(&mut *tx)
+                .await?;
+            }
+            tx.commit().await?;
+            Ok(())
+        })
+    }
+
+    fn recent(&self, sender_character_id: Option<Uuid>, limit: i64) -> RepoFuture<'_, Vec<ChatLogEntry>> {
+        Box::pin(async move {
+            let rows = sqlx::query(
+                r#"
+                SELECT id, channel, sender_character_id, sender_name, recipient_name, map_id, message, filtered,
+                       EXTRACT(EPOCH FROM sent_at)::float8 AS sent_at
+                FROM chat_log
+                WHERE $1::uuid IS NULL OR sender_character_id = $1
+                ORDER BY id DESC
+                LIMIT $2
+                "#
+            )
+            .bind(sender_character_id)
+            .bind(limit)
+            .fetch_all(&self.pool)
+            .await?;
+
+            Ok(rows.iter()
+                .filter_map(|r| {
+                    let channel: String = r.get("channel");
+                    Some(ChatLogEntry {
+                        id: r.get("id"),
+                        channel: ChatChannel::from_key(&channel)?,
+                        sender_character_id: r.get::<Option<Uuid>, _>("sender_character_id").map(|id| id.to_string()),
+                        sender_name: r.get("sender_name"),
+                        recipient_name: r.get("recipient_name"),
+                        map_id: r.get("map_id"),
+                        message: r.get("message"),
+                        filtered: r.get("filtered"),
+                        sent_at: from_epoch(r.get("sent_at")),
+                    })
+                })
+                .collect())
+        })
+    }
+}
+
+fn guild_rank(character_id: Uuid, rank: &str) -> GuildRank {
+    GuildRank::from_key(rank).unwrap_or_else(|| {
+        tracing::warn!("Unknown guild rank {:?} for {}, treating as member", rank, character_id);
+        GuildRank::Member
+    })
+}
+
+impl GuildRepo for PgRepo {
+    fn create<'a>(&'a self, name: &'a str, leader: Uuid) -> RepoFuture<'a, GuildRecord> {
+        Box::pin(async move {
+            let mut tx = self.pool.begin().await?;
+            let id = Uuid::new_v4();
+            sqlx::query("INSERT INTO guilds (id, name) VALUES ($1, $2)")
+                .bind(id)
+                .bind(name)
+                .execute(&mut *tx)
+                .await
+                .map_err(|e| on_unique_violation(e, RepoError::GuildNameTaken))?;
+            sqlx::query("INSERT INTO guild_members (character_id, guild_id, rank) VALUES ($1, $2, 'leader')")
+                .bind(leader)
+                .bind(id)
+                .execute(&mut *tx)
+                .await?;
+            tx.commit().await?;
+            Ok(GuildRecord { id, name: name.to_string(), notice: String::new() })
+        })
+    }
+
+    fn membership(&self, character_id: Uuid) -> RepoFuture<'_, Option<(GuildRecord, GuildRank)>> {
+        Box::pin(async move {
+            let row: Option<(Uuid, String, String, String)> = sqlx::query_as(
+                "SELECT g.id, g.name, g.notice, m.rank FROM guild_members m JOIN guilds g ON g.id = m.guild_id WHERE m.character_id = $1"
+            )
+            .bind(character_id)
+            .fetch_optional(&self.pool)
+            .await?;
+            Ok(row.map(|(id, name, notice, rank)| (GuildRecord { id, name, notice }, guild_rank(character_id, &rank))))
+        })
+    }
+
+    fn members(&self, guild_id: Uuid) -> RepoFuture<'_, Vec<GuildMemberRecord>> {
+        Box::pin(async move {
+            let rows = sqlx::query(
+                r#"
+                SELECT c.id, c.name, c.class_id, c.level, m.rank
+                FROM guild_members m
+                JOIN characters c ON c.id = m.character_id
+                WHERE m.guild_id = $1
+                ORDER BY CASE m.rank WHEN 'leader' THEN 0 WHEN 'officer' THEN 1 ELSE 2 END, m.joined_at
+                "#
+            )
+            .bind(guild_id)
+            .fetch_all(&self.pool)
+            .await?;
+
+            Ok(rows.iter()
+                .map(|r| {
+                    let character_id: Uuid = r.get("id");
+                    GuildMemberRecord {
+                        character_id,
+                        name: r.get("name"),
+                        class_id: r.try_get("class_id").unwrap_or(1),
+                        level: r.try_get("level").unwrap_or(1),
+                        rank: guild_rank(character_id, r.get("rank")),
+                    }
+                })
+                .collect())
+        })
+    }
+
+    fn invite<'a>(&'a self, guild_id: Uuid, character_id: Uuid, invited_by: &'a str) -> RepoFuture<'a, ()> {
+        Box::pin(async move {
+            sqlx::query(
+                r#"
+                INSERT INTO guild_invites (guild_id, character_id, invited_by) VALUES ($1, $2, $3)
+                ON CONFLICT (guild_id, character_id) DO UPDATE SET invited_by = $3, created_at = CURRENT_TIMESTAMP
+                "#
+            )
+            .bind(guild_id)
+            .bind(character_id)
+            .bind(invited_by)
+            .execute(&self.pool)
+            .await?;
+            Ok(())
+        })
+    }
+
+    fn invites(&self, character_id: Uuid) -> RepoFuture<'_, Vec<GuildInviteRecord>> {
+        Box::pin(async move {
+            let rows: Vec<(Uuid, String, String)> = sqlx::query_as(
+                r#"
+                SELECT i.guild_id, g.name, i.invited_by
+                FROM guild_invites i
+                JOIN guilds g ON g.id = i.guild_id
+                WHERE i.character_id = $1
+                ORDER BY i.created_at
+                "#
+            )
+            .bind(character_id)
+            .fetch_all(&self.pool)
+            .await?;
+            Ok(rows.into_iter()
+                .map(|(guild_id, guild_name, invited_by)| GuildInviteRecord { guild_id, guild_name, invited_by })
+                .collect())
+        })
+    }
+
+    fn accept_invite(&self, guild_id: Uuid, character_id: Uuid) -> RepoFuture<'_, bool> {
+        Box::pin(async move {
+            let mut tx = self.pool.begin().await?;
+            let taken = sqlx::query("DELETE FROM guild_invites WHERE guild_id = $1 AND character_id = $2")
+                .bind(guild_id)
+                .bind(character_id)
+                .execute(&mut *tx)
+                .await?;
+            if taken.rows_affected() == 0 {
+                return Ok(false);
+            }
+            let joined = sqlx::query(
+                "INSERT INTO guild_members (character_id, guild_id, rank) VALUES ($1, $2, 'member') ON CONFLICT DO NOTHING"
+            )
+            .bind(character_id)
+            .bind(guild_id)
+            .execute(&mut *tx)
+            .await?;
+            if joined.rows_affected() == 0 {
+                return Ok(false);
+            }
+            sqlx::query("DELETE FROM guild_invites WHERE character_id = $1")
+                .bind(character_id)
+                .execute(&mut *tx)
+                .await?;
+            tx.commit().await?;
+            Ok(true)
+        })
+    }
+
+    fn remove_member(&self, guild_id: Uuid, character_id: Uuid) -> RepoFuture<'_, bool> {
+        Box::pin(async move {
+            let result = sqlx::query("DELETE FROM guild_members WHERE guild_id = $1 AND character_id = $2")
+                .bind(guild_id)
+                .bind(character_id)
+                .execute(&self.pool)
+                .await?;
+            Ok(result.rows_affected() > 0)
+        })
+    }
+
+    fn set_rank(&self, guild_id: Uuid, character_id: Uuid, rank: GuildRank) -> RepoFuture<'_, bool> {
+        Box::pin(async move {
+            let mut tx = self.pool.begin().await?;
+            if rank == GuildRank::Leader {
+                // One leader per guild: step the old one down first
+                sqlx::query(
+                    "UPDATE guild_members SET rank = 'officer' WHERE guild_id = $1 AND rank = 'leader' AND character_id <> $2 \
+                     AND EXISTS (SELECT 1 FROM guild_members WHERE guild_id = $1 AND character_id = $2)"
+                )
+                .bind(guild_id)
+                .bind(character_id)
+                .execute(&mut *tx)
+                .await?;
+            }
+            let result = sqlx::query("UPDATE guild_members SET rank = $3 WHERE guild_id = $1 AND character_id = $2")
+                .bind(guild_id)
+                .bind(character_id)
+                .bind(rank.key())
+                .execute(&mut *tx)
+                .await?;
+            tx.commit().await?;
+            Ok(result.rows_affected() > 0)
+        })
+    }
+
+    fn set_notice<'a>(&'a self, guild_id: Uuid, notice: &'a str) -> RepoFuture<'a, ()> {
+        Box::pin(async move {
+            sqlx::query("UPDATE guilds SET notice = $2 WHERE id = $1")
+                .bind(guild_id)
+                .bind(notice)
+                .execute(&self.pool)
+                .await?;
+            Ok(())
+        })
+    }
+
+    fn disband(&self, guild_id: Uuid) -> RepoFuture<'_, ()> {
+        Box::pin(async move {
+            // Members, invites and bank items go with it (ON DELETE CASCADE)
+            sqlx::query("DELETE FROM guilds WHERE id = $1")
+                .bind(guild_id)
+                .execute(&self.pool)
+                .await?;
+            Ok(())
+        })
+    }
+
+    fn bank(&self, guild_id: Uuid) -> RepoFuture<'_, GuildBank> {
+        Box::pin(async move {
+            let mut conn = self.pool.acquire().await?;
+            Ok(load_bank(&mut conn, guild_id, false).await?.unwrap_or_default())
+        })
+    }
+
+    fn transfer(&self, guild_id: Uuid, transfer: BankTransfer) -> RepoFuture<'_, Result<GuildBank, String>> {
+        Box::pin(async move {
+            let mut tx = self.pool.begin().await?;
+            let Some(mut bank) = load_bank(&mut tx, guild_id, true).await? else {
+                return Ok(Err("Guild not found".to_string()));
+            };
+            // Dropping the transaction releases the row lock
+            let member = match transfer(&mut bank) {
+                Ok(member) => member,
+                Err(e) => return Ok(Err(e)),
+            };
+
+            sqlx::query("UPDATE guilds SET bank_gold = $2 WHERE id = $1")
+                .bind(guild_id)
+                .bind(bank.gold)
+                .execute(&mut *tx)
+                .await?;
+            sqlx::query("DELETE FROM guild_bank_items WHERE guild_id = $1")
+                .bind(guild_id)
+                .execute(&mut *tx)
+                .await?;
+            for (i, stack) in bank.slots.iter().enumerate() {
+                let Some(stack) = stack else { continue; };
+                sqlx::query(
+                    "INSERT INTO guild_bank_items (guild_id, slot_index, item_id, quantity, enhancement_level) VALUES ($1, $2, $3, $4, $5)"
+                )
+                .bind(guild_id)
+                .bind(i as i32)
+                .bind(stack.item_id)
+                .bind(stack.quantity)
+                .bind(stack.enhancement)
+                .execute(&mut *tx)
+                .await?;
+            }
+
+            sqlx::query("UPDATE characters SET gold = $2 WHERE id = $1")
+                .bind(member.character_id)
+                .bind(member.gold)
+                .execute(&mut *tx)
+                .await?;
+            save_inventory(&mut tx, member.character_id, &member.inventory).await?;
+            tx.commit().await?;
+            Ok(Ok(bank))
+        })
+    }
+}
+
+/// A guild's bank; `lock` holds the guild row until the transaction ends
+async fn load_bank(conn: &mut PgConnection, guild_id: Uuid, lock: bool) -> Result<Option<GuildBank>, sqlx::Error> {
+    let lock = if lock { " FOR UPDATE" } else { "" };
+    let gold: Option<(i64,)> = sqlx::query_as(&format!("SELECT bank_gold FROM guilds WHERE id = $1{}", lock))
+        .bind(guild_id)
+        .fetch_optional(&mut *conn)
+        .await?;
+    let Some((gold,)) = gold else { return Ok(None); };
+
+    let rows: Vec<(i32, i32, i32, i32)> = sqlx::query_as(
+        "SELECT slot_index, item_id, quantity, enhancement_level FROM guild_bank_items WHERE guild_id = $1"
+    )
+    .bind(guild_id)
+    .fetch_all(&mut *conn)
+    .await?;
+
+    let mut bank = GuildBank { gold, ..GuildBank::default() };
+    for (slot_index, item_id, quantity, enhancement) in rows {
+        if get_item_by_id(item_id).is_none() {
+            tracing::warn!("Dropping unknown item {} from guild bank {}", item_id, guild_id);
+            continue;
+        }
+        match bank.slots.get_mut(slot_index as usize) {
+            Some(slot) if slot.is_none() => *slot = Some(ItemStack { item_id, quantity, enhancement }),
+            _ => tracing::warn!("Guild bank {} has no slot {} for item {}", guild_id, slot_index, item_id),
+        }
+    }
+    Ok(Some(bank))
+}
+
+/// Replace a character's stored inventory (inside the save transaction)
+async fn save_inventory(conn: &mut PgConnection, character_id: Uuid, inventory: &Inventory) -> Result<(), sqlx::Error> {
+    sqlx::query("DELETE FROM character_inventory WHERE character_id = $1")
+        .bind(character_id)
+        .execute(&mut *conn)
+        .await?;
+
+    let bag = inventory.slots.iter()
+        .enumerate()
+        .filter_map(|(i, s)| s.as_ref().map(|stack| (stack, Some(i as i32), None)));
+    let equipped = inventory.equipment.iter()
+        .map(|(slot, stack)| (stack, None, Some(slot.key())));
+
+    for (stack, slot_index, equipped_slot) in bag.chain(equipped) {
+        sqlx::query(
+            r#"
+            INSERT INTO character_inventory
+                (character_id, item_id, quantity, slot_index, is_equipped, equipped_slot, enhancement_level)
+            VALUES ($1, $2, $3, $4, $5, $6, $7)
+            "#
+        )
+        .bind(character_id)
+        .bind(stack.item_id)
+        .bind(stack.quantity)
+        .bind(slot_index)
+        .bind(equipped_slot.is_some())
+        .bind(equipped_slot)
+        .bind(stack.enhancement)
+        .execute(&mut *conn)
+        .await?;
+    }
+
+    Ok(())
+}
+
+/// Write a character's skills (inside the save transaction). Skills it
+/// still knows keep their `learned_at`, so the book loads in learning order.
+async fn save_skills(conn: &mut PgConnection, character_id: Uuid, book: &SkillBook) -> Result<(), sqlx::Error> {
+    let known: Vec<i32> = book.learned.iter().map(|s| s.skill_id).collect();
+    sqlx::query("DELETE FROM character_skills WHERE character_id = $1 AND skill_id <> ALL($2)")
+        .bind(character_id)
+        .bind(&known)
+        .execute(&mut *conn)
+        .await?;
+
+    for skill in &book.learned {
+        let slot = book.bar.iter()
+            .position(|s| *s == Some(skill.skill_id))
+            .map(|i| i as i32 + 1);
+        sqlx::query(
+            r#"
+            INSERT INTO character_skills (character_id, skill_id, skill_level, slot_index) VALUES ($1, $2, $3, $4)
+            ON CONFLICT (character_id, skill_id) DO UPDATE SET skill_level = $3, slot_index = $4
+            "#
+        )
+        .bind(character_id)
+        .bind(skill.skill_id)
+        .bind(skill.level)
+        .bind(slot)
+        .execute(&mut *conn)
+        .await?;
+    }
+
+    Ok(())
+}
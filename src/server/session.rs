@@ -0,0 +1,149 @@
+//! Session tokens - JWT access tokens and the `AuthUser` extractor
+//!
+//! Login issues a signed token carrying the user id and the active
+//! character id. Handlers that take `AuthUser` reject requests whose
+//! `Authorization: Bearer <token>` header is missing, invalid or expired.
+
+use axum::extract::{FromRef, FromRequestParts};
+use axum::http::{header, request::Parts};
+use jsonwebtoken::{DecodingKey, EncodingKey, Header, Validation};
+use serde::{Deserialize, Serialize};
+use uuid::Uuid;
+
+use super::error::ApiError;
+
+/// Access token lifetime (seconds)
+pub const TOKEN_TTL_SECS: i64 = 12 * 60 * 60;
+
+/// Token payload
+#[derive(Debug, Clone, Serialize, Deserialize, PartialEq)]
+pub struct Claims {
+    /// User id
+    pub sub: Uuid,
+    /// Active character id
+    pub cid: Option<Uuid>,
+    /// Issued at (unix seconds)
+    pub iat: i64,
+    /// Expires at (unix seconds)
+    pub exp: i64,
+}
+
+/// Signing keys, shared with handlers as router `State` through `FromRef`
+#[derive(Clone)]
+pub struct SessionKeys {
+    encoding: EncodingKey,
+    decoding: DecodingKey,
+}
+
+impl SessionKeys {
+    pub fn new(secret: &[u8]) -> Self {
+        Self {
+            encoding: EncodingKey::from_secret(secret),
+            decoding: DecodingKey::from_secret(secret),
+        }
+    }
+
+    /// Sign a token for a user (and optionally their active character)
+    pub fn issue(&self, user_id: Uuid, character_id: Option<Uuid>) -> Result<String, jsonwebtoken::errors::Error> {
+        let now = chrono::Utc::now().timestamp();
+        let claims = Claims {
+            sub: user_id,
+            cid: character_id,
+            iat: now,
+            exp: now + TOKEN_TTL_SECS,
+        };
+        jsonwebtoken::encode(&Header::default(), &claims, &self.encoding)
+    }
+
+    /// Verify signature and expiry
+    pub fn verify(&self, token: &str) -> Result<Claims, jsonwebtoken::errors::Error> {
+        jsonwebtoken::decode::<Claims>(token, &self.decoding, &Validation::default()).map(|data| data.claims)
+    }
+}
+
+/// Authenticated caller
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct AuthUser {
+    pub user_id: Uuid,
+    pub character_id: Option<Uuid>,
+}
+
+impl AuthUser {
+    /// The character the caller is playing, for routes that need one
+    pub fn character(&self) -> Result<Uuid, ApiError> {
+        self.character_id.ok_or_else(|| ApiError::BadRequest("No character selected".to_string()))
+    }
+}
+
+impl From<Claims> for AuthUser {
+    fn from(claims: Claims) -> Self {
+        Self {
+            user_id: claims.sub,
+            character_id: claims.cid,
+        }
+    }
+}
+
+impl<S> FromRequestParts<S> for AuthUser
+where
+    S: Send + Sync,
+    SessionKeys: FromRef<S>,
+{
+    type Rejection = ApiError;
+
+    async fn from_request_parts(parts: &mut Parts, state: &S) -> Result<Self, Self::Rejection> {
+        let keys = SessionKeys::from_ref(state);
+
+        let token = parts.headers.get(header::AUTHORIZATION)
+            .and_then(|value| value.to_str().ok())
+            .and_then(|value| value.strip_prefix("Bearer "))
+            .ok_or(ApiError::Unauthorized("Missing access token"))?;
+
+        match keys.verify(token.trim()) {
+            Ok(claims) => Ok(claims.into()),
+            Err(e) if matches!(e.kind(), jsonwebtoken::errors::ErrorKind::ExpiredSignature) => {
+                Err(ApiError::Unauthorized("Access token expired"))
+            }
+            Err(_) => Err(ApiError::Unauthorized("Invalid access token")),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use axum::http::{Request, StatusCode};
+
+    async fn extract(keys: &SessionKeys, auth_header: Option<String>) -> Result<AuthUser, StatusCode> {
+        let mut builder = Request::builder().uri("/");
+        if let Some(value) = auth_header {
+            builder = builder.header(header::AUTHORIZATION, value);
+        }
+        let (mut parts, _) = builder.body(()).unwrap().into_parts();
+        AuthUser::from_request_parts(&mut parts, keys).await.map_err(|e| e.status())
+    }
+
+    #[tokio::test]
+    async fn test_valid_token_exposes_identity() {
+        let keys = SessionKeys::new(b"test-secret");
+        let (user_id, character_id) = (Uuid::new_v4(), Uuid::new_v4());
+        let token = keys.issue(user_id, Some(character_id)).unwrap();
+
+        let user = extract(&keys, Some(format!("Bearer {}", token))).await.unwrap();
+        assert_eq!(user, AuthUser { user_id, character_id: Some(character_id) });
+    }
+
+    #[tokio::test]
+    async fn test_rejects_missing_forged_and_expired_tokens() {
+        let keys = SessionKeys::new(b"test-secret");
+        assert_eq!(extract(&keys, None).await, Err(StatusCode::UNAUTHORIZED));
+
+        let forged = SessionKeys::new(b"other-secret").issue(Uuid::new_v4(), None).unwrap();
+        assert_eq!(extract(&keys, Some(format!("Bearer {}", forged))).await, Err(StatusCode::UNAUTHORIZED));
+
+        let past = chrono::Utc::now().timestamp() - 3600;
+        let claims = Claims { sub: Uuid::new_v4(), cid: None, iat: past - 60, exp: past };
+        let expired = jsonwebtoken::encode(&Header::default(), &claims, &keys.encoding).unwrap();
+        assert_eq!(extract(&keys, Some(format!("Bearer {}", expired))).await, Err(StatusCode::UNAUTHORIZED));
+    }
+}
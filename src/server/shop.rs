@@ -0,0 +1,183 @@
+//! Shop handlers - Axum REST API
+//!
+//! Shops are NPCs of type `NpcType::Shop`; what each one sells is game
+//! data (see `shared::data::shops`).
+
+use axum::extract::{Path, State};
+use axum::Json;
+
+use crate::shared::api::{ShopBuyRequest, ShopListing, ShopResponse, ShopSellRequest};
+use crate::shared::data::items::get_item_by_id;
+use crate::shared::data::maps::{find_npc, NpcDef, NpcType};
+use crate::shared::data::shops::{get_shop_items, sells};
+use crate::shared::domain::Player;
+use super::error::ApiError;
+use super::persistence::{at_place, save_player, Place};
+use super::repo::Repos;
+use super::session::AuthUser;
+use super::world::WorldHandle;
+
+/// Buy `quantity` of an item a shop sells. Nothing changes unless the
+/// player can pay for all of it and it all fits in the bag.
+pub fn buy(player: &mut Player, npc_id: &str, item_id: i32, quantity: i32) -> Result<(), String> {
+    if !sells(npc_id, item_id) {
+        return Err("The shop does not sell that".to_string());
+    }
+    let def = get_item_by_id(item_id).ok_or("Unknown item")?;
+    if quantity <= 0 {
+        return Err("Invalid quantity".to_string());
+    }
+    let cost = def.price_buy.checked_mul(quantity as i64).ok_or("Invalid quantity")?;
+    if cost > player.gold {
+        return Err("Not enough gold".to_string());
+    }
+
+    let mut inventory = player.inventory.clone();
+    if inventory.add_item(item_id, quantity) > 0 {
+        return Err("Not enough room in your bag".to_string());
+    }
+    player.inventory = inventory;
+    player.gold -= cost;
+    Ok(())
+}
+
+/// Sell items from a bag slot for their sell price; returns the gold earned
+pub fn sell(player: &mut Player, slot_index: usize, quantity: i32) -> Result<i64, String> {
+    let stack = player.inventory.slots.get(slot_index)
+        .and_then(|s| s.as_ref())
+        .ok_or("No item in slot")?;
+    let def = stack.get_def().ok_or("Unknown item")?;
+    if def.price_sell <= 0 {
+        return Err(format!("{} cannot be sold", def.name));
+    }
+    if quantity <= 0 || quantity > stack.quantity {
+        return Err("Invalid quantity".to_string());
+    }
+
+    let earned = def.price_sell.checked_mul(quantity as i64).ok_or("Invalid quantity")?;
+    let gold = player.gold.checked_add(earned).ok_or("You cannot carry that much gold")?;
+    player.inventory.take(slot_index, quantity)?;
+    player.gold = gold;
+    Ok(earned)
+}
+
+/// A shop NPC on any map, with the id of its map
+fn find_shop(npc_id: &str) -> Result<(&'static str, &'static NpcDef), ApiError> {
+    find_npc(npc_id)
+        .filter(|(_, npc)| npc.npc_type == NpcType::Shop)
+        .ok_or(ApiError::NotFound("Shop"))
+}
+
+fn shop_response(shop: &NpcDef, player: Player) -> Json<ShopResponse> {
+    Json(ShopResponse {
+        npc_id: shop.id.to_string(),
+        items: get_shop_items(shop.id).into_iter()
+            .map(|def| ShopListing { item_id: def.id, price: def.price_buy })
+            .collect(),
+        inventory: player.inventory,
+        gold: player.gold,
+    })
+}
+
+// --- Server Handlers ---
+
+pub async fn get_shop_handler(
+    State(world): State<WorldHandle>,
+    user: AuthUser,
+    Path(npc_id): Path<String>,
+) -> Result<Json<ShopResponse>, ApiError> {
+    let character_id = user.character()?;
+    let (map_id, shop) = find_shop(&npc_id)?;
+    let ((), player) = at_place(&world, character_id, Place::Npc { map_id, npc_id: shop.id }, "the shop", |_| Ok(()))
+        .map_err(ApiError::BadRequest)?;
+    Ok(shop_response(shop, player))
+}
+
+pub async fn buy_handler(
+    State(repos): State<Repos>,
+    State(world): State<WorldHandle>,
+    user: AuthUser,
+    Path(npc_id): Path<String>,
+    Json(req): Json<ShopBuyRequest>,
+) -> Result<Json<ShopResponse>, ApiError> {
+    let character_id = user.character()?;
+    let (map_id, shop) = find_shop(&npc_id)?;
+    let ((), player) = at_place(&world, character_id, Place::Npc { map_id, npc_id: shop.id }, "the shop", |p| {
+        buy(p, shop.id, req.item_id, req.quantity)
+    }).map_err(ApiError::BadRequest)?;
+    save_player(&repos, &player).await?;
+    Ok(shop_response(shop, player))
+}
+
+pub async fn sell_handler(
+    State(repos): State<Repos>,
+    State(world): State<WorldHandle>,
+    user: AuthUser,
+    Path(npc_id): Path<String>,
+    Json(req): Json<ShopSellRequest>,
+) -> Result<Json<ShopResponse>, ApiError> {
+    let character_id = user.character()?;
+    let (map_id, shop) = find_shop(&npc_id)?;
+    let (_, player) = at_place(&world, character_id, Place::Npc { map_id, npc_id: shop.id }, "the shop", |p| {
+        sell(p, req.slot_index, req.quantity)
+    }).map_err(ApiError::BadRequest)?;
+    save_player(&repos, &player).await?;
+    Ok(shop_response(shop, player))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::shared::data::items::{IRON_SWORD, RED_POTION, WOODEN_SWORD};
+    use crate::shared::domain::item::inventory::{INVENTORY_SIZE, Inventory};
+    use crate::shared::domain::PlayerClass;
+
+    fn player(gold: i64) -> Player {
+        let mut player = Player::new("alice".to_string(), PlayerClass::Warrior);
+        player.gold = gold;
+        player
+    }
+
+    #[test]
+    fn test_buy_checks_stock_gold_and_room() {
+        let mut alice = player(1000);
+        buy(&mut alice, "shopkeeper", RED_POTION.id, 4).unwrap();
+        assert_eq!((alice.gold, alice.inventory.count_item(RED_POTION.id)), (800, 4));
+
+        // The shop does not sell iron swords, and blacksmiths are not general stores
+        assert!(buy(&mut alice, "shopkeeper", IRON_SWORD.id, 1).is_err());
+        assert!(buy(&mut alice, "blacksmith", RED_POTION.id, 1).is_err());
+        assert!(buy(&mut alice, "shopkeeper", RED_POTION.id, 0).is_err());
+        assert_eq!(buy(&mut alice, "shopkeeper", RED_POTION.id, 17), Err("Not enough gold".to_string()));
+
+        // Swords don't stack, so a nearly full bag takes only one
+        alice.inventory = Inventory::new();
+        for _ in 0..INVENTORY_SIZE - 1 {
+            alice.inventory.add_item(WOODEN_SWORD.id, 1);
+        }
+        assert_eq!(buy(&mut alice, "shopkeeper", WOODEN_SWORD.id, 2), Err("Not enough room in your bag".to_string()));
+        assert_eq!(alice.gold, 800);
+        buy(&mut alice, "shopkeeper", WOODEN_SWORD.id, 1).unwrap();
+        assert!(alice.inventory.is_full());
+    }
+
+    #[test]
+    fn test_sell_pays_the_sell_price() {
+        let mut alice = player(0);
+        alice.inventory.add_item(RED_POTION.id, 5);
+        alice.inventory.add_item(IRON_SWORD.id, 1);
+
+        assert_eq!(sell(&mut alice, 0, 2), Ok(2 * RED_POTION.price_sell));
+        assert_eq!(sell(&mut alice, 1, 1), Ok(IRON_SWORD.price_sell));
+        assert_eq!(alice.inventory.count_item(RED_POTION.id), 3);
+        assert!(alice.inventory.slots[1].is_none());
+        assert!(sell(&mut alice, 0, 4).is_err());
+        assert!(sell(&mut alice, 1, 1).is_err());
+        assert_eq!(alice.gold, 2 * RED_POTION.price_sell + IRON_SWORD.price_sell);
+
+        // A full purse refuses the sale and keeps the potions
+        alice.gold = i64::MAX;
+        assert_eq!(sell(&mut alice, 0, 1), Err("You cannot carry that much gold".to_string()));
+        assert_eq!(alice.inventory.count_item(RED_POTION.id), 3);
+    }
+}
@@ -1,51 +1,51 @@
-use axum::{Json, extract::Query};
-use serde::Deserialize;
-use crate::shared::domain::skill::models::Skill;
-use crate::shared::data::skills::{ALL_SKILLS, SkillEffectType};
+//! Skill handlers - Axum REST API
+//!
+//! Skill definitions come from `shared::data::skills`; what each character
+//! has learned and placed on its hotbar is stored with the character (see
+//! `repo::SkillRepo`).
 
-#[derive(Deserialize)]
-pub struct SkillFilter {
-    pub class_id: Option<i32>,
-    pub level: Option<i32>,
-}
+use axum::{Json, extract::{Query, State}};
 
-/// Convert SkillDef to Skill domain model
-fn skill_def_to_skill(def: &crate::shared::data::skills::SkillDef) -> Skill {
-    Skill {
-        id: def.id,
-        name: def.name.to_string(),
-        class_id: def.class_id,
-        req_level: def.req_level,
-        mp_cost: def.mp_cost,
-        cooldown_ms: def.cooldown_ms,
-        description: Some(def.description_key.to_string()),
-        effect_type: Some(match def.effect_type {
-            SkillEffectType::Damage => "damage",
-            SkillEffectType::Heal => "heal",
-            SkillEffectType::Buff => "buff",
-            SkillEffectType::Debuff => "debuff",
-            SkillEffectType::DamageOverTime => "damage_over_time",
-            SkillEffectType::HealOverTime => "heal_over_time",
-        }.to_string()),
-        base_value: def.base_value,
-        icon_path: Some(def.icon_path.to_string()),
-    }
-}
+use crate::shared::api::{AssignSkillRequest, LearnSkillRequest, SkillBookResponse};
+use crate::shared::domain::skill::models::{Skill, SkillBook};
+use crate::shared::domain::skill::SkillQuery;
+use crate::shared::data::skills::get_skill_by_id;
+use super::error::ApiError;
+use super::persistence::update_character;
+use super::repo::Repos;
+use super::session::AuthUser;
+use super::world::WorldHandle;
 
-/// Handler to get all skills or filter by class/level
-/// Now uses Rust constants for perfect consistency with client.
-pub async fn get_skills(Query(filter): Query<SkillFilter>) -> Json<Vec<Skill>> {
-    let mut skills: Vec<Skill> = ALL_SKILLS.iter()
-        .map(|def| skill_def_to_skill(def))
-        .collect();
+/// Handler to get all skills or filter by class/circle/level
+pub async fn get_skills(Query(query): Query<SkillQuery>) -> Json<Vec<Skill>> {
+    Json(query.run())
+}
 
-    if let Some(cid) = filter.class_id {
-        skills.retain(|s| s.class_id == Some(cid) || s.class_id.is_none());
-    }
+fn skill_book_response(skills: SkillBook) -> Json<SkillBookResponse> {
+    Json(SkillBookResponse { success: true, skills: Some(skills), error: None })
+}
 
-    if let Some(lvl) = filter.level {
-        skills.retain(|s| s.req_level <= lvl);
-    }
+/// Learn a skill; class and level requirements come from `SkillDef`
+pub async fn learn_skill_handler(
+    State(repos): State<Repos>,
+    State(world): State<WorldHandle>,
+    user: AuthUser,
+    Json(req): Json<LearnSkillRequest>,
+) -> Result<Json<SkillBookResponse>, ApiError> {
+    let player = update_character(&repos, &world, user, |p| {
+        let def = get_skill_by_id(req.skill_id).ok_or_else(|| format!("Unknown skill: {}", req.skill_id))?;
+        p.skills.learn(def, p.class.id(), p.level)
+    }).await?;
+    Ok(skill_book_response(player.skills))
+}
 
-    Json(skills)
+/// Place a learned skill on the hotbar, or clear a slot
+pub async fn assign_skill_handler(
+    State(repos): State<Repos>,
+    State(world): State<WorldHandle>,
+    user: AuthUser,
+    Json(req): Json<AssignSkillRequest>,
+) -> Result<Json<SkillBookResponse>, ApiError> {
+    let player = update_character(&repos, &world, user, |p| p.skills.assign(req.slot, req.skill_id)).await?;
+    Ok(skill_book_response(player.skills))
 }
@@ -0,0 +1,123 @@
+//! Login throttling - per-IP failure windows
+//!
+//! Failed logins are counted per client IP in memory. Once an IP reaches
+//! `max_ip_failures` within the window it is refused until the window
+//! runs out. Per-account lockout lives in the `users` table
+//! (`failed_login_attempts`, `locked_until`) so it survives restarts.
+
+use std::collections::HashMap;
+use std::net::IpAddr;
+use std::sync::{Arc, Mutex};
+use std::time::{Duration, Instant};
+
+use serde::Deserialize;
+
+/// Expired windows are swept once this many IPs are tracked
+const PRUNE_THRESHOLD: usize = 10_000;
+
+/// Login rate limits (the `rate_limits` section of the server config)
+#[derive(Debug, Clone, Copy, PartialEq, Deserialize)]
+#[serde(default, deny_unknown_fields)]
+pub struct LoginLimits {
+    /// Failed logins allowed from one IP per window
+    pub max_ip_failures: u32,
+    pub ip_window_secs: u64,
+    /// Failed logins before an account is locked
+    pub max_account_failures: i32,
+    /// How long a locked account stays locked
+    pub account_lockout_secs: u64,
+}
+
+impl Default for LoginLimits {
+    fn default() -> Self {
+        Self {
+            max_ip_failures: 20,
+            ip_window_secs: 10 * 60,
+            max_account_failures: 5,
+            account_lockout_secs: 15 * 60,
+        }
+    }
+}
+
+impl LoginLimits {
+    pub fn ip_window(&self) -> Duration {
+        Duration::from_secs(self.ip_window_secs)
+    }
+}
+
+#[derive(Debug, Clone, Copy)]
+struct Window {
+    started: Instant,
+    failures: u32,
+}
+
+/// Per-IP failure counters, shared with handlers through the router state
+#[derive(Clone, Default)]
+pub struct LoginThrottle {
+    limits: LoginLimits,
+    windows: Arc<Mutex<HashMap<IpAddr, Window>>>,
+}
+
+impl LoginThrottle {
+    pub fn new(limits: LoginLimits) -> Self {
+        Self {
+            limits,
+            windows: Arc::default(),
+        }
+    }
+
+    pub fn limits(&self) -> &LoginLimits {
+        &self.limits
+    }
+
+    /// `Err(retry_after)` while the IP is over its failure budget
+    pub fn check(&self, ip: IpAddr, now: Instant) -> Result<(), Duration> {
+        let window = self.limits.ip_window();
+        let mut windows = self.windows.lock().unwrap();
+        match windows.get(&ip) {
+            Some(w) if now.duration_since(w.started) >= window => {
+                windows.remove(&ip);
+                Ok(())
+            }
+            Some(w) if w.failures >= self.limits.max_ip_failures => Err(window - now.duration_since(w.started)),
+            _ => Ok(()),
+        }
+    }
+
+    pub fn record_failure(&self, ip: IpAddr, now: Instant) {
+        let ip_window = self.limits.ip_window();
+        let mut windows = self.windows.lock().unwrap();
+        if windows.len() >= PRUNE_THRESHOLD {
+            windows.retain(|_, w| now.duration_since(w.started) < ip_window);
+        }
+        let window = windows.entry(ip).or_insert(Window { started: now, failures: 0 });
+        if now.duration_since(window.started) >= ip_window {
+            *window = Window { started: now, failures: 0 };
+        }
+        window.failures += 1;
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_ip_is_blocked_until_window_ends() {
+        let limits = LoginLimits { max_ip_failures: 3, ..Default::default() };
+        let throttle = LoginThrottle::new(limits);
+        let ip: IpAddr = "10.0.0.1".parse().unwrap();
+        let other: IpAddr = "10.0.0.2".parse().unwrap();
+        let start = Instant::now();
+
+        for _ in 0..limits.max_ip_failures {
+            assert!(throttle.check(ip, start).is_ok());
+            throttle.record_failure(ip, start);
+        }
+        let later = start + Duration::from_secs(60);
+        assert_eq!(throttle.check(ip, later), Err(limits.ip_window() - Duration::from_secs(60)));
+        assert!(throttle.check(other, later).is_ok());
+
+        assert!(throttle.check(ip, start + limits.ip_window()).is_ok());
+    }
+}
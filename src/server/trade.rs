@@ -0,0 +1,251 @@
+//! Trades - offers, locking and the exchange itself
+//!
+//! A trade opens when a player accepts another's request and lives only
+//! in the `World`. Each side puts bag items and gold on the table, then
+//! locks its offer; once both are locked each side confirms, and the
+//! second confirmation swaps everything. Offers name bag slots, so
+//! equipped items can never be offered. The exchange is checked against
+//! both bags again right before it happens, and the result is written
+//! to the database for both characters in one transaction.
+
+use crate::shared::domain::item::inventory::ItemStack;
+use crate::shared::domain::Player;
+use crate::shared::protocol::{TradeItem, TradeOffer, TradeSlot};
+
+/// Seconds an unanswered trade request stays valid
+pub const REQUEST_TIMEOUT_SECS: f64 = 30.0;
+
+/// Tiles (on the same map) within which two players can trade
+pub const TRADE_RANGE: i32 = 3;
+
+/// Most item stacks one side can offer
+pub const MAX_TRADE_ITEMS: usize = 8;
+
+/// Whether two tiles on the same map are close enough to trade
+pub fn in_range(a: (i32, i32), b: (i32, i32)) -> bool {
+    (a.0 - b.0).abs().max((a.1 - b.1).abs()) <= TRADE_RANGE
+}
+
+/// Both sides of an open trade, by entity id
+#[derive(Debug, Clone, PartialEq)]
+pub struct Trade {
+    traders: [String; 2],
+    offers: [TradeOffer; 2],
+}
+
+impl Trade {
+    pub fn new(a: String, b: String) -> Self {
+        Self { traders: [a, b], offers: Default::default() }
+    }
+
+    pub fn traders(&self) -> &[String; 2] {
+        &self.traders
+    }
+
+    pub fn offers(&self) -> &[TradeOffer; 2] {
+        &self.offers
+    }
+
+    fn side(&self, id: &str) -> Result<usize, String> {
+        self.traders.iter().position(|t| t == id).ok_or_else(|| "You are not in this trade".to_string())
+    }
+
+    /// The other trader's id
+    pub fn partner(&self, id: &str) -> Option<&str> {
+        let side = self.side(id).ok()?;
+        Some(&self.traders[1 - side])
+    }
+
+    /// `id`'s offer followed by their partner's
+    pub fn offers_for(&self, id: &str) -> Option<(&TradeOffer, &TradeOffer)> {
+        let side = self.side(id).ok()?;
+        Some((&self.offers[side], &self.offers[1 - side]))
+    }
+
+    /// Replace an unlocked offer
+    pub fn set_offer(&mut self, id: &str, items: Vec<TradeItem>, gold: i64) -> Result<(), String> {
+        let side = self.side(id)?;
+        let offer = &mut self.offers[side];
+        if offer.locked {
+            return Err("Your offer is locked".to_string());
+        }
+        offer.items = items;
+        offer.gold = gold;
+        Ok(())
+    }
+
+    pub fn lock(&mut self, id: &str) -> Result<(), String> {
+        let side = self.side(id)?;
+        self.offers[side].locked = true;
+        Ok(())
+    }
+
+    /// Agree to both locked offers; true once both sides have
+    pub fn confirm(&mut self, id: &str) -> Result<bool, String> {
+        let side = self.side(id)?;
+        if !self.offers.iter().all(|o| o.locked) {
+            return Err("Both offers must be locked first".to_string());
+        }
+        self.offers[side].confirmed = true;
+        Ok(self.offers.iter().all(|o| o.confirmed))
+    }
+}
+
+/// Both characters right after an exchange, to be saved together
+#[derive(Debug, Clone)]
+pub struct CompletedTrade {
+    pub players: [Player; 2],
+}
+
+/// Check the slots a player wants to offer against their bag
+pub fn build_offer(player: &Player, slots: &[TradeSlot], gold: i64) -> Result<Vec<TradeItem>, String> {
+    if slots.len() > MAX_TRADE_ITEMS {
+        return Err(format!("You can offer at most {} items", MAX_TRADE_ITEMS));
+    }
+    if gold < 0 || gold > player.gold {
+        return Err("You don't have that much gold".to_string());
+    }
+
+    let mut items: Vec<TradeItem> = Vec::with_capacity(slots.len());
+    for slot in slots {
+        if items.iter().any(|i| i.slot_index == slot.slot_index) {
+            return Err("Each bag slot can be offered once".to_string());
+        }
+        let stack = player.inventory.slots.get(slot.slot_index)
+            .and_then(|s| s.as_ref())
+            .ok_or("No item in slot")?;
+        if slot.quantity <= 0 || slot.quantity > stack.quantity {
+            return Err("Invalid quantity".to_string());
+        }
+        let def = stack.get_def().ok_or("Unknown item")?;
+        if !def.tradeable {
+            return Err(format!("{} cannot be traded", def.name));
+        }
+        items.push(TradeItem {
+            slot_index: slot.slot_index,
+            item: ItemStack { quantity: slot.quantity, ..stack.clone() },
+        });
+    }
+    Ok(items)
+}
+
+/// Take an offer out of a player's bag and gold, failing if the bag no
+/// longer holds what was offered
+fn withdraw(player: &mut Player, offer: &TradeOffer) -> Result<(), String> {
+    if offer.gold > player.gold {
+        return Err(format!("{} no longer has the offered gold", player.username));
+    }
+    player.gold -= offer.gold;
+    for offered in &offer.items {
+        let taken = player.inventory.take(offered.slot_index, offered.item.quantity).ok();
+        if taken.as_ref() != Some(&offered.item) {
+            return Err(format!("{} no longer has the offered items", player.username));
+        }
+    }
+    Ok(())
+}
+
+fn deposit(player: &mut Player, offer: &TradeOffer) -> Result<(), String> {
+    player.gold = player.gold.checked_add(offer.gold)
+        .ok_or_else(|| format!("{} cannot carry that much gold", player.username))?;
+    for offered in &offer.items {
+        player.inventory.insert_stack(offered.item.clone())
+            .map_err(|_| format!("{} does not have room for the items", player.username))?;
+    }
+    Ok(())
+}
+
+/// Swap both offers between two players. Either both change or neither does.
+pub fn exchange(a: &mut Player, b: &mut Player, offers: &[TradeOffer; 2]) -> Result<(), String> {
+    let (mut new_a, mut new_b) = (a.clone(), b.clone());
+    withdraw(&mut new_a, &offers[0])?;
+    withdraw(&mut new_b, &offers[1])?;
+    deposit(&mut new_a, &offers[1])?;
+    deposit(&mut new_b, &offers[0])?;
+    *a = new_a;
+    *b = new_b;
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::shared::data::items::{IRON_SWORD, RED_POTION};
+    use crate::shared::domain::item::inventory::EquipSlot;
+    use crate::shared::domain::PlayerClass;
+
+    fn player(name: &str) -> Player {
+        Player::new(name.to_string(), PlayerClass::Warrior)
+    }
+
+    fn slot(slot_index: usize, quantity: i32) -> TradeSlot {
+        TradeSlot { slot_index, quantity }
+    }
+
+    #[test]
+    fn test_offers_come_from_the_bag() {
+        let mut alice = player("alice");
+        alice.inventory.add_item(RED_POTION.id, 5);
+        alice.inventory.add_item(IRON_SWORD.id, 1);
+        alice.inventory.equip(1, EquipSlot::Weapon).unwrap();
+
+        assert_eq!(build_offer(&alice, &[slot(0, 3)], 40).unwrap()[0].item, ItemStack::new(RED_POTION.id, 3));
+        assert!(build_offer(&alice, &[slot(0, 6)], 0).is_err());
+        assert!(build_offer(&alice, &[slot(0, 1), slot(0, 1)], 0).is_err());
+        // The sword is equipped, so its old slot is empty
+        assert!(build_offer(&alice, &[slot(1, 1)], 0).is_err());
+        assert!(build_offer(&alice, &[], alice.gold + 1).is_err());
+    }
+
+    #[test]
+    fn test_lock_then_confirm() {
+        let mut trade = Trade::new("a".to_string(), "b".to_string());
+        trade.set_offer("a", Vec::new(), 10).unwrap();
+        assert!(trade.confirm("a").is_err());
+        trade.lock("a").unwrap();
+        assert!(trade.set_offer("a", Vec::new(), 20).is_err());
+        trade.lock("b").unwrap();
+        assert_eq!(trade.confirm("a"), Ok(false));
+        assert_eq!(trade.confirm("b"), Ok(true));
+        assert_eq!(trade.partner("b"), Some("a"));
+        assert!(trade.lock("c").is_err());
+    }
+
+    #[test]
+    fn test_exchange_is_all_or_nothing() {
+        let mut alice = player("alice");
+        let mut bob = player("bob");
+        alice.inventory.add_item(IRON_SWORD.id, 1);
+        bob.inventory.add_item(RED_POTION.id, 10);
+
+        let offer = |items: Vec<TradeItem>, gold| TradeOffer { items, gold, locked: true, confirmed: true };
+        let offers = [
+            offer(build_offer(&alice, &[slot(0, 1)], 30).unwrap(), 30),
+            offer(build_offer(&bob, &[slot(0, 4)], 0).unwrap(), 0),
+        ];
+        exchange(&mut alice, &mut bob, &offers).unwrap();
+        assert_eq!((alice.gold, bob.gold), (70, 130));
+        assert_eq!(alice.inventory.count_item(RED_POTION.id), 4);
+        assert_eq!(bob.inventory.count_item(IRON_SWORD.id), 1);
+        assert_eq!(bob.inventory.count_item(RED_POTION.id), 6);
+
+        // The sword is gone now, so nothing changes hands
+        let (bag_a, bag_b) = (alice.inventory.clone(), bob.inventory.clone());
+        assert!(exchange(&mut alice, &mut bob, &offers).is_err());
+        assert_eq!((alice.gold, bob.gold), (70, 130));
+        assert_eq!((alice.inventory, bob.inventory), (bag_a, bag_b));
+    }
+
+    #[test]
+    fn test_gold_that_would_overflow_refuses_the_trade() {
+        let mut alice = player("alice");
+        let mut bob = player("bob");
+        bob.gold = i64::MAX - 10;
+        let offers = [
+            TradeOffer { items: Vec::new(), gold: 30, locked: true, confirmed: true },
+            TradeOffer { items: Vec::new(), gold: 0, locked: true, confirmed: true },
+        ];
+        assert_eq!(exchange(&mut alice, &mut bob, &offers), Err("bob cannot carry that much gold".to_string()));
+        assert_eq!((alice.gold, bob.gold), (100, i64::MAX - 10));
+    }
+}
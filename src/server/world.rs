@@ -0,0 +1,1969 @@
+//! Game world simulation - authoritative fixed-tick loop
+//!
+//! The world owns every map instance, monster and connected player.
+//! Connections only queue intents; `World::tick` applies them,
+//! runs monster AI and sends every player a snapshot of their map.
+//! Intents that reach other players (chat, parties, shared kill exp)
+//! are applied once every player's input for the tick has been read.
+
+use std::collections::{HashMap, VecDeque};
+use std::sync::{Arc, Mutex};
+use std::time::Duration;
+
+use rand::rngs::SmallRng;
+use rand::{Rng, SeedableRng};
+use tokio::sync::mpsc::UnboundedSender;
+
+use crate::shared::combat::{resolve_attack, skill_attack_kind, AttackKind, AttackResult, Combatant, HitOutcome};
+use crate::shared::constants::{GRID_UNIT, MOVE_DURATION};
+use crate::shared::data::maps::{self, MapTile, PortalDef, SpawnPoint};
+use crate::shared::data::monsters::{get_monster_by_id, roll_kill_rewards, KillRewards};
+use crate::shared::data::skills::{get_skill_by_id, SkillEffectType, SkillTarget};
+use crate::shared::domain::monster::{Monster, MonsterAIType, MonsterData};
+use crate::shared::domain::shared::models::{Direction, Position};
+use crate::shared::domain::Player;
+use crate::shared::protocol::{
+    ChatChannel, ChatMessage, ClientMessage, CombatEvent, EntityKind, EntityState, PartyMember, PartyState, PlayerVitals,
+    TradeSlot, TradeState,
+    ServerMessage,
+};
+use super::chat::{clean_message, ChatLimiter, ChatSettings, NewChatLog, WordFilter};
+use super::guild::GuildTag;
+use super::party::{self, Party};
+use super::trade::{self, CompletedTrade, Trade};
+
+/// Shared handle used by connections and the tick loop
+pub type WorldHandle = Arc<Mutex<World>>;
+
+/// Seconds between monster steps
+const MONSTER_STEP_INTERVAL: f64 = 0.4;
+
+/// Grace period for step timing so client/server clock jitter doesn't drop inputs
+const MOVE_TOLERANCE: f64 = 0.05;
+
+/// Maximum queued intents per player per tick (extra input is dropped)
+const MAX_INPUTS_PER_TICK: usize = 8;
+
+/// A connected player
+struct PlayerEntity {
+    player: Player,
+    outbox: UnboundedSender<ServerMessage>,
+    inputs: VecDeque<ClientMessage>,
+    queued_move: Option<Direction>,
+    next_move_at: f64,
+    skill_ready_at: HashMap<i32, f64>,
+    chat_limiter: ChatLimiter,
+    party: Option<u64>,
+    /// Open party invites: inviter id -> expiry time
+    invites: HashMap<String, f64>,
+    guild: Option<GuildTag>,
+    trade: Option<u64>,
+    /// Open trade requests: requester id -> expiry time
+    trade_requests: HashMap<String, f64>,
+    /// Backed by a `characters` row (guests are not saved)
+    persistent: bool,
+}
+
+impl PlayerEntity {
+    fn grid(&self) -> (i32, i32) {
+        (self.player.position.x as i32, self.player.position.y as i32)
+    }
+
+    fn facing_tile(&self) -> (i32, i32) {
+        let (x, y) = self.grid();
+        let (dx, dy) = self.player.direction.offset();
+        (x + dx, y + dy)
+    }
+
+    fn send(&self, msg: ServerMessage) {
+        // A closed outbox means the connection is shutting down; it will leave on its own.
+        let _ = self.outbox.send(msg);
+    }
+
+    fn to_entity_state(&self) -> EntityState {
+        let (x, y) = self.grid();
+        EntityState {
+            id: self.player.id.clone(),
+            name: self.player.username.clone(),
+            kind: EntityKind::Player {
+                class_id: self.player.class.id(),
+                gender: self.player.gender.clone(),
+                level: self.player.level,
+                guild: self.guild.as_ref().map(|g| g.name.clone()),
+            },
+            x,
+            y,
+            direction: self.player.direction,
+            hp: self.player.combat_stats.hp,
+            max_hp: self.player.combat_stats.max_hp,
+        }
+    }
+
+    fn vitals(&self) -> PlayerVitals {
+        PlayerVitals {
+            hp: self.player.combat_stats.hp,
+            max_hp: self.player.combat_stats.max_hp,
+            mp: self.player.combat_stats.mp,
+            max_mp: self.player.combat_stats.max_mp,
+            level: self.player.level,
+            exp: self.player.exp,
+            gold: self.player.gold,
+        }
+    }
+
+    fn party_member(&self) -> PartyMember {
+        PartyMember {
+            id: self.player.id.clone(),
+            name: self.player.username.clone(),
+            class_id: self.player.class.id(),
+            level: self.player.level,
+            map_id: self.player.current_map.clone(),
+            hp: self.player.combat_stats.hp,
+            max_hp: self.player.combat_stats.max_hp,
+            mp: self.player.combat_stats.mp,
+            max_mp: self.player.combat_stats.max_mp,
+        }
+    }
+}
+
+/// A monster bound to a spawn point
+struct MonsterEntity {
+    monster: Monster,
+    spawn: SpawnPoint,
+    x: i32,
+    y: i32,
+    next_step_at: f64,
+    dead_until: Option<f64>,
+}
+
+impl MonsterEntity {
+    fn spawn(data: &MonsterData, spawn: &SpawnPoint) -> Self {
+        Self {
+            monster: Monster::new(data, Position::new(spawn.x as f64, spawn.y as f64)),
+            spawn: spawn.clone(),
+            x: spawn.x,
+            y: spawn.y,
+            next_step_at: 0.0,
+            dead_until: None,
+        }
+    }
+
+    fn is_alive(&self) -> bool {
+        self.dead_until.is_none()
+    }
+
+    fn to_entity_state(&self) -> EntityState {
+        EntityState {
+            id: self.monster.id.clone(),
+            name: self.monster.name.clone(),
+            kind: EntityKind::Monster { monster_id: self.monster.monster_id },
+            x: self.x,
+            y: self.y,
+            direction: self.monster.direction,
+            hp: self.monster.hp,
+            max_hp: self.monster.max_hp,
+        }
+    }
+}
+
+/// One running copy of a map
+struct MapInstance {
+    width: i32,
+    height: i32,
+    tiles: Vec<MapTile>,
+    start: (i32, i32),
+    monsters: Vec<MonsterEntity>,
+}
+
+impl MapInstance {
+    fn load(map_id: &str) -> Option<Self> {
+        let def = maps::get_map_by_id(map_id)?;
+
+        let (width, height, tiles) = match maps::get_map_layout(map_id) {
+            Some(layout) => {
+                let height = layout.len() as i32;
+                let width = layout.iter().map(|row| row.len()).max().unwrap_or(0) as i32;
+                let mut tiles = vec![MapTile::Wall; (width * height) as usize];
+                for (y, row) in layout.iter().enumerate() {
+                    for (x, c) in row.chars().enumerate() {
+                        tiles[y * width as usize + x] = MapTile::from_char(c);
+                    }
+                }
+                (width, height, tiles)
+            }
+            // Maps without an authored layout are open fields
+            None => (def.width as i32, def.height as i32, vec![MapTile::Grass; def.width * def.height]),
+        };
+
+        let start = if map_id == maps::MILLES_VILLAGE.id {
+            maps::MILLES_VILLAGE_START
+        } else {
+            (width / 2, height / 2)
+        };
+
+        let monsters = maps::get_map_spawns(map_id)
+            .iter()
+            .filter_map(|spawn| {
+                let def = get_monster_by_id(spawn.monster_id)?;
+                Some(MonsterEntity::spawn(&MonsterData::from(def), spawn))
+            })
+            .collect();
+
+        Some(Self { width, height, tiles, start, monsters })
+    }
+
+    fn is_walkable(&self, x: i32, y: i32) -> bool {
+        if x < 0 || y < 0 || x >= self.width || y >= self.height {
+            return false;
+        }
+        self.tiles[(y * self.width + x) as usize].is_walkable()
+    }
+
+    fn monster_at_mut(&mut self, x: i32, y: i32) -> Option<&mut MonsterEntity> {
+        self.monsters.iter_mut().find(|m| m.is_alive() && m.x == x && m.y == y)
+    }
+}
+
+/// The authoritative game world
+pub struct World {
+    tick: u64,
+    now: f64,
+    maps: HashMap<String, MapInstance>,
+    players: HashMap<String, PlayerEntity>,
+    /// Character states to write back, queued on map change
+    pending_saves: Vec<Player>,
+    chat: ChatSettings,
+    word_filter: WordFilter,
+    /// Delivered chat waiting to be written to `chat_log`
+    chat_log: Vec<NewChatLog>,
+    parties: HashMap<u64, Party>,
+    next_party_id: u64,
+    trades: HashMap<u64, Trade>,
+    next_trade_id: u64,
+    /// Exchanges waiting to be written to the database
+    completed_trades: Vec<CompletedTrade>,
+    /// Combat rolls
+    rng: SmallRng,
+}
+
+/// A chat message that passed the sender's checks
+struct OutgoingChat {
+    from: String,
+    channel: ChatChannel,
+    to: Option<String>,
+    text: String,
+}
+
+enum PartyRequest {
+    Invite(String),
+    Accept(String),
+    Leave,
+    Kick(String),
+}
+
+enum TradeRequest {
+    Ask(String),
+    Accept(String),
+    Offer(Vec<TradeSlot>, i64),
+    Lock,
+    Confirm,
+    Cancel,
+}
+
+/// Part of a player's input that involves other players, applied after
+/// every input of the tick has been read
+enum FollowUp {
+    Chat(OutgoingChat),
+    Party { from: String, request: PartyRequest },
+    Trade { from: String, request: TradeRequest },
+    /// Rewards for the killer and the party members near them
+    Kill { killer: String, monster_id: i32 },
+    /// A party heal reaching the members near the caster
+    PartyHeal { caster: String, skill_id: i32, amount: i32 },
+}
+
+impl Default for World {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl World {
+    pub fn new() -> Self {
+        Self::with_rng(SmallRng::from_entropy())
+    }
+
+    /// World with a fixed combat RNG, for reproducible simulations
+    pub fn with_seed(seed: u64) -> Self {
+        Self::with_rng(SmallRng::seed_from_u64(seed))
+    }
+
+    fn with_rng(rng: SmallRng) -> Self {
+        Self {
+            tick: 0,
+            now: 0.0,
+            maps: HashMap::new(),
+            players: HashMap::new(),
+            pending_saves: Vec::new(),
+            chat: ChatSettings::default(),
+            word_filter: WordFilter::default(),
+            chat_log: Vec::new(),
+            parties: HashMap::new(),
+            next_party_id: 1,
+            trades: HashMap::new(),
+            next_trade_id: 1,
+            completed_trades: Vec::new(),
+            rng,
+        }
+    }
+
+    /// Use these chat limits and banned words
+    pub fn with_chat(mut self, settings: ChatSettings) -> Self {
+        self.word_filter = WordFilter::new(&settings.banned_words);
+        self.chat = settings;
+        self
+    }
+
+    pub fn into_handle(self) -> WorldHandle {
+        Arc::new(Mutex::new(self))
+    }
+
+    pub fn player_count(&self) -> usize {
+        self.players.len()
+    }
+
+    /// Add a player to the world. Returns the entity id used in snapshots.
+    ///
+    /// `persistent` players are included in autosaves. A character saved
+    /// on a map that no longer exists is moved to the starting village.
+    pub fn join(
+        &mut self,
+        mut player: Player,
+        outbox: UnboundedSender<ServerMessage>,
+        persistent: bool,
+    ) -> Result<String, String> {
+        if self.players.contains_key(&player.id) {
+            return Err("Character is already in the world".to_string());
+        }
+
+        if !self.ensure_map(&player.current_map) {
+            tracing::warn!("Unknown map {} for {}, moving to {}", player.current_map, player.id, maps::MILLES_VILLAGE.id);
+            player.current_map = maps::MILLES_VILLAGE.id.to_string();
+            player.position = Position::new(maps::MILLES_VILLAGE_START.0 as f64, maps::MILLES_VILLAGE_START.1 as f64);
+            if !self.ensure_map(&player.current_map) {
+                return Err(format!("Unknown map: {}", player.current_map));
+            }
+        }
+        let map = &self.maps[&player.current_map];
+
+        let (x, y) = (player.position.x as i32, player.position.y as i32);
+        if !map.is_walkable(x, y) {
+            player.position = Position::new(map.start.0 as f64, map.start.1 as f64);
+        }
+
+        let id = player.id.clone();
+        let entity = PlayerEntity {
+            player,
+            outbox,
+            inputs: VecDeque::new(),
+            queued_move: None,
+            next_move_at: 0.0,
+            skill_ready_at: HashMap::new(),
+            chat_limiter: ChatLimiter::default(),
+            party: None,
+            invites: HashMap::new(),
+            guild: None,
+            trade: None,
+            trade_requests: HashMap::new(),
+            persistent,
+        };
+        entity.send(ServerMessage::Welcome {
+            entity_id: id.clone(),
+            map_id: entity.player.current_map.clone(),
+            tick: self.tick,
+        });
+        self.players.insert(id.clone(), entity);
+        Ok(id)
+    }
+
+    /// Remove a player, returning their final state
+    pub fn leave(&mut self, id: &str) -> Option<Player> {
+        self.remove_from_party(id, "left");
+        self.cancel_trade(id, "left");
+        self.players.remove(id).map(|p| p.player)
+    }
+
+    /// Current state of a player in the world
+    pub fn player_state(&self, id: &str) -> Option<Player> {
+        self.players.get(id).map(|p| p.player.clone())
+    }
+
+    /// Run `f` against a player in the world; None if they are not online
+    pub fn with_player<R>(&mut self, id: &str, f: impl FnOnce(&mut Player) -> R) -> Option<R> {
+        self.players.get_mut(id).map(|p| f(&mut p.player))
+    }
+
+    pub fn is_online(&self, id: &str) -> bool {
+        self.players.contains_key(id)
+    }
+
+    /// Show (or clear) the guild on a player's nameplate and route their guild chat
+    pub fn set_guild(&mut self, id: &str, guild: Option<GuildTag>) {
+        if let Some(p) = self.players.get_mut(id) {
+            p.guild = guild;
+        }
+    }
+
+    /// Send a notice to one player, if they are online
+    pub fn notify(&self, id: &str, message: &str) {
+        self.send_to(id, ServerMessage::Notice { message: message.to_string() });
+    }
+
+    /// Send a notice to every online member of a guild
+    pub fn notify_guild(&self, guild_id: uuid::Uuid, message: &str) {
+        for p in self.players.values().filter(|p| p.guild.as_ref().is_some_and(|g| g.id == guild_id)) {
+            p.send(ServerMessage::Notice { message: message.to_string() });
+        }
+    }
+
+    /// Snapshot of every persistent player, for periodic autosave
+    pub fn persistent_players(&self) -> Vec<Player> {
+        self.players.values()
+            .filter(|p| p.persistent)
+            .map(|p| p.player.clone())
+            .collect()
+    }
+
+    /// Drain the states queued for saving since the last call (latest per character)
+    pub fn take_pending_saves(&mut self) -> Vec<Player> {
+        let mut seen = std::collections::HashSet::new();
+        let mut saves: Vec<Player> = std::mem::take(&mut self.pending_saves)
+            .into_iter()
+            .rev()
+            .filter(|p| seen.insert(p.id.clone()))
+            .collect();
+        saves.reverse();
+        saves
+    }
+
+    /// Drain the chat delivered since the last call
+    pub fn take_chat_log(&mut self) -> Vec<NewChatLog> {
+        std::mem::take(&mut self.chat_log)
+    }
+
+    /// Drain the trades completed since the last call, oldest first
+    pub fn take_completed_trades(&mut self) -> Vec<CompletedTrade> {
+        std::mem::take(&mut self.completed_trades)
+    }
+
+    /// Load a map instance on first use. Returns false for unknown maps.
+    fn ensure_map(&mut self, map_id: &str) -> bool {
+        if self.maps.contains_key(map_id) {
+            return true;
+        }
+        match MapInstance::load(map_id) {
+            Some(instance) => {
+                self.maps.insert(map_id.to_string(), instance);
+                true
+            }
+            None => false,
+        }
+    }
+
+    /// Move a player through a portal and queue a save
+    fn change_map(&mut self, id: &str, portal: &PortalDef) {
+        if self.teleport(id, portal.target_map, portal.target_x, portal.target_y).is_none() {
+            tracing::warn!("Portal leads to unknown map {}", portal.target_map);
+        }
+    }
+
+    /// Put an online player at `(x, y)` on any map (the map's start if
+    /// that tile is blocked) and queue a save. Returns their new state;
+    /// None if the map is unknown or the player is not online.
+    pub fn teleport(&mut self, id: &str, map_id: &str, x: i32, y: i32) -> Option<Player> {
+        if !self.players.contains_key(id) || !self.ensure_map(map_id) {
+            return None;
+        }
+        let map = &self.maps[map_id];
+        let (x, y) = if map.is_walkable(x, y) { (x, y) } else { map.start };
+
+        let p = self.players.get_mut(id)?;
+        p.player.current_map = map_id.to_string();
+        p.player.position = Position::new(x as f64, y as f64);
+        p.queued_move = None;
+        p.send(ServerMessage::MapChanged { map_id: map_id.to_string(), x, y });
+
+        if p.persistent {
+            self.pending_saves.push(p.player.clone());
+        }
+        Some(p.player.clone())
+    }
+
+    /// Disconnect a player with a reason, returning their final state.
+    /// The connection notices its closed outbox and ends without saving.
+    pub fn kick(&mut self, id: &str, reason: &str) -> Option<Player> {
+        self.remove_from_party(id, "left");
+        self.cancel_trade(id, "left");
+        let p = self.players.remove(id)?;
+        p.send(ServerMessage::Error { message: reason.to_string() });
+        Some(p.player)
+    }
+
+    /// Queue a client intent for the next tick
+    pub fn push_input(&mut self, id: &str, msg: ClientMessage) {
+        if let Some(p) = self.players.get_mut(id)
+            && p.inputs.len() < MAX_INPUTS_PER_TICK
+        {
+            p.inputs.push_back(msg);
+        }
+    }
+
+    /// Advance the simulation by `dt` seconds
+    pub fn tick(&mut self, dt: f64) {
+        self.tick += 1;
+        self.now += dt;
+
+        let mut events: Vec<(String, CombatEvent)> = Vec::new();
+        let mut follow_ups = Vec::new();
+
+        self.process_inputs(&mut events, &mut follow_ups);
+        self.apply_follow_ups(follow_ups, &mut events);
+        self.apply_movement();
+        self.check_trades();
+        self.update_monsters(&mut events);
+        self.broadcast(events);
+    }
+
+    fn process_inputs(&mut self, events: &mut Vec<(String, CombatEvent)>, follow_ups: &mut Vec<FollowUp>) {
+        let now = self.now;
+        let rng = &mut self.rng;
+
+        for p in self.players.values_mut() {
+            while let Some(input) = p.inputs.pop_front() {
+                match input {
+                    ClientMessage::Join { .. } => {
+                        p.send(ServerMessage::Error { message: "Already joined".to_string() });
+                    }
+                    ClientMessage::Move { direction } => {
+                        p.player.direction = direction;
+                        p.queued_move = Some(direction);
+                    }
+                    ClientMessage::Attack => {
+                        if !p.player.can_attack(now * 1000.0) {
+                            continue;
+                        }
+                        p.player.register_attack(now * 1000.0);
+
+                        let (tx, ty) = p.facing_tile();
+                        let Some(map) = self.maps.get_mut(&p.player.current_map) else { continue; };
+                        let Some(target) = map.monster_at_mut(tx, ty) else { continue; };
+
+                        let attacker = Combatant::from(&p.player.combat_stats);
+                        let result = resolve_attack(rng, &attacker, &Combatant::from(&target.monster), AttackKind::Physical, None);
+                        let event = damage_monster(&p.player.id, None, target, result, now);
+                        if event.killed {
+                            follow_ups.push(FollowUp::Kill { killer: p.player.id.clone(), monster_id: target.monster.monster_id });
+                        }
+                        events.push((p.player.current_map.clone(), event));
+                    }
+                    ClientMessage::UseSkill { skill_id } => {
+                        match cast_skill(p, skill_id, &mut self.maps, rng, now, events) {
+                            Ok(Some(follow_up)) => follow_ups.push(follow_up),
+                            Ok(None) => {}
+                            Err(message) => p.send(ServerMessage::Error { message }),
+                        }
+                    }
+                    ClientMessage::Ping { client_time } => {
+                        p.send(ServerMessage::Pong { client_time });
+                    }
+                    ClientMessage::Chat { channel, to, text } => match clean_message(&text) {
+                        Ok(_) if !p.chat_limiter.allow(now, &self.chat) => {
+                            p.send(ServerMessage::Error { message: "You are sending messages too quickly".to_string() });
+                        }
+                        Ok(text) => follow_ups.push(FollowUp::Chat(OutgoingChat { from: p.player.id.clone(), channel, to, text })),
+                        Err(message) => p.send(ServerMessage::Error { message }),
+                    },
+                    ClientMessage::PartyInvite { name } => {
+                        follow_ups.push(FollowUp::Party { from: p.player.id.clone(), request: PartyRequest::Invite(name) });
+                    }
+                    ClientMessage::PartyAccept { inviter_id } => {
+                        follow_ups.push(FollowUp::Party { from: p.player.id.clone(), request: PartyRequest::Accept(inviter_id) });
+                    }
+                    ClientMessage::PartyLeave => {
+                        follow_ups.push(FollowUp::Party { from: p.player.id.clone(), request: PartyRequest::Leave });
+                    }
+                    ClientMessage::PartyKick { member_id } => {
+                        follow_ups.push(FollowUp::Party { from: p.player.id.clone(), request: PartyRequest::Kick(member_id) });
+                    }
+                    ClientMessage::TradeRequest { name } => {
+                        follow_ups.push(FollowUp::Trade { from: p.player.id.clone(), request: TradeRequest::Ask(name) });
+                    }
+                    ClientMessage::TradeAccept { from_id } => {
+                        follow_ups.push(FollowUp::Trade { from: p.player.id.clone(), request: TradeRequest::Accept(from_id) });
+                    }
+                    ClientMessage::TradeOffer { items, gold } => {
+                        follow_ups.push(FollowUp::Trade { from: p.player.id.clone(), request: TradeRequest::Offer(items, gold) });
+                    }
+                    ClientMessage::TradeLock => {
+                        follow_ups.push(FollowUp::Trade { from: p.player.id.clone(), request: TradeRequest::Lock });
+                    }
+                    ClientMessage::TradeConfirm => {
+                        follow_ups.push(FollowUp::Trade { from: p.player.id.clone(), request: TradeRequest::Confirm });
+                    }
+                    ClientMessage::TradeCancel => {
+                        follow_ups.push(FollowUp::Trade { from: p.player.id.clone(), request: TradeRequest::Cancel });
+                    }
+                }
+            }
+        }
+    }
+
+    fn apply_follow_ups(&mut self, follow_ups: Vec<FollowUp>, events: &mut Vec<(String, CombatEvent)>) {
+        for follow_up in follow_ups {
+            match follow_up {
+                FollowUp::Chat(chat) => self.deliver_chat(chat),
+                FollowUp::Party { from, request } => {
+                    let result = match request {
+                        PartyRequest::Invite(name) => self.party_invite(&from, &name),
+                        PartyRequest::Accept(inviter_id) => self.party_accept(&from, &inviter_id),
+                        PartyRequest::Leave => self.party_leave(&from),
+                        PartyRequest::Kick(member_id) => self.party_kick(&from, &member_id),
+                    };
+                    if let Err(message) = result {
+                        self.send_to(&from, ServerMessage::Error { message });
+                    }
+                }
+                FollowUp::Trade { from, request } => {
+                    let result = match request {
+                        TradeRequest::Ask(name) => self.trade_ask(&from, &name),
+                        TradeRequest::Accept(requester_id) => self.trade_accept(&from, &requester_id),
+                        TradeRequest::Offer(items, gold) => self.trade_offer(&from, &items, gold),
+                        TradeRequest::Lock => self.trade_lock(&from),
+                        TradeRequest::Confirm => self.trade_confirm(&from),
+                        TradeRequest::Cancel => self.trade_cancel(&from),
+                    };
+                    if let Err(message) = result {
+                        self.send_to(&from, ServerMessage::Error { message });
+                    }
+                }
+                FollowUp::Kill { killer, monster_id } => self.reward_kill(&killer, monster_id),
+                FollowUp::PartyHeal { caster, skill_id, amount } => self.heal_party(&caster, skill_id, amount, events),
+            }
+        }
+    }
+
+    fn send_to(&self, id: &str, msg: ServerMessage) {
+        if let Some(p) = self.players.get(id) {
+            p.send(msg);
+        }
+    }
+
+    fn notify_party(&self, party_id: u64, message: &str) {
+        let Some(party) = self.parties.get(&party_id) else { return; };
+        for member in party.members() {
+            self.send_to(member, ServerMessage::Notice { message: message.to_string() });
+        }
+    }
+
+    /// `id` first, then the members of their party close enough to share with them
+    fn members_near(&self, id: &str) -> Vec<String> {
+        let Some(p) = self.players.get(id) else { return Vec::new(); };
+        let mut near = vec![id.to_string()];
+        if let Some(party) = p.party.and_then(|party_id| self.parties.get(&party_id)) {
+            near.extend(party.members().iter()
+                .filter(|member| member.as_str() != id)
+                .filter(|member| self.players.get(member.as_str()).is_some_and(|other| {
+                    other.player.current_map == p.player.current_map
+                        && !other.player.is_dead()
+                        && party::in_range(other.grid(), p.grid())
+                }))
+                .cloned());
+        }
+        near
+    }
+
+    /// Roll a kill's rewards and split the exp between the killer and
+    /// the party members near them. Gold and items go to the killer.
+    fn reward_kill(&mut self, killer: &str, monster_id: i32) {
+        let Some(def) = get_monster_by_id(monster_id) else { return; };
+        let Some(level) = self.players.get(killer).map(|p| p.player.level) else { return; };
+
+        let sharers = self.members_near(killer);
+        let levels: Vec<i32> = sharers.iter().map(|id| self.players[id].player.level).collect();
+        let shares = party::exp_shares(def, &levels);
+        let loot = roll_kill_rewards(&mut self.rng, def, level);
+
+        for (id, exp) in sharers.iter().zip(shares) {
+            let rewards = if id == killer {
+                KillRewards { exp, ..loot.clone() }
+            } else {
+                KillRewards { monster_id, exp, gold: 0, items: Vec::new() }
+            };
+            if let Some(p) = self.players.get_mut(id) {
+                grant_rewards(p, rewards, &mut self.pending_saves);
+            }
+        }
+    }
+
+    /// Heal the party members near the caster (the caster healed themselves)
+    fn heal_party(&mut self, caster: &str, skill_id: i32, amount: i32, events: &mut Vec<(String, CombatEvent)>) {
+        for id in self.members_near(caster).into_iter().skip(1) {
+            let Some(p) = self.players.get_mut(&id) else { continue; };
+            p.player.heal(amount);
+            events.push((p.player.current_map.clone(), CombatEvent {
+                attacker_id: caster.to_string(),
+                target_id: id.clone(),
+                skill_id: Some(skill_id),
+                amount: -amount,
+                killed: false,
+                outcome: HitOutcome::Hit,
+            }));
+        }
+    }
+
+    fn party_invite(&mut self, from: &str, name: &str) -> Result<(), String> {
+        let Some(inviter) = self.players.get(from) else { return Ok(()); };
+        let name = name.trim();
+        let target = self.players.values()
+            .find(|p| p.player.username.eq_ignore_ascii_case(name))
+            .ok_or_else(|| format!("{} is not online", name))?;
+        if target.player.id == from {
+            return Err("You cannot invite yourself".to_string());
+        }
+        if target.party.is_some() {
+            return Err(format!("{} is already in a party", target.player.username));
+        }
+        if let Some(party) = inviter.party.and_then(|id| self.parties.get(&id)) {
+            if party.leader() != from {
+                return Err("Only the party leader can invite".to_string());
+            }
+            if party.is_full() {
+                return Err("Your party is full".to_string());
+            }
+        }
+
+        let invite = ServerMessage::PartyInvite { from_id: from.to_string(), from: inviter.player.username.clone() };
+        let notice = ServerMessage::Notice { message: format!("Invited {} to the party", target.player.username) };
+        let target_id = target.player.id.clone();
+        let expires_at = self.now + party::INVITE_TIMEOUT_SECS;
+        if let Some(target) = self.players.get_mut(&target_id) {
+            target.invites.insert(from.to_string(), expires_at);
+            target.send(invite);
+        }
+        self.send_to(from, notice);
+        Ok(())
+    }
+
+    fn party_accept(&mut self, from: &str, inviter_id: &str) -> Result<(), String> {
+        let now = self.now;
+        let Some(p) = self.players.get_mut(from) else { return Ok(()); };
+        p.invites.retain(|_, expires_at| *expires_at > now);
+        if p.invites.remove(inviter_id).is_none() {
+            return Err("That invite has expired".to_string());
+        }
+        if p.party.is_some() {
+            return Err("You are already in a party".to_string());
+        }
+        p.invites.clear();
+        let name = p.player.username.clone();
+
+        let inviter = self.players.get(inviter_id).ok_or("The inviter is no longer online")?;
+        let party_id = match inviter.party {
+            Some(party_id) => {
+                let party = self.parties.get_mut(&party_id).ok_or("That party no longer exists")?;
+                if party.leader() != inviter_id {
+                    return Err("The inviter no longer leads the party".to_string());
+                }
+                if party.is_full() {
+                    return Err("That party is full".to_string());
+                }
+                party.add(from.to_string());
+                party_id
+            }
+            None => {
+                let party_id = self.next_party_id;
+                self.next_party_id += 1;
+                self.parties.insert(party_id, Party::new(inviter_id.to_string(), from.to_string()));
+                if let Some(inviter) = self.players.get_mut(inviter_id) {
+                    inviter.party = Some(party_id);
+                }
+                party_id
+            }
+        };
+
+        if let Some(p) = self.players.get_mut(from) {
+            p.party = Some(party_id);
+        }
+        self.notify_party(party_id, &format!("{} joined the party", name));
+        Ok(())
+    }
+
+    fn party_leave(&mut self, from: &str) -> Result<(), String> {
+        if self.players.get(from).is_some_and(|p| p.party.is_none()) {
+            return Err("You are not in a party".to_string());
+        }
+        self.send_to(from, ServerMessage::Notice { message: "You left the party".to_string() });
+        self.remove_from_party(from, "left");
+        Ok(())
+    }
+
+    fn party_kick(&mut self, from: &str, member_id: &str) -> Result<(), String> {
+        let party = self.players.get(from)
+            .and_then(|p| p.party)
+            .and_then(|id| self.parties.get(&id))
+            .ok_or("You are not in a party")?;
+        if party.leader() != from {
+            return Err("Only the party leader can remove members".to_string());
+        }
+        if member_id == from {
+            return Err("Leave the party instead".to_string());
+        }
+        if !party.contains(member_id) {
+            return Err("That player is not in your party".to_string());
+        }
+        self.send_to(member_id, ServerMessage::Notice { message: "You were removed from the party".to_string() });
+        self.remove_from_party(member_id, "was removed from");
+        Ok(())
+    }
+
+    /// Take a player out of their party, telling the others; a party
+    /// left with one member is disbanded
+    fn remove_from_party(&mut self, id: &str, verb: &str) {
+        let Some(p) = self.players.get_mut(id) else { return; };
+        let Some(party_id) = p.party.take() else { return; };
+        let name = p.player.username.clone();
+        let Some(party) = self.parties.get_mut(&party_id) else { return; };
+        let was_leader = party.leader() == id;
+        party.remove(id);
+
+        if party.is_disbanded() {
+            let members = party.members().to_vec();
+            self.parties.remove(&party_id);
+            for member in members {
+                if let Some(p) = self.players.get_mut(&member) {
+                    p.party = None;
+                    p.send(ServerMessage::Notice { message: format!("{} {} the party; the party was disbanded", name, verb) });
+                }
+            }
+            return;
+        }
+
+        self.notify_party(party_id, &format!("{} {} the party", name, verb));
+        if was_leader
+            && let Some(leader) = self.parties.get(&party_id).and_then(|party| self.players.get(party.leader()))
+        {
+            self.notify_party(party_id, &format!("{} now leads the party", leader.player.username));
+        }
+    }
+
+    /// Whether two online players stand close enough on the same map to trade
+    fn can_trade(&self, a: &str, b: &str) -> bool {
+        match (self.players.get(a), self.players.get(b)) {
+            (Some(a), Some(b)) => a.player.current_map == b.player.current_map && trade::in_range(a.grid(), b.grid()),
+            _ => false,
+        }
+    }
+
+    fn trade_ask(&mut self, from: &str, name: &str) -> Result<(), String> {
+        let Some(requester) = self.players.get(from) else { return Ok(()); };
+        let name = name.trim();
+        let target = self.players.values()
+            .find(|p| p.player.username.eq_ignore_ascii_case(name))
+            .ok_or_else(|| format!("{} is not online", name))?;
+        if target.player.id == from {
+            return Err("You cannot trade with yourself".to_string());
+        }
+        // Guests are never saved, so anything they received would be lost
+        if !requester.persistent || !target.persistent {
+            return Err("Guests cannot trade".to_string());
+        }
+        if requester.trade.is_some() {
+            return Err("You are already trading".to_string());
+        }
+        if target.trade.is_some() {
+            return Err(format!("{} is busy trading", target.player.username));
+        }
+        if !self.can_trade(from, &target.player.id) {
+            return Err(format!("{} is too far away to trade", target.player.username));
+        }
+
+        let request = ServerMessage::TradeRequest { from_id: from.to_string(), from: requester.player.username.clone() };
+        let notice = ServerMessage::Notice { message: format!("Asked {} to trade", target.player.username) };
+        let target_id = target.player.id.clone();
+        let expires_at = self.now + trade::REQUEST_TIMEOUT_SECS;
+        if let Some(target) = self.players.get_mut(&target_id) {
+            target.trade_requests.insert(from.to_string(), expires_at);
+            target.send(request);
+        }
+        self.send_to(from, notice);
+        Ok(())
+    }
+
+    fn trade_accept(&mut self, from: &str, requester_id: &str) -> Result<(), String> {
+        let now = self.now;
+        let Some(p) = self.players.get_mut(from) else { return Ok(()); };
+        p.trade_requests.retain(|_, expires_at| *expires_at > now);
+        if p.trade_requests.remove(requester_id).is_none() {
+            return Err("That trade request has expired".to_string());
+        }
+        if p.trade.is_some() {
+            return Err("You are already trading".to_string());
+        }
+
+        let requester = self.players.get(requester_id).ok_or("That player is no longer online")?;
+        if requester.trade.is_some() {
+            return Err(format!("{} is busy trading", requester.player.username));
+        }
+        if !self.can_trade(from, requester_id) {
+            return Err(format!("{} is too far away to trade", requester.player.username));
+        }
+
+        let trade_id = self.next_trade_id;
+        self.next_trade_id += 1;
+        self.trades.insert(trade_id, Trade::new(requester_id.to_string(), from.to_string()));
+        for id in [from, requester_id] {
+            if let Some(p) = self.players.get_mut(id) {
+                p.trade = Some(trade_id);
+                p.trade_requests.clear();
+            }
+        }
+        self.send_trade(trade_id);
+        Ok(())
+    }
+
+    /// The open trade a player is in
+    fn trade_of(&self, id: &str) -> Result<u64, String> {
+        self.players.get(id).and_then(|p| p.trade).ok_or_else(|| "You are not trading".to_string())
+    }
+
+    fn trade_offer(&mut self, from: &str, slots: &[TradeSlot], gold: i64) -> Result<(), String> {
+        let trade_id = self.trade_of(from)?;
+        let items = trade::build_offer(&self.players[from].player, slots, gold)?;
+        self.trades.get_mut(&trade_id).ok_or("You are not trading")?.set_offer(from, items, gold)?;
+        self.send_trade(trade_id);
+        Ok(())
+    }
+
+    fn trade_lock(&mut self, from: &str) -> Result<(), String> {
+        let trade_id = self.trade_of(from)?;
+        self.trades.get_mut(&trade_id).ok_or("You are not trading")?.lock(from)?;
+        self.send_trade(trade_id);
+        Ok(())
+    }
+
+    fn trade_confirm(&mut self, from: &str) -> Result<(), String> {
+        let trade_id = self.trade_of(from)?;
+        if self.trades.get_mut(&trade_id).ok_or("You are not trading")?.confirm(from)? {
+            self.complete_trade(trade_id);
+        } else {
+            self.send_trade(trade_id);
+        }
+        Ok(())
+    }
+
+    fn trade_cancel(&mut self, from: &str) -> Result<(), String> {
+        self.trade_of(from)?;
+        self.cancel_trade(from, "cancelled");
+        Ok(())
+    }
+
+    /// Show both traders the current offers
+    fn send_trade(&self, trade_id: u64) {
+        let Some(trade) = self.trades.get(&trade_id) else { return; };
+        for id in trade.traders() {
+            let (Some(partner_id), Some((mine, theirs))) = (trade.partner(id), trade.offers_for(id)) else { continue; };
+            let partner = self.players.get(partner_id).map(|p| p.player.username.clone()).unwrap_or_default();
+            self.send_to(id, ServerMessage::Trade(TradeState {
+                partner_id: partner_id.to_string(),
+                partner,
+                mine: mine.clone(),
+                theirs: theirs.clone(),
+            }));
+        }
+    }
+
+    /// Close a trade, telling both traders why
+    fn close_trade(&mut self, trade_id: u64, completed: bool, reason: &str) {
+        let Some(trade) = self.trades.remove(&trade_id) else { return; };
+        for id in trade.traders() {
+            if let Some(p) = self.players.get_mut(id) {
+                p.trade = None;
+                p.send(ServerMessage::TradeClosed { completed, reason: reason.to_string() });
+            }
+        }
+    }
+
+    /// End a player's open trade without an exchange
+    fn cancel_trade(&mut self, id: &str, verb: &str) {
+        let Some(p) = self.players.get(id) else { return; };
+        let Some(trade_id) = p.trade else { return; };
+        let reason = format!("{} {} the trade", p.player.username, verb);
+        self.close_trade(trade_id, false, &reason);
+    }
+
+    /// Swap the offers once both sides confirmed and queue the result for saving
+    fn complete_trade(&mut self, trade_id: u64) {
+        let Some(trade) = self.trades.get(&trade_id) else { return; };
+        let [a, b] = trade.traders().clone();
+        let result = match self.players.get_disjoint_mut([a.as_str(), b.as_str()]) {
+            [Some(a), Some(b)] => trade::exchange(&mut a.player, &mut b.player, trade.offers())
+                .map(|()| CompletedTrade { players: [a.player.clone(), b.player.clone()] }),
+            _ => Err("Your trade partner is no longer online".to_string()),
+        };
+
+        match result {
+            Ok(completed) => {
+                tracing::info!("Trade between {} and {} completed", a, b);
+                // Saves queued earlier (a kill this tick, a map change) hold the
+                // traders as they were before the exchange; writing one after the
+                // trade would hand the goods back
+                for save in self.pending_saves.iter_mut() {
+                    if let Some(after) = completed.players.iter().find(|p| p.id == save.id) {
+                        *save = after.clone();
+                    }
+                }
+                self.completed_trades.push(completed);
+                self.close_trade(trade_id, true, "Trade complete");
+            }
+            Err(reason) => self.close_trade(trade_id, false, &reason),
+        }
+    }
+
+    /// Cancel trades whose traders moved apart or changed map
+    fn check_trades(&mut self) {
+        let apart: Vec<u64> = self.trades.iter()
+            .filter(|(_, trade)| !self.can_trade(&trade.traders()[0], &trade.traders()[1]))
+            .map(|(id, _)| *id)
+            .collect();
+        for trade_id in apart {
+            self.close_trade(trade_id, false, "Trade cancelled: you moved too far apart");
+        }
+    }
+
+    /// Send the message to its channel's listeners and log it
+    fn deliver_chat(&mut self, chat: OutgoingChat) {
+        let Some(sender) = self.players.get(&chat.from) else { return; };
+
+        let (listeners, to): (Vec<&PlayerEntity>, Option<String>) = match chat.channel {
+            ChatChannel::Map => {
+                let map_id = &sender.player.current_map;
+                (self.players.values().filter(|p| &p.player.current_map == map_id).collect(), None)
+            }
+            ChatChannel::Global => (self.players.values().collect(), None),
+            ChatChannel::Whisper => {
+                let name = chat.to.as_deref().unwrap_or_default().trim();
+                match self.players.values().find(|p| p.player.username.eq_ignore_ascii_case(name)) {
+                    Some(recipient) if recipient.player.id == sender.player.id => {
+                        sender.send(ServerMessage::Error { message: "You cannot whisper to yourself".to_string() });
+                        return;
+                    }
+                    Some(recipient) => (vec![sender, recipient], Some(recipient.player.username.clone())),
+                    None => {
+                        sender.send(ServerMessage::Error { message: format!("{} is not online", name) });
+                        return;
+                    }
+                }
+            }
+            ChatChannel::Party => match sender.party.and_then(|id| self.parties.get(&id)) {
+                Some(party) => (party.members().iter().filter_map(|id| self.players.get(id)).collect(), None),
+                None => {
+                    sender.send(ServerMessage::Error { message: "You are not in a party".to_string() });
+                    return;
+                }
+            },
+            ChatChannel::Guild => match &sender.guild {
+                Some(guild) => (
+                    self.players.values().filter(|p| p.guild.as_ref().is_some_and(|g| g.id == guild.id)).collect(),
+                    None,
+                ),
+                None => {
+                    sender.send(ServerMessage::Error { message: "You are not in a guild".to_string() });
+                    return;
+                }
+            },
+        };
+
+        let filtered = self.word_filter.apply(&chat.text);
+        let message = ChatMessage {
+            channel: chat.channel,
+            from_id: sender.player.id.clone(),
+            from: sender.player.username.clone(),
+            to: to.clone(),
+            text: filtered.clone().unwrap_or_else(|| chat.text.clone()),
+        };
+        for listener in listeners {
+            listener.send(ServerMessage::Chat(message.clone()));
+        }
+
+        self.chat_log.push(NewChatLog {
+            channel: chat.channel,
+            sender_character_id: sender.persistent.then(|| uuid::Uuid::parse_str(&sender.player.id).ok()).flatten(),
+            sender_name: sender.player.username.clone(),
+            recipient_name: to,
+            map_id: sender.player.current_map.clone(),
+            message: chat.text,
+            filtered: filtered.is_some(),
+            sent_at: chrono::Utc::now(),
+        });
+    }
+
+    fn apply_movement(&mut self) {
+        let now = self.now;
+        let mut transfers: Vec<(String, &'static PortalDef)> = Vec::new();
+
+        for p in self.players.values_mut() {
+            let Some(direction) = p.queued_move else { continue; };
+            if now + MOVE_TOLERANCE < p.next_move_at || p.player.is_dead() {
+                continue;
+            }
+            p.queued_move = None;
+
+            let Some(map) = self.maps.get(&p.player.current_map) else { continue; };
+            let (x, y) = p.grid();
+            let (dx, dy) = direction.offset();
+            if map.is_walkable(x + dx, y + dy) {
+                p.player.position = Position::new((x + dx) as f64, (y + dy) as f64);
+                p.next_move_at = now + MOVE_DURATION as f64;
+
+                if let Some(portal) = maps::get_map_portals(&p.player.current_map)
+                    .iter()
+                    .find(|portal| (portal.x, portal.y) == (x + dx, y + dy))
+                {
+                    transfers.push((p.player.id.clone(), portal));
+                }
+            }
+        }
+
+        for (id, portal) in transfers {
+            self.change_map(&id, portal);
+        }
+    }
+
+    fn update_monsters(&mut self, events: &mut Vec<(String, CombatEvent)>) {
+        let now = self.now;
+        let rng = &mut self.rng;
+        let mut deaths = Vec::new();
+
+        for (map_id, map) in self.maps.iter_mut() {
+            for m in map.monsters.iter_mut() {
+                if let Some(until) = m.dead_until {
+                    if now >= until {
+                        let def = get_monster_by_id(m.spawn.monster_id);
+                        if let Some(def) = def {
+                            *m = MonsterEntity::spawn(&MonsterData::from(def), &m.spawn.clone());
+                        }
+                    }
+                    continue;
+                }
+
+                if m.monster.ai_type != MonsterAIType::Aggressive {
+                    continue;
+                }
+
+                // Nearest living player on this map
+                let detection = m.monster.detection_range / GRID_UNIT as f64;
+                let target = self.players.values_mut()
+                    .filter(|p| &p.player.current_map == map_id && !p.player.is_dead())
+                    .map(|p| {
+                        let (px, py) = p.grid();
+                        let d = (((px - m.x).pow(2) + (py - m.y).pow(2)) as f64).sqrt();
+                        (d, p)
+                    })
+                    .filter(|(d, _)| *d < detection)
+                    .min_by(|a, b| a.0.total_cmp(&b.0));
+
+                match target {
+                    Some((distance, p)) if distance <= 1.1 => {
+                        if m.monster.can_attack(now * 1000.0) {
+                            m.monster.register_attack(now * 1000.0);
+                            let defender = Combatant::from(&p.player.combat_stats);
+                            let result = resolve_attack(rng, &Combatant::from(&m.monster), &defender, AttackKind::Physical, None);
+                            p.player.take_damage(result.damage);
+                            events.push((map_id.clone(), CombatEvent {
+                                attacker_id: m.monster.id.clone(),
+                                target_id: p.player.id.clone(),
+                                skill_id: None,
+                                amount: result.damage,
+                                killed: p.player.is_dead(),
+                                outcome: result.outcome,
+                            }));
+                            if p.player.is_dead() {
+                                deaths.push(p.player.id.clone());
+                            }
+                        }
+                    }
+                    Some((_, p)) => {
+                        if now >= m.next_step_at {
+                            let (px, py) = p.grid();
+                            step_toward(m, map.width, map.height, &map.tiles, px, py);
+                            m.next_step_at = now + MONSTER_STEP_INTERVAL;
+                        }
+                    }
+                    None => {
+                        if now >= m.next_step_at && (m.x, m.y) != (m.spawn.x, m.spawn.y) {
+                            let (sx, sy) = (m.spawn.x, m.spawn.y);
+                            step_toward(m, map.width, map.height, &map.tiles, sx, sy);
+                            m.next_step_at = now + MONSTER_STEP_INTERVAL;
+                        }
+                    }
+                }
+            }
+        }
+
+        for id in deaths {
+            self.respawn(&id);
+        }
+    }
+
+    /// Bring a player who died back at full HP: at their bind point if
+    /// they have one, otherwise at the start of the map they died on
+    fn respawn(&mut self, id: &str) {
+        let Some(p) = self.players.get_mut(id) else { return; };
+        p.player.combat_stats.hp = p.player.combat_stats.max_hp;
+        p.queued_move = None;
+        let bind = p.player.bind_point.clone();
+        let current_map = p.player.current_map.clone();
+
+        if let Some(bind) = bind
+            && self.teleport(id, &bind.map_id, bind.x, bind.y).is_some()
+        {
+            return;
+        }
+        let Some(start) = self.maps.get(&current_map).map(|m| m.start) else { return; };
+        if let Some(p) = self.players.get_mut(id) {
+            p.player.position = Position::new(start.0 as f64, start.1 as f64);
+        }
+    }
+
+    fn broadcast(&self, events: Vec<(String, CombatEvent)>) {
+        for (map_id, event) in events {
+            for p in self.players.values().filter(|p| p.player.current_map == map_id) {
+                p.send(ServerMessage::Combat(event.clone()));
+            }
+        }
+
+        let parties: HashMap<u64, PartyState> = self.parties.iter()
+            .map(|(id, party)| (*id, PartyState {
+                leader_id: party.leader().to_string(),
+                members: party.members().iter().filter_map(|m| self.players.get(m)).map(|m| m.party_member()).collect(),
+            }))
+            .collect();
+
+        for p in self.players.values() {
+            let map_id = &p.player.current_map;
+            let mut entities: Vec<EntityState> = self.players.values()
+                .filter(|other| &other.player.current_map == map_id)
+                .map(|other| other.to_entity_state())
+                .collect();
+
+            if let Some(map) = self.maps.get(map_id) {
+                entities.extend(map.monsters.iter().filter(|m| m.is_alive()).map(|m| m.to_entity_state()));
+            }
+
+            p.send(ServerMessage::Snapshot {
+                tick: self.tick,
+                you: p.vitals(),
+                entities,
+                party: p.party.and_then(|id| parties.get(&id).cloned()),
+            });
+        }
+    }
+}
+
+/// Apply damage to a monster and build the resulting event
+fn damage_monster(attacker_id: &str, skill_id: Option<i32>, target: &mut MonsterEntity, result: AttackResult, now: f64) -> CombatEvent {
+    target.monster.take_damage(result.damage);
+    let killed = target.monster.is_dead();
+    if killed {
+        target.dead_until = Some(now + target.spawn.respawn_time_ms as f64 / 1000.0);
+    }
+
+    CombatEvent {
+        attacker_id: attacker_id.to_string(),
+        target_id: target.monster.id.clone(),
+        skill_id,
+        amount: result.damage,
+        killed,
+        outcome: result.outcome,
+    }
+}
+
+/// Apply a player's share of a kill and tell them
+fn grant_rewards(p: &mut PlayerEntity, mut rewards: KillRewards, pending_saves: &mut Vec<Player>) {
+    p.player.add_exp(rewards.exp);
+    // A full purse keeps what it holds; report only the gold that fit
+    let gold = p.player.gold.saturating_add(rewards.gold);
+    rewards.gold = gold - p.player.gold;
+    p.player.gold = gold;
+    // Report only what fit in the bag
+    for item in rewards.items.iter_mut() {
+        item.quantity -= p.player.inventory.add_item(item.item_id, item.quantity);
+    }
+    rewards.items.retain(|item| item.quantity > 0);
+
+    p.send(ServerMessage::Rewards(rewards));
+    if p.persistent {
+        pending_saves.push(p.player.clone());
+    }
+}
+
+/// Validate and execute a skill cast. Kills and party heals are
+/// returned for the world to finish.
+fn cast_skill(
+    p: &mut PlayerEntity,
+    skill_id: i32,
+    maps: &mut HashMap<String, MapInstance>,
+    rng: &mut impl Rng,
+    now: f64,
+    events: &mut Vec<(String, CombatEvent)>,
+) -> Result<Option<FollowUp>, String> {
+    let skill = get_skill_by_id(skill_id).ok_or("Unknown skill")?;
+
+    if !p.player.skills.knows(skill_id) {
+        return Err(format!("{} has not been learned", skill.name));
+    }
+
+    if skill.class_id.is_some_and(|c| c != p.player.class.id()) {
+        return Err(format!("{} is not a {} skill", skill.name, p.player.class.name()));
+    }
+    if skill.req_level > p.player.level {
+        return Err(format!("{} requires level {}", skill.name, skill.req_level));
+    }
+    if p.skill_ready_at.get(&skill_id).is_some_and(|t| now < *t) {
+        return Err(format!("{} is on cooldown", skill.name));
+    }
+    if p.player.combat_stats.mp < skill.mp_cost {
+        return Err(format!("Not enough MP for {}", skill.name));
+    }
+
+    p.player.combat_stats.mp -= skill.mp_cost;
+    p.skill_ready_at.insert(skill_id, now + skill.cooldown_ms as f64 / 1000.0);
+
+    match skill.effect_type {
+        SkillEffectType::Damage => {
+            let (tx, ty) = p.facing_tile();
+            if let Some(target) = maps.get_mut(&p.player.current_map).and_then(|m| m.monster_at_mut(tx, ty)) {
+                let attacker = Combatant::from(&p.player.combat_stats);
+                let kind = skill_attack_kind(skill);
+                let result = resolve_attack(rng, &attacker, &Combatant::from(&target.monster), kind, Some(skill.base_value));
+                let event = damage_monster(&p.player.id, Some(skill_id), target, result, now);
+                let killed = event.killed.then(|| FollowUp::Kill { killer: p.player.id.clone(), monster_id: target.monster.monster_id });
+                events.push((p.player.current_map.clone(), event));
+                return Ok(killed);
+            }
+        }
+        SkillEffectType::Heal => {
+            p.player.heal(skill.base_value);
+            events.push((p.player.current_map.clone(), CombatEvent {
+                attacker_id: p.player.id.clone(),
+                target_id: p.player.id.clone(),
+                skill_id: Some(skill_id),
+                amount: -skill.base_value,
+                killed: false,
+                outcome: HitOutcome::Hit,
+            }));
+            if skill.target == SkillTarget::Party {
+                return Ok(Some(FollowUp::PartyHeal { caster: p.player.id.clone(), skill_id, amount: skill.base_value }));
+            }
+        }
+        // Buffs, debuffs and over-time effects are not simulated yet
+        _ => {}
+    }
+
+    Ok(None)
+}
+
+/// Move a monster one tile toward a target, axis with the larger gap first
+fn step_toward(m: &mut MonsterEntity, width: i32, height: i32, tiles: &[MapTile], tx: i32, ty: i32) {
+    let dx = tx - m.x;
+    let dy = ty - m.y;
+    let (mx, my) = if dx.abs() > dy.abs() { (dx.signum(), 0) } else { (0, dy.signum()) };
+
+    let (nx, ny) = (m.x + mx, m.y + my);
+    if nx < 0 || ny < 0 || nx >= width || ny >= height || !tiles[(ny * width + nx) as usize].is_walkable() {
+        return;
+    }
+
+    m.monster.direction = match (mx, my) {
+        (1, _) => Direction::Right,
+        (-1, _) => Direction::Left,
+        (_, 1) => Direction::Down,
+        _ => Direction::Up,
+    };
+    m.x = nx;
+    m.y = ny;
+    m.monster.position = Position::new(nx as f64, ny as f64);
+}
+
+/// Run the fixed-rate simulation loop forever (`tick_rate` per second)
+pub fn spawn_tick_loop(world: WorldHandle, tick_rate: u32) -> tokio::task::JoinHandle<()> {
+    tokio::spawn(async move {
+        let period = Duration::from_secs_f64(1.0 / tick_rate.max(1) as f64);
+        let mut interval = tokio::time::interval(period);
+        interval.set_missed_tick_behavior(tokio::time::MissedTickBehavior::Skip);
+
+        loop {
+            interval.tick().await;
+            world.lock().unwrap().tick(period.as_secs_f64());
+        }
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::shared::domain::PlayerClass;
+    use tokio::sync::mpsc::{unbounded_channel, UnboundedReceiver};
+
+    fn join_at(world: &mut World, x: i32, y: i32) -> (String, UnboundedReceiver<ServerMessage>) {
+        join_map(world, maps::MILLES_VILLAGE.id, x, y)
+    }
+
+    fn join_map(world: &mut World, map_id: &str, x: i32, y: i32) -> (String, UnboundedReceiver<ServerMessage>) {
+        let mut player = Player::new("tester".to_string(), PlayerClass::Warrior);
+        player.current_map = map_id.to_string();
+        player.position = Position::new(x as f64, y as f64);
+        let (tx, rx) = unbounded_channel();
+        let id = world.join(player, tx, true).unwrap();
+        (id, rx)
+    }
+
+    fn position(world: &World, id: &str) -> (i32, i32) {
+        world.players[id].grid()
+    }
+
+    #[test]
+    fn test_join_sends_welcome_and_fixes_spawn() {
+        let mut world = World::new();
+        // (3, 1) is water, not a valid spawn tile
+        let (id, mut rx) = join_at(&mut world, 3, 1);
+
+        assert!(matches!(rx.try_recv(), Ok(ServerMessage::Welcome { .. })));
+        assert_eq!(position(&world, &id), maps::MILLES_VILLAGE_START);
+    }
+
+    #[test]
+    fn test_movement_is_validated() {
+        let mut world = World::new();
+        let (id, _rx) = join_at(&mut world, 3, 0);
+
+        // Water below blocks the step
+        world.push_input(&id, ClientMessage::Move { direction: Direction::Down });
+        world.tick(0.1);
+        assert_eq!(position(&world, &id), (3, 0));
+
+        world.push_input(&id, ClientMessage::Move { direction: Direction::Left });
+        world.tick(0.1);
+        assert_eq!(position(&world, &id), (2, 0));
+    }
+
+    #[test]
+    fn test_rejects_skill_from_other_class() {
+        let mut world = World::new();
+        let (id, mut rx) = join_at(&mut world, 8, 8);
+        let fireball = crate::shared::data::skills::FIREBALL.id;
+
+        world.push_input(&id, ClientMessage::UseSkill { skill_id: fireball });
+        world.tick(0.1);
+
+        let errors: Vec<_> = std::iter::from_fn(|| rx.try_recv().ok())
+            .filter(|m| matches!(m, ServerMessage::Error { .. }))
+            .collect();
+        assert_eq!(errors.len(), 1);
+    }
+
+    #[test]
+    fn test_unknown_map_falls_back_to_village() {
+        let mut world = World::new();
+        let (id, _rx) = join_map(&mut world, "no_such_map", 3, 3);
+
+        let player = world.player_state(&id).unwrap();
+        assert_eq!(player.current_map, maps::MILLES_VILLAGE.id);
+        assert_eq!(position(&world, &id), maps::MILLES_VILLAGE_START);
+    }
+
+    #[test]
+    fn test_portal_changes_map_and_queues_save() {
+        let mut world = World::new();
+        // The plains portal at (5, 5) leads back to the village
+        let (id, mut rx) = join_map(&mut world, maps::MILLES_PLAINS.id, 5, 6);
+
+        world.push_input(&id, ClientMessage::Move { direction: Direction::Up });
+        world.tick(0.1);
+
+        let player = world.player_state(&id).unwrap();
+        assert_eq!(player.current_map, maps::MILLES_VILLAGE.id);
+        assert!(std::iter::from_fn(|| rx.try_recv().ok())
+            .any(|m| matches!(m, ServerMessage::MapChanged { ref map_id, .. } if map_id == maps::MILLES_VILLAGE.id)));
+
+        let saves = world.take_pending_saves();
+        assert_eq!(saves.len(), 1);
+        assert_eq!(saves[0].current_map, maps::MILLES_VILLAGE.id);
+        assert!(world.take_pending_saves().is_empty());
+    }
+
+    #[test]
+    fn test_dead_players_return_to_their_bind_point() {
+        let mut world = World::with_seed(7);
+        // Next to the giant rats on the plains, one hit from dying
+        let (bound, mut rx) = join_map(&mut world, maps::MILLES_PLAINS.id, 11, 10);
+        let (unbound, _unbound_rx) = join_map(&mut world, maps::MILLES_PLAINS.id, 21, 15);
+        let bind = crate::shared::domain::BindPoint { map_id: maps::MILLES_VILLAGE.id.to_string(), x: 8, y: 8 };
+        world.with_player(&bound, |p| p.bind_point = Some(bind));
+        for id in [&bound, &unbound] {
+            world.with_player(id, |p| p.combat_stats.hp = 1);
+        }
+
+        for _ in 0..50 {
+            world.tick(0.5);
+        }
+
+        let player = world.player_state(&bound).unwrap();
+        assert_eq!((player.current_map.as_str(), position(&world, &bound)), (maps::MILLES_VILLAGE.id, (8, 8)));
+        assert_eq!(player.combat_stats.hp, player.combat_stats.max_hp);
+        assert!(drain(&mut rx).iter().any(|m| matches!(m, ServerMessage::MapChanged { x: 8, y: 8, .. })));
+
+        // Without a bind point they come back where they fell, at the map's start
+        let player = world.player_state(&unbound).unwrap();
+        assert_eq!(player.current_map, maps::MILLES_PLAINS.id);
+        assert_eq!(position(&world, &unbound), world.maps[maps::MILLES_PLAINS.id].start);
+    }
+
+    #[test]
+    fn test_rejects_unlearned_and_underleveled_skills() {
+        let mut world = World::new();
+        let (id, mut rx) = join_at(&mut world, 8, 8);
+        let skill = crate::shared::data::skills::get_available_skills(PlayerClass::Warrior.id(), 99)
+            .into_iter()
+            .find(|s| s.class_id.is_some() && s.req_level > 1)
+            .unwrap();
+
+        let mut errors = |world: &mut World| {
+            world.push_input(&id, ClientMessage::UseSkill { skill_id: skill.id });
+            world.tick(0.1);
+            std::iter::from_fn(|| rx.try_recv().ok())
+                .filter(|m| matches!(m, ServerMessage::Error { .. }))
+                .count()
+        };
+        assert_eq!(errors(&mut world), 1);
+
+        // Learned out of band, but still below the required level
+        world.with_player(&id, |p| p.skills.learned.push(crate::shared::domain::skill::models::LearnedSkill {
+            skill_id: skill.id,
+            level: 1,
+        }));
+        assert_eq!(errors(&mut world), 1);
+    }
+
+    #[test]
+    fn test_kill_rewards_are_granted_reported_and_saved() {
+        let mut world = World::new();
+        let (id, mut rx) = join_at(&mut world, 8, 8);
+        let slime = crate::shared::data::monsters::SLIME.id;
+
+        let p = &world.players[&id];
+        let (gold, exp) = (p.player.gold, p.player.exp);
+        world.reward_kill(&id, slime);
+        let saves = world.take_pending_saves();
+
+        let rewards = std::iter::from_fn(|| rx.try_recv().ok())
+            .find_map(|m| match m {
+                ServerMessage::Rewards(r) => Some(r),
+                _ => None,
+            })
+            .unwrap();
+        assert_eq!(rewards.monster_id, slime);
+        assert!(rewards.gold > 0);
+        assert_eq!(saves.len(), 1);
+        assert_eq!(saves[0].gold, gold + rewards.gold);
+        assert_eq!(saves[0].exp, exp + rewards.exp);
+
+        // A full purse stays full instead of overflowing
+        world.with_player(&id, |p| p.gold = i64::MAX);
+        world.reward_kill(&id, slime);
+        assert_eq!(world.take_pending_saves()[0].gold, i64::MAX);
+    }
+
+    fn chats(rx: &mut UnboundedReceiver<ServerMessage>) -> Vec<ChatMessage> {
+        std::iter::from_fn(|| rx.try_recv().ok())
+            .filter_map(|m| match m {
+                ServerMessage::Chat(chat) => Some(chat),
+                _ => None,
+            })
+            .collect()
+    }
+
+    fn say(world: &mut World, id: &str, channel: ChatChannel, to: Option<&str>, text: &str) {
+        world.push_input(id, ClientMessage::Chat { channel, to: to.map(str::to_string), text: text.to_string() });
+        world.tick(0.1);
+    }
+
+    #[test]
+    fn test_chat_channels_reach_the_right_players() {
+        let settings = ChatSettings { banned_words: vec!["darn".to_string()], ..Default::default() };
+        let mut world = World::new().with_chat(settings);
+        let (alice, mut alice_rx) = join_at(&mut world, 8, 8);
+        let (bob, mut bob_rx) = join_at(&mut world, 9, 8);
+        let (carol, mut carol_rx) = join_map(&mut world, maps::MILLES_PLAINS.id, 5, 6);
+        for (id, name) in [(&alice, "Alice"), (&bob, "Bob"), (&carol, "Carol")] {
+            world.with_player(id, |p| p.username = name.to_string());
+        }
+
+        say(&mut world, &alice, ChatChannel::Map, None, "darn slimes");
+        assert_eq!(chats(&mut bob_rx)[0].text, "**** slimes");
+        assert_eq!(chats(&mut alice_rx).len(), 1);
+        assert!(chats(&mut carol_rx).is_empty());
+
+        say(&mut world, &alice, ChatChannel::Whisper, Some("carol"), "psst");
+        assert_eq!(chats(&mut carol_rx)[0].to.as_deref(), Some("Carol"));
+        assert_eq!(chats(&mut alice_rx).len(), 1);
+        assert!(chats(&mut bob_rx).is_empty());
+
+        say(&mut world, &carol, ChatChannel::Global, None, "hello all");
+        assert!([&mut alice_rx, &mut bob_rx, &mut carol_rx].into_iter().all(|rx| chats(rx).len() == 1));
+
+        // The log keeps what was typed
+        let log = world.take_chat_log();
+        assert_eq!(log.len(), 3);
+        assert_eq!((log[0].message.as_str(), log[0].filtered), ("darn slimes", true));
+        assert_eq!(log[1].recipient_name.as_deref(), Some("Carol"));
+        assert!(world.take_chat_log().is_empty());
+    }
+
+    #[test]
+    fn test_chat_is_rate_limited() {
+        let settings = ChatSettings { max_messages: 2, window_secs: 10.0, ..Default::default() };
+        let mut world = World::new().with_chat(settings);
+        let (id, mut rx) = join_at(&mut world, 8, 8);
+
+        for _ in 0..3 {
+            world.push_input(&id, ClientMessage::Chat { channel: ChatChannel::Map, to: None, text: "spam".to_string() });
+        }
+        world.tick(0.1);
+        let messages: Vec<_> = std::iter::from_fn(|| rx.try_recv().ok()).collect();
+        assert_eq!(messages.iter().filter(|m| matches!(m, ServerMessage::Chat(_))).count(), 2);
+        assert!(messages.iter().any(|m| matches!(m, ServerMessage::Error { message } if message.contains("too quickly"))));
+    }
+
+    fn drain(rx: &mut UnboundedReceiver<ServerMessage>) -> Vec<ServerMessage> {
+        std::iter::from_fn(|| rx.try_recv().ok()).collect()
+    }
+
+    /// Party in the latest snapshot
+    fn snapshot_party(rx: &mut UnboundedReceiver<ServerMessage>) -> Option<PartyState> {
+        drain(rx).into_iter()
+            .rev()
+            .find_map(|m| match m {
+                ServerMessage::Snapshot { party, .. } => Some(party),
+                _ => None,
+            })
+            .flatten()
+    }
+
+    fn errors(messages: &[ServerMessage]) -> Vec<&str> {
+        messages.iter()
+            .filter_map(|m| match m {
+                ServerMessage::Error { message } => Some(message.as_str()),
+                _ => None,
+            })
+            .collect()
+    }
+
+    fn named(world: &mut World, players: &[(&String, &str)]) {
+        for (id, name) in players {
+            world.with_player(id, |p| p.username = name.to_string());
+        }
+    }
+
+    fn form_party(world: &mut World, leader: &str, members: &[&String]) {
+        for member in members {
+            let name = world.players[member.as_str()].player.username.clone();
+            world.push_input(leader, ClientMessage::PartyInvite { name });
+            world.tick(0.1);
+            world.push_input(member, ClientMessage::PartyAccept { inviter_id: leader.to_string() });
+            world.tick(0.1);
+        }
+    }
+
+    #[test]
+    fn test_party_invite_accept_kick_and_leave() {
+        let mut world = World::new();
+        let (alice, mut alice_rx) = join_at(&mut world, 8, 8);
+        let (bob, mut bob_rx) = join_at(&mut world, 9, 8);
+        let (carol, mut carol_rx) = join_at(&mut world, 10, 8);
+        named(&mut world, &[(&alice, "Alice"), (&bob, "Bob"), (&carol, "Carol")]);
+
+        // Accepting needs an invite
+        world.push_input(&bob, ClientMessage::PartyAccept { inviter_id: alice.clone() });
+        world.tick(0.1);
+        assert_eq!(errors(&drain(&mut bob_rx)), ["That invite has expired"]);
+
+        world.push_input(&alice, ClientMessage::PartyInvite { name: "bob".to_string() });
+        world.tick(0.1);
+        assert!(drain(&mut bob_rx).iter().any(|m| matches!(m, ServerMessage::PartyInvite { from_id, .. } if *from_id == alice)));
+        world.push_input(&bob, ClientMessage::PartyAccept { inviter_id: alice.clone() });
+        world.tick(0.1);
+        let party = snapshot_party(&mut alice_rx).unwrap();
+        assert_eq!(party.leader_id, alice);
+        assert_eq!(party.members.iter().map(|m| m.name.as_str()).collect::<Vec<_>>(), ["Alice", "Bob"]);
+
+        // Only the leader invites and kicks
+        world.push_input(&bob, ClientMessage::PartyInvite { name: "Carol".to_string() });
+        world.push_input(&bob, ClientMessage::PartyKick { member_id: alice.clone() });
+        world.tick(0.1);
+        assert_eq!(errors(&drain(&mut bob_rx)), ["Only the party leader can invite", "Only the party leader can remove members"]);
+
+        form_party(&mut world, &alice, &[&carol]);
+        assert_eq!(snapshot_party(&mut carol_rx).unwrap().members.len(), 3);
+
+        // Party chat reaches members only
+        world.push_input(&alice, ClientMessage::PartyKick { member_id: carol.clone() });
+        world.tick(0.1);
+        assert!(snapshot_party(&mut carol_rx).is_none());
+        say(&mut world, &bob, ChatChannel::Party, None, "just us");
+        assert_eq!(chats(&mut alice_rx).len(), 1);
+        assert!(chats(&mut carol_rx).is_empty());
+
+        // Without the leader, one member is left and the party ends
+        world.push_input(&alice, ClientMessage::PartyLeave);
+        world.tick(0.1);
+        assert!(snapshot_party(&mut bob_rx).is_none());
+        say(&mut world, &bob, ChatChannel::Party, None, "hello?");
+        assert_eq!(errors(&drain(&mut bob_rx)), ["You are not in a party"]);
+    }
+
+    #[test]
+    fn test_party_shares_kill_exp_near_the_killer() {
+        let mut world = World::with_seed(1);
+        let (alice, mut alice_rx) = join_at(&mut world, 8, 8);
+        let (bob, mut bob_rx) = join_at(&mut world, 9, 8);
+        let (carol, mut carol_rx) = join_map(&mut world, maps::MILLES_PLAINS.id, 5, 6);
+        named(&mut world, &[(&alice, "Alice"), (&bob, "Bob"), (&carol, "Carol")]);
+        form_party(&mut world, &alice, &[&bob, &carol]);
+        drain(&mut alice_rx);
+        drain(&mut bob_rx);
+        drain(&mut carol_rx);
+
+        let slime = &crate::shared::data::monsters::SLIME;
+        world.reward_kill(&alice, slime.id);
+        let rewards = |rx: &mut UnboundedReceiver<ServerMessage>| drain(rx).into_iter().find_map(|m| match m {
+            ServerMessage::Rewards(r) => Some(r),
+            _ => None,
+        });
+
+        // Carol is on another map and gets nothing
+        let share = party::exp_shares(slime, &[1, 1])[0];
+        let killer = rewards(&mut alice_rx).unwrap();
+        let helper = rewards(&mut bob_rx).unwrap();
+        assert_eq!((killer.exp, helper.exp), (share, share));
+        assert!(killer.gold > 0);
+        assert_eq!(helper.gold, 0);
+        assert!(rewards(&mut carol_rx).is_none());
+    }
+
+    #[test]
+    fn test_party_heal_reaches_members_in_range() {
+        let mut world = World::new();
+        let (alice, _alice_rx) = join_at(&mut world, 8, 8);
+        let (bob, _bob_rx) = join_at(&mut world, 9, 8);
+        let (carol, _carol_rx) = join_map(&mut world, maps::MILLES_PLAINS.id, 5, 6);
+        named(&mut world, &[(&alice, "Alice"), (&bob, "Bob"), (&carol, "Carol")]);
+        form_party(&mut world, &alice, &[&bob, &carol]);
+
+        let mass_heal = &crate::shared::data::skills::MASS_HEAL;
+        world.with_player(&alice, |p| {
+            p.class = PlayerClass::Cleric;
+            p.set_level(mass_heal.req_level);
+            p.combat_stats.mp = mass_heal.mp_cost;
+            p.skills.learned.push(crate::shared::domain::skill::models::LearnedSkill { skill_id: mass_heal.id, level: 1 });
+        });
+        for id in [&bob, &carol] {
+            world.with_player(id, |p| p.take_damage(20));
+        }
+
+        world.push_input(&alice, ClientMessage::UseSkill { skill_id: mass_heal.id });
+        world.tick(0.1);
+        let hp = |world: &World, id: &str| {
+            let stats = &world.players[id].player.combat_stats;
+            (stats.hp, stats.max_hp)
+        };
+        let (bob_hp, bob_max) = hp(&world, &bob);
+        let (carol_hp, carol_max) = hp(&world, &carol);
+        assert_eq!(bob_hp, bob_max);
+        assert_eq!(carol_hp, carol_max - 20);
+    }
+
+    #[test]
+    fn test_guild_chat_and_nameplates() {
+        let mut world = World::new();
+        let (alice, mut alice_rx) = join_at(&mut world, 8, 8);
+        let (bob, mut bob_rx) = join_map(&mut world, maps::MILLES_PLAINS.id, 5, 6);
+        let (carol, mut carol_rx) = join_at(&mut world, 9, 8);
+        named(&mut world, &[(&alice, "Alice"), (&bob, "Bob"), (&carol, "Carol")]);
+
+        let tag = GuildTag { id: uuid::Uuid::new_v4(), name: "Knights".to_string() };
+        world.set_guild(&alice, Some(tag.clone()));
+        world.set_guild(&bob, Some(tag));
+
+        // Guild chat crosses maps but not guilds
+        say(&mut world, &alice, ChatChannel::Guild, None, "raid at nine");
+        assert_eq!(chats(&mut bob_rx)[0].text, "raid at nine");
+        assert_eq!(chats(&mut alice_rx).len(), 1);
+        assert!(chats(&mut carol_rx).is_empty());
+
+        say(&mut world, &carol, ChatChannel::Guild, None, "hello?");
+        assert_eq!(errors(&drain(&mut carol_rx)), ["You are not in a guild"]);
+
+        let guild_of = |world: &World, id: &str| match world.players[id].to_entity_state().kind {
+            EntityKind::Player { guild, .. } => guild,
+            EntityKind::Monster { .. } => unreachable!(),
+        };
+        assert_eq!(guild_of(&world, &alice).as_deref(), Some("Knights"));
+        world.set_guild(&alice, None);
+        assert_eq!(guild_of(&world, &alice), None);
+    }
+
+    fn closed(messages: &[ServerMessage]) -> Option<(bool, &str)> {
+        messages.iter().find_map(|m| match m {
+            ServerMessage::TradeClosed { completed, reason } => Some((*completed, reason.as_str())),
+            _ => None,
+        })
+    }
+
+    fn open_trade(world: &mut World, from: &str, to: &str, to_name: &str) {
+        world.push_input(from, ClientMessage::TradeRequest { name: to_name.to_string() });
+        world.tick(0.1);
+        world.push_input(to, ClientMessage::TradeAccept { from_id: from.to_string() });
+        world.tick(0.1);
+    }
+
+    #[test]
+    fn test_trade_exchanges_after_both_confirm() {
+        use crate::shared::data::items::{IRON_SWORD, RED_POTION};
+
+        let mut world = World::new();
+        let (alice, mut alice_rx) = join_at(&mut world, 8, 8);
+        let (bob, mut bob_rx) = join_at(&mut world, 9, 8);
+        named(&mut world, &[(&alice, "Alice"), (&bob, "Bob")]);
+        world.with_player(&alice, |p| p.inventory.add_item(IRON_SWORD.id, 1));
+        world.with_player(&bob, |p| p.inventory.add_item(RED_POTION.id, 10));
+
+        open_trade(&mut world, &alice, &bob, "bob");
+        let offer = |slot_index, quantity, gold| ClientMessage::TradeOffer { items: vec![TradeSlot { slot_index, quantity }], gold };
+        world.push_input(&alice, offer(0, 1, 40));
+        world.push_input(&bob, offer(0, 4, 0));
+        world.push_input(&alice, ClientMessage::TradeConfirm);
+        world.tick(0.1);
+        assert_eq!(errors(&drain(&mut alice_rx)), ["Both offers must be locked first"]);
+
+        world.push_input(&alice, ClientMessage::TradeLock);
+        world.push_input(&bob, ClientMessage::TradeLock);
+        world.tick(0.1);
+        world.push_input(&alice, ClientMessage::TradeConfirm);
+        world.tick(0.1);
+        let state = drain(&mut bob_rx).into_iter().rev().find_map(|m| match m {
+            ServerMessage::Trade(state) => Some(state),
+            _ => None,
+        });
+        let state = state.unwrap();
+        assert_eq!((state.partner.as_str(), state.theirs.gold, state.theirs.confirmed), ("Alice", 40, true));
+        assert!(world.take_completed_trades().is_empty());
+
+        world.push_input(&bob, ClientMessage::TradeConfirm);
+        world.tick(0.1);
+        assert_eq!(closed(&drain(&mut alice_rx)), Some((true, "Trade complete")));
+        let a = world.player_state(&alice).unwrap();
+        let b = world.player_state(&bob).unwrap();
+        assert_eq!((a.gold, b.gold), (60, 140));
+        assert_eq!((a.inventory.count_item(RED_POTION.id), b.inventory.count_item(IRON_SWORD.id)), (4, 1));
+
+        // Queued for one combined save, with both sides as they are now
+        let trades = world.take_completed_trades();
+        assert_eq!(trades.len(), 1);
+        assert_eq!(trades[0].players.each_ref().map(|p| p.gold), [60, 140]);
+        assert!(world.players[alice.as_str()].trade.is_none());
+    }
+
+    #[tokio::test]
+    async fn test_saves_queued_before_a_trade_do_not_undo_it() {
+        use crate::server::characters::load_player;
+        use crate::server::repo::{NewCharacter, Repos};
+        use crate::shared::data::items::IRON_SWORD;
+
+        let repos = Repos::in_memory();
+        let user_id = uuid::Uuid::new_v4();
+        let mut world = World::new();
+        let mut ids = Vec::new();
+        for (name, x) in [("Alice", 8.0), ("Bob", 9.0)] {
+            let character = NewCharacter { name: name.to_string(), class_id: 1, gender: "male".to_string(), hp: 100, mp: 50 };
+            let summary = repos.characters.create(user_id, character).await.unwrap();
+            let mut player = load_player(&repos, user_id, uuid::Uuid::parse_str(&summary.id).unwrap()).await.unwrap().unwrap();
+            player.position = Position::new(x, 8.0);
+            let (tx, rx) = unbounded_channel();
+            ids.push((world.join(player, tx, true).unwrap(), rx));
+        }
+        let [(alice, _alice_rx), (bob, _bob_rx)] = <[_; 2]>::try_from(ids).unwrap();
+        world.with_player(&alice, |p| p.inventory.add_item(IRON_SWORD.id, 1));
+
+        open_trade(&mut world, &alice, &bob, "bob");
+        world.push_input(&alice, ClientMessage::TradeOffer { items: vec![TradeSlot { slot_index: 0, quantity: 1 }], gold: 30 });
+        world.tick(0.1);
+        for id in [&alice, &bob] {
+            world.push_input(id, ClientMessage::TradeLock);
+        }
+        world.tick(0.1);
+        world.push_input(&alice, ClientMessage::TradeConfirm);
+        world.tick(0.1);
+
+        // A kill reward queues Alice's save, sword and all, just before the
+        // trade completes in the same tick
+        world.reward_kill(&alice, crate::shared::data::monsters::SLIME.id);
+        world.push_input(&bob, ClientMessage::TradeConfirm);
+        world.tick(0.1);
+        let after = [&alice, &bob].map(|id| world.player_state(id).unwrap());
+        assert!(after[0].exp > 0);
+        assert_eq!(after[1].gold, crate::shared::data::characters::defaults::STARTING_GOLD + 30);
+
+        let handle = world.into_handle();
+        crate::server::persistence::flush(&handle, &repos, false).await;
+
+        // Stored as they are in the world: the sword and 30 gold with Bob,
+        // the kill's exp and gold with Alice
+        for (player, swords) in after.iter().zip([0, 1]) {
+            let id = uuid::Uuid::parse_str(&player.id).unwrap();
+            let stored = repos.characters.load(user_id, id).await.unwrap().unwrap();
+            assert_eq!((stored.gold, stored.exp), (player.gold, player.exp));
+            assert_eq!(repos.inventory.load(id).await.unwrap().count_item(IRON_SWORD.id), swords);
+        }
+    }
+
+    #[test]
+    fn test_trade_cancels_on_distance_and_disconnect() {
+        let mut world = World::new();
+        let (alice, mut alice_rx) = join_at(&mut world, 8, 8);
+        let (bob, mut bob_rx) = join_at(&mut world, 9, 8);
+        named(&mut world, &[(&alice, "Alice"), (&bob, "Bob")]);
+
+        open_trade(&mut world, &alice, &bob, "bob");
+        world.teleport(&bob, maps::MILLES_VILLAGE.id, 15, 8);
+        world.tick(0.1);
+        assert_eq!(closed(&drain(&mut alice_rx)), Some((false, "Trade cancelled: you moved too far apart")));
+        assert!(closed(&drain(&mut bob_rx)).is_some());
+
+        world.teleport(&bob, maps::MILLES_VILLAGE.id, 9, 8);
+        open_trade(&mut world, &alice, &bob, "bob");
+        world.leave(&alice);
+        assert_eq!(closed(&drain(&mut bob_rx)), Some((false, "Alice left the trade")));
+        assert!(world.players[bob.as_str()].trade.is_none());
+        assert!(world.take_completed_trades().is_empty());
+
+        // Guests are never saved, so they can't trade
+        let (tx, _rx) = unbounded_channel();
+        let mut guest = Player::new("Guest".to_string(), PlayerClass::Warrior);
+        guest.position = Position::new(10.0, 8.0);
+        world.join(guest, tx, false).unwrap();
+        world.push_input(&bob, ClientMessage::TradeRequest { name: "guest".to_string() });
+        world.tick(0.1);
+        assert_eq!(errors(&drain(&mut bob_rx)), ["Guests cannot trade"]);
+    }
+}
@@ -7,30 +7,47 @@
 #[cfg(feature = "server")]
 #[tokio::main]
 async fn main() {
-    use axum::{
-        routing::{get, post},
-        Router,
-    };
-    use tower_http::cors::{CorsLayer, Any};
+    use axum::Router;
+    use legend_client::server::config::ServerConfig;
+    use std::time::Duration;
     use tower_http::services::ServeDir;
     use tracing_subscriber;
     
     // Initialize logging
     tracing_subscriber::fmt::init();
     
-    // Database connection
-    let database_url = std::env::var("DATABASE_URL")
-        .unwrap_or_else(|_| "postgresql://legend:legend@db:5432/legend".to_string());
+    // Configuration (file + environment); refuse to start on bad values
+    let config = match ServerConfig::load() {
+        Ok(config) => config,
+        Err(e) => {
+            eprintln!("❌ Invalid server configuration: {}", e);
+            std::process::exit(1);
+        }
+    };
+    
+    // Game content from data files, before anything looks definitions up
+    if let Some(dir) = &config.content_dir {
+        match legend_client::shared::data::GameContent::load_dir(dir) {
+            Ok(content) => legend_client::shared::data::install(content),
+            Err(errors) => {
+                for e in errors {
+                    eprintln!("❌ Invalid game content: {}", e);
+                }
+                std::process::exit(1);
+            }
+        }
+        println!("✅ Game content loaded from {}", dir.display());
+    }
     
+    // Database connection
     let pool = sqlx::postgres::PgPoolOptions::new()
-        .max_connections(5)
-        .connect(&database_url)
+        .max_connections(config.database.max_connections)
+        .min_connections(config.database.min_connections)
+        .acquire_timeout(Duration::from_secs(config.database.acquire_timeout_secs))
+        .connect(&config.database.url)
         .await
         .expect("Failed to connect to database");
     
-    // Initialize global DB pool
-    legend_client::server::db::init_db(pool.clone());
-    
     // Run migrations
     sqlx::migrate!("./migrations")
         .run(&pool)
@@ -39,38 +56,55 @@ async fn main() {
     
     println!("✅ Database connected and migrated");
     
-    // CORS configuration
-    let cors = CorsLayer::new()
-        .allow_origin(Any)
-        .allow_methods(Any)
-        .allow_headers(Any);
+    let repos = legend_client::server::repo::Repos::postgres(pool);
+    
+    // Authoritative world simulation
+    let world = legend_client::server::world::World::new().with_chat(config.chat.clone()).into_handle();
+    legend_client::server::world::spawn_tick_loop(world.clone(), config.tick_rate);
+    legend_client::server::persistence::spawn_autosave_loop(world.clone(), repos.clone());
+    
+    // Serialized game data for `/api/data`, refreshed on content edits
+    let data = legend_client::server::data::DataCatalog::build();
+    if let Some(dir) = &config.content_dir
+        && config.content_reload_secs > 0
+        && config.content_reload_limit > 0
+    {
+        let interval = Duration::from_secs(config.content_reload_secs);
+        legend_client::server::data::spawn_content_reload_loop(dir.clone(), interval, config.content_reload_limit, data.clone());
+    }
     
-    // API Router
-    let api_routes = Router::new()
-        .route("/health", get(health_check))
-        .route("/login", post(legend_client::server::auth::login_handler))
-        .route("/register", post(legend_client::server::auth::register_handler))
-        .route("/monsters", get(legend_client::server::monsters::get_monsters))
-        .route("/monsters/{id}", get(legend_client::server::monsters::get_monster_by_id))
-        .route("/skills", get(legend_client::server::skills::get_skills));
+    if config.jwt_secret.is_none() {
+        tracing::warn!("⚠️ Dev mode: signing tokens with the public development secret");
+    }
+    let secret = config.signing_secret().expect("checked by ServerConfig::validate");
+    
+    let state = legend_client::server::app::AppState {
+        repos,
+        // Access token signing keys
+        keys: legend_client::server::session::SessionKeys::new(secret.as_bytes()),
+        // Per-IP login failure counters
+        throttle: legend_client::server::throttle::LoginThrottle::new(config.rate_limits),
+        world,
+        data,
+    };
+    
+    if !config.asset_root.exists() {
+        tracing::warn!("⚠️ Asset root {} does not exist", config.asset_root.display());
+    }
     
     // Main Router
     let app = Router::new()
-        .nest("/api", api_routes)
-        .nest_service("/assets", ServeDir::new("public/assets"))
-        .layer(cors)
-        .layer(axum::Extension(pool));
+        .nest("/api", legend_client::server::app::api_router(state))
+        .nest_service("/assets", ServeDir::new(&config.asset_root))
+        .layer(config.cors_layer());
     
-    let addr = "0.0.0.0:3000";
-    println!("🎮 Legend API Server: http://{}", addr);
+    println!("🎮 Legend API Server: http://{}", config.listen_addr);
     
-    let listener = tokio::net::TcpListener::bind(addr).await.unwrap();
-    axum::serve(listener, app).await.unwrap();
-}
-
-#[cfg(feature = "server")]
-async fn health_check() -> &'static str {
-    "OK"
+    let listener = tokio::net::TcpListener::bind(config.listen_addr).await.unwrap();
+    // Client addresses feed the login throttle
+    axum::serve(listener, app.into_make_service_with_connect_info::<std::net::SocketAddr>())
+        .await
+        .unwrap();
 }
 
 #[cfg(not(feature = "server"))]
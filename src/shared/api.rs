@@ -0,0 +1,519 @@
+//! HTTP API types - request/response bodies shared by client and server
+//!
+//! Every response carries `success` plus an optional `error` so the
+//! client can show a message without inspecting status codes. Endpoints
+//! that fail with a non-2xx status return an `ApiErrorBody` instead.
+
+use serde::{Deserialize, Serialize};
+use crate::shared::data::enhancements::EnhanceOutcome;
+use crate::shared::domain::character::BindPoint;
+use crate::shared::domain::guild::{GuildBank, GuildRank};
+use crate::shared::domain::item::inventory::{EquipSlot, Inventory};
+use crate::shared::domain::skill::models::SkillBook;
+use crate::shared::domain::Player;
+
+/// REST API root (no trailing slash). Web builds are served from the same
+/// origin; native builds can point elsewhere with `LEGEND_API_URL`.
+pub fn api_url() -> String {
+    let default_api = if cfg!(target_arch = "wasm32") { "/api" } else { "http://localhost:3000/api" };
+    std::env::var("LEGEND_API_URL").unwrap_or_else(|_| default_api.to_string())
+}
+
+/// Characters allowed per account
+pub const MAX_CHARACTERS_PER_ACCOUNT: usize = 4;
+
+// ============ Errors ============
+
+/// Stable, machine-readable error codes
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum ApiErrorCode {
+    BadRequest,
+    ValidationFailed,
+    InvalidCredentials,
+    TooManyAttempts,
+    Unauthorized,
+    NotFound,
+    UsernameTaken,
+    NameTaken,
+    GuildNameTaken,
+    /// The character is in the world, so it cannot be changed that way
+    CharacterOnline,
+    /// Authenticated, but the account's role does not allow this
+    Forbidden,
+    /// The account is banned or suspended
+    AccountDisabled,
+    Internal,
+}
+
+/// Body of every non-2xx response
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct ApiErrorBody {
+    pub code: ApiErrorCode,
+    /// Human-readable; safe to show to players
+    pub message: String,
+    /// Seconds until a throttled request may be retried
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub retry_after_secs: Option<u64>,
+    /// Per-field problems for `validation_failed`
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub fields: Vec<FieldError>,
+}
+
+/// One invalid request field
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct FieldError {
+    pub field: String,
+    pub message: String,
+}
+
+// ============ Auth ============
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct LoginRequest {
+    pub username: String,
+    pub password: String,
+}
+
+/// Account login. The token is not bound to a character until one is selected.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct LoginResponse {
+    pub success: bool,
+    /// Bearer token for authenticated endpoints
+    pub token: Option<String>,
+    #[serde(default)]
+    pub characters: Vec<CharacterSummary>,
+    pub error: Option<String>,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct RegisterRequest {
+    pub username: String,
+    pub password: String,
+    pub class_idx: i32,
+    pub gender: String,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct RegisterResponse {
+    pub success: bool,
+    pub message: String,
+}
+
+// ============ Characters ============
+
+/// Roster entry shown on character select
+#[derive(Debug, Clone, Serialize, Deserialize, PartialEq)]
+pub struct CharacterSummary {
+    pub id: String,
+    pub name: String,
+    pub class_id: i32,
+    pub gender: String,
+    pub level: i32,
+    pub current_map: String,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct CharacterListResponse {
+    pub success: bool,
+    #[serde(default)]
+    pub characters: Vec<CharacterSummary>,
+    pub error: Option<String>,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct CreateCharacterRequest {
+    pub name: String,
+    pub class_id: i32,
+    pub gender: String,
+}
+
+/// Result of creating or deleting a character
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct CharacterResponse {
+    pub success: bool,
+    pub character: Option<CharacterSummary>,
+    pub error: Option<String>,
+}
+
+/// Character chosen to play. The new token is bound to that character.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct SelectCharacterResponse {
+    pub success: bool,
+    pub player: Option<Player>,
+    pub token: Option<String>,
+    pub error: Option<String>,
+}
+
+/// Result of an explicit save of the active character
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct SaveCharacterResponse {
+    pub success: bool,
+    pub error: Option<String>,
+}
+
+// ============ Inventory ============
+
+/// Bag and equipment after a change
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct InventoryResponse {
+    pub success: bool,
+    pub inventory: Option<Inventory>,
+    pub error: Option<String>,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct MoveItemRequest {
+    pub from: usize,
+    pub to: usize,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct EquipItemRequest {
+    pub slot_index: usize,
+    pub equip_slot: EquipSlot,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct UnequipItemRequest {
+    pub equip_slot: EquipSlot,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct DiscardItemRequest {
+    pub slot_index: usize,
+    pub quantity: i32,
+}
+
+// ============ Skills ============
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct LearnSkillRequest {
+    pub skill_id: i32,
+}
+
+/// Hotbar slot (0-based) and the skill to put there; `None` clears it
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct AssignSkillRequest {
+    pub slot: usize,
+    pub skill_id: Option<i32>,
+}
+
+/// Learned skills and hotbar after a change
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct SkillBookResponse {
+    pub success: bool,
+    pub skills: Option<SkillBook>,
+    pub error: Option<String>,
+}
+
+// ============ Game Data ============
+
+/// Content hashes of the read-only datasets under `/data`
+///
+/// `version` changes whenever any dataset does; each entry in `datasets`
+/// is that dataset's ETag, so a client only refetches what changed.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct DataVersionResponse {
+    pub version: String,
+    pub datasets: std::collections::BTreeMap<String, String>,
+}
+
+// ============ Guilds ============
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct CreateGuildRequest {
+    pub name: String,
+}
+
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct GuildMemberInfo {
+    pub character_id: String,
+    pub name: String,
+    pub class_id: i32,
+    pub level: i32,
+    pub rank: GuildRank,
+    pub online: bool,
+}
+
+/// The caller's guild; members are ordered leader, officers, members
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct GuildResponse {
+    pub id: String,
+    pub name: String,
+    pub notice: String,
+    pub my_rank: GuildRank,
+    pub members: Vec<GuildMemberInfo>,
+}
+
+/// Invite a character by name
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct GuildInviteRequest {
+    pub name: String,
+}
+
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct GuildInviteInfo {
+    pub guild_id: String,
+    pub guild_name: String,
+    pub invited_by: String,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct GuildInvitesResponse {
+    pub invites: Vec<GuildInviteInfo>,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct GuildSetRankRequest {
+    pub rank: GuildRank,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct GuildNoticeRequest {
+    pub notice: String,
+}
+
+/// Move items from a bag slot into the guild bank
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct GuildDepositRequest {
+    pub slot_index: usize,
+    pub quantity: i32,
+}
+
+/// Move items from a guild bank slot into the bag
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct GuildWithdrawRequest {
+    pub bank_slot: usize,
+    pub quantity: i32,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct GuildGoldRequest {
+    pub amount: i64,
+}
+
+/// The guild bank and the caller's own bag and gold after a change
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct GuildBankResponse {
+    pub bank: GuildBank,
+    pub inventory: Inventory,
+    pub gold: i64,
+}
+
+// ============ Shops ============
+
+/// An item on sale and its price per unit
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct ShopListing {
+    pub item_id: i32,
+    pub price: i64,
+}
+
+/// Buy `quantity` of an item the shop sells
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct ShopBuyRequest {
+    pub item_id: i32,
+    pub quantity: i32,
+}
+
+/// Sell items from a bag slot
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct ShopSellRequest {
+    pub slot_index: usize,
+    pub quantity: i32,
+}
+
+/// A shop's wares and the caller's own bag and gold after a change
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct ShopResponse {
+    pub npc_id: String,
+    pub items: Vec<ShopListing>,
+    pub inventory: Inventory,
+    pub gold: i64,
+}
+
+// ============ Inns ============
+
+/// What resting costs the caller and where they are bound
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct InnResponse {
+    pub npc_id: String,
+    pub rest_cost: i64,
+    pub gold: i64,
+    pub bind_point: Option<BindPoint>,
+    /// The bind point is this inn's
+    pub bound_here: bool,
+}
+
+// ============ Blacksmiths ============
+
+/// What enhancing the item in a bag slot once more costs, and its odds
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct EnhanceOption {
+    pub slot_index: usize,
+    /// Level the item reaches on a success
+    pub level: i32,
+    pub gold_cost: i64,
+    pub material_id: i32,
+    pub material_quantity: i32,
+    pub success_chance: f64,
+    pub destroy_chance: f64,
+}
+
+/// Try to enhance the equipment in a bag slot
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct EnhanceRequest {
+    pub slot_index: usize,
+}
+
+/// The caller's bag, gold and what the blacksmith can do with it; after an
+/// attempt, also how it went
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct BlacksmithResponse {
+    pub npc_id: String,
+    pub options: Vec<EnhanceOption>,
+    pub inventory: Inventory,
+    pub gold: i64,
+    pub outcome: Option<EnhanceOutcome>,
+}
+
+// ============ GM ============
+
+/// Account privilege level; each role can do everything the ones before it can
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum Role {
+    Player,
+    /// Game master: moderation and character tools
+    Gm,
+    /// Can also grant and revoke roles
+    Admin,
+}
+
+impl Role {
+    pub fn key(self) -> &'static str {
+        match self {
+            Self::Player => "player",
+            Self::Gm => "gm",
+            Self::Admin => "admin",
+        }
+    }
+
+    pub fn from_key(key: &str) -> Option<Self> {
+        match key {
+            "player" => Some(Self::Player),
+            "gm" => Some(Self::Gm),
+            "admin" => Some(Self::Admin),
+            _ => None,
+        }
+    }
+}
+
+/// Whether an account may log in
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[serde(tag = "status", rename_all = "snake_case")]
+pub enum AccountStatus {
+    Active,
+    /// Until lifted by a GM
+    Banned,
+    Suspended { until: chrono::DateTime<chrono::Utc> },
+}
+
+/// An account as GMs see it
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct GmAccountResponse {
+    pub id: String,
+    pub username: String,
+    pub role: Role,
+    #[serde(flatten)]
+    pub status: AccountStatus,
+}
+
+/// Full state of a character and who owns it
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct GmCharacterResponse {
+    pub owner: GmAccountResponse,
+    /// In the world right now; `character` is the live copy
+    pub online: bool,
+    pub character: Player,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct GmTeleportRequest {
+    pub map_id: String,
+    pub x: i32,
+    pub y: i32,
+}
+
+/// Everything is added to what the character already has
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+pub struct GmGrantRequest {
+    #[serde(default)]
+    pub items: Vec<crate::shared::data::monsters::ItemGrant>,
+    #[serde(default)]
+    pub gold: i64,
+    #[serde(default)]
+    pub exp: i64,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct GmSetLevelRequest {
+    pub level: i32,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct GmBanRequest {
+    pub reason: String,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct GmSuspendRequest {
+    pub duration_secs: u64,
+    pub reason: String,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct GmSetRoleRequest {
+    pub role: Role,
+}
+
+/// One recorded GM action; `details` is the request that caused it
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct AuditEntry {
+    pub id: i64,
+    pub gm_user_id: String,
+    pub action: String,
+    pub target_user_id: Option<String>,
+    pub target_character_id: Option<String>,
+    pub details: String,
+    pub created_at: chrono::DateTime<chrono::Utc>,
+}
+
+/// Newest first
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct GmAuditResponse {
+    pub entries: Vec<AuditEntry>,
+}
+
+/// One logged chat message; `message` is what the sender typed
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct ChatLogEntry {
+    pub id: i64,
+    pub channel: crate::shared::protocol::ChatChannel,
+    /// None for guests
+    pub sender_character_id: Option<String>,
+    pub sender_name: String,
+    pub recipient_name: Option<String>,
+    pub map_id: String,
+    pub message: String,
+    /// The word filter changed what others saw
+    pub filtered: bool,
+    pub sent_at: chrono::DateTime<chrono::Utc>,
+}
+
+/// Newest first
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct GmChatLogResponse {
+    pub entries: Vec<ChatLogEntry>,
+}
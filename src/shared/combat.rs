@@ -0,0 +1,220 @@
+//! Combat resolution - shared by the server simulation and offline play
+//!
+//! An attack rolls to hit (attacker `hit_rate` against defender
+//! `avoid_rate`), then to crit (`critical_rate`), then its damage is
+//! reduced by the defender's `defense` or `magic_defense`. The RNG is
+//! passed in so tests can use a fixed or seeded generator.
+
+use rand::Rng;
+use serde::{Deserialize, Serialize};
+
+use crate::shared::data::skills::SkillDef;
+use crate::shared::domain::monster::Monster;
+use crate::shared::domain::shared::models::CombatStats;
+
+/// Hit chance bounds (%), so every attack can land or miss
+pub const MIN_HIT_CHANCE: i32 = 5;
+pub const MAX_HIT_CHANCE: i32 = 95;
+
+/// Critical hits deal this many percent of normal damage
+pub const CRITICAL_DAMAGE_PERCENT: i32 = 150;
+
+/// Monster accuracy and evasion, which monster data does not define
+const MONSTER_BASE_HIT: i32 = 80;
+const MONSTER_BASE_AVOID: i32 = 5;
+const MONSTER_CRITICAL_RATE: i32 = 5;
+
+/// Damage type; decides which defense applies
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum AttackKind {
+    Physical,
+    Magical,
+}
+
+/// Result of the hit roll
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum HitOutcome {
+    Miss,
+    #[default]
+    Hit,
+    Critical,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct AttackResult {
+    pub outcome: HitOutcome,
+    /// Damage after mitigation (0 on a miss)
+    pub damage: i32,
+}
+
+/// The numbers combat needs from either side of an attack
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct Combatant {
+    pub attack_min: i32,
+    pub attack_max: i32,
+    pub magic_attack: i32,
+    pub defense: i32,
+    pub magic_defense: i32,
+    pub hit_rate: i32,
+    pub avoid_rate: i32,
+    pub critical_rate: i32,
+}
+
+impl From<&CombatStats> for Combatant {
+    fn from(stats: &CombatStats) -> Self {
+        Self {
+            attack_min: stats.attack_min,
+            attack_max: stats.attack_max,
+            magic_attack: stats.magic_attack,
+            defense: stats.defense,
+            magic_defense: stats.magic_defense,
+            hit_rate: stats.hit_rate,
+            avoid_rate: stats.avoid_rate,
+            critical_rate: stats.critical_rate,
+        }
+    }
+}
+
+impl From<&Monster> for Combatant {
+    fn from(monster: &Monster) -> Self {
+        Self {
+            attack_min: monster.attack_min,
+            attack_max: monster.attack_max,
+            magic_attack: monster.attack_max,
+            defense: monster.defense,
+            magic_defense: monster.defense / 2,
+            hit_rate: MONSTER_BASE_HIT + monster.level,
+            avoid_rate: MONSTER_BASE_AVOID + monster.level / 2,
+            critical_rate: MONSTER_CRITICAL_RATE,
+        }
+    }
+}
+
+/// Mage and cleric skills deal magic damage; everything else is physical
+pub fn skill_attack_kind(skill: &SkillDef) -> AttackKind {
+    match skill.class_id {
+        Some(3) | Some(4) => AttackKind::Magical,
+        _ => AttackKind::Physical,
+    }
+}
+
+/// Chance (%) that an attack lands
+pub fn hit_chance(attacker: &Combatant, defender: &Combatant, kind: AttackKind) -> i32 {
+    // Spells are harder to dodge than blows
+    let avoid = match kind {
+        AttackKind::Physical => defender.avoid_rate,
+        AttackKind::Magical => defender.avoid_rate / 2,
+    };
+    (attacker.hit_rate - avoid).clamp(MIN_HIT_CHANCE, MAX_HIT_CHANCE)
+}
+
+/// Resolve one attack.
+///
+/// `skill_power` is a skill's `base_value`; skills add it to half of the
+/// attacker's rolled power. Plain attacks pass `None`.
+pub fn resolve_attack<R: Rng + ?Sized>(
+    rng: &mut R,
+    attacker: &Combatant,
+    defender: &Combatant,
+    kind: AttackKind,
+    skill_power: Option<i32>,
+) -> AttackResult {
+    if rng.gen_range(0..100) >= hit_chance(attacker, defender, kind) {
+        return AttackResult { outcome: HitOutcome::Miss, damage: 0 };
+    }
+
+    let (power, defense) = match kind {
+        AttackKind::Physical => {
+            let roll = rng.gen_range(attacker.attack_min..=attacker.attack_max.max(attacker.attack_min));
+            (roll, defender.defense)
+        }
+        AttackKind::Magical => (attacker.magic_attack, defender.magic_defense),
+    };
+    let mut damage = match skill_power {
+        Some(base) => base + power / 2,
+        None => power,
+    };
+
+    let critical = rng.gen_range(0..100) < attacker.critical_rate.clamp(0, 100);
+    if critical {
+        damage = damage * CRITICAL_DAMAGE_PERCENT / 100;
+    }
+
+    AttackResult {
+        outcome: if critical { HitOutcome::Critical } else { HitOutcome::Hit },
+        damage: (damage - defense / 2).max(1),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use rand::rngs::mock::StepRng;
+    use rand::rngs::StdRng;
+    use rand::SeedableRng;
+
+    fn combatant(hit_rate: i32, avoid_rate: i32, critical_rate: i32, defense: i32) -> Combatant {
+        Combatant {
+            attack_min: 10,
+            attack_max: 10,
+            magic_attack: 20,
+            defense,
+            magic_defense: defense,
+            hit_rate,
+            avoid_rate,
+            critical_rate,
+        }
+    }
+
+    #[test]
+    fn test_hit_chance_is_clamped() {
+        let sharp = combatant(500, 0, 0, 0);
+        let clumsy = combatant(0, 0, 0, 0);
+        let nimble = combatant(0, 500, 0, 0);
+        assert_eq!(hit_chance(&sharp, &clumsy, AttackKind::Physical), MAX_HIT_CHANCE);
+        assert_eq!(hit_chance(&clumsy, &nimble, AttackKind::Physical), MIN_HIT_CHANCE);
+    }
+
+    #[test]
+    fn test_low_rolls_hit_and_crit_with_mitigation() {
+        // A zero RNG rolls 0 every time: always under the hit and crit chances
+        let mut rng = StepRng::new(0, 0);
+        let attacker = combatant(90, 0, 10, 0);
+        let defender = combatant(0, 0, 0, 8);
+
+        let physical = resolve_attack(&mut rng, &attacker, &defender, AttackKind::Physical, None);
+        assert_eq!(physical, AttackResult { outcome: HitOutcome::Critical, damage: 15 - 4 });
+
+        let spell = resolve_attack(&mut rng, &attacker, &defender, AttackKind::Magical, Some(30));
+        assert_eq!(spell, AttackResult { outcome: HitOutcome::Critical, damage: (30 + 10) * 3 / 2 - 4 });
+    }
+
+    #[test]
+    fn test_high_rolls_miss() {
+        // 95% of the u32 range: every d100 roll comes out as 95
+        let mut rng = StepRng::new(4_080_218_932, 0);
+        let result = resolve_attack(&mut rng, &combatant(90, 0, 0, 0), &combatant(0, 0, 0, 0), AttackKind::Physical, None);
+        assert_eq!(result, AttackResult { outcome: HitOutcome::Miss, damage: 0 });
+    }
+
+    #[test]
+    fn test_seeded_rng_is_deterministic_and_respects_rates() {
+        let attacker = combatant(60, 0, 20, 0);
+        let defender = combatant(0, 10, 0, 100);
+        let run = |seed| {
+            let mut rng = StdRng::seed_from_u64(seed);
+            (0..1000)
+                .map(|_| resolve_attack(&mut rng, &attacker, &defender, AttackKind::Physical, None))
+                .collect::<Vec<_>>()
+        };
+
+        let results = run(7);
+        assert_eq!(results, run(7));
+
+        let hits = results.iter().filter(|r| r.outcome != HitOutcome::Miss).count();
+        assert!((400..600).contains(&hits), "hits: {}", hits);
+        // Defense far above the attack still leaves 1 damage
+        assert!(results.iter().filter(|r| r.outcome == HitOutcome::Hit).all(|r| r.damage == 1));
+    }
+}
@@ -2,10 +2,13 @@
 //!
 //! All character class definitions and sprite configurations.
 
+use serde::Serialize;
+
 use crate::shared::domain::shared::models::Stats;
 
 /// Player class definition
-#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize)]
+#[serde(into = "i32")]
 pub enum ClassId {
     Warrior = 1,
     Rogue = 2,
@@ -14,8 +17,14 @@ pub enum ClassId {
     MartialArtist = 5,
 }
 
+impl From<ClassId> for i32 {
+    fn from(id: ClassId) -> Self {
+        id as i32
+    }
+}
+
 /// Class definition with all metadata
-#[derive(Debug, Clone)]
+#[derive(Debug, Clone, Serialize)]
 pub struct ClassDef {
     pub id: ClassId,
     pub name: &'static str,
@@ -273,7 +282,7 @@ pub fn get_character_sprite(class_name: &str, gender: &str) -> Option<&'static C
 pub const MAX_LEVEL: i32 = 99;
 
 /// Experience entry for a level
-#[derive(Debug, Clone, Copy)]
+#[derive(Debug, Clone, Copy, Serialize)]
 pub struct ExpEntry {
     pub level: i32,
     pub exp_to_next: i64,      // 다음 레벨까지 필요한 경험치
@@ -386,7 +395,7 @@ pub const EXP_TABLE: [ExpEntry; 99] = [
 
 /// Get exp entry for a specific level (1-99)
 pub fn get_exp_entry(level: i32) -> Option<&'static ExpEntry> {
-    if level >= 1 && level <= 99 {
+    if (1..=99).contains(&level) {
         Some(&EXP_TABLE[(level - 1) as usize])
     } else {
         None
@@ -442,7 +451,7 @@ pub fn get_level_up_reward(class_id: i32, new_level: i32) -> LevelUpReward {
         None => (20, 10),
     };
     
-    let skill_unlock = crate::shared::data::skills::ALL_SKILLS.iter()
+    let skill_unlock = crate::shared::data::skills::all_skills().iter()
         .find(|s| s.class_id == Some(class_id) && s.req_level == new_level)
         .map(|s| s.id);
     
@@ -460,9 +469,10 @@ pub fn get_level_up_reward(class_id: i32, new_level: i32) -> LevelUpReward {
 
 pub mod defaults {
     pub const STARTING_GOLD: i64 = 100;
-    pub const STARTING_MAP: &str = "village_milles";
-    pub const STARTING_X: f64 = 400.0;
-    pub const STARTING_Y: f64 = 300.0;
+    pub const STARTING_MAP: &str = "milles_village";
+    /// Starting grid tile (see `maps::MILLES_VILLAGE_START`)
+    pub const STARTING_X: f64 = 8.0;
+    pub const STARTING_Y: f64 = 8.0;
     pub const INVENTORY_SLOTS: usize = 24;
     pub const BASE_ATTACK_COOLDOWN: f64 = 1000.0;
     pub const STAT_POINTS_PER_LEVEL: i32 = 2;
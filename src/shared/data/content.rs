@@ -0,0 +1,327 @@
+//! Data-driven game content
+//!
+//! The `const` definitions in the sibling modules are the built-in
+//! content. A content directory can replace whole sections with a
+//! `<section>.ron` or `<section>.json` file holding a list of entries;
+//! sections without a file keep the built-in data. Lookups such as
+//! `get_monster_by_id` read whatever content is active, which the server
+//! swaps when the files change.
+
+use std::collections::HashSet;
+use std::fmt::Display;
+use std::hash::Hash;
+use std::path::{Path, PathBuf};
+use std::sync::{LazyLock, RwLock};
+
+use serde::{Deserialize, Serialize};
+
+use super::characters::MAX_LEVEL;
+use super::enhancements::{EnhancementDef, ENHANCEMENTS};
+use super::inns::{InnDef, INNS};
+use super::items::{ItemDef, ALL_ITEMS};
+use super::maps::{MapDef, ALL_MAPS};
+use super::monsters::{LootDropDef, MonsterDef, ALL_MONSTERS, MONSTER_DROPS};
+use super::shops::{ShopItemDef, SHOP_ITEMS};
+use super::skills::{SkillDef, ALL_SKILLS};
+
+/// File stems a content directory may contain
+pub const SECTIONS: &[&str] = &["monsters", "drops", "skills", "items", "maps", "shops", "enhancements", "inns"];
+
+/// Supported data file formats, in lookup order
+const EXTENSIONS: &[&str] = &["ron", "json"];
+
+#[derive(Debug, thiserror::Error)]
+pub enum ContentError {
+    #[error("cannot read {path}: {source}")]
+    Read { path: PathBuf, source: std::io::Error },
+    #[error("cannot parse {path}: {message}")]
+    Parse { path: PathBuf, message: String },
+    #[error("{section} has both .ron and .json files")]
+    Ambiguous { section: &'static str },
+    #[error("{section} {id}: {reason}")]
+    Invalid { section: &'static str, id: String, reason: String },
+}
+
+/// Every data-driven definition
+#[derive(Debug, Clone, Serialize)]
+pub struct GameContent {
+    pub monsters: Vec<MonsterDef>,
+    pub drops: Vec<LootDropDef>,
+    pub skills: Vec<SkillDef>,
+    pub items: Vec<ItemDef>,
+    pub maps: Vec<MapDef>,
+    pub shops: Vec<ShopItemDef>,
+    pub enhancements: Vec<EnhancementDef>,
+    pub inns: Vec<InnDef>,
+}
+
+static BUILTIN: LazyLock<GameContent> = LazyLock::new(GameContent::builtin);
+
+/// Loaded content; `None` until something is installed
+static ACTIVE: RwLock<Option<&'static GameContent>> = RwLock::new(None);
+
+/// The content lookups currently read
+pub fn content() -> &'static GameContent {
+    ACTIVE.read().unwrap().unwrap_or(&BUILTIN)
+}
+
+/// Make `content` the active content for every lookup
+///
+/// Earlier content is never freed because callers may still hold
+/// `&'static` references into it; a reload leaks one copy of the data,
+/// which is why the server caps its reloads (`content_reload_limit`).
+pub fn install(content: GameContent) {
+    *ACTIVE.write().unwrap() = Some(Box::leak(Box::new(content)));
+}
+
+impl GameContent {
+    /// The compiled-in definitions
+    pub fn builtin() -> Self {
+        Self {
+            monsters: ALL_MONSTERS.iter().map(|m| (*m).clone()).collect(),
+            drops: MONSTER_DROPS.to_vec(),
+            skills: ALL_SKILLS.iter().map(|s| (*s).clone()).collect(),
+            items: ALL_ITEMS.iter().map(|i| (*i).clone()).collect(),
+            maps: ALL_MAPS.iter().map(|m| (*m).clone()).collect(),
+            shops: SHOP_ITEMS.to_vec(),
+            enhancements: ENHANCEMENTS.to_vec(),
+            inns: INNS.to_vec(),
+        }
+    }
+
+    /// Built-in content with each section present in `dir` replaced, then
+    /// validated. Every problem found is returned, not just the first.
+    pub fn load_dir(dir: &Path) -> Result<Self, Vec<ContentError>> {
+        let mut content = Self::builtin();
+        let mut errors = Vec::new();
+        if let Some(monsters) = read_section(dir, "monsters", &mut errors) {
+            content.monsters = monsters;
+        }
+        if let Some(drops) = read_section(dir, "drops", &mut errors) {
+            content.drops = drops;
+        }
+        if let Some(skills) = read_section(dir, "skills", &mut errors) {
+            content.skills = skills;
+        }
+        if let Some(items) = read_section(dir, "items", &mut errors) {
+            content.items = items;
+        }
+        if let Some(maps) = read_section(dir, "maps", &mut errors) {
+            content.maps = maps;
+        }
+        if let Some(shops) = read_section(dir, "shops", &mut errors) {
+            content.shops = shops;
+        }
+        if let Some(enhancements) = read_section(dir, "enhancements", &mut errors) {
+            content.enhancements = enhancements;
+        }
+        if let Some(inns) = read_section(dir, "inns", &mut errors) {
+            content.inns = inns;
+        }
+        if errors.is_empty() { content.validated() } else { Err(errors) }
+    }
+
+    /// `self` if `validate` finds nothing wrong
+    pub fn validated(self) -> Result<Self, Vec<ContentError>> {
+        let errors = self.validate();
+        if errors.is_empty() { Ok(self) } else { Err(errors) }
+    }
+
+    /// Check ids are unique, values are within their allowed ranges and
+    /// references resolve (see `integrity`)
+    pub fn validate(&self) -> Vec<ContentError> {
+        let mut errors = Vec::new();
+        let levels = 1..=MAX_LEVEL;
+        let circles = 1..=5;
+
+        unique_ids("monsters", self.monsters.iter().map(|m| m.id), &mut errors);
+        for m in &self.monsters {
+            let mut check = |ok: bool, reason: &str| invalid(&mut errors, "monsters", m.id, ok, reason);
+            check(circles.contains(&m.circle), "circle must be 1-5");
+            check(levels.contains(&m.level), "level must be 1-99");
+            check(m.hp_max > 0, "hp_max must be positive");
+            check(m.mp_max >= 0, "mp_max must not be negative");
+            check(0 <= m.attack_min && m.attack_min <= m.attack_max, "attack_min must be between 0 and attack_max");
+            check(m.defense >= 0, "defense must not be negative");
+            check(m.exp_reward >= 0, "exp_reward must not be negative");
+            check(0 <= m.gold_min && m.gold_min <= m.gold_max, "gold_min must be between 0 and gold_max");
+            check(m.detection_range >= 0.0 && m.attack_range > 0.0 && m.move_speed > 0.0, "ranges and move_speed must be positive");
+        }
+
+        for d in &self.drops {
+            let id = format!("{}->{}", d.monster_id, d.item_id);
+            let mut check = |ok: bool, reason: &str| invalid(&mut errors, "drops", &id, ok, reason);
+            check((0.0..=1.0).contains(&d.probability), "probability must be between 0 and 1");
+            check(1 <= d.min_quantity && d.min_quantity <= d.max_quantity, "min_quantity must be between 1 and max_quantity");
+        }
+
+        unique_ids("skills", self.skills.iter().map(|s| s.id), &mut errors);
+        for s in &self.skills {
+            let mut check = |ok: bool, reason: &str| invalid(&mut errors, "skills", s.id, ok, reason);
+            check(circles.contains(&s.circle), "circle must be 1-5");
+            check(levels.contains(&s.req_level), "req_level must be 1-99");
+            check(s.mp_cost >= 0 && s.cooldown_ms >= 0, "mp_cost and cooldown_ms must not be negative");
+        }
+
+        unique_ids("items", self.items.iter().map(|i| i.id), &mut errors);
+        for i in &self.items {
+            let mut check = |ok: bool, reason: &str| invalid(&mut errors, "items", i.id, ok, reason);
+            check((1..=12).contains(&i.grade), "grade must be 1-12");
+            check(levels.contains(&i.req_level), "req_level must be 1-99");
+            check(i.price_buy >= 0 && i.price_sell >= 0, "prices must not be negative");
+            check(if i.stackable { i.max_stack >= 1 } else { i.max_stack == 1 }, "max_stack must be 1, or at least 1 if stackable");
+        }
+
+        unique_ids("maps", self.maps.iter().map(|m| m.id), &mut errors);
+        for m in &self.maps {
+            let mut check = |ok: bool, reason: &str| invalid(&mut errors, "maps", m.id, ok, reason);
+            check(!m.id.is_empty(), "id must not be empty");
+            check(circles.contains(&m.circle), "circle must be 1-5");
+            check(m.width > 0 && m.height > 0, "width and height must be positive");
+            check(levels.contains(&m.min_level) && m.min_level <= m.max_level, "min_level must be between 1 and max_level");
+        }
+
+        unique_ids("shops", self.shops.iter().map(|s| format!("{}->{}", s.npc_id, s.item_id)), &mut errors);
+
+        unique_ids("enhancements", self.enhancements.iter().map(|e| e.level), &mut errors);
+        for e in &self.enhancements {
+            let mut check = |ok: bool, reason: &str| invalid(&mut errors, "enhancements", e.level, ok, reason);
+            // Levels must run 1, 2, 3... so every level below the top can be enhanced
+            check(1 <= e.level && e.level as usize <= self.enhancements.len(), "levels must run from 1 without gaps");
+            check(e.gold_cost >= 0 && e.material_quantity >= 0, "costs must not be negative");
+            check(e.success_chance >= 0.0 && e.destroy_chance >= 0.0, "chances must not be negative");
+            check(e.success_chance + e.destroy_chance <= 1.0, "success_chance + destroy_chance must not exceed 1");
+            check(e.stat_bonus_pct >= 0, "stat_bonus_pct must not be negative");
+        }
+
+        unique_ids("inns", self.inns.iter().map(|i| i.npc_id), &mut errors);
+        for i in &self.inns {
+            invalid(&mut errors, "inns", i.npc_id, i.rest_cost >= 0 && i.novice_rest_cost >= 0, "rest costs must not be negative");
+        }
+
+        errors.extend(super::integrity::check_references(self));
+        errors
+    }
+}
+
+fn invalid(errors: &mut Vec<ContentError>, section: &'static str, id: impl Display, ok: bool, reason: &str) {
+    if !ok {
+        errors.push(ContentError::Invalid { section, id: id.to_string(), reason: reason.to_string() });
+    }
+}
+
+fn unique_ids<T: Hash + Eq + Display>(section: &'static str, ids: impl Iterator<Item = T>, errors: &mut Vec<ContentError>) {
+    let mut seen = HashSet::new();
+    for id in ids {
+        if seen.contains(&id) {
+            errors.push(ContentError::Invalid { section, id: id.to_string(), reason: "duplicate id".to_string() });
+        } else {
+            seen.insert(id);
+        }
+    }
+}
+
+/// Data files for one section that exist in `dir`
+pub fn section_files(dir: &Path, section: &str) -> Vec<PathBuf> {
+    EXTENSIONS.iter()
+        .map(|ext| dir.join(format!("{}.{}", section, ext)))
+        .filter(|path| path.is_file())
+        .collect()
+}
+
+/// Parse a section file, or `None` if the section has none (or it failed)
+///
+/// Definitions borrow their strings from the file text, so it is leaked
+/// along with the content.
+fn read_section<T: Deserialize<'static>>(dir: &Path, section: &'static str, errors: &mut Vec<ContentError>) -> Option<Vec<T>> {
+    let path = match section_files(dir, section).as_slice() {
+        [] => return None,
+        [path] => path.clone(),
+        _ => {
+            errors.push(ContentError::Ambiguous { section });
+            return None;
+        }
+    };
+    let text = match std::fs::read_to_string(&path) {
+        Ok(text) => &*Box::leak(text.into_boxed_str()),
+        Err(source) => {
+            errors.push(ContentError::Read { path, source });
+            return None;
+        }
+    };
+    let parsed = if path.extension().is_some_and(|ext| ext == "json") {
+        serde_json::from_str(text).map_err(|e| e.to_string())
+    } else {
+        ron::from_str(text).map_err(|e| e.to_string())
+    };
+    parsed.map_err(|message| errors.push(ContentError::Parse { path, message })).ok()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn content_dir(files: &[(&str, &str)]) -> PathBuf {
+        let dir = std::env::temp_dir().join(format!("legend-content-{}", uuid::Uuid::new_v4()));
+        std::fs::create_dir_all(&dir).unwrap();
+        for (name, text) in files {
+            std::fs::write(dir.join(name), text).unwrap();
+        }
+        dir
+    }
+
+    #[test]
+    fn test_builtin_content_is_valid() {
+        let errors = GameContent::builtin().validate();
+        assert!(errors.is_empty(), "{:?}", errors);
+    }
+
+    #[test]
+    fn test_builtin_sections_round_trip() {
+        // A dump of the built-in data is a valid starting point for designers
+        let builtin = GameContent::builtin();
+        let dir = content_dir(&[
+            ("monsters.ron", &ron::to_string(&builtin.monsters).unwrap()),
+            ("skills.ron", &ron::to_string(&builtin.skills).unwrap()),
+            ("items.json", &serde_json::to_string(&builtin.items).unwrap()),
+            ("inns.ron", &ron::to_string(&builtin.inns).unwrap()),
+        ]);
+        let loaded = GameContent::load_dir(&dir).unwrap();
+        assert_eq!(loaded.monsters.len(), builtin.monsters.len());
+        assert_eq!(loaded.skills[0].target, builtin.skills[0].target);
+        assert_eq!(loaded.items.last().unwrap().equipment_sprite, builtin.items.last().unwrap().equipment_sprite);
+        assert_eq!(loaded.inns[0].map_id, builtin.inns[0].map_id);
+    }
+
+    #[test]
+    fn test_sections_override_builtin() {
+        let drops = r#"[(monster_id: 101, item_id: 1, probability: 0.5, min_quantity: 1, max_quantity: 2)]"#;
+        let mut maps = GameContent::builtin().maps;
+        maps[0].name = "Milles";
+        let maps = serde_json::to_string(&maps).unwrap();
+        let dir = content_dir(&[("drops.ron", drops), ("maps.json", &maps)]);
+
+        let content = GameContent::load_dir(&dir).unwrap();
+        assert_eq!(content.drops.len(), 1);
+        assert_eq!(content.drops[0].probability, 0.5);
+        assert_eq!(content.maps[0].name, "Milles");
+        assert_eq!(content.monsters.len(), ALL_MONSTERS.len());
+    }
+
+    #[test]
+    fn test_load_reports_every_problem() {
+        let drops = r#"[
+            (monster_id: 101, item_id: 1, probability: 1.5, min_quantity: 1, max_quantity: 1),
+            (monster_id: 102, item_id: 1, probability: 0.1, min_quantity: 3, max_quantity: 1),
+        ]"#;
+        let dir = content_dir(&[("drops.ron", drops)]);
+        let errors = GameContent::load_dir(&dir).unwrap_err();
+        assert_eq!(errors.len(), 2, "{:?}", errors);
+
+        // Unknown fields are schema errors, not silently ignored
+        let dir = content_dir(&[("drops.ron", "[(monster_id: 101, item: 1)]"), ("items.ron", "[]"), ("items.json", "[]")]);
+        let errors = GameContent::load_dir(&dir).unwrap_err();
+        assert!(errors.iter().any(|e| matches!(e, ContentError::Parse { .. })));
+        assert!(errors.iter().any(|e| matches!(e, ContentError::Ambiguous { section: "items" })));
+    }
+}
@@ -0,0 +1,150 @@
+//! Equipment enhancement data
+//!
+//! Blacksmiths (`NpcType::Blacksmith`) raise a piece of equipment one
+//! level at a time. Each entry prices the attempt that reaches `level` and
+//! gives its odds: the item gains the level on a success, is destroyed on
+//! a destroy roll, and otherwise stays as it was. The gold and materials
+//! are spent whatever the outcome. An enhanced item's stats grow by the
+//! `stat_bonus_pct` of its level (see `enhanced_stats`).
+
+use rand::Rng;
+use serde::{Deserialize, Serialize};
+
+use super::items::ItemStats;
+
+/// Material every built-in enhancement consumes
+pub const ENHANCEMENT_STONE_ID: i32 = 500;
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(deny_unknown_fields)]
+pub struct EnhancementDef {
+    /// Level the item reaches on a success
+    pub level: i32,
+    pub gold_cost: i64,
+    pub material_id: i32,
+    pub material_quantity: i32,
+    /// Chance (0-1) of reaching `level`
+    pub success_chance: f64,
+    /// Chance (0-1) of losing the item; the rest of the odds change nothing
+    pub destroy_chance: f64,
+    /// Percent added to each of the item's stats at `level`
+    pub stat_bonus_pct: i32,
+}
+
+const fn step(level: i32, gold_cost: i64, stones: i32, success_chance: f64, destroy_chance: f64, stat_bonus_pct: i32) -> EnhancementDef {
+    EnhancementDef {
+        level,
+        gold_cost,
+        material_id: ENHANCEMENT_STONE_ID,
+        material_quantity: stones,
+        success_chance,
+        destroy_chance,
+        stat_bonus_pct,
+    }
+}
+
+pub const ENHANCEMENTS: &[EnhancementDef] = &[
+    step(1, 100, 1, 1.0, 0.0, 5),
+    step(2, 200, 1, 0.95, 0.0, 10),
+    step(3, 400, 1, 0.9, 0.0, 15),
+    step(4, 700, 2, 0.8, 0.0, 22),
+    step(5, 1_000, 2, 0.7, 0.0, 30),
+    // From +6 on, a bad roll can cost the item
+    step(6, 1_500, 3, 0.6, 0.05, 40),
+    step(7, 2_200, 3, 0.5, 0.1, 50),
+    step(8, 3_000, 4, 0.4, 0.15, 62),
+    step(9, 4_000, 5, 0.3, 0.2, 76),
+    step(10, 5_500, 6, 0.2, 0.25, 100),
+];
+
+/// What an enhancement attempt did to the item
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum EnhanceOutcome {
+    Success,
+    Failure,
+    Destroyed,
+}
+
+/// The attempt that takes an item from `level` to the next, if there is one
+pub fn next_enhancement(level: i32) -> Option<&'static EnhancementDef> {
+    super::content::content().enhancements.iter().find(|e| e.level == level + 1)
+}
+
+/// Highest level blacksmiths can reach
+pub fn max_enhancement() -> i32 {
+    super::content::content().enhancements.iter().map(|e| e.level).max().unwrap_or(0)
+}
+
+/// Percent stat bonus of an item enhanced to `level`
+pub fn stat_bonus_pct(level: i32) -> i32 {
+    super::content::content().enhancements.iter()
+        .filter(|e| e.level <= level)
+        .max_by_key(|e| e.level)
+        .map_or(0, |e| e.stat_bonus_pct)
+}
+
+/// `stats` of an item enhanced to `level`; each bonus rounds up so every
+/// level adds something to small stats. Potion heals are not scaled.
+pub fn enhanced_stats(stats: &ItemStats, level: i32) -> ItemStats {
+    let pct = stat_bonus_pct(level);
+    let scale = |stat: i32| if stat > 0 { stat + (stat * pct + 99) / 100 } else { stat };
+    ItemStats {
+        attack: scale(stats.attack),
+        defense: scale(stats.defense),
+        magic_attack: scale(stats.magic_attack),
+        magic_defense: scale(stats.magic_defense),
+        hp: scale(stats.hp),
+        mp: scale(stats.mp),
+        str_stat: scale(stats.str_stat),
+        dex_stat: scale(stats.dex_stat),
+        int_stat: scale(stats.int_stat),
+        con_stat: scale(stats.con_stat),
+        wis_stat: scale(stats.wis_stat),
+        ..stats.clone()
+    }
+}
+
+/// Roll an enhancement attempt
+pub fn roll_enhancement<R: Rng + ?Sized>(rng: &mut R, def: &EnhancementDef) -> EnhanceOutcome {
+    let roll = rng.gen_range(0.0..1.0);
+    if roll < def.success_chance {
+        EnhanceOutcome::Success
+    } else if roll < def.success_chance + def.destroy_chance {
+        EnhanceOutcome::Destroyed
+    } else {
+        EnhanceOutcome::Failure
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use rand::rngs::mock::StepRng;
+
+    #[test]
+    fn test_enhanced_stats_scale_with_level() {
+        let stats = ItemStats { attack: 15, defense: 0, heal_hp: 50, ..ItemStats::ZERO };
+        assert_eq!(enhanced_stats(&stats, 0).attack, 15);
+        // 5% of 15 rounds up to 1
+        assert_eq!(enhanced_stats(&stats, 1).attack, 16);
+        assert_eq!(enhanced_stats(&stats, 7).attack, 15 + 8);
+        assert_eq!(enhanced_stats(&stats, 10).attack, 30);
+        // Levels past the table keep the last bonus
+        assert_eq!(enhanced_stats(&stats, 12).attack, 30);
+        assert_eq!(enhanced_stats(&stats, 10).defense, 0);
+        assert_eq!(enhanced_stats(&stats, 10).heal_hp, 50);
+    }
+
+    #[test]
+    fn test_roll_splits_the_odds() {
+        let def = next_enhancement(6).unwrap();
+        assert_eq!(def.level, 7);
+        // StepRng yields raw integers; the f64 is built from the high bits
+        let roll_at = |fraction: f64| roll_enhancement(&mut StepRng::new((fraction * u64::MAX as f64) as u64, 0), def);
+        assert_eq!(roll_at(0.1), EnhanceOutcome::Success);
+        assert_eq!(roll_at(0.55), EnhanceOutcome::Destroyed);
+        assert_eq!(roll_at(0.9), EnhanceOutcome::Failure);
+        assert!(next_enhancement(max_enhancement()).is_none());
+    }
+}
@@ -0,0 +1,49 @@
+//! Inn data constants
+//!
+//! An inn is kept by an `NpcType::Inn` NPC. Resting there restores HP and
+//! MP in full for a fee, which characters up to `NOVICE_MAX_LEVEL` pay at
+//! the novice rate. Binding at an inn makes a tile in its town the place
+//! the character comes back to after dying.
+
+use serde::{Deserialize, Serialize};
+
+use super::maps::{MILLES_VILLAGE, MILLES_VILLAGE_START};
+
+/// Highest level that still rests at the novice rate
+pub const NOVICE_MAX_LEVEL: i32 = 10;
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(deny_unknown_fields)]
+pub struct InnDef {
+    pub npc_id: &'static str,
+    /// Map the innkeeper stands on, and the one bound characters return to
+    pub map_id: &'static str,
+    pub rest_cost: i64,
+    pub novice_rest_cost: i64,
+    /// Tile on `map_id` characters bound here return to
+    pub bind_x: i32,
+    pub bind_y: i32,
+}
+
+impl InnDef {
+    /// What a character of `level` pays to rest
+    pub fn rest_cost(&self, level: i32) -> i64 {
+        if level <= NOVICE_MAX_LEVEL { self.novice_rest_cost } else { self.rest_cost }
+    }
+}
+
+pub const INNS: &[InnDef] = &[
+    // Milles tavern; bound characters return to the village square
+    InnDef {
+        npc_id: "innkeeper",
+        map_id: MILLES_VILLAGE.id,
+        rest_cost: 50,
+        novice_rest_cost: 10,
+        bind_x: MILLES_VILLAGE_START.0,
+        bind_y: MILLES_VILLAGE_START.1,
+    },
+];
+
+pub fn get_inn(npc_id: &str) -> Option<&'static InnDef> {
+    super::content::content().inns.iter().find(|inn| inn.npc_id == npc_id)
+}
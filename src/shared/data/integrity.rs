@@ -0,0 +1,219 @@
+//! Cross-reference checks for game data
+//!
+//! `GameContent::validate` covers each definition on its own; this module
+//! checks that definitions point at things that exist: drops at items and
+//! monsters, spawns at monsters, portals at maps, positions (and building
+//! doors) inside their map, skills and items at classes, shop listings
+//! at shop NPCs and purchasable items, inns at their map, innkeeper and
+//! bind tile, enhancements at material items, and monsters at sprite
+//! configs.
+
+use std::collections::HashSet;
+
+use super::characters::{defaults, get_class_by_id};
+use super::content::{ContentError, GameContent};
+use super::items::ItemCategory;
+use super::maps::{
+    get_map_buildings, get_map_layout, get_map_npcs, get_map_portals, get_map_spawns, layout_tile, map_size, MapDef, NpcType,
+};
+use super::monsters::{get_monster_sprite_config, PRELOADED_MONSTER_SPRITES};
+
+fn broken(errors: &mut Vec<ContentError>, section: &'static str, id: impl ToString, reason: String) {
+    errors.push(ContentError::Invalid { section, id: id.to_string(), reason });
+}
+
+/// Why `(x, y)` is not a usable position on `map`, if it is not
+fn position_problem(map: &MapDef, x: i32, y: i32, walkable: bool) -> Option<String> {
+    let (width, height) = map_size(map);
+    if x < 0 || y < 0 || x >= width || y >= height {
+        return Some(format!("({}, {}) is outside {} ({}x{})", x, y, map.id, width, height));
+    }
+    if walkable
+        && let Some(layout) = get_map_layout(map.id)
+        && !layout_tile(layout, x, y).is_some_and(|tile| tile.is_walkable())
+    {
+        return Some(format!("({}, {}) on {} is not walkable", x, y, map.id));
+    }
+    None
+}
+
+/// Every broken reference in `content`
+pub fn check_references(content: &GameContent) -> Vec<ContentError> {
+    let mut errors = Vec::new();
+    let monster_ids: HashSet<i32> = content.monsters.iter().map(|m| m.id).collect();
+    let item_ids: HashSet<i32> = content.items.iter().map(|i| i.id).collect();
+    let map = |id: &str| content.maps.iter().find(|m| m.id == id);
+
+    for d in &content.drops {
+        let id = format!("{}->{}", d.monster_id, d.item_id);
+        if !monster_ids.contains(&d.monster_id) {
+            broken(&mut errors, "drops", &id, format!("monster {} does not exist", d.monster_id));
+        }
+        if !item_ids.contains(&d.item_id) {
+            broken(&mut errors, "drops", &id, format!("item {} does not exist", d.item_id));
+        }
+    }
+
+    for s in &content.skills {
+        if let Some(class_id) = s.class_id
+            && get_class_by_id(class_id).is_none()
+        {
+            broken(&mut errors, "skills", s.id, format!("class {} does not exist", class_id));
+        }
+    }
+
+    for i in &content.items {
+        if let Some(class_id) = i.req_class
+            && get_class_by_id(class_id).is_none()
+        {
+            broken(&mut errors, "items", i.id, format!("class {} does not exist", class_id));
+        }
+    }
+
+    for m in &content.monsters {
+        if get_monster_sprite_config(m.sprite_type).is_none() {
+            broken(&mut errors, "monsters", m.id, format!("sprite type {:?} has no sprite config", m.sprite_type));
+        }
+    }
+    for sprite_type in PRELOADED_MONSTER_SPRITES {
+        if get_monster_sprite_config(sprite_type).is_none() {
+            broken(&mut errors, "sprites", sprite_type, "preloaded sprite has no sprite config".to_string());
+        }
+    }
+
+    let mut npc_ids = HashSet::new();
+    for def in &content.maps {
+        for spawn in get_map_spawns(def.id) {
+            let id = format!("{} ({}, {})", def.id, spawn.x, spawn.y);
+            if !monster_ids.contains(&spawn.monster_id) {
+                broken(&mut errors, "spawns", &id, format!("monster {} does not exist", spawn.monster_id));
+            }
+            if let Some(problem) = position_problem(def, spawn.x, spawn.y, true) {
+                broken(&mut errors, "spawns", &id, problem);
+            }
+        }
+
+        for npc in get_map_npcs(def.id) {
+            if !npc_ids.insert(npc.id) {
+                broken(&mut errors, "npcs", npc.id, "duplicate id".to_string());
+            }
+            // NPCs may stand behind counters, so only the bounds matter
+            if let Some(problem) = position_problem(def, npc.x, npc.y, false) {
+                broken(&mut errors, "npcs", npc.id, problem);
+            }
+        }
+
+        for building in get_map_buildings(def.id) {
+            // Doors are walked through, so they must be walkable
+            if let Some(problem) = position_problem(def, building.x, building.y, true) {
+                broken(&mut errors, "buildings", building.id, problem);
+            }
+        }
+
+        for portal in get_map_portals(def.id) {
+            let id = format!("{} ({}, {})", def.id, portal.x, portal.y);
+            if let Some(problem) = position_problem(def, portal.x, portal.y, true) {
+                broken(&mut errors, "portals", &id, problem);
+            }
+            match map(portal.target_map) {
+                Some(target) => {
+                    if let Some(problem) = position_problem(target, portal.target_x, portal.target_y, true) {
+                        broken(&mut errors, "portals", &id, format!("target {}", problem));
+                    }
+                }
+                None => broken(&mut errors, "portals", &id, format!("target map {:?} does not exist", portal.target_map)),
+            }
+        }
+    }
+
+    for s in &content.shops {
+        let id = format!("{}->{}", s.npc_id, s.item_id);
+        let npc = content.maps.iter().flat_map(|m| get_map_npcs(m.id)).find(|npc| npc.id == s.npc_id);
+        match npc {
+            Some(npc) if npc.npc_type != NpcType::Shop => {
+                broken(&mut errors, "shops", &id, format!("npc {} is not a shop", s.npc_id));
+            }
+            Some(_) => {}
+            None => broken(&mut errors, "shops", &id, format!("npc {} does not exist", s.npc_id)),
+        }
+        match content.items.iter().find(|i| i.id == s.item_id) {
+            Some(item) if item.price_buy <= 0 => {
+                broken(&mut errors, "shops", &id, format!("item {} has no price_buy", s.item_id));
+            }
+            Some(_) => {}
+            None => broken(&mut errors, "shops", &id, format!("item {} does not exist", s.item_id)),
+        }
+    }
+
+    for e in &content.enhancements {
+        match content.items.iter().find(|i| i.id == e.material_id) {
+            Some(item) if item.category != ItemCategory::Material => {
+                broken(&mut errors, "enhancements", e.level, format!("item {} is not a material", e.material_id));
+            }
+            Some(_) => {}
+            None => broken(&mut errors, "enhancements", e.level, format!("item {} does not exist", e.material_id)),
+        }
+    }
+
+    for inn in &content.inns {
+        let Some(def) = map(inn.map_id) else {
+            broken(&mut errors, "inns", inn.npc_id, format!("map {:?} does not exist", inn.map_id));
+            continue;
+        };
+        match get_map_npcs(def.id).iter().find(|npc| npc.id == inn.npc_id) {
+            Some(npc) if npc.npc_type != NpcType::Inn => {
+                broken(&mut errors, "inns", inn.npc_id, format!("npc {} is not an innkeeper", inn.npc_id));
+            }
+            Some(_) => {}
+            None => broken(&mut errors, "inns", inn.npc_id, format!("npc {} is not on {}", inn.npc_id, def.id)),
+        }
+        if let Some(problem) = position_problem(def, inn.bind_x, inn.bind_y, true) {
+            broken(&mut errors, "inns", inn.npc_id, format!("bind tile {}", problem));
+        }
+    }
+
+    match map(defaults::STARTING_MAP) {
+        Some(start) => {
+            if let Some(problem) = position_problem(start, defaults::STARTING_X as i32, defaults::STARTING_Y as i32, true) {
+                broken(&mut errors, "defaults", "STARTING_X/Y", problem);
+            }
+        }
+        None => broken(&mut errors, "defaults", "STARTING_MAP", format!("map {:?} does not exist", defaults::STARTING_MAP)),
+    }
+
+    errors
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_builtin_references_resolve() {
+        let errors = check_references(&GameContent::builtin());
+        assert!(errors.is_empty(), "{}", errors.iter().map(|e| e.to_string()).collect::<Vec<_>>().join("\n"));
+    }
+
+    #[test]
+    fn test_broken_references_are_reported() {
+        let mut content = GameContent::builtin();
+        content.drops[0].item_id = 9999;
+        content.skills[0].class_id = Some(42);
+        content.monsters[0].sprite_type = "nothing";
+        content.shops[0].npc_id = "blacksmith";
+        content.enhancements[0].material_id = 1;
+        content.inns[0].map_id = "nowhere";
+        // Without the plains, the village portal leads nowhere
+        content.maps.retain(|m| m.id != "milles_plains");
+
+        let reasons: Vec<String> = check_references(&content).iter().map(|e| e.to_string()).collect();
+        assert_eq!(reasons.len(), 7, "{:?}", reasons);
+        assert!(reasons.iter().any(|r| r.contains("item 9999 does not exist")));
+        assert!(reasons.iter().any(|r| r.contains("class 42 does not exist")));
+        assert!(reasons.iter().any(|r| r.contains("\"nothing\" has no sprite config")));
+        assert!(reasons.iter().any(|r| r.contains("npc blacksmith is not a shop")));
+        assert!(reasons.iter().any(|r| r.contains("item 1 is not a material")));
+        assert!(reasons.iter().any(|r| r.contains("map \"nowhere\" does not exist")));
+        assert!(reasons.iter().any(|r| r.contains("target map \"milles_plains\" does not exist")));
+    }
+}
@@ -2,8 +2,11 @@
 //!
 //! All item definitions that were previously stored in the database.
 
+use serde::{Deserialize, Serialize};
+
 /// Item types
-#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
 pub enum ItemCategory {
     Weapon,
     Armor,
@@ -36,7 +39,8 @@ pub enum ArmorType {
 }
 
 /// Item definition
-#[derive(Debug, Clone)]
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(deny_unknown_fields)]
 pub struct ItemDef {
     pub id: i32,
     pub name: &'static str,
@@ -53,13 +57,22 @@ pub struct ItemDef {
     pub icon_path: &'static str,
     /// Paper Doll layer sprite path (256x256, 4x4 grid matching character animation)
     /// Only for equippable items (weapons, armor, etc.)
+    #[serde(borrow)]
     pub equipment_sprite: Option<&'static str>,
     pub stackable: bool,
     pub max_stack: i32,
+    /// Can change hands in a player trade; data files may leave it out
+    #[serde(default = "tradeable_by_default")]
+    pub tradeable: bool,
+}
+
+fn tradeable_by_default() -> bool {
+    true
 }
 
-/// Item stat bonuses
-#[derive(Debug, Clone, Default)]
+/// Item stat bonuses; stats left out of a data file are zero
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+#[serde(default, deny_unknown_fields)]
 pub struct ItemStats {
     pub attack: i32,
     pub defense: i32,
@@ -95,6 +108,7 @@ pub const RED_POTION: ItemDef = ItemDef {
     equipment_sprite: None,
     stackable: true,
     max_stack: 99,
+    tradeable: true,
 };
 
 pub const BLUE_POTION: ItemDef = ItemDef {
@@ -114,6 +128,7 @@ pub const BLUE_POTION: ItemDef = ItemDef {
     equipment_sprite: None,
     stackable: true,
     max_stack: 99,
+    tradeable: true,
 };
 
 pub const LARGE_RED_POTION: ItemDef = ItemDef {
@@ -133,6 +148,7 @@ pub const LARGE_RED_POTION: ItemDef = ItemDef {
     equipment_sprite: None,
     stackable: true,
     max_stack: 99,
+    tradeable: true,
 };
 
 // ============ Warrior Weapons ============
@@ -154,6 +170,7 @@ pub const WOODEN_SWORD: ItemDef = ItemDef {
     equipment_sprite: Some("/assets/equipment/weapons/wooden_sword.png"),
     stackable: false,
     max_stack: 1,
+    tradeable: true,
 };
 
 pub const IRON_SWORD: ItemDef = ItemDef {
@@ -173,6 +190,7 @@ pub const IRON_SWORD: ItemDef = ItemDef {
     equipment_sprite: Some("/assets/equipment/weapons/iron_sword.png"),
     stackable: false,
     max_stack: 1,
+    tradeable: true,
 };
 
 pub const STEEL_SWORD: ItemDef = ItemDef {
@@ -192,6 +210,7 @@ pub const STEEL_SWORD: ItemDef = ItemDef {
     equipment_sprite: Some("/assets/equipment/weapons/steel_sword.png"),
     stackable: false,
     max_stack: 1,
+    tradeable: true,
 };
 
 // ============ Rogue Weapons ============
@@ -213,6 +232,7 @@ pub const RUSTY_DAGGER: ItemDef = ItemDef {
     equipment_sprite: Some("/assets/equipment/weapons/rusty_dagger.png"),
     stackable: false,
     max_stack: 1,
+    tradeable: true,
 };
 
 pub const IRON_DAGGER: ItemDef = ItemDef {
@@ -232,6 +252,7 @@ pub const IRON_DAGGER: ItemDef = ItemDef {
     equipment_sprite: Some("/assets/equipment/weapons/iron_dagger.png"),
     stackable: false,
     max_stack: 1,
+    tradeable: true,
 };
 
 // ============ Mage Weapons ============
@@ -253,6 +274,7 @@ pub const WOODEN_STAFF: ItemDef = ItemDef {
     equipment_sprite: Some("/assets/equipment/weapons/wooden_staff.png"),
     stackable: false,
     max_stack: 1,
+    tradeable: true,
 };
 
 pub const MAGIC_STAFF: ItemDef = ItemDef {
@@ -272,6 +294,7 @@ pub const MAGIC_STAFF: ItemDef = ItemDef {
     equipment_sprite: Some("/assets/equipment/weapons/magic_staff.png"),
     stackable: false,
     max_stack: 1,
+    tradeable: true,
 };
 
 // ============ Armor ============
@@ -293,6 +316,7 @@ pub const LEATHER_ARMOR: ItemDef = ItemDef {
     equipment_sprite: Some("/assets/equipment/armor/leather_armor.png"),
     stackable: false,
     max_stack: 1,
+    tradeable: true,
 };
 
 // ============ Helmets ============
@@ -314,6 +338,7 @@ pub const IRON_HELMET: ItemDef = ItemDef {
     equipment_sprite: Some("/assets/equipment/helmets/iron_helmet.png"),
     stackable: false,
     max_stack: 1,
+    tradeable: true,
 };
 
 // ============ Shields ============
@@ -335,6 +360,30 @@ pub const WOODEN_SHIELD: ItemDef = ItemDef {
     equipment_sprite: Some("/assets/equipment/shields/wooden_shield.png"),
     stackable: false,
     max_stack: 1,
+    tradeable: true,
+};
+
+// ============ Materials ============
+
+/// Consumed by blacksmith enhancement (see `enhancements`)
+pub const ENHANCEMENT_STONE: ItemDef = ItemDef {
+    id: super::enhancements::ENHANCEMENT_STONE_ID,
+    name: "Enhancement Stone",
+    name_key: "item.enhancement_stone",
+    description_key: "item.enhancement_stone.desc",
+    category: ItemCategory::Material,
+    sub_type: "stone",
+    grade: 1,
+    req_level: 1,
+    req_class: None,
+    stats: ItemStats::ZERO,
+    price_buy: 200,
+    price_sell: 20,
+    icon_path: "/assets/items/enhancement_stone.png",
+    equipment_sprite: None,
+    stackable: true,
+    max_stack: 99,
+    tradeable: true,
 };
 
 // Helper constant for cleaner initialization
@@ -378,28 +427,33 @@ pub const ALL_ITEMS: &[&ItemDef] = &[
     &IRON_HELMET,
     // Shields
     &WOODEN_SHIELD,
+    // Materials
+    &ENHANCEMENT_STONE,
 ];
 
+/// Item definitions currently in use (see `content`)
+pub fn all_items() -> &'static [ItemDef] {
+    &super::content::content().items
+}
+
 /// Get item by ID
 pub fn get_item_by_id(id: i32) -> Option<&'static ItemDef> {
-    ALL_ITEMS.iter().find(|item| item.id == id).copied()
+    all_items().iter().find(|item| item.id == id)
 }
 
 /// Get items by category
 pub fn get_items_by_category(category: ItemCategory) -> Vec<&'static ItemDef> {
-    ALL_ITEMS.iter()
+    all_items().iter()
         .filter(|item| item.category == category)
-        .copied()
         .collect()
 }
 
 /// Get weapons for a specific class
 pub fn get_weapons_for_class(class_id: i32) -> Vec<&'static ItemDef> {
-    ALL_ITEMS.iter()
+    all_items().iter()
         .filter(|item| {
             item.category == ItemCategory::Weapon &&
             (item.req_class.is_none() || item.req_class == Some(class_id))
         })
-        .copied()
         .collect()
 }
@@ -2,8 +2,13 @@
 //!
 //! All map definitions organized by circle regions.
 
+use serde::{Deserialize, Serialize};
+
+use crate::shared::domain::map::models::ObjectType;
+
 /// Map definition
-#[derive(Debug, Clone)]
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(deny_unknown_fields)]
 pub struct MapDef {
     pub id: &'static str,
     pub name: &'static str,
@@ -57,7 +62,7 @@ impl MapTile {
 }
 
 /// Monster spawn point
-#[derive(Debug, Clone)]
+#[derive(Debug, Clone, Serialize)]
 pub struct SpawnPoint {
     pub x: i32,
     pub y: i32,
@@ -66,7 +71,7 @@ pub struct SpawnPoint {
 }
 
 /// NPC definition
-#[derive(Debug, Clone)]
+#[derive(Debug, Clone, Serialize)]
 pub struct NpcDef {
     pub id: &'static str,
     pub name_key: &'static str,
@@ -75,7 +80,8 @@ pub struct NpcDef {
     pub npc_type: NpcType,
 }
 
-#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize)]
+#[serde(rename_all = "snake_case")]
 pub enum NpcType {
     Shop,
     Inn,
@@ -85,8 +91,24 @@ pub enum NpcType {
     Portal,
 }
 
+/// A building's entrance: services tied to the building are used
+/// from within `BUILDING_REACH` tiles of its door
+#[derive(Debug, Clone, Serialize)]
+pub struct BuildingDef {
+    pub id: &'static str,
+    pub obj_type: ObjectType,
+    pub x: i32,
+    pub y: i32,
+}
+
+/// Tiles (on the same map) from a building's door within which it can be used
+pub const BUILDING_REACH: i32 = 2;
+
+/// Tiles (on the same map) from an NPC within which it can be talked to
+pub const NPC_REACH: i32 = 2;
+
 /// Portal definition
-#[derive(Debug, Clone)]
+#[derive(Debug, Clone, Serialize)]
 pub struct PortalDef {
     pub x: i32,
     pub y: i32,
@@ -244,20 +266,89 @@ pub const ALL_MAPS: &[&MapDef] = &[
     &SHADOW_SANCTUARY, &DARK_CASTLE, &THRONE_OF_DARKNESS,
 ];
 
+/// Map definitions currently in use (see `content`)
+pub fn all_maps() -> &'static [MapDef] {
+    &super::content::content().maps
+}
+
 pub fn get_map_by_id(id: &str) -> Option<&'static MapDef> {
-    ALL_MAPS.iter().find(|m| m.id == id).copied()
+    all_maps().iter().find(|m| m.id == id)
 }
 
 pub fn get_maps_by_circle(circle: i32) -> Vec<&'static MapDef> {
-    ALL_MAPS.iter().filter(|m| m.circle == circle).copied().collect()
+    all_maps().iter().filter(|m| m.circle == circle).collect()
 }
 
 pub fn get_towns() -> Vec<&'static MapDef> {
-    ALL_MAPS.iter().filter(|m| m.is_town).copied().collect()
+    all_maps().iter().filter(|m| m.is_town).collect()
 }
 
 pub fn get_dungeons() -> Vec<&'static MapDef> {
-    ALL_MAPS.iter().filter(|m| m.is_dungeon).copied().collect()
+    all_maps().iter().filter(|m| m.is_dungeon).collect()
+}
+
+// ============================================================
+// MAP LAYOUTS
+// ============================================================
+
+/// Milles Village tile layout (row = grid y, column = grid x)
+/// 'F' marks the fountain, rendered by the client and walkable like grass.
+pub const MILLES_VILLAGE_LAYOUT: &[&str] = &[
+    "GGGGGGSSSSGGGGGG",
+    "GGBWGSSSSSGGBWWG",
+    "GGDWGSSSSSGWDDWG",
+    "GGGGGGSSSSGGGGGG",
+    "SSSSSSSSSSSSSSSS",
+    "SSSSSFFFSSSSSSSS",
+    "SSSSSFFFSSSSSSSS",
+    "SSSSSFFFSSSSSSSS",
+    "SSSSSSSSSSSSSSSS",
+    "GGGGGGSSSSGGGGGG",
+    "GBWGGSSSSSGGBWWG",
+    "GBDDGSSSSSGWWDDG",
+    "GGGGGGSSSSGGGGGG",
+    "GGGGGGSSSSGGGGGG",
+    "TTTTGGSSSSGGTTTT",
+    "TTTTGGSSSSGGTTTT",
+];
+
+/// Grid tile where new characters appear in Milles Village
+pub const MILLES_VILLAGE_START: (i32, i32) = (8, 8);
+
+/// Tile at a grid position of an authored layout (None = out of bounds)
+pub fn layout_tile(layout: &[&str], x: i32, y: i32) -> Option<MapTile> {
+    if x < 0 || y < 0 {
+        return None;
+    }
+    layout.get(y as usize)
+        .and_then(|row| row.chars().nth(x as usize))
+        .map(MapTile::from_char)
+}
+
+/// Get the tile layout for a map, if one has been authored
+pub fn get_map_layout(map_id: &str) -> Option<&'static [&'static str]> {
+    match map_id {
+        "milles_village" => Some(MILLES_VILLAGE_LAYOUT),
+        _ => None,
+    }
+}
+
+/// Grid size (width, height): the authored layout if there is one,
+/// otherwise the `MapDef` dimensions
+pub fn map_size(def: &MapDef) -> (i32, i32) {
+    match get_map_layout(def.id) {
+        Some(layout) => (layout.iter().map(|row| row.len()).max().unwrap_or(0) as i32, layout.len() as i32),
+        None => (def.width as i32, def.height as i32),
+    }
+}
+
+/// Whether a character can stand at `(x, y)`; maps without a layout are open fields
+pub fn is_walkable_at(def: &MapDef, x: i32, y: i32) -> bool {
+    let (width, height) = map_size(def);
+    if x < 0 || y < 0 || x >= width || y >= height {
+        return false;
+    }
+    get_map_layout(def.id).is_none_or(|layout| layout_tile(layout, x, y).is_some_and(|tile| tile.is_walkable()))
 }
 
 // ============================================================
@@ -285,28 +376,104 @@ pub const WOLF_DEN_SPAWNS: &[SpawnPoint] = &[
     SpawnPoint { x: 12, y: 12, monster_id: 199, respawn_time_ms: 300000 }, // Wolf Alpha (Boss)
 ];
 
+pub const MILLES_VILLAGE_SPAWNS: &[SpawnPoint] = &[
+    SpawnPoint { x: 3, y: 3, monster_id: 101, respawn_time_ms: 30000 },    // Giant Rat
+    SpawnPoint { x: 3, y: 13, monster_id: 102, respawn_time_ms: 30000 },   // Vampire Bat
+    SpawnPoint { x: 13, y: 3, monster_id: 103, respawn_time_ms: 30000 },   // Slime
+    SpawnPoint { x: 13, y: 13, monster_id: 103, respawn_time_ms: 30000 },  // Slime
+];
+
+/// Get spawn points for a map
+pub fn get_map_spawns(map_id: &str) -> &'static [SpawnPoint] {
+    match map_id {
+        "milles_village" => MILLES_VILLAGE_SPAWNS,
+        "milles_plains" => MILLES_PLAINS_SPAWNS,
+        "wolf_forest" => WOLF_FOREST_SPAWNS,
+        "wolf_den" => WOLF_DEN_SPAWNS,
+        _ => &[],
+    }
+}
+
 // ============================================================
 // NPC CONFIGURATIONS
 // ============================================================
 
 pub const MILLES_NPCS: &[NpcDef] = &[
-    NpcDef { id: "innkeeper", name_key: "npc.innkeeper", x: 8, y: 8, npc_type: NpcType::Inn },
-    NpcDef { id: "shopkeeper", name_key: "npc.shopkeeper", x: 12, y: 8, npc_type: NpcType::Shop },
-    NpcDef { id: "blacksmith", name_key: "npc.blacksmith", x: 16, y: 8, npc_type: NpcType::Blacksmith },
-    NpcDef { id: "warrior_trainer", name_key: "npc.warrior_trainer", x: 10, y: 16, npc_type: NpcType::ClassTrainer },
-    NpcDef { id: "mage_trainer", name_key: "npc.mage_trainer", x: 14, y: 16, npc_type: NpcType::ClassTrainer },
-    NpcDef { id: "quest_elder", name_key: "npc.quest_elder", x: 16, y: 16, npc_type: NpcType::QuestGiver },
+    NpcDef { id: "innkeeper", name_key: "npc.innkeeper", x: 2, y: 2, npc_type: NpcType::Inn },
+    NpcDef { id: "shopkeeper", name_key: "npc.shopkeeper", x: 12, y: 11, npc_type: NpcType::Shop },
+    NpcDef { id: "blacksmith", name_key: "npc.blacksmith", x: 13, y: 2, npc_type: NpcType::Blacksmith },
+    NpcDef { id: "warrior_trainer", name_key: "npc.warrior_trainer", x: 2, y: 12, npc_type: NpcType::ClassTrainer },
+    NpcDef { id: "mage_trainer", name_key: "npc.mage_trainer", x: 13, y: 12, npc_type: NpcType::ClassTrainer },
+    NpcDef { id: "quest_elder", name_key: "npc.quest_elder", x: 4, y: 6, npc_type: NpcType::QuestGiver },
+];
+
+/// Get the NPCs standing on a map
+pub fn get_map_npcs(map_id: &str) -> &'static [NpcDef] {
+    match map_id {
+        "milles_village" => MILLES_NPCS,
+        _ => &[],
+    }
+}
+
+/// An NPC on a map by id
+pub fn get_npc(map_id: &str, npc_id: &str) -> Option<&'static NpcDef> {
+    get_map_npcs(map_id).iter().find(|npc| npc.id == npc_id)
+}
+
+/// An NPC on any map by id, with the id of the map it stands on
+pub fn find_npc(npc_id: &str) -> Option<(&'static str, &'static NpcDef)> {
+    all_maps().iter().find_map(|m| get_npc(m.id, npc_id).map(|npc| (m.id, npc)))
+}
+
+/// Whether `(x, y)` on a map is within reach of the NPC
+pub fn near_npc(map_id: &str, npc_id: &str, x: i32, y: i32) -> bool {
+    get_npc(map_id, npc_id).is_some_and(|npc| (npc.x - x).abs().max((npc.y - y).abs()) <= NPC_REACH)
+}
+
+// ============================================================
+// BUILDING CONFIGURATIONS
+// ============================================================
+
+pub const MILLES_BUILDINGS: &[BuildingDef] = &[
+    BuildingDef { id: "milles_tavern", obj_type: ObjectType::Tavern, x: 2, y: 2 },
+    BuildingDef { id: "milles_smithy", obj_type: ObjectType::Blacksmith, x: 13, y: 2 },
+    BuildingDef { id: "milles_guild_hall", obj_type: ObjectType::GuildHall, x: 2, y: 11 },
+    BuildingDef { id: "milles_house", obj_type: ObjectType::House, x: 13, y: 11 },
 ];
 
+/// Get the buildings on a map
+pub fn get_map_buildings(map_id: &str) -> &'static [BuildingDef] {
+    match map_id {
+        "milles_village" => MILLES_BUILDINGS,
+        _ => &[],
+    }
+}
+
+/// Whether `(x, y)` on a map is within reach of a building of the given type
+pub fn near_building(map_id: &str, obj_type: ObjectType, x: i32, y: i32) -> bool {
+    get_map_buildings(map_id).iter()
+        .filter(|b| b.obj_type == obj_type)
+        .any(|b| (b.x - x).abs().max((b.y - y).abs()) <= BUILDING_REACH)
+}
+
 // ============================================================
 // PORTAL CONFIGURATIONS
 // ============================================================
 
 pub const MILLES_VILLAGE_PORTALS: &[PortalDef] = &[
-    PortalDef { x: 16, y: 30, target_map: "milles_plains", target_x: 5, target_y: 5 },
+    PortalDef { x: 7, y: 15, target_map: "milles_plains", target_x: 5, target_y: 6 },
 ];
 
 pub const MILLES_PLAINS_PORTALS: &[PortalDef] = &[
-    PortalDef { x: 5, y: 5, target_map: "milles_village", target_x: 16, target_y: 28 },
+    PortalDef { x: 5, y: 5, target_map: "milles_village", target_x: 7, target_y: 14 },
     PortalDef { x: 60, y: 60, target_map: "wolf_forest", target_x: 5, target_y: 5 },
 ];
+
+/// Get the portals leading out of a map
+pub fn get_map_portals(map_id: &str) -> &'static [PortalDef] {
+    match map_id {
+        "milles_village" => MILLES_VILLAGE_PORTALS,
+        "milles_plains" => MILLES_PLAINS_PORTALS,
+        _ => &[],
+    }
+}
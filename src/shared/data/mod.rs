@@ -9,6 +9,11 @@ pub mod characters;
 pub mod skills;
 pub mod items;
 pub mod maps;
+pub mod shops;
+pub mod inns;
+pub mod enhancements;
+pub mod content;
+pub mod integrity;
 
 // Re-export commonly used types
 pub use assets::*;
@@ -17,3 +22,7 @@ pub use characters::*;
 pub use skills::*;
 pub use items::*;
 pub use maps::*;
+pub use shops::*;
+pub use inns::*;
+pub use enhancements::*;
+pub use content::*;
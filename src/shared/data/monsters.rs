@@ -3,10 +3,14 @@
 //! All monster definitions organized by circle and region.
 //! Circle 1: Lv 1-20, Circle 2: Lv 21-40, Circle 3: Lv 41-60, Circle 4: Lv 61-80, Circle 5: Lv 81-99
 
-use crate::shared::domain::monster::{MonsterAIType, SpriteSize};
+use rand::Rng;
+use serde::{Deserialize, Serialize};
+
+use crate::shared::domain::monster::{LootDrop, MonsterAIType, MonsterData, SpriteSize};
 
 /// Monster definition constant data
-#[derive(Debug, Clone)]
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(deny_unknown_fields)]
 pub struct MonsterDef {
     pub id: i32,
     pub name: &'static str,
@@ -37,6 +41,43 @@ impl MonsterDef {
     }
 }
 
+impl From<&MonsterDef> for MonsterData {
+    fn from(def: &MonsterDef) -> Self {
+        let loot_table = get_monster_drops(def.id)
+            .into_iter()
+            .map(|d| LootDrop {
+                item_id: d.item_id,
+                probability: d.probability,
+                min_quantity: d.min_quantity,
+                max_quantity: d.max_quantity,
+            })
+            .collect();
+
+        MonsterData {
+            id: def.id,
+            name: def.name.to_string(),
+            level: def.level,
+            max_hp: def.hp_max,
+            attack_min: def.attack_min,
+            attack_max: def.attack_max,
+            defense: def.defense,
+            exp_reward: def.exp_reward,
+            gold_min: def.gold_min,
+            gold_max: def.gold_max,
+            ai_type: def.ai_type,
+            detection_range: def.detection_range,
+            attack_range: def.attack_range,
+            move_speed: def.move_speed,
+            sprite_path: def.sprite_path(),
+            sprite_type: def.sprite_type.to_string(),
+            sprite_size: def.sprite_size,
+            description: format!("{}.desc", def.name_key),
+            metadata: None,
+            loot_table,
+        }
+    }
+}
+
 // ============================================================
 // CIRCLE 1: MILLES PLAINS (Lv 1-20)
 // ============================================================
@@ -365,31 +406,37 @@ pub const ALL_MONSTERS: &[&MonsterDef] = &[
     &LICH, &DEATH_KNIGHT, &SHADOW_DRAGON, &ARCH_LICH, &DARK_LORD,
 ];
 
+/// Monster definitions currently in use (see `content`)
+pub fn all_monsters() -> &'static [MonsterDef] {
+    &super::content::content().monsters
+}
+
 pub fn get_monster_by_name(name: &str) -> Option<&'static MonsterDef> {
-    ALL_MONSTERS.iter().find(|m| m.name == name).copied()
+    all_monsters().iter().find(|m| m.name == name)
 }
 
 pub fn get_monster_by_id(id: i32) -> Option<&'static MonsterDef> {
-    ALL_MONSTERS.iter().find(|m| m.id == id).copied()
+    all_monsters().iter().find(|m| m.id == id)
 }
 
 pub fn get_monsters_by_circle(circle: i32) -> Vec<&'static MonsterDef> {
-    ALL_MONSTERS.iter().filter(|m| m.circle == circle).copied().collect()
+    all_monsters().iter().filter(|m| m.circle == circle).collect()
 }
 
 pub fn get_monsters_by_region(region: &str) -> Vec<&'static MonsterDef> {
-    ALL_MONSTERS.iter().filter(|m| m.region == region).copied().collect()
+    all_monsters().iter().filter(|m| m.region == region).collect()
 }
 
 pub fn get_bosses() -> Vec<&'static MonsterDef> {
-    ALL_MONSTERS.iter().filter(|m| m.is_boss).copied().collect()
+    all_monsters().iter().filter(|m| m.is_boss).collect()
 }
 
 // ============================================================
 // LOOT SYSTEM
 // ============================================================
 
-#[derive(Debug, Clone)]
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(deny_unknown_fields)]
 pub struct LootDropDef {
     pub monster_id: i32,
     pub item_id: i32,
@@ -405,10 +452,14 @@ pub const MONSTER_DROPS: &[LootDropDef] = &[
     LootDropDef { monster_id: 103, item_id: 1, probability: 0.2, min_quantity: 1, max_quantity: 1 },
     LootDropDef { monster_id: 103, item_id: 2, probability: 0.05, min_quantity: 1, max_quantity: 1 },
     LootDropDef { monster_id: 199, item_id: 10, probability: 0.1, min_quantity: 1, max_quantity: 1 }, // Boss drops weapon
+    // Enhancement stones
+    LootDropDef { monster_id: 102, item_id: 500, probability: 0.03, min_quantity: 1, max_quantity: 1 },
+    LootDropDef { monster_id: 103, item_id: 500, probability: 0.05, min_quantity: 1, max_quantity: 1 },
+    LootDropDef { monster_id: 199, item_id: 500, probability: 0.5, min_quantity: 1, max_quantity: 3 },
 ];
 
 pub fn get_monster_drops(monster_id: i32) -> Vec<&'static LootDropDef> {
-    MONSTER_DROPS.iter().filter(|d| d.monster_id == monster_id).collect()
+    super::content::content().drops.iter().filter(|d| d.monster_id == monster_id).collect()
 }
 
 // ============================================================
@@ -436,20 +487,30 @@ pub const MONSTER_SPRITES: &[MonsterSpriteConfig] = &[
     MonsterSpriteConfig { sprite_type: "scorpion", frame_width: 48, frame_height: 48, frames_per_anim: 4, animation_speed: 0.12 },
     MonsterSpriteConfig { sprite_type: "mummy", frame_width: 48, frame_height: 48, frames_per_anim: 4, animation_speed: 0.18 },
     MonsterSpriteConfig { sprite_type: "goblin", frame_width: 48, frame_height: 48, frames_per_anim: 4, animation_speed: 0.12 },
+    MonsterSpriteConfig { sprite_type: "frost_wolf", frame_width: 48, frame_height: 48, frames_per_anim: 4, animation_speed: 0.12 },
     // Large (64x64)
     MonsterSpriteConfig { sprite_type: "golem", frame_width: 64, frame_height: 64, frames_per_anim: 4, animation_speed: 0.2 },
     MonsterSpriteConfig { sprite_type: "ghost", frame_width: 64, frame_height: 64, frames_per_anim: 4, animation_speed: 0.2 },
     MonsterSpriteConfig { sprite_type: "yeti", frame_width: 64, frame_height: 64, frames_per_anim: 4, animation_speed: 0.18 },
     MonsterSpriteConfig { sprite_type: "dark_knight", frame_width: 64, frame_height: 64, frames_per_anim: 4, animation_speed: 0.15 },
     MonsterSpriteConfig { sprite_type: "lich", frame_width: 64, frame_height: 64, frames_per_anim: 4, animation_speed: 0.15 },
+    MonsterSpriteConfig { sprite_type: "fire_elemental", frame_width: 64, frame_height: 64, frames_per_anim: 4, animation_speed: 0.15 },
+    MonsterSpriteConfig { sprite_type: "hell_hound", frame_width: 64, frame_height: 64, frames_per_anim: 4, animation_speed: 0.12 },
+    MonsterSpriteConfig { sprite_type: "lava_golem", frame_width: 64, frame_height: 64, frames_per_anim: 4, animation_speed: 0.2 },
+    MonsterSpriteConfig { sprite_type: "death_knight", frame_width: 64, frame_height: 64, frames_per_anim: 4, animation_speed: 0.15 },
     // Boss (128x128)
     MonsterSpriteConfig { sprite_type: "wolf_alpha", frame_width: 128, frame_height: 128, frames_per_anim: 4, animation_speed: 0.15 },
     MonsterSpriteConfig { sprite_type: "scorpion_king", frame_width: 128, frame_height: 128, frames_per_anim: 4, animation_speed: 0.15 },
     MonsterSpriteConfig { sprite_type: "ice_golem", frame_width: 128, frame_height: 128, frames_per_anim: 4, animation_speed: 0.2 },
     MonsterSpriteConfig { sprite_type: "inferno_demon", frame_width: 128, frame_height: 128, frames_per_anim: 4, animation_speed: 0.15 },
+    MonsterSpriteConfig { sprite_type: "shadow_dragon", frame_width: 128, frame_height: 128, frames_per_anim: 4, animation_speed: 0.15 },
+    MonsterSpriteConfig { sprite_type: "arch_lich", frame_width: 128, frame_height: 128, frames_per_anim: 4, animation_speed: 0.15 },
     MonsterSpriteConfig { sprite_type: "dark_lord", frame_width: 128, frame_height: 128, frames_per_anim: 4, animation_speed: 0.12 },
 ];
 
+/// Monster spritesheets the client loads up front
+pub const PRELOADED_MONSTER_SPRITES: &[&str] = &["rat", "bat", "slime", "wolf", "skeleton", "goblin", "ghost"];
+
 pub fn get_monster_sprite_config(sprite_type: &str) -> Option<&'static MonsterSpriteConfig> {
     MONSTER_SPRITES.iter().find(|c| c.sprite_type == sprite_type)
 }
@@ -459,9 +520,8 @@ pub fn get_monster_sprite_config(sprite_type: &str) -> Option<&'static MonsterSp
 // ============================================================
 
 pub fn get_monsters_for_level(player_level: i32) -> Vec<&'static MonsterDef> {
-    ALL_MONSTERS.iter()
+    all_monsters().iter()
         .filter(|m| (m.level - player_level).abs() <= 5)
-        .copied()
         .collect()
 }
 
@@ -474,3 +534,41 @@ pub fn calculate_exp_reward(monster: &MonsterDef, player_level: i32) -> i32 {
     };
     (monster.exp_reward as f64 * multiplier) as i32
 }
+
+/// An item granted on a kill
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct ItemGrant {
+    pub item_id: i32,
+    pub quantity: i32,
+}
+
+/// Everything a kill pays out
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct KillRewards {
+    pub monster_id: i32,
+    pub exp: i64,
+    pub gold: i64,
+    pub items: Vec<ItemGrant>,
+}
+
+/// Roll exp, gold and `MONSTER_DROPS` for a kill
+pub fn roll_kill_rewards<R: Rng + ?Sized>(rng: &mut R, monster: &MonsterDef, player_level: i32) -> KillRewards {
+    let gold = rng.gen_range(monster.gold_min..=monster.gold_max.max(monster.gold_min));
+
+    let mut items = Vec::new();
+    for drop in get_monster_drops(monster.id) {
+        if rng.gen_bool(drop.probability.clamp(0.0, 1.0)) {
+            items.push(ItemGrant {
+                item_id: drop.item_id,
+                quantity: rng.gen_range(drop.min_quantity..=drop.max_quantity.max(drop.min_quantity)),
+            });
+        }
+    }
+
+    KillRewards {
+        monster_id: monster.id,
+        exp: calculate_exp_reward(monster, player_level) as i64,
+        gold: gold as i64,
+        items,
+    }
+}
@@ -0,0 +1,43 @@
+//! Shop data constants
+//!
+//! Each entry puts one item on sale at a shop NPC (see `MILLES_NPCS`),
+//! listed in the order the shop shows them. Items are bought for their
+//! `price_buy`; shops buy back anything with a `price_sell` above zero,
+//! whether or not they sell it themselves.
+
+use serde::{Deserialize, Serialize};
+
+use super::items::{get_item_by_id, ItemDef};
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(deny_unknown_fields)]
+pub struct ShopItemDef {
+    pub npc_id: &'static str,
+    pub item_id: i32,
+}
+
+pub const SHOP_ITEMS: &[ShopItemDef] = &[
+    // Milles general store
+    ShopItemDef { npc_id: "shopkeeper", item_id: 1 },   // Red Potion
+    ShopItemDef { npc_id: "shopkeeper", item_id: 2 },   // Blue Potion
+    ShopItemDef { npc_id: "shopkeeper", item_id: 3 },   // Large Red Potion
+    ShopItemDef { npc_id: "shopkeeper", item_id: 10 },  // Wooden Sword
+    ShopItemDef { npc_id: "shopkeeper", item_id: 20 },  // Rusty Dagger
+    ShopItemDef { npc_id: "shopkeeper", item_id: 30 },  // Wooden Staff
+    ShopItemDef { npc_id: "shopkeeper", item_id: 100 }, // Leather Armor
+    ShopItemDef { npc_id: "shopkeeper", item_id: 300 }, // Wooden Shield
+    ShopItemDef { npc_id: "shopkeeper", item_id: 500 }, // Enhancement Stone
+];
+
+/// Items an NPC sells, in shop order
+pub fn get_shop_items(npc_id: &str) -> Vec<&'static ItemDef> {
+    super::content::content().shops.iter()
+        .filter(|s| s.npc_id == npc_id)
+        .filter_map(|s| get_item_by_id(s.item_id))
+        .collect()
+}
+
+/// Whether an NPC sells an item
+pub fn sells(npc_id: &str, item_id: i32) -> bool {
+    super::content::content().shops.iter().any(|s| s.npc_id == npc_id && s.item_id == item_id)
+}
@@ -3,8 +3,11 @@
 //! All skill definitions organized by circle and class.
 //! Circle 1: Lv 1-20, Circle 2: Lv 21-40, Circle 3: Lv 41-60, Circle 4: Lv 61-80, Circle 5: Lv 81-99
 
+use serde::{Deserialize, Serialize};
+
 /// Effect types for skills
-#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
 pub enum SkillEffectType {
     Damage,
     Heal,
@@ -15,16 +18,19 @@ pub enum SkillEffectType {
 }
 
 /// Skill target type
-#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
 pub enum SkillTarget {
     Single,
     Area,
+    #[serde(rename = "self")]
     Self_,
     Party,
 }
 
 /// Skill definition
-#[derive(Debug, Clone)]
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(deny_unknown_fields)]
 pub struct SkillDef {
     pub id: i32,
     pub name: &'static str,
@@ -604,31 +610,33 @@ pub const ALL_SKILLS: &[&SkillDef] = &[
     &TIGER_PALM, &FLYING_KICK, &PRESSURE_POINT, &CHI_BURST, &HUNDRED_FISTS, &ENLIGHTENMENT,
 ];
 
+/// Skill definitions currently in use (see `content`)
+pub fn all_skills() -> &'static [SkillDef] {
+    &super::content::content().skills
+}
+
 /// Get skill by ID
 pub fn get_skill_by_id(id: i32) -> Option<&'static SkillDef> {
-    ALL_SKILLS.iter().find(|s| s.id == id).copied()
+    all_skills().iter().find(|s| s.id == id)
 }
 
 /// Get skills for a specific class
 pub fn get_skills_for_class(class_id: i32) -> Vec<&'static SkillDef> {
-    ALL_SKILLS.iter()
+    all_skills().iter()
         .filter(|s| s.class_id == Some(class_id) || s.class_id.is_none())
-        .copied()
         .collect()
 }
 
 /// Get skills for a specific class and circle
 pub fn get_skills_for_class_circle(class_id: i32, circle: i32) -> Vec<&'static SkillDef> {
-    ALL_SKILLS.iter()
+    all_skills().iter()
         .filter(|s| (s.class_id == Some(class_id) || s.class_id.is_none()) && s.circle == circle)
-        .copied()
         .collect()
 }
 
 /// Get available skills for a class at a given level
 pub fn get_available_skills(class_id: i32, level: i32) -> Vec<&'static SkillDef> {
-    ALL_SKILLS.iter()
+    all_skills().iter()
         .filter(|s| (s.class_id == Some(class_id) || s.class_id.is_none()) && s.req_level <= level)
-        .copied()
         .collect()
 }
@@ -1,2 +1,2 @@
 pub mod models;
-pub use models::{Player, PlayerClass, StatType};
+pub use models::{BindPoint, Player, PlayerClass, StatType};
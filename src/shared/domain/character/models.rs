@@ -1,5 +1,6 @@
 use serde::{Deserialize, Serialize};
 use crate::shared::domain::shared::models::{Position, Stats, CombatStats, Direction};
+use crate::shared::data::characters::{can_level_up, defaults::STAT_POINTS_PER_LEVEL, exp_to_next_level};
 use uuid::Uuid;
 
 #[derive(Debug, Clone, Serialize, Deserialize)]
@@ -21,12 +22,16 @@ pub struct Player {
     pub combat_stats: CombatStats,
     
     // 장비 및 인벤토리
-    pub equipment: std::collections::HashMap<crate::shared::domain::item::models::EquipmentSlot, crate::shared::domain::item::models::Item>,
-    pub inventory: Vec<Option<crate::shared::domain::item::models::Item>>, // 24 slots, some maybe empty
+    pub inventory: crate::shared::domain::item::inventory::Inventory, // 24 slots + equipped items
+    #[serde(default)]
+    pub skills: crate::shared::domain::skill::models::SkillBook, // learned skills + hotbar
     pub gold: i64,
     pub position: Position,
     pub direction: Direction,
     pub current_map: String,
+    /// Where the character comes back after dying, once bound at an inn
+    #[serde(default)]
+    pub bind_point: Option<BindPoint>,
     
     // 상태
     pub is_moving: bool,
@@ -36,6 +41,14 @@ pub struct Player {
     pub attack_cooldown: f64,
 }
 
+/// A tile a character is bound to (see `shared::data::inns`)
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct BindPoint {
+    pub map_id: String,
+    pub x: i32,
+    pub y: i32,
+}
+
 impl Player {
     pub fn new(username: String, class: PlayerClass) -> Self {
         let stats = class.get_base_stats();
@@ -48,13 +61,14 @@ impl Player {
             class,
             level: 1,
             exp: 0,
-            exp_to_next_level: 100,
+            exp_to_next_level: exp_to_next_level(1),
             stats,
             stat_points: 0,
             combat_stats,
-            equipment: std::collections::HashMap::new(),
-            inventory: vec![None; 24], // 24 slots
+            inventory: Default::default(),
+            skills: crate::shared::domain::skill::models::SkillBook::starter(class.id()),
             current_map: "village".to_string(),
+            bind_point: None,
             position: Position::new(400.0, 300.0),
             direction: Direction::Down,
             gold: 100,
@@ -77,7 +91,7 @@ impl Player {
     
     pub fn add_exp(&mut self, amount: i64) {
         self.exp += amount;
-        while self.exp >= self.exp_to_next_level {
+        while can_level_up(self.level, self.exp) {
             self.level_up();
         }
     }
@@ -85,15 +99,22 @@ impl Player {
     fn level_up(&mut self) {
         self.exp -= self.exp_to_next_level;
         self.level += 1;
-        self.exp_to_next_level = self.calculate_exp_to_next_level();
+        self.exp_to_next_level = exp_to_next_level(self.level);
         self.stat_points += 2; // User requested 2 points per level
         
         // 레벨업 시 HP/MP 회복
         self.combat_stats = CombatStats::from_stats(&self.stats, self.level);
     }
     
-    fn calculate_exp_to_next_level(&self) -> i64 {
-        (100.0 * (self.level as f64).powf(1.5)) as i64
+    /// Jump straight to `level` with no exp toward the next one. Raising
+    /// the level grants the stat points those level-ups would have.
+    pub fn set_level(&mut self, level: i32) {
+        let gained = level - self.level;
+        self.level = level;
+        self.exp = 0;
+        self.exp_to_next_level = exp_to_next_level(level);
+        self.stat_points = (self.stat_points + gained * STAT_POINTS_PER_LEVEL).max(0);
+        self.combat_stats = CombatStats::from_stats(&self.stats, self.level);
     }
     
     pub fn add_stat(&mut self, stat_type: StatType, amount: i32) {
@@ -163,6 +184,28 @@ impl PlayerClass {
         }
     }
 
+    pub fn from_id(id: i32) -> Option<Self> {
+        match id {
+            1 => Some(PlayerClass::Warrior),
+            2 => Some(PlayerClass::Rogue),
+            3 => Some(PlayerClass::Mage),
+            4 => Some(PlayerClass::Cleric),
+            5 => Some(PlayerClass::MartialArtist),
+            _ => None,
+        }
+    }
+
+    /// Asset folder name used for sprites and manifests
+    pub fn sprite_name(&self) -> &'static str {
+        match self {
+            PlayerClass::Warrior => "warrior",
+            PlayerClass::Rogue => "rogue",
+            PlayerClass::Mage => "mage",
+            PlayerClass::Cleric => "cleric",
+            PlayerClass::MartialArtist => "martial_artist",
+        }
+    }
+
     pub fn get_base_stats(&self) -> Stats {
         // Legend of Darkness style roughly
         match self {
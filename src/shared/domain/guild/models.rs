@@ -0,0 +1,208 @@
+//! Guilds - ranks, what each rank may do, and the shared bank
+//!
+//! Shared so the client can grey out actions its rank cannot take; the
+//! server checks the same rules before touching `guild_*` tables.
+
+use serde::{Deserialize, Serialize};
+
+use crate::shared::domain::item::inventory::{insert_into, ItemStack};
+
+/// Longest guild notice in characters
+pub const GUILD_NOTICE_MAX: usize = 200;
+
+/// Most members in one guild
+pub const MAX_GUILD_MEMBERS: usize = 50;
+
+/// Item slots in a guild bank
+pub const GUILD_BANK_SLOTS: usize = 48;
+
+/// Ordered from least to most trusted
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Hash, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum GuildRank {
+    Member,
+    Officer,
+    Leader,
+}
+
+impl GuildRank {
+    /// Value stored in `guild_members.rank`
+    pub fn key(self) -> &'static str {
+        match self {
+            Self::Member => "member",
+            Self::Officer => "officer",
+            Self::Leader => "leader",
+        }
+    }
+
+    pub fn from_key(key: &str) -> Option<Self> {
+        match key {
+            "member" => Some(Self::Member),
+            "officer" => Some(Self::Officer),
+            "leader" => Some(Self::Leader),
+            _ => None,
+        }
+    }
+
+    pub fn name(self) -> &'static str {
+        match self {
+            Self::Member => "Member",
+            Self::Officer => "Officer",
+            Self::Leader => "Leader",
+        }
+    }
+
+    pub fn can_invite(self) -> bool {
+        self >= Self::Officer
+    }
+
+    pub fn can_edit_notice(self) -> bool {
+        self >= Self::Officer
+    }
+
+    /// Anyone may deposit; taking items and gold out is for officers
+    pub fn can_withdraw(self) -> bool {
+        self >= Self::Officer
+    }
+
+    /// Kicking needs a strictly higher rank than the target's
+    pub fn can_kick(self, target: GuildRank) -> bool {
+        self.can_invite() && self > target
+    }
+
+    /// Promoting, demoting and handing over leadership
+    pub fn can_set_ranks(self) -> bool {
+        self == Self::Leader
+    }
+
+    pub fn can_disband(self) -> bool {
+        self == Self::Leader
+    }
+}
+
+/// Gold and items shared by a guild
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct GuildBank {
+    pub gold: i64,
+    pub slots: Vec<Option<ItemStack>>,
+}
+
+impl Default for GuildBank {
+    fn default() -> Self {
+        Self {
+            gold: 0,
+            slots: vec![None; GUILD_BANK_SLOTS],
+        }
+    }
+}
+
+impl GuildBank {
+    /// Store a whole stack, topping up equal stacks first
+    pub fn deposit(&mut self, stack: ItemStack) -> Result<(), &'static str> {
+        if insert_into(&mut self.slots, stack) {
+            Ok(())
+        } else {
+            Err("Guild bank is full")
+        }
+    }
+
+    /// Take up to `quantity` items out of a slot
+    pub fn withdraw(&mut self, slot_index: usize, quantity: i32) -> Result<ItemStack, &'static str> {
+        if quantity <= 0 {
+            return Err("Invalid quantity");
+        }
+        let Some(stack) = self.slots.get_mut(slot_index).and_then(|s| s.as_mut()) else {
+            return Err("No item in slot");
+        };
+
+        let taken = ItemStack {
+            quantity: quantity.min(stack.quantity),
+            ..stack.clone()
+        };
+        stack.quantity -= taken.quantity;
+        if stack.quantity <= 0 {
+            self.slots[slot_index] = None;
+        }
+        Ok(taken)
+    }
+
+    pub fn deposit_gold(&mut self, amount: i64) -> Result<(), &'static str> {
+        if amount <= 0 {
+            return Err("Invalid amount");
+        }
+        self.gold = self.gold.checked_add(amount).ok_or("Guild bank cannot hold that much gold")?;
+        Ok(())
+    }
+
+    pub fn withdraw_gold(&mut self, amount: i64) -> Result<(), &'static str> {
+        if amount <= 0 {
+            return Err("Invalid amount");
+        }
+        if amount > self.gold {
+            return Err("Not enough gold in the guild bank");
+        }
+        self.gold -= amount;
+        Ok(())
+    }
+
+    /// No gold and no items
+    pub fn is_empty(&self) -> bool {
+        self.gold == 0 && self.slots.iter().all(|s| s.is_none())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_rank_permissions() {
+        assert!(!GuildRank::Member.can_invite());
+        assert!(GuildRank::Officer.can_invite());
+        assert!(GuildRank::Officer.can_withdraw());
+        assert!(!GuildRank::Member.can_withdraw());
+
+        assert!(GuildRank::Officer.can_kick(GuildRank::Member));
+        assert!(!GuildRank::Officer.can_kick(GuildRank::Officer));
+        assert!(GuildRank::Leader.can_kick(GuildRank::Officer));
+        assert!(!GuildRank::Member.can_kick(GuildRank::Member));
+
+        assert!(GuildRank::Leader.can_set_ranks());
+        assert!(!GuildRank::Officer.can_disband());
+
+        for rank in [GuildRank::Member, GuildRank::Officer, GuildRank::Leader] {
+            assert_eq!(GuildRank::from_key(rank.key()), Some(rank));
+        }
+    }
+
+    #[test]
+    fn test_bank_items_and_gold() {
+        let mut bank = GuildBank::default();
+        assert!(bank.is_empty());
+
+        let sword = ItemStack { item_id: 10, quantity: 1, enhancement: 2 };
+        bank.deposit(sword.clone()).unwrap();
+        bank.deposit(ItemStack::new(1, 5)).unwrap();
+        bank.deposit(ItemStack::new(1, 3)).unwrap();
+        assert_eq!(bank.slots[1], Some(ItemStack::new(1, 8)));
+
+        assert_eq!(bank.withdraw(0, 1), Ok(sword));
+        assert_eq!(bank.withdraw(1, 20).unwrap().quantity, 8);
+        assert!(bank.withdraw(1, 1).is_err());
+
+        bank.deposit_gold(100).unwrap();
+        assert!(bank.withdraw_gold(101).is_err());
+        bank.withdraw_gold(100).unwrap();
+        assert!(bank.is_empty());
+    }
+
+    #[test]
+    fn test_full_bank_rejects_whole_stack() {
+        let mut bank = GuildBank::default();
+        for slot in bank.slots.iter_mut() {
+            *slot = Some(ItemStack::single(10));
+        }
+        assert_eq!(bank.deposit(ItemStack::new(1, 1)), Err("Guild bank is full"));
+        assert!(bank.slots.iter().all(|s| s.as_ref().is_some_and(|s| s.item_id == 10)));
+    }
+}
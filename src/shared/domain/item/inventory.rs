@@ -0,0 +1,616 @@
+//! Inventory System
+//!
+//! Player inventory management with equipment slots and item stacking.
+//! Shared so the server can validate changes and persist them to
+//! `character_inventory`.
+
+use serde::{Deserialize, Serialize};
+use crate::shared::data::enhancements::enhanced_stats;
+use crate::shared::data::items::{ItemDef, ItemCategory, ItemStats, get_item_by_id};
+
+/// Maximum inventory slots
+pub const INVENTORY_SIZE: usize = 24;
+
+/// Equipment slot types
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum EquipSlot {
+    Weapon,
+    Shield,
+    Helmet,
+    Armor,
+    Pants,
+    Boots,
+    Gloves,
+    Ring1,
+    Ring2,
+    Necklace,
+}
+
+impl EquipSlot {
+    pub fn all() -> &'static [EquipSlot] {
+        &[
+            EquipSlot::Weapon,
+            EquipSlot::Shield,
+            EquipSlot::Helmet,
+            EquipSlot::Armor,
+            EquipSlot::Pants,
+            EquipSlot::Boots,
+            EquipSlot::Gloves,
+            EquipSlot::Ring1,
+            EquipSlot::Ring2,
+            EquipSlot::Necklace,
+        ]
+    }
+    
+    pub fn name(&self) -> &'static str {
+        match self {
+            EquipSlot::Weapon => "Weapon",
+            EquipSlot::Shield => "Shield",
+            EquipSlot::Helmet => "Helmet",
+            EquipSlot::Armor => "Armor",
+            EquipSlot::Pants => "Pants",
+            EquipSlot::Boots => "Boots",
+            EquipSlot::Gloves => "Gloves",
+            EquipSlot::Ring1 => "Ring 1",
+            EquipSlot::Ring2 => "Ring 2",
+            EquipSlot::Necklace => "Necklace",
+        }
+    }
+
+    /// Value stored in `character_inventory.equipped_slot`
+    pub fn key(&self) -> &'static str {
+        match self {
+            EquipSlot::Weapon => "weapon",
+            EquipSlot::Shield => "shield",
+            EquipSlot::Helmet => "helmet",
+            EquipSlot::Armor => "armor",
+            EquipSlot::Pants => "pants",
+            EquipSlot::Boots => "boots",
+            EquipSlot::Gloves => "gloves",
+            EquipSlot::Ring1 => "ring1",
+            EquipSlot::Ring2 => "ring2",
+            EquipSlot::Necklace => "necklace",
+        }
+    }
+
+    pub fn from_key(key: &str) -> Option<EquipSlot> {
+        EquipSlot::all().iter().copied().find(|slot| slot.key() == key)
+    }
+}
+
+/// An item stack in inventory
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct ItemStack {
+    /// Item definition ID
+    pub item_id: i32,
+    /// Quantity (for stackable items)
+    pub quantity: i32,
+    /// Enhancement level (for equipment)
+    pub enhancement: i32,
+}
+
+impl ItemStack {
+    pub fn new(item_id: i32, quantity: i32) -> Self {
+        Self {
+            item_id,
+            quantity,
+            enhancement: 0,
+        }
+    }
+
+    pub fn single(item_id: i32) -> Self {
+        Self::new(item_id, 1)
+    }
+
+    pub fn get_def(&self) -> Option<&'static ItemDef> {
+        get_item_by_id(self.item_id)
+    }
+
+    pub fn can_stack_with(&self, other: &ItemStack) -> bool {
+        if self.item_id != other.item_id {
+            return false;
+        }
+        if let Some(def) = self.get_def() {
+            def.stackable && self.enhancement == other.enhancement
+        } else {
+            false
+        }
+    }
+
+    pub fn max_stack(&self) -> i32 {
+        self.get_def().map(|d| d.max_stack).unwrap_or(1)
+    }
+
+    /// Item name with its enhancement level, e.g. `+7 Iron Sword`
+    pub fn display_name(&self) -> String {
+        let name = self.get_def().map(|d| d.name).unwrap_or("Unknown item");
+        if self.enhancement > 0 { format!("+{} {}", self.enhancement, name) } else { name.to_string() }
+    }
+
+    /// The item's stats at its enhancement level
+    pub fn stats(&self) -> Option<ItemStats> {
+        self.get_def().map(|def| enhanced_stats(&def.stats, self.enhancement))
+    }
+
+    /// Tooltip text: the name, then every stat the item grants
+    pub fn tooltip(&self) -> Vec<String> {
+        let mut lines = vec![self.display_name()];
+        if let Some(stats) = self.stats() {
+            let named = [
+                ("Attack", stats.attack),
+                ("Defense", stats.defense),
+                ("Magic Attack", stats.magic_attack),
+                ("Magic Defense", stats.magic_defense),
+                ("HP", stats.hp),
+                ("MP", stats.mp),
+                ("STR", stats.str_stat),
+                ("DEX", stats.dex_stat),
+                ("INT", stats.int_stat),
+                ("CON", stats.con_stat),
+                ("WIS", stats.wis_stat),
+            ];
+            lines.extend(named.iter().filter(|(_, v)| *v != 0).map(|(name, v)| format!("{} {:+}", name, v)));
+        }
+        lines
+    }
+}
+
+/// Player inventory component
+#[derive(Debug, Clone, Default, PartialEq, Serialize, Deserialize)]
+#[cfg_attr(feature = "client", derive(bevy::prelude::Component))]
+pub struct Inventory {
+    /// Main inventory slots (24 slots)
+    pub slots: [Option<ItemStack>; INVENTORY_SIZE],
+    /// Equipment slots
+    pub equipment: std::collections::HashMap<EquipSlot, ItemStack>,
+}
+
+impl Inventory {
+    pub fn new() -> Self {
+        Self {
+            slots: Default::default(),
+            equipment: std::collections::HashMap::new(),
+        }
+    }
+
+    /// Add item to inventory. Returns remaining quantity that couldn't fit.
+    pub fn add_item(&mut self, item_id: i32, mut quantity: i32) -> i32 {
+        let Some(def) = get_item_by_id(item_id) else {
+            return quantity;
+        };
+
+        // Try to stack with existing items first
+        if def.stackable {
+            for slot in self.slots.iter_mut() {
+                if quantity <= 0 {
+                    break;
+                }
+                if let Some(stack) = slot
+                    && stack.item_id == item_id && stack.quantity < def.max_stack
+                {
+                    let space = def.max_stack - stack.quantity;
+                    let add = quantity.min(space);
+                    stack.quantity += add;
+                    quantity -= add;
+                }
+            }
+        }
+
+        // Add to empty slots
+        for slot in self.slots.iter_mut() {
+            if quantity <= 0 {
+                break;
+            }
+            if slot.is_none() {
+                let add = if def.stackable {
+                    quantity.min(def.max_stack)
+                } else {
+                    1
+                };
+                *slot = Some(ItemStack::new(item_id, add));
+                quantity -= add;
+            }
+        }
+
+        quantity // Return remaining that couldn't fit
+    }
+
+    /// Remove item from inventory. Returns actual amount removed.
+    pub fn remove_item(&mut self, item_id: i32, mut quantity: i32) -> i32 {
+        let mut removed = 0;
+
+        for slot in self.slots.iter_mut() {
+            if quantity <= 0 {
+                break;
+            }
+            if let Some(stack) = slot
+                && stack.item_id == item_id
+            {
+                let take = quantity.min(stack.quantity);
+                stack.quantity -= take;
+                quantity -= take;
+                removed += take;
+
+                if stack.quantity <= 0 {
+                    *slot = None;
+                }
+            }
+        }
+
+        removed
+    }
+
+    /// Check if inventory has at least `quantity` of item
+    pub fn has_item(&self, item_id: i32, quantity: i32) -> bool {
+        let total: i32 = self.slots.iter()
+            .filter_map(|s| s.as_ref())
+            .filter(|s| s.item_id == item_id)
+            .map(|s| s.quantity)
+            .sum();
+        total >= quantity
+    }
+
+    /// Get total count of an item
+    pub fn count_item(&self, item_id: i32) -> i32 {
+        self.slots.iter()
+            .filter_map(|s| s.as_ref())
+            .filter(|s| s.item_id == item_id)
+            .map(|s| s.quantity)
+            .sum()
+    }
+
+    /// Find first slot containing item
+    pub fn find_item(&self, item_id: i32) -> Option<usize> {
+        self.slots.iter()
+            .position(|s| s.as_ref().map(|i| i.item_id == item_id).unwrap_or(false))
+    }
+
+    /// Get number of empty slots
+    pub fn empty_slots(&self) -> usize {
+        self.slots.iter().filter(|s| s.is_none()).count()
+    }
+
+    /// Check if inventory is full
+    pub fn is_full(&self) -> bool {
+        self.empty_slots() == 0
+    }
+
+    /// Move a stack to another slot, merging equal stacks or swapping otherwise
+    pub fn move_item(&mut self, from: usize, to: usize) -> Result<(), &'static str> {
+        if from >= INVENTORY_SIZE || to >= INVENTORY_SIZE {
+            return Err("Invalid slot");
+        }
+        if from == to {
+            return Ok(());
+        }
+        let Some(mut stack) = self.slots[from].take() else {
+            return Err("No item in slot");
+        };
+
+        if let Some(target) = self.slots[to].as_mut()
+            && target.can_stack_with(&stack)
+        {
+            let add = stack.quantity.min(target.max_stack() - target.quantity);
+            target.quantity += add;
+            stack.quantity -= add;
+            if stack.quantity > 0 {
+                self.slots[from] = Some(stack);
+            }
+            return Ok(());
+        }
+
+        self.slots[from] = self.slots[to].take();
+        self.slots[to] = Some(stack);
+        Ok(())
+    }
+
+    /// Destroy up to `quantity` items from a slot. Returns the amount removed.
+    pub fn discard(&mut self, slot_index: usize, quantity: i32) -> Result<i32, &'static str> {
+        if quantity <= 0 {
+            return Err("Invalid quantity");
+        }
+        let Some(stack) = self.slots.get_mut(slot_index).and_then(|s| s.as_mut()) else {
+            return Err("No item in slot");
+        };
+
+        let removed = quantity.min(stack.quantity);
+        stack.quantity -= removed;
+        if stack.quantity <= 0 {
+            self.slots[slot_index] = None;
+        }
+        Ok(removed)
+    }
+
+    /// Take up to `quantity` items out of a slot as a stack, keeping its enhancement
+    pub fn take(&mut self, slot_index: usize, quantity: i32) -> Result<ItemStack, &'static str> {
+        if quantity <= 0 {
+            return Err("Invalid quantity");
+        }
+        let Some(stack) = self.slots.get_mut(slot_index).and_then(|s| s.as_mut()) else {
+            return Err("No item in slot");
+        };
+
+        let taken = ItemStack {
+            quantity: quantity.min(stack.quantity),
+            ..stack.clone()
+        };
+        stack.quantity -= taken.quantity;
+        if stack.quantity <= 0 {
+            self.slots[slot_index] = None;
+        }
+        Ok(taken)
+    }
+
+    /// Put a whole stack into the bag, topping up equal stacks first.
+    /// Nothing changes unless all of it fits.
+    pub fn insert_stack(&mut self, stack: ItemStack) -> Result<(), &'static str> {
+        if insert_into(&mut self.slots, stack) {
+            Ok(())
+        } else {
+            Err("Inventory full")
+        }
+    }
+
+    /// Equip item from inventory slot
+    pub fn equip(&mut self, slot_index: usize, equip_slot: EquipSlot) -> Result<(), &'static str> {
+        let Some(stack) = self.slots.get_mut(slot_index).and_then(|s| s.take()) else {
+            return Err("No item in slot");
+        };
+
+        let Some(def) = stack.get_def() else {
+            self.slots[slot_index] = Some(stack);
+            return Err("Unknown item");
+        };
+
+        // Validate item can go in slot
+        let valid = match equip_slot {
+            EquipSlot::Weapon => def.category == ItemCategory::Weapon,
+            EquipSlot::Shield => def.sub_type == "shield",
+            EquipSlot::Helmet => def.sub_type == "helmet",
+            EquipSlot::Armor => def.sub_type == "chest",
+            EquipSlot::Pants => def.sub_type == "pants",
+            EquipSlot::Boots => def.sub_type == "boots",
+            EquipSlot::Gloves => def.sub_type == "gloves",
+            EquipSlot::Ring1 | EquipSlot::Ring2 => def.sub_type == "ring",
+            EquipSlot::Necklace => def.sub_type == "necklace",
+        };
+
+        if !valid {
+            self.slots[slot_index] = Some(stack);
+            return Err("Item cannot be equipped in that slot");
+        }
+
+        // Swap with current equipment
+        let old_equip = self.equipment.insert(equip_slot, stack);
+        if let Some(old) = old_equip {
+            self.slots[slot_index] = Some(old);
+        }
+
+        Ok(())
+    }
+
+    /// Unequip item to inventory
+    pub fn unequip(&mut self, equip_slot: EquipSlot) -> Result<(), &'static str> {
+        if self.is_full() {
+            return Err("Inventory full");
+        }
+
+        let Some(stack) = self.equipment.remove(&equip_slot) else {
+            return Err("Nothing equipped in slot");
+        };
+
+        // Find empty slot
+        for slot in self.slots.iter_mut() {
+            if slot.is_none() {
+                *slot = Some(stack);
+                return Ok(());
+            }
+        }
+
+        // Shouldn't reach here due to is_full check
+        self.equipment.insert(equip_slot, stack);
+        Err("No space in inventory")
+    }
+
+    /// Calculate total stat bonuses from equipment
+    pub fn equipment_stats(&self) -> EquipmentStats {
+        let mut stats = EquipmentStats::default();  
+
+        // Enhanced items count at their enhanced stats
+        for item in self.equipment.values().filter_map(ItemStack::stats) {
+            stats.attack += item.attack;
+            stats.defense += item.defense;
+            stats.magic_attack += item.magic_attack;
+            stats.magic_defense += item.magic_defense;
+            stats.hp += item.hp;
+            stats.mp += item.mp;
+            stats.str_stat += item.str_stat;
+            stats.dex_stat += item.dex_stat;
+            stats.int_stat += item.int_stat;
+            stats.con_stat += item.con_stat;
+            stats.wis_stat += item.wis_stat;
+        }
+
+        stats
+    }
+}
+
+/// Merge a stack into `slots`, or leave them untouched if it does not fit
+pub(crate) fn insert_into(slots: &mut [Option<ItemStack>], stack: ItemStack) -> bool {
+    if stack.quantity <= 0 {
+        return false;
+    }
+
+    let room: i32 = slots.iter()
+        .map(|s| match s {
+            None => stack.max_stack(),
+            Some(s) if s.can_stack_with(&stack) => (s.max_stack() - s.quantity).max(0),
+            Some(_) => 0,
+        })
+        .sum();
+    if room < stack.quantity {
+        return false;
+    }
+
+    let mut left = stack.quantity;
+    for s in slots.iter_mut().flatten() {
+        if left > 0 && s.can_stack_with(&stack) {
+            let add = left.min(s.max_stack() - s.quantity).max(0);
+            s.quantity += add;
+            left -= add;
+        }
+    }
+    for slot in slots.iter_mut() {
+        if left > 0 && slot.is_none() {
+            let add = left.min(stack.max_stack());
+            *slot = Some(ItemStack { quantity: add, ..stack.clone() });
+            left -= add;
+        }
+    }
+    true
+}
+
+/// Equipment stat bonuses
+#[derive(Debug, Clone, Default)]
+pub struct EquipmentStats {
+    pub attack: i32,
+    pub defense: i32,
+    pub magic_attack: i32,
+    pub magic_defense: i32,
+    pub hp: i32,
+    pub mp: i32,
+    pub str_stat: i32,
+    pub dex_stat: i32,
+    pub int_stat: i32,
+    pub con_stat: i32,
+    pub wis_stat: i32,
+}
+
+/// Use a consumable item
+pub fn use_consumable(
+    inventory: &mut Inventory,
+    slot_index: usize,
+    player_hp: &mut i32,
+    player_mp: &mut i32,
+    max_hp: i32,
+    max_mp: i32,
+) -> Result<String, &'static str> {
+    let Some(stack) = inventory.slots[slot_index].as_ref() else {
+        return Err("No item in slot");
+    };
+
+    let Some(def) = stack.get_def() else {
+        return Err("Unknown item");
+    };
+
+    if def.category != ItemCategory::Consumable {
+        return Err("Item is not consumable");
+    }
+
+    // Apply effects
+    let mut message = String::new();
+
+    if def.stats.heal_hp > 0 {
+        let heal = def.stats.heal_hp;
+        *player_hp = (*player_hp + heal).min(max_hp);
+        message.push_str(&format!("Restored {} HP. ", heal));
+    }
+
+    if def.stats.heal_mp > 0 {
+        let heal = def.stats.heal_mp;
+        *player_mp = (*player_mp + heal).min(max_mp);
+        message.push_str(&format!("Restored {} MP. ", heal));
+    }
+
+    // Remove one item
+    if let Some(stack) = inventory.slots[slot_index].as_mut() {
+        stack.quantity -= 1;
+        if stack.quantity <= 0 {
+            inventory.slots[slot_index] = None;
+        }
+    }
+
+    Ok(message)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_move_merges_and_swaps() {
+        let mut inventory = Inventory::new();
+        inventory.slots[0] = Some(ItemStack::new(1, 3));
+        inventory.slots[1] = Some(ItemStack::new(1, 4));
+        inventory.slots[2] = Some(ItemStack::single(10));
+
+        inventory.move_item(0, 1).unwrap();
+        assert!(inventory.slots[0].is_none());
+        assert_eq!(inventory.slots[1].as_ref().unwrap().quantity, 7);
+
+        inventory.move_item(1, 2).unwrap();
+        assert_eq!(inventory.slots[1].as_ref().unwrap().item_id, 10);
+        assert_eq!(inventory.slots[2].as_ref().unwrap().item_id, 1);
+
+        assert!(inventory.move_item(0, 5).is_err());
+        assert!(inventory.move_item(1, INVENTORY_SIZE).is_err());
+    }
+
+    #[test]
+    fn test_discard_partial_stack() {
+        let mut inventory = Inventory::new();
+        inventory.slots[0] = Some(ItemStack::new(1, 5));
+
+        assert_eq!(inventory.discard(0, 2), Ok(2));
+        assert_eq!(inventory.count_item(1), 3);
+        assert_eq!(inventory.discard(0, 10), Ok(3));
+        assert!(inventory.slots[0].is_none());
+    }
+
+    #[test]
+    fn test_take_and_insert_keep_enhancement() {
+        let mut inventory = Inventory::new();
+        inventory.slots[0] = Some(ItemStack { item_id: 10, quantity: 1, enhancement: 3 });
+        inventory.slots[1] = Some(ItemStack::new(1, 5));
+
+        let sword = inventory.take(0, 1).unwrap();
+        assert_eq!(sword.enhancement, 3);
+        assert!(inventory.slots[0].is_none());
+        assert_eq!(inventory.take(1, 2).unwrap().quantity, 2);
+        assert_eq!(inventory.count_item(1), 3);
+
+        inventory.insert_stack(sword.clone()).unwrap();
+        assert_eq!(inventory.slots[0], Some(sword));
+
+        // All or nothing
+        for slot in inventory.slots.iter_mut().filter(|s| s.is_none()) {
+            *slot = Some(ItemStack::single(10));
+        }
+        let max = ItemStack::new(1, 1).max_stack();
+        assert!(inventory.insert_stack(ItemStack::new(1, max)).is_err());
+        assert_eq!(inventory.count_item(1), 3);
+        inventory.insert_stack(ItemStack::new(1, max - 3)).unwrap();
+        assert_eq!(inventory.count_item(1), max);
+    }
+
+    #[test]
+    fn test_enhancement_shows_in_names_and_equipment_stats() {
+        let mut inventory = Inventory::new();
+        inventory.slots[0] = Some(ItemStack { item_id: 11, quantity: 1, enhancement: 7 });
+        let sword = inventory.slots[0].clone().unwrap();
+        assert_eq!(sword.display_name(), "+7 Iron Sword");
+        assert_eq!(sword.tooltip(), vec!["+7 Iron Sword".to_string(), "Attack +23".to_string()]);
+        assert_eq!(ItemStack::single(11).display_name(), "Iron Sword");
+
+        inventory.equip(0, EquipSlot::Weapon).unwrap();
+        assert_eq!(inventory.equipment_stats().attack, 23);
+    }
+
+    #[test]
+    fn test_equip_slot_keys_round_trip() {
+        for slot in EquipSlot::all() {
+            assert_eq!(EquipSlot::from_key(slot.key()), Some(*slot));
+        }
+    }
+}
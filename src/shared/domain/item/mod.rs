@@ -1,2 +1,3 @@
 pub mod models;
+pub mod inventory;
 pub use models::*;
@@ -7,13 +7,15 @@ pub mod sprite;
 pub mod item;
 pub mod skill;
 pub mod map;
+pub mod guild;
 
 pub mod shared;
 
 // Re-export commonly used types
-pub use character::models::{Player, PlayerClass, StatType};
+pub use character::models::{BindPoint, Player, PlayerClass, StatType};
 pub use monster::{Monster, MonsterData, MonsterDataDto, MonsterAIType, SpriteSize};
 pub use item::models::{Item, ItemType, EquipmentSlot};
+pub use item::inventory::{EquipSlot, Inventory, ItemStack};
 pub use skill::models::Skill;
 pub use map::*;
 
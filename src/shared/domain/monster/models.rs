@@ -3,20 +3,15 @@ use serde_json::Value;
 use crate::shared::domain::shared::models::{Position, Direction};
 
 /// Sprite size for monsters - determines frame dimensions
-#[derive(Debug, Clone, Copy, Serialize, Deserialize, PartialEq)]
+#[derive(Debug, Clone, Copy, Serialize, Deserialize, PartialEq, Default)]
 pub enum SpriteSize {
+    #[default]
     Small,   // 32x32 (Lv 1-10)
     Medium,  // 48x48 (Lv 11-50)
     Large,   // 64x64 (Lv 51-98)
     Boss,    // 128x128 (Lv 99+)
 }
 
-impl Default for SpriteSize {
-    fn default() -> Self {
-        SpriteSize::Small
-    }
-}
-
 impl From<&str> for SpriteSize {
     fn from(s: &str) -> Self {
         match s.to_lowercase().as_str() {
@@ -116,36 +111,6 @@ impl Monster {
         }
     }
     
-    /// Calculate loot when monster dies
-    /// Returns (gold_reward, item_rewards)
-    pub fn calculate_loot(&self) -> (i32, Vec<(i32, i32)>) {
-        use rand::Rng;
-        let mut rng = rand::thread_rng();
-        
-        // 1. Gold (Random between min/max)
-        let gold = if self.gold_max > self.gold_min {
-            rng.gen_range(self.gold_min..=self.gold_max)
-        } else {
-            self.gold_min
-        };
-        
-        // 2. Items (Based on probability)
-        let mut dropped_items = Vec::new();
-        for drop in &self.loot_table {
-            let roll: f64 = rng.r#gen();
-            if roll <= drop.probability {
-                let quantity = if drop.max_quantity > drop.min_quantity {
-                    rng.gen_range(drop.min_quantity..=drop.max_quantity)
-                } else {
-                    drop.min_quantity
-                };
-                dropped_items.push((drop.item_id, quantity));
-            }
-        }
-        
-        (gold, dropped_items)
-    }
-    
     pub fn take_damage(&mut self, damage: i32) {
         self.hp = (self.hp - damage).max(0);
     }
@@ -158,13 +123,10 @@ impl Monster {
         current_time - self.last_attack_time >= self.attack_cooldown
     }
     
-    pub fn attack(&mut self, current_time: f64) -> i32 {
+    /// Start the attack cooldown; damage comes from `shared::combat`
+    pub fn register_attack(&mut self, current_time: f64) {
         self.last_attack_time = current_time;
         self.is_attacking = true;
-        
-        use rand::Rng;
-        let mut rng = rand::thread_rng();
-        rng.gen_range(self.attack_min..=self.attack_max)
     }
 }
 
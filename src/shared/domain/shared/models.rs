@@ -140,3 +140,15 @@ pub enum Direction {
     Left,
     Right,
 }
+
+impl Direction {
+    /// Grid offset of a single step in this direction
+    pub fn offset(&self) -> (i32, i32) {
+        match self {
+            Direction::Up => (0, -1),
+            Direction::Down => (0, 1),
+            Direction::Left => (-1, 0),
+            Direction::Right => (1, 0),
+        }
+    }
+}
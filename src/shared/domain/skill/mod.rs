@@ -1,7 +1,4 @@
 pub mod models;
-pub mod server; // Must be available to both CSR/SSR for the stub
+pub mod server; // Shared query used by the Axum handler and the client fetch
 
-
-// Re-exporting the struct specific to the server function if needed, 
-// but usually we import the function 'get_skills' (or the struct GetSkills which the macro generates).
-pub use server::get_skills;
+pub use server::{get_skills, SkillQuery};
@@ -1,4 +1,5 @@
 use serde::{Deserialize, Serialize};
+use crate::shared::data::skills::{get_available_skills, SkillDef, SkillEffectType};
 
 
 #[derive(Clone, Debug, Serialize, Deserialize, PartialEq)]
@@ -15,3 +16,96 @@ pub struct Skill {
     pub base_value: i32,
     pub icon_path: Option<String>,
 }
+
+impl From<&SkillDef> for Skill {
+    fn from(def: &SkillDef) -> Self {
+        Self {
+            id: def.id,
+            name: def.name.to_string(),
+            class_id: def.class_id,
+            req_level: def.req_level,
+            mp_cost: def.mp_cost,
+            cooldown_ms: def.cooldown_ms,
+            description: Some(def.description_key.to_string()),
+            effect_type: Some(match def.effect_type {
+                SkillEffectType::Damage => "damage",
+                SkillEffectType::Heal => "heal",
+                SkillEffectType::Buff => "buff",
+                SkillEffectType::Debuff => "debuff",
+                SkillEffectType::DamageOverTime => "damage_over_time",
+                SkillEffectType::HealOverTime => "heal_over_time",
+            }.to_string()),
+            base_value: def.base_value,
+            icon_path: Some(def.icon_path.to_string()),
+        }
+    }
+}
+
+/// Hotbar slots bound to keys 1-5
+pub const SKILL_BAR_SLOTS: usize = 5;
+
+/// A skill the character has learned
+#[derive(Clone, Debug, Serialize, Deserialize, PartialEq)]
+pub struct LearnedSkill {
+    pub skill_id: i32,
+    pub level: i32,
+}
+
+/// Learned skills and the hotbar, persisted in `character_skills`
+#[derive(Clone, Debug, Default, Serialize, Deserialize, PartialEq)]
+pub struct SkillBook {
+    pub learned: Vec<LearnedSkill>,
+    /// Skill id in each hotbar slot
+    pub bar: [Option<i32>; SKILL_BAR_SLOTS],
+}
+
+impl SkillBook {
+    /// Level 1 skills of a class, placed on the bar in definition order
+    pub fn starter(class_id: i32) -> Self {
+        let mut book = Self::default();
+        for def in get_available_skills(class_id, 1) {
+            let _ = book.learn(def, class_id, 1);
+            if let Some(slot) = book.bar.iter().position(|s| s.is_none()) {
+                book.bar[slot] = Some(def.id);
+            }
+        }
+        book
+    }
+
+    pub fn knows(&self, skill_id: i32) -> bool {
+        self.learned.iter().any(|s| s.skill_id == skill_id)
+    }
+
+    /// Learn a skill if the class and level requirements are met
+    pub fn learn(&mut self, def: &SkillDef, class_id: i32, level: i32) -> Result<(), String> {
+        if def.class_id.is_some_and(|c| c != class_id) {
+            return Err(format!("{} belongs to another class", def.name));
+        }
+        if def.req_level > level {
+            return Err(format!("{} requires level {}", def.name, def.req_level));
+        }
+        if self.knows(def.id) {
+            return Err(format!("{} is already learned", def.name));
+        }
+        self.learned.push(LearnedSkill { skill_id: def.id, level: 1 });
+        Ok(())
+    }
+
+    /// Put a learned skill in a hotbar slot (or clear it with `None`).
+    /// A skill occupies at most one slot.
+    pub fn assign(&mut self, slot: usize, skill_id: Option<i32>) -> Result<(), String> {
+        if slot >= SKILL_BAR_SLOTS {
+            return Err("Invalid skill bar slot".to_string());
+        }
+        if let Some(id) = skill_id {
+            if !self.knows(id) {
+                return Err("Skill not learned".to_string());
+            }
+            for s in self.bar.iter_mut().filter(|s| **s == Some(id)) {
+                *s = None;
+            }
+        }
+        self.bar[slot] = skill_id;
+        Ok(())
+    }
+}
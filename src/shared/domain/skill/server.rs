@@ -1,53 +1,93 @@
 //! Skill API - Shared between client and server
 //!
-//! CSR: Fetches from REST API
-//! SSR: Fetches directly from DB
+//! `SkillQuery` filters the static skill data in `shared::data::skills`.
+//! The server answers it directly; the client sends it to `GET /api/skills`,
+//! whose handler runs the same query.
 
+use serde::{Deserialize, Serialize};
+
+use crate::shared::data::skills::{all_skills, SkillDef};
 use crate::shared::domain::skill::models::Skill;
 
-/// Get skills for a class and level
-/// On server: queries DB
-/// On client: calls API
-pub async fn get_skills(class_id: Option<i32>, level: i32) -> Result<Vec<Skill>, String> {
+/// Skill filter; unset fields match everything
+#[derive(Debug, Clone, Default, PartialEq, Serialize, Deserialize)]
+pub struct SkillQuery {
+    /// Class skills plus common skills (those without a class)
+    pub class_id: Option<i32>,
+    pub circle: Option<i32>,
+    /// Skills usable at this level
+    pub level: Option<i32>,
+}
+
+impl SkillQuery {
+    pub fn matches(&self, def: &SkillDef) -> bool {
+        self.class_id.is_none_or(|c| def.class_id.is_none_or(|dc| dc == c))
+            && self.circle.is_none_or(|c| def.circle == c)
+            && self.level.is_none_or(|l| def.req_level <= l)
+    }
+
+    /// Matching skills in definition order
+    pub fn run(&self) -> Vec<Skill> {
+        all_skills().iter()
+            .filter(|def| self.matches(def))
+            .map(Skill::from)
+            .collect()
+    }
+
+    /// URL query string for `GET /api/skills` (empty when unfiltered)
+    pub fn to_query_string(&self) -> String {
+        let params: Vec<String> = [("class_id", self.class_id), ("circle", self.circle), ("level", self.level)]
+            .iter()
+            .filter_map(|(key, value)| value.map(|v| format!("{}={}", key, v)))
+            .collect();
+        if params.is_empty() { String::new() } else { format!("?{}", params.join("&")) }
+    }
+}
+
+/// Get skills matching a query
+/// On server: reads the static skill data
+/// On client: calls the API
+pub async fn get_skills(query: SkillQuery) -> Result<Vec<Skill>, String> {
     cfg_if::cfg_if! {
         if #[cfg(feature = "server")] {
-            let pool = crate::server::db::get_db_pool();
-            
-            let skills = sqlx::query_as::<_, Skill>(
-                "SELECT id, name, class_id, req_level, mp_cost, cooldown_ms, description, effect_type, base_value, icon_path FROM skill_definitions WHERE (class_id = $1 OR class_id IS NULL) AND req_level <= $2"
-            )
-            .bind(class_id)
-            .bind(level)
-            .fetch_all(pool)
-            .await
-            .map_err(|e: sqlx::Error| e.to_string())?;
-            
-            Ok(skills)
+            Ok(query.run())
         } else {
-            // Client side fetch using reqwest
-            let base_url = if cfg!(target_arch = "wasm32") {
-                "/api/skills"
-            } else {
-                "http://localhost:3000/api/skills"
-            };
-            
-            let mut query_params = format!("?level={}", level);
-            if let Some(cid) = class_id {
-                query_params.push_str(&format!("&class_id={}", cid));
-            }
-            
-            let url = format!("{}{}", base_url, query_params);
-            
+            let url = format!("{}/skills{}", crate::shared::api::api_url(), query.to_query_string());
+
             let response = reqwest::get(url)
                 .await
                 .map_err(|e| e.to_string())?;
-                
-            let skills = response
+
+            response
                 .json::<Vec<Skill>>()
                 .await
-                .map_err(|e| e.to_string())?;
-                
-            Ok(skills)
+                .map_err(|e| e.to_string())
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_query_filters_class_circle_and_level() {
+        let query = SkillQuery { class_id: Some(1), circle: Some(1), level: Some(5) };
+        let skills = query.run();
+        assert!(!skills.is_empty());
+        for skill in &skills {
+            let def = crate::shared::data::skills::get_skill_by_id(skill.id).unwrap();
+            assert!(def.class_id.is_none() || def.class_id == Some(1));
+            assert_eq!(def.circle, 1);
+            assert!(def.req_level <= 5);
         }
+        assert_eq!(SkillQuery::default().run().len(), all_skills().len());
+    }
+
+    #[test]
+    fn test_query_string() {
+        assert_eq!(SkillQuery::default().to_query_string(), "");
+        let query = SkillQuery { class_id: Some(3), circle: None, level: Some(10) };
+        assert_eq!(query.to_query_string(), "?class_id=3&level=10");
     }
 }
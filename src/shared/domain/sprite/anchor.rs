@@ -226,10 +226,10 @@ impl CharacterAnchors {
         
         if let Some(mirror_dir) = mirror_direction {
             let mirror_key = Self::animation_key(state, mirror_dir);
-            if let Some(anim) = self.animations.get(&mirror_key) {
-                if let Some(frame) = anim.get_frame(frame_index) {
-                    return Some(frame.mirrored(self.frame_width));
-                }
+            if let Some(anim) = self.animations.get(&mirror_key)
+                && let Some(frame) = anim.get_frame(frame_index)
+            {
+                return Some(frame.mirrored(self.frame_width));
             }
         }
         
@@ -3,3 +3,7 @@
 pub mod constants;
 pub mod domain;
 pub mod data;
+pub mod protocol;
+pub mod api;
+pub mod combat;
+pub mod validation;
@@ -0,0 +1,277 @@
+//! Real-time protocol - WebSocket messages shared by client and server
+//!
+//! Every frame is a JSON text message tagged by `type`.
+//! The server owns the simulation: clients only send intents
+//! (move, attack, cast) and render the snapshots they receive.
+
+use serde::{Deserialize, Serialize};
+use crate::shared::combat::HitOutcome;
+use crate::shared::data::monsters::KillRewards;
+use crate::shared::domain::item::inventory::ItemStack;
+use crate::shared::domain::shared::models::Direction;
+
+/// Default server simulation rate (ticks per second)
+pub const TICK_RATE: u32 = 10;
+
+/// WebSocket endpoint path (relative to the API root)
+pub const WS_PATH: &str = "/api/ws";
+
+/// Longest chat message (characters)
+pub const MAX_CHAT_LEN: usize = 200;
+
+/// Who hears a chat message
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum ChatChannel {
+    /// Everyone on the sender's map
+    Map,
+    /// One player, by character name
+    Whisper,
+    /// The sender's party
+    Party,
+    /// Everyone online
+    Global,
+    /// The sender's guild
+    Guild,
+}
+
+impl ChatChannel {
+    pub fn key(self) -> &'static str {
+        match self {
+            Self::Map => "map",
+            Self::Whisper => "whisper",
+            Self::Party => "party",
+            Self::Global => "global",
+            Self::Guild => "guild",
+        }
+    }
+
+    pub fn from_key(key: &str) -> Option<Self> {
+        match key {
+            "map" => Some(Self::Map),
+            "whisper" => Some(Self::Whisper),
+            "party" => Some(Self::Party),
+            "global" => Some(Self::Global),
+            "guild" => Some(Self::Guild),
+            _ => None,
+        }
+    }
+}
+
+/// A chat line as delivered (after the word filter)
+#[derive(Debug, Clone, Serialize, Deserialize, PartialEq)]
+pub struct ChatMessage {
+    pub channel: ChatChannel,
+    /// Sender's entity id and name
+    pub from_id: String,
+    pub from: String,
+    /// Whisper recipient's name
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub to: Option<String>,
+    pub text: String,
+}
+
+/// Messages sent from client to server
+#[derive(Debug, Clone, Serialize, Deserialize, PartialEq)]
+#[serde(tag = "type", rename_all = "snake_case")]
+pub enum ClientMessage {
+    /// Enter the world. Must be the first message on a connection.
+    Join {
+        name: String,
+        class_id: i32,
+        gender: String,
+    },
+    /// Step one tile in a direction
+    Move { direction: Direction },
+    /// Basic attack on the tile in front of the player
+    Attack,
+    /// Cast a skill by SkillDef id
+    UseSkill { skill_id: i32 },
+    /// Keep-alive / latency probe
+    Ping { client_time: f64 },
+    /// Say something; `to` names the whisper recipient
+    Chat {
+        channel: ChatChannel,
+        #[serde(default, skip_serializing_if = "Option::is_none")]
+        to: Option<String>,
+        text: String,
+    },
+    /// Invite a player, by character name, to the sender's party (or a new one)
+    PartyInvite { name: String },
+    /// Join the party of a player who invited us
+    PartyAccept { inviter_id: String },
+    /// Leave the current party
+    PartyLeave,
+    /// Remove a member (leader only)
+    PartyKick { member_id: String },
+    /// Ask a nearby player, by character name, to trade
+    TradeRequest { name: String },
+    /// Open a trade with a player who asked us
+    TradeAccept { from_id: String },
+    /// Replace our side of the open trade
+    TradeOffer { items: Vec<TradeSlot>, gold: i64 },
+    /// Fix our side; confirming is possible once both sides are locked
+    TradeLock,
+    /// Agree to the exchange; it happens when both sides confirm
+    TradeConfirm,
+    /// Close the open trade without exchanging anything
+    TradeCancel,
+}
+
+/// Messages sent from server to client
+#[derive(Debug, Clone, Serialize, Deserialize, PartialEq)]
+#[serde(tag = "type", rename_all = "snake_case")]
+pub enum ServerMessage {
+    /// Join accepted; `entity_id` identifies the player in snapshots
+    Welcome {
+        entity_id: String,
+        map_id: String,
+        tick: u64,
+    },
+    /// Full state of the player's current map
+    Snapshot {
+        tick: u64,
+        you: PlayerVitals,
+        entities: Vec<EntityState>,
+        /// The player's party, if any
+        #[serde(default, skip_serializing_if = "Option::is_none")]
+        party: Option<PartyState>,
+    },
+    /// The player took a portal and now stands on another map
+    MapChanged {
+        map_id: String,
+        x: i32,
+        y: i32,
+    },
+    /// Result of an attack or skill
+    Combat(CombatEvent),
+    /// Exp, gold and items granted to this player for a kill
+    Rewards(KillRewards),
+    /// Reply to `ClientMessage::Ping`
+    Pong { client_time: f64 },
+    /// Someone said something this player can hear (including themselves)
+    Chat(ChatMessage),
+    /// Someone invited this player to their party
+    PartyInvite { from_id: String, from: String },
+    /// Someone asked this player to trade
+    TradeRequest { from_id: String, from: String },
+    /// The open trade changed
+    Trade(TradeState),
+    /// The trade ended; `completed` if the items and gold changed hands
+    TradeClosed { completed: bool, reason: String },
+    /// Something happened that the player should know about
+    Notice { message: String },
+    /// Request rejected (not fatal)
+    Error { message: String },
+}
+
+/// Kind-specific entity data
+#[derive(Debug, Clone, Serialize, Deserialize, PartialEq)]
+#[serde(tag = "kind", rename_all = "snake_case")]
+pub enum EntityKind {
+    Player {
+        class_id: i32,
+        gender: String,
+        level: i32,
+        /// Guild name shown on the nameplate
+        #[serde(default, skip_serializing_if = "Option::is_none")]
+        guild: Option<String>,
+    },
+    Monster {
+        monster_id: i32,
+    },
+}
+
+/// A visible entity on the map
+#[derive(Debug, Clone, Serialize, Deserialize, PartialEq)]
+pub struct EntityState {
+    pub id: String,
+    pub name: String,
+    #[serde(flatten)]
+    pub kind: EntityKind,
+    pub x: i32,
+    pub y: i32,
+    pub direction: Direction,
+    pub hp: i32,
+    pub max_hp: i32,
+}
+
+/// Private state of the receiving player
+#[derive(Debug, Clone, Serialize, Deserialize, PartialEq)]
+pub struct PlayerVitals {
+    pub hp: i32,
+    pub max_hp: i32,
+    pub mp: i32,
+    pub max_mp: i32,
+    pub level: i32,
+    pub exp: i64,
+    pub gold: i64,
+}
+
+/// A party member as shown in the party frames
+#[derive(Debug, Clone, Serialize, Deserialize, PartialEq)]
+pub struct PartyMember {
+    pub id: String,
+    pub name: String,
+    pub class_id: i32,
+    pub level: i32,
+    pub map_id: String,
+    pub hp: i32,
+    pub max_hp: i32,
+    pub mp: i32,
+    pub max_mp: i32,
+}
+
+/// The receiving player's party (including themselves)
+#[derive(Debug, Clone, Serialize, Deserialize, PartialEq)]
+pub struct PartyState {
+    pub leader_id: String,
+    /// In join order, the leader first
+    pub members: Vec<PartyMember>,
+}
+
+/// A bag slot put into a trade
+#[derive(Debug, Clone, Copy, Serialize, Deserialize, PartialEq)]
+pub struct TradeSlot {
+    pub slot_index: usize,
+    pub quantity: i32,
+}
+
+/// An offered item and the bag slot it comes from
+#[derive(Debug, Clone, Serialize, Deserialize, PartialEq)]
+pub struct TradeItem {
+    pub slot_index: usize,
+    pub item: ItemStack,
+}
+
+/// One side of a trade
+#[derive(Debug, Clone, Default, Serialize, Deserialize, PartialEq)]
+pub struct TradeOffer {
+    pub items: Vec<TradeItem>,
+    pub gold: i64,
+    pub locked: bool,
+    pub confirmed: bool,
+}
+
+/// The open trade as seen by the receiving player
+#[derive(Debug, Clone, Serialize, Deserialize, PartialEq)]
+pub struct TradeState {
+    pub partner_id: String,
+    pub partner: String,
+    pub mine: TradeOffer,
+    pub theirs: TradeOffer,
+}
+
+/// Damage or healing applied by the simulation
+#[derive(Debug, Clone, Serialize, Deserialize, PartialEq)]
+pub struct CombatEvent {
+    pub attacker_id: String,
+    pub target_id: String,
+    pub skill_id: Option<i32>,
+    /// Positive = damage, negative = healing
+    pub amount: i32,
+    pub killed: bool,
+    /// Miss / hit / critical (heals are always hits)
+    #[serde(default)]
+    pub outcome: HitOutcome,
+}
@@ -0,0 +1,140 @@
+//! Input validation - rules shared by the server and the client forms
+//!
+//! Each check returns the message to show for its field. The server runs
+//! them before touching the database; the client can run them to reject
+//! a form early.
+
+use crate::shared::api::{FieldError, RegisterRequest};
+use crate::shared::data::characters::get_class_by_id;
+
+pub const USERNAME_MIN: usize = 3;
+pub const USERNAME_MAX: usize = 12;
+pub const CHARACTER_NAME_MIN: usize = 2;
+pub const CHARACTER_NAME_MAX: usize = 12;
+pub const GUILD_NAME_MIN: usize = 3;
+pub const GUILD_NAME_MAX: usize = 20;
+pub const PASSWORD_MIN: usize = 8;
+/// bcrypt ignores everything past 72 bytes
+pub const PASSWORD_MAX_BYTES: usize = 72;
+
+pub const GENDERS: [&str; 2] = ["male", "female"];
+
+/// Usernames double as the first character's name, so they follow the
+/// character name rules and are limited to ASCII
+pub fn validate_username(username: &str) -> Result<(), String> {
+    let len = username.chars().count();
+    if !(USERNAME_MIN..=USERNAME_MAX).contains(&len) {
+        return Err(format!("Username must be {}-{} characters", USERNAME_MIN, USERNAME_MAX));
+    }
+    if !username.chars().all(|c| c.is_ascii_alphanumeric()) {
+        return Err("Username may only contain English letters and digits".to_string());
+    }
+    Ok(())
+}
+
+pub fn validate_password(password: &str) -> Result<(), String> {
+    if password.chars().count() < PASSWORD_MIN {
+        return Err(format!("Password must be at least {} characters", PASSWORD_MIN));
+    }
+    if password.len() > PASSWORD_MAX_BYTES {
+        return Err(format!("Password must be at most {} bytes", PASSWORD_MAX_BYTES));
+    }
+    if !password.chars().any(char::is_alphabetic) || !password.chars().any(|c| c.is_ascii_digit()) {
+        return Err("Password must contain a letter and a digit".to_string());
+    }
+    Ok(())
+}
+
+pub fn validate_character_name(name: &str) -> Result<(), String> {
+    let len = name.chars().count();
+    if !(CHARACTER_NAME_MIN..=CHARACTER_NAME_MAX).contains(&len) {
+        return Err(format!("Name must be {}-{} characters", CHARACTER_NAME_MIN, CHARACTER_NAME_MAX));
+    }
+    if !name.chars().all(|c| c.is_alphanumeric()) {
+        return Err("Name may only contain letters and digits".to_string());
+    }
+    Ok(())
+}
+
+/// Words separated by single spaces
+pub fn validate_guild_name(name: &str) -> Result<(), String> {
+    let len = name.chars().count();
+    if !(GUILD_NAME_MIN..=GUILD_NAME_MAX).contains(&len) {
+        return Err(format!("Guild name must be {}-{} characters", GUILD_NAME_MIN, GUILD_NAME_MAX));
+    }
+    if !name.split(' ').all(|word| !word.is_empty() && word.chars().all(char::is_alphanumeric)) {
+        return Err("Guild name may only contain letters, digits and single spaces".to_string());
+    }
+    Ok(())
+}
+
+pub fn validate_class_id(class_id: i32) -> Result<(), String> {
+    match get_class_by_id(class_id) {
+        Some(_) => Ok(()),
+        None => Err(format!("Unknown class: {}", class_id)),
+    }
+}
+
+pub fn validate_gender(gender: &str) -> Result<(), String> {
+    if GENDERS.contains(&gender) {
+        Ok(())
+    } else {
+        Err(format!("Unknown gender: {}", gender))
+    }
+}
+
+/// Every problem with a registration form, one entry per bad field
+pub fn validate_registration(req: &RegisterRequest) -> Vec<FieldError> {
+    [
+        ("username", validate_username(req.username.trim())),
+        ("password", validate_password(&req.password)),
+        ("class_idx", validate_class_id(req.class_idx)),
+        ("gender", validate_gender(&req.gender)),
+    ]
+    .into_iter()
+    .filter_map(|(field, result)| result.err().map(|message| FieldError { field: field.to_string(), message }))
+    .collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn request(username: &str, password: &str, class_idx: i32, gender: &str) -> RegisterRequest {
+        RegisterRequest {
+            username: username.to_string(),
+            password: password.to_string(),
+            class_idx,
+            gender: gender.to_string(),
+        }
+    }
+
+    #[test]
+    fn test_valid_registration_passes() {
+        assert!(validate_registration(&request("hero01", "secret123", 1, "female")).is_empty());
+    }
+
+    #[test]
+    fn test_reports_every_bad_field() {
+        let errors = validate_registration(&request("a!", "short", 6, "other"));
+        let fields: Vec<_> = errors.iter().map(|e| e.field.as_str()).collect();
+        assert_eq!(fields, ["username", "password", "class_idx", "gender"]);
+    }
+
+    #[test]
+    fn test_guild_name_rules() {
+        assert!(validate_guild_name("Knights of Milles").is_ok());
+        assert!(validate_guild_name("ab").is_err());
+        assert!(validate_guild_name(" Knights").is_err());
+        assert!(validate_guild_name("Knights  Two").is_err());
+        assert!(validate_guild_name("<script>").is_err());
+    }
+
+    #[test]
+    fn test_password_rules() {
+        assert!(validate_password("abcdefgh").is_err());
+        assert!(validate_password("12345678").is_err());
+        assert!(validate_password(&format!("a1{}", "x".repeat(PASSWORD_MAX_BYTES))).is_err());
+        assert!(validate_password("abcdefg1").is_ok());
+    }
+}
@@ -0,0 +1,42 @@
+//! Game data validator - schema and cross-reference checks
+//!
+//! Checks the built-in game data, or a content directory layered over it,
+//! and lists every problem found. Exits non-zero if there are any.
+//!
+//! Run: cargo run --bin legend-validate-data -- [content_dir]
+
+use std::path::Path;
+use std::process::ExitCode;
+
+use legend_client::shared::data::GameContent;
+
+fn main() -> ExitCode {
+    let dir = std::env::args().nth(1);
+    let result = match &dir {
+        Some(dir) => GameContent::load_dir(Path::new(dir)),
+        None => GameContent::builtin().validated(),
+    };
+    let source = dir.as_deref().unwrap_or("built-in data");
+
+    match result {
+        Ok(content) => {
+            println!(
+                "✅ {}: {} monsters, {} drops, {} skills, {} items, {} maps",
+                source,
+                content.monsters.len(),
+                content.drops.len(),
+                content.skills.len(),
+                content.items.len(),
+                content.maps.len(),
+            );
+            ExitCode::SUCCESS
+        }
+        Err(errors) => {
+            for e in &errors {
+                eprintln!("❌ {}", e);
+            }
+            eprintln!("{}: {} problem(s)", source, errors.len());
+            ExitCode::FAILURE
+        }
+    }
+}